@@ -0,0 +1,114 @@
+extern crate ir;
+
+use ir::ir_diagnostic::*;
+use ir::ir_lexer::*;
+use ir::ir_parser::*;
+use ir::ir_typechecker::*;
+use ir::ir_x86_64::*;
+use ir::ir_aarch64::*;
+use ir::ir_riscv64::*;
+use ir::ir_wasm::*;
+
+use std::env;
+
+extern crate getopts;
+use getopts::Options;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut opts = Options::new();
+    opts.reqopt("f", "file", "Input file to compile", "FILE");
+    opts.optflag("", "color", "Color diagnostics with ANSI escapes");
+    opts.optopt(
+        "t", "target",
+        "Target to emit code for: x86-64 (default), aarch64, riscv64, or \
+         wasm32 for WebAssembly text",
+        "TARGET"
+    );
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => { m }
+        Err(f) => { panic!("{}", f) }
+    };
+
+    let filename = match matches.opt_str("f") {
+        Some(x) => x,
+        None => {
+            println!("Must provide a -f filename");
+            process::exit(1);
+        },
+    };
+
+    let path = Path::new(&filename);
+    let display = path.display();
+
+    let mut file = match File::open(path) {
+        Err(why) => panic!(
+            "couldn't open {}: {}", display, why
+        ),
+        Ok(file) => file,
+    };
+
+    let mut source = String::new();
+
+    if let Err(why) = file.read_to_string(&mut source) {
+        panic!("couldn't read {}: {}", display, why);
+    }
+
+    let color = matches.opt_present("color");
+    let mut sources = SourceMap::new();
+    let file_id = sources.add(&filename, &source);
+
+    let tokens = match tokenize(&source, file_id).into_result() {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            for err in errors {
+                eprint!("{}", sources.render(&err.to_diagnostic(), color));
+            }
+            process::exit(1);
+        }
+    };
+
+    let node = match parse(&tokens).into_result() {
+        Ok(node) => node,
+        Err(errors) => {
+            for diag in errors {
+                eprint!("{}", sources.render(&diag, color));
+            }
+            process::exit(1);
+        }
+    };
+
+    if let Err(errors) = typecheck(&node) {
+        for err in errors {
+            eprint!("{}", sources.render(&err.to_diagnostic(), color));
+        }
+        process::exit(1);
+    }
+
+    let target = matches.opt_str("t").unwrap_or("x86-64".to_owned());
+
+    let asm = match target.as_str() {
+        "x86-64" => generate_x86_64(&node),
+        "aarch64" => generate_aarch64(&node),
+        "riscv64" => generate_riscv64(&node),
+        "wasm32" => generate_wat(&node),
+        _ => {
+            eprintln!("error: unknown target '{}'", target);
+            process::exit(1);
+        }
+    };
+
+    match asm {
+        Ok(asm) => print!("{}", asm),
+        Err(err) => {
+            eprint!("{}", sources.render(&err.to_diagnostic(), color));
+            process::exit(1);
+        }
+    }
+}
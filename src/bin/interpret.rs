@@ -0,0 +1,201 @@
+extern crate ir;
+
+use ir::ir_diagnostic::*;
+use ir::ir_lexer::*;
+use ir::ir_parser::*;
+use ir::ir_typechecker::*;
+use ir::ir_interpreter::*;
+
+use std::env;
+
+extern crate getopts;
+use getopts::Options;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::process;
+use std::thread;
+
+/// Each call the program makes nests a few calls in the interpreter, so it
+/// gets a stack big enough to reach `MAX_CALL_DEPTH`.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let runner = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || run(&args))
+        .unwrap();
+
+    match runner.join() {
+        Ok(status) => process::exit(status),
+        Err(_) => process::exit(1),
+    }
+}
+
+/// Run the program named in `args`, giving the status to exit with: 0 if it
+/// ran to completion, and 1 if it could not be run or failed as it ran.
+fn run(args: &[String]) -> i32 {
+    let mut opts = Options::new();
+    opts.reqopt("f", "file", "Input file to run", "FILE");
+    opts.optflag("", "color", "Color diagnostics with ANSI escapes");
+    opts.optopt("e", "entry", "Function to run (default: main)", "NAME");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => { m }
+        Err(f) => { panic!("{}", f) }
+    };
+
+    let filename = match matches.opt_str("f") {
+        Some(x) => x,
+        None => {
+            println!("Must provide a -f filename");
+            return 1;
+        },
+    };
+
+    let path = Path::new(&filename);
+    let display = path.display();
+
+    let mut file = match File::open(path) {
+        Err(why) => panic!(
+            "couldn't open {}: {}", display, why
+        ),
+        Ok(file) => file,
+    };
+
+    let mut source = String::new();
+
+    if let Err(why) = file.read_to_string(&mut source) {
+        panic!("couldn't read {}: {}", display, why);
+    }
+
+    let color = matches.opt_present("color");
+    let mut sources = SourceMap::new();
+    let file_id = sources.add(&filename, &source);
+
+    let tokens = match tokenize(&source, file_id).into_result() {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            for err in errors {
+                eprint!("{}", sources.render(&err.to_diagnostic(), color));
+            }
+            return 1;
+        }
+    };
+
+    let node = match parse(&tokens).into_result() {
+        Ok(node) => node,
+        Err(errors) => {
+            for diag in errors {
+                eprint!("{}", sources.render(&diag, color));
+            }
+            return 1;
+        }
+    };
+
+    if let Err(errors) = typecheck(&node) {
+        for err in errors {
+            eprint!("{}", sources.render(&err.to_diagnostic(), color));
+        }
+        return 1;
+    }
+
+    let mut interp = match Interpreter::new(&node) {
+        Ok(interp) => interp,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return 1;
+        }
+    };
+
+    let entry = matches.opt_str("e").unwrap_or("main".to_owned());
+
+    let sig = match interp.signature(&entry) {
+        Some(sig) => sig,
+        None => {
+            eprintln!("error: no function @{} to run", entry);
+            return 1;
+        }
+    };
+
+    // Any arguments left on the command line are passed to the entry
+    // function, read according to the types of its parameters.
+    if sig.arglist.len() != matches.free.len() {
+        eprintln!(
+            "error: @{} takes {} arguments but was given {}",
+            entry, sig.arglist.len(), matches.free.len()
+        );
+        return 1;
+    }
+
+    let mut args = Vec::new();
+
+    for (param, text) in sig.arglist.iter().zip(&matches.free) {
+        match parse_value(text, &param.typename) {
+            Some(val) => args.push(val),
+            None => {
+                eprintln!(
+                    "error: cannot pass '{}' as {}", text, param.typename
+                );
+                return 1;
+            }
+        }
+    }
+
+    match interp.call(&entry, args) {
+        Ok(val) => println!("{}", val),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return 1;
+        }
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    /// The status `interpret` exits with when run on `source`.
+    fn status(name: &str, source: &str) -> i32 {
+        let path = env::temp_dir()
+            .join(format!("ir-interpret-{}-{}.ir", process::id(), name));
+
+        fs::write(&path, source).unwrap();
+
+        let args = vec!(
+            "interpret".to_owned(),
+            "-f".to_owned(),
+            path.to_string_lossy().into_owned(),
+        );
+        let status = run(&args);
+
+        let _ = fs::remove_file(&path);
+
+        status
+    }
+
+    #[test]
+    fn diagnostics_exit_non_zero() {
+        let ok = "func @main:i32 () {\n    ret 0\n}\n";
+
+        assert_eq!(status("ok", ok), 0);
+
+        let bad = [
+            ("lex", "func @main:i32 () {\n    ret $\n}\n"),
+            ("parse", "func @main:i32 (\n"),
+            ("type", "func @main:i32 () {\n    ret 1.5\n}\n"),
+            ("run", "func @main:i32 () {\n    sdiv %x:i32 1 0\n    ret %x\n}"),
+        ];
+
+        for &(name, source) in &bad {
+            assert_eq!(status(name, source), 1, "{}", name);
+        }
+    }
+}
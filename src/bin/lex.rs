@@ -2,24 +2,24 @@ extern crate getopts;
 use getopts::Options;
 
 use std::env;
-use std::error::Error;
 use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use std::io::Read;
 use std::path::Path;
 
 extern crate ir;
 
+use ir::ir_diagnostic::*;
 use ir::ir_lexer::*;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let mut opts = Options::new();
     opts.reqopt("f", "file", "Input file to parse", "FILE");
+    opts.optflag("", "color", "Color diagnostics with ANSI escapes");
 
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => { m }
-        Err(f) => { panic!(f.to_string()) }
+        Err(f) => { panic!("{}", f) }
     };
 
     let filename = match matches.opt_str("f") {
@@ -33,25 +33,32 @@ fn main() {
     let path = Path::new(&filename);
     let display = path.display();
 
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!(
-            "couldn't open {}: {}", display, why.description()
+            "couldn't open {}: {}", display, why
         ),
-        Ok(file) => BufReader::new(file),
+        Ok(file) => file,
     };
 
-    let mut s = String::new();
-    let mut tokens = Vec::new();
+    let mut source = String::new();
 
-    let mut row = 0;
-
-    while file.read_line(&mut s).unwrap() > 0 {
-        tokens.extend(tokenize_line(&s, row));
+    if let Err(why) = file.read_to_string(&mut source) {
+        panic!("couldn't read {}: {}", display, why);
+    }
 
-        row += 1;
+    let color = matches.opt_present("color");
+    let mut sources = SourceMap::new();
+    let file_id = sources.add(&filename, &source);
 
-        s.clear();
-    }
+    let tokens = match tokenize(&source, file_id).into_result() {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            for err in errors {
+                eprint!("{}", sources.render(&err.to_diagnostic(), color));
+            }
+            return;
+        }
+    };
 
     print_tokens(&tokens);
 }
@@ -1,5 +1,6 @@
 extern crate ir;
 
+use ir::ir_diagnostic::*;
 use ir::ir_lexer::*;
 use ir::ir_parser::*;
 use ir::ir_typechecker::*;
@@ -9,10 +10,8 @@ use std::env;
 extern crate getopts;
 use getopts::Options;
 
-use std::error::Error;
 use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use std::io::Read;
 use std::path::Path;
 
 
@@ -20,10 +19,11 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     let mut opts = Options::new();
     opts.reqopt("f", "file", "Input file to parse", "FILE");
+    opts.optflag("", "color", "Color diagnostics with ANSI escapes");
 
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => { m }
-        Err(f) => { panic!(f.to_string()) }
+        Err(f) => { panic!("{}", f) }
     };
 
     let filename = match matches.opt_str("f") {
@@ -37,28 +37,50 @@ fn main() {
     let path = Path::new(&filename);
     let display = path.display();
 
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!(
-            "couldn't open {}: {}", display, why.description()
+            "couldn't open {}: {}", display, why
         ),
-        Ok(file) => BufReader::new(file),
+        Ok(file) => file,
     };
 
-    let mut s = String::new();
-    let mut tokens = Vec::new();
+    let mut source = String::new();
 
-    let mut row = 0;
+    if let Err(why) = file.read_to_string(&mut source) {
+        panic!("couldn't read {}: {}", display, why);
+    }
 
-    while file.read_line(&mut s).unwrap() > 0 {
-        tokens.extend(tokenize_line(&s, row));
+    let color = matches.opt_present("color");
+    let mut sources = SourceMap::new();
+    let file_id = sources.add(&filename, &source);
 
-        row += 1;
+    let tokens = match tokenize(&source, file_id).into_result() {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            for err in errors {
+                eprint!("{}", sources.render(&err.to_diagnostic(), color));
+            }
+            return;
+        }
+    };
 
-        s.clear();
-    }
+    let node = match parse(&tokens).into_result() {
+        Ok(node) => node,
+        Err(errors) => {
+            for diag in errors {
+                eprint!("{}", sources.render(&diag, color));
+            }
+            return;
+        }
+    };
 
-    if let Some(node) = parse(&tokens) {
-        println!("{}", typecheck(&node));
+    match typecheck(&node) {
+        Ok(()) => println!("true"),
+        Err(errors) => {
+            for err in errors {
+                eprint!("{}", sources.render(&err.to_diagnostic(), color));
+            }
+            println!("false");
+        }
     }
-
 }
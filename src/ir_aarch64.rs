@@ -0,0 +1,1207 @@
+use ir_codegen::*;
+use ir_lexer::*;
+use ir_parser::*;
+use ir_type_layout::*;
+use ir_typechecker::*;
+
+use std::fmt;
+
+/// Integer arguments, in the order AAPCS64 assigns them.
+const INT_ARG_REGS: [&'static str; 8] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7",
+];
+
+/// Float arguments, in the order AAPCS64 assigns them.
+const FLOAT_ARG_REGS: [&'static str; 8] = [
+    "d0", "d1", "d2", "d3", "d4", "d5", "d6", "d7",
+];
+
+/// Values live in the callee-saved registers first, so that calls rarely
+/// need to save anything. Argument registers are never used, and `x14` to
+/// `x17` along with `d29` to `d31` are left free as scratch. `x18` is
+/// reserved by some platforms and is never touched.
+static REGISTERS: RegisterFile = RegisterFile {
+    int_regs: &[
+        "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27", "x28",
+        "x9", "x10", "x11", "x12", "x13",
+    ],
+    float_regs: &[
+        "d8", "d9", "d10", "d11", "d12", "d13", "d14", "d15",
+        "d16", "d17", "d18", "d19", "d20", "d21", "d22", "d23",
+    ],
+    callee_saved: &[
+        "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27", "x28",
+        "d8", "d9", "d10", "d11", "d12", "d13", "d14", "d15",
+    ],
+};
+
+/// Scratch registers that break cycles in parallel moves. Nothing else is
+/// kept in them while a parallel move runs.
+fn cycle_temp(class: RegClass) -> Loc {
+    return match class {
+        RegClass::Int => Loc::Reg ("x14"),
+        RegClass::Float => Loc::Reg ("d29"),
+    };
+}
+
+fn reg_class_of(reg: &str) -> RegClass {
+    return if reg.starts_with("d") {
+        RegClass::Float
+    }
+    else {
+        RegClass::Int
+    };
+}
+
+/// The 32-bit view of the 64-bit register `reg`.
+fn w(reg: &str) -> String {
+    return format!("w{}", &reg[1..]);
+}
+
+/// The view of the float register `reg` that holds a value of type `t`.
+fn fp(reg: &str, t: &Type) -> String {
+    return match t {
+        &Type::F32 => format!("s{}", &reg[1..]),
+        _ => reg.to_owned(),
+    };
+}
+
+/// A memory operand: a base register plus a displacement.
+#[derive(Clone, Copy)]
+struct Addr {
+    base: &'static str,
+    disp: i64,
+}
+
+impl Addr {
+    fn offset(&self, by: i64) -> Addr {
+        return Addr {base: self.base, disp: self.disp + by};
+    }
+}
+
+impl fmt::Display for Addr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.disp == 0 {
+            write!(f, "[{}]", self.base)
+        }
+        else {
+            write!(f, "[{}, #{}]", self.base, self.disp)
+        }
+    }
+}
+
+struct Emitter<'e> {
+    sig: &'e FuncSig,
+    blocks: &'e Vec<BasicBlock>,
+    frame: &'e FrameAlloc<'e>,
+    structs: &'e StructTable,
+    sigs: &'e SigTable<'e>,
+    /// Size of the area at the bottom of the frame where arguments are put
+    /// for calls that pass some on the stack.
+    out_size: u64,
+    out: String,
+    /// Blocks that carry the phi moves of an edge out of a block with more
+    /// than one successor: their label, their moves and where they go.
+    trampolines: Vec<(String, Vec<Move>, Vec<ConstMove<'e>>, String)>,
+}
+
+impl<'e> Emitter<'e> {
+    fn ins<S: AsRef<str>>(&mut self, text: S) {
+        self.out.push_str("    ");
+        self.out.push_str(text.as_ref());
+        self.out.push('\n');
+    }
+
+    fn label(&mut self, label: &str) {
+        self.out.push_str(label);
+        self.out.push_str(":\n");
+    }
+
+    fn block_label(&self, label: &str) -> String {
+        return format!(".L{}.{}", self.sig.name, label);
+    }
+
+    /// Where the stack slot at `offset` into the local area is.
+    fn slot(&self, offset: u64) -> Addr {
+        return Addr {base: "sp", disp: (self.out_size + offset) as i64};
+    }
+
+    /// Where callee-saved register `i` is kept while the function runs.
+    fn save_slot(&self, i: usize) -> Addr {
+        let base = self.out_size + self.frame.local_size;
+
+        return Addr {base: "sp", disp: (base + 8 * i as u64) as i64};
+    }
+
+    /// The operand for an access of `size` bytes at `addr`. Offsets that
+    /// cannot be encoded in the instruction are added up in x15 first.
+    fn mem(&mut self, addr: Addr, size: u64) -> String {
+        let size = size as i64;
+        let scaled = addr.disp >= 0 && addr.disp % size == 0
+            && addr.disp / size < 4096;
+
+        if scaled || (addr.disp >= -256 && addr.disp < 256) {
+            return format!("{}", addr);
+        }
+
+        self.mov_imm(addr.disp as u64, "x15");
+        self.ins(format!("add x15, {}, x15", addr.base));
+
+        return "[x15]".to_owned();
+    }
+
+    fn home(&self, name: &str) -> Option<Loc> {
+        return self.frame.homes.get(name).cloned();
+    }
+
+    fn var_type(&self, var: &Variable) -> &'e Type {
+        return self.frame.types[var.name.as_str()];
+    }
+
+    fn mov(&mut self, class: RegClass, dst: Loc, src: Loc) {
+        match (class, dst, src) {
+            _ if dst == src => {}
+            (_, Loc::Stack (d), Loc::Stack (s)) => {
+                let from = self.mem(self.slot(s), 8);
+                self.ins(format!("ldr x16, {}", from));
+                let to = self.mem(self.slot(d), 8);
+                self.ins(format!("str x16, {}", to));
+            }
+            (RegClass::Int, Loc::Reg (d), Loc::Reg (s)) => {
+                self.ins(format!("mov {}, {}", d, s));
+            }
+            (RegClass::Float, Loc::Reg (d), Loc::Reg (s)) => {
+                self.ins(format!("fmov {}, {}", d, s));
+            }
+            (_, Loc::Reg (d), Loc::Stack (s)) => {
+                let from = self.mem(self.slot(s), 8);
+                self.ins(format!("ldr {}, {}", d, from));
+            }
+            (_, Loc::Stack (d), Loc::Reg (s)) => {
+                let to = self.mem(self.slot(d), 8);
+                self.ins(format!("str {}, {}", s, to));
+            }
+        }
+    }
+
+    /// Store the scalar in `reg` to the home of `name`, if it has one.
+    fn put(&mut self, name: &str, reg: &'static str) {
+        if let Some (home) = self.home(name) {
+            self.mov(reg_class_of(reg), home, Loc::Reg (reg));
+        }
+    }
+
+    /// Materialize `bits` in `reg`, 16 bits at a time when it is too big
+    /// for a single move.
+    fn mov_imm(&mut self, bits: u64, reg: &str) {
+        let val = bits as i64;
+
+        if val >= -65536 && val <= 65535 {
+            self.ins(format!("mov {}, #{}", reg, val));
+            return;
+        }
+
+        let mut first = true;
+
+        for shift in (0..4).map(|i| i * 16) {
+            let chunk = (bits >> shift) & 0xffff;
+
+            if chunk == 0 {
+                continue;
+            }
+
+            let op = if first { "movz" } else { "movk" };
+            self.ins(format!("{} {}, #{}, lsl #{}", op, reg, chunk, shift));
+            first = false;
+        }
+    }
+
+    fn add_imm(&mut self, reg: &str, val: i64) {
+        if val == 0 {
+            return;
+        }
+
+        if val > 0 && val < 4096 {
+            self.ins(format!("add {}, {}, #{}", reg, reg, val));
+        }
+        else if val < 0 && val > -4096 {
+            self.ins(format!("sub {}, {}, #{}", reg, reg, -val));
+        }
+        else {
+            self.mov_imm(val as u64, "x14");
+            self.ins(format!("add {}, {}, x14", reg, reg));
+        }
+    }
+
+    /// Load an integer or pointer operand of type `t` into `reg`.
+    fn load_int(&mut self, op: &Operand, t: &Type, reg: &'static str) {
+        if let Some (var) = op.var() {
+            if let Some (home) = self.home(&var.name) {
+                self.mov(RegClass::Int, Loc::Reg (reg), home);
+            }
+        }
+        else if let Some (bits) = imm_bits(op, t) {
+            self.mov_imm(bits, reg);
+        }
+    }
+
+    /// Load a float operand of type `t` into the float register `reg`.
+    fn load_float(&mut self, op: &Operand, t: &Type, reg: &'static str) {
+        if let Some (var) = op.var() {
+            if let Some (home) = self.home(&var.name) {
+                self.mov(RegClass::Float, Loc::Reg (reg), home);
+            }
+        }
+        else if let Some (bits) = imm_bits(op, t) {
+            self.mov_imm(bits, "x15");
+            self.ins(format!("fmov {}, x15", reg));
+        }
+    }
+
+    /// Bring the integer in `reg` back to canonical form for `t` after an
+    /// operation that may have disturbed the bits above its width.
+    fn normalize(&mut self, reg: &str, t: &Type) {
+        match t {
+            &Type::I1 => self.ins(format!("and {}, {}, #1", reg, reg)),
+            &Type::I8 => self.ins(format!("sxtb {}, {}", reg, w(reg))),
+            &Type::I16 => self.ins(format!("sxth {}, {}", reg, w(reg))),
+            &Type::I32 => self.ins(format!("sxtw {}, {}", reg, w(reg))),
+            _ => {}
+        }
+    }
+
+    /// Turn the canonical integer in `reg` into its value read as unsigned.
+    fn zero_extend(&mut self, reg: &str, t: &Type) {
+        match t {
+            &Type::I1 => self.ins(format!("and {}, {}, #1", reg, reg)),
+            &Type::I8 => self.ins(format!("and {}, {}, #0xff", reg, reg)),
+            &Type::I16 => self.ins(format!("and {}, {}, #0xffff", reg, reg)),
+            &Type::I32 => self.ins(format!("mov {}, {}", w(reg), w(reg))),
+            _ => {}
+        }
+    }
+
+    /// Turn the canonical integer in `reg` into its value read as signed.
+    /// Only an `i1` is not already kept that way.
+    fn sign_extend(&mut self, reg: &str, t: &Type) {
+        if let &Type::I1 = t {
+            self.ins(format!("sbfx {}, {}, #0, #1", reg, reg));
+        }
+    }
+
+    fn load_ptr(&mut self, ptr: Option<&Variable>) {
+        if let Some (ptr) = ptr {
+            let t = self.var_type(ptr);
+            self.load_int(&Operand::OpVariable (ptr.clone()), t, "x17");
+        }
+    }
+
+    fn copy_mem(&mut self, dst: Addr, src: Addr, size: u64) {
+        let mut done = 0;
+
+        for &(width, reg, suffix) in &[
+            (8, "x16", ""), (4, "w16", ""), (2, "w16", "h"), (1, "w16", "b")
+        ] {
+            while size - done >= width {
+                let at = done as i64;
+                let from = self.mem(src.offset(at), width);
+                self.ins(format!("ldr{} {}, {}", suffix, reg, from));
+                let to = self.mem(dst.offset(at), width);
+                self.ins(format!("str{} {}, {}", suffix, reg, to));
+                done += width;
+            }
+        }
+    }
+
+    fn size_of(&self, t: &Type, tl: &TokLoc) -> Result<u64, CodegenError> {
+        return layout_of(t, self.structs)
+            .map(|layout| layout.size)
+            .map_err(|err| CodegenError::BadType (err, tl.clone()));
+    }
+
+    /// The slot holding the aggregate `name`.
+    fn aggregate_addr(&self, name: &str) -> Addr {
+        return match self.home(name) {
+            Some (Loc::Stack (offset)) => self.slot(offset),
+            _ => Addr {base: "sp", disp: 0},
+        };
+    }
+
+    /// Load a value of type `t` from `addr` into the home of `dest`.
+    fn load_mem(&mut self, t: &Type, addr: Addr, dest: &str, tl: &TokLoc)
+        -> Result<(), CodegenError>
+    {
+        let (load, size) = match t {
+            &Type::I1 => ("ldrb w16", 1),
+            &Type::I8 => ("ldrsb x16", 1),
+            &Type::I16 => ("ldrsh x16", 2),
+            &Type::I32 => ("ldrsw x16", 4),
+            &Type::I64 | &Type::Ptr (_) => ("ldr x16", 8),
+            &Type::F32 => ("ldr s30", 4),
+            &Type::F64 => ("ldr d30", 8),
+            _ => {
+                let size = self.size_of(t, tl)?;
+                let dst = self.aggregate_addr(dest);
+                self.copy_mem(dst, addr, size);
+                return Ok (());
+            }
+        };
+
+        let from = self.mem(addr, size);
+        self.ins(format!("{}, {}", load, from));
+
+        match reg_class(t) {
+            Some (RegClass::Float) => self.put(dest, "d30"),
+            _ => self.put(dest, "x16"),
+        }
+
+        return Ok (());
+    }
+
+    /// Store `value`, of type `t`, to `addr`. When `ptr` is given, it is
+    /// loaded into x17 once the value is in hand, for `addr` to be based on.
+    fn store_mem(
+        &mut self, t: &Type, value: &Operand, ptr: Option<&Variable>,
+        addr: Addr, tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        let (store, size) = match t {
+            &Type::I1 | &Type::I8 => ("strb w16", 1),
+            &Type::I16 => ("strh w16", 2),
+            &Type::I32 => ("str w16", 4),
+            &Type::I64 | &Type::Ptr (_) => ("str x16", 8),
+            &Type::F32 => ("str s30", 4),
+            &Type::F64 => ("str d30", 8),
+            _ => {
+                let size = self.size_of(t, tl)?;
+                let src = match value.var() {
+                    Some (var) => self.aggregate_addr(&var.name),
+                    None => return Ok (()),
+                };
+
+                self.load_ptr(ptr);
+                self.copy_mem(addr, src, size);
+                return Ok (());
+            }
+        };
+
+        match reg_class(t) {
+            Some (RegClass::Float) => self.load_float(value, t, "d30"),
+            _ => self.load_int(value, t, "x16"),
+        }
+
+        self.load_ptr(ptr);
+
+        let to = self.mem(addr, size);
+        self.ins(format!("{}, {}", store, to));
+
+        return Ok (());
+    }
+
+    /// Where control should go to take the edge from `pred` to the block
+    /// labeled `target`: the block itself, or a trampoline that first sets
+    /// the block's phis.
+    fn edge(&mut self, pred: &BasicBlock, target: &str)
+        -> Result<String, CodegenError>
+    {
+        let succ = self.blocks.iter().find(|b| b.label == target);
+
+        return match succ {
+            Some (succ) if has_phis(succ) => {
+                let label = format!(
+                    ".L{}.{}.{}", self.sig.name, pred.label, target
+                );
+
+                if !self.trampolines.iter().any(|t| t.0 == label) {
+                    let (moves, consts) =
+                        phi_moves(self.frame, succ, &pred.label)?;
+                    let to = self.block_label(target);
+                    self.trampolines.push((label.clone(), moves, consts, to));
+                }
+
+                Ok (label)
+            }
+            _ => Ok (self.block_label(target)),
+        };
+    }
+
+    fn parallel_move(&mut self, moves: Vec<Move>, consts: Vec<ConstMove>) {
+        for mv in sequence_moves(moves, cycle_temp) {
+            self.mov(mv.class, mv.dst, mv.src);
+        }
+
+        for mv in consts {
+            let reg = match (mv.dst, cycle_temp(mv.class)) {
+                (Loc::Reg (reg), _) | (_, Loc::Reg (reg)) => reg,
+                _ => continue,
+            };
+
+            match mv.class {
+                RegClass::Int => self.load_int(mv.imm, mv.t, reg),
+                RegClass::Float => self.load_float(mv.imm, mv.t, reg),
+            }
+
+            self.mov(mv.class, mv.dst, Loc::Reg (reg));
+        }
+    }
+
+    fn prologue(&mut self) -> Result<(), CodegenError> {
+        let save_size = (8 * self.frame.callee_saved.len() as u64 + 15)
+            / 16 * 16;
+        let frame_size = self.out_size + self.frame.local_size + save_size;
+
+        self.ins("stp x29, x30, [sp, #-16]!");
+        self.ins("mov x29, sp");
+
+        if frame_size >= 4096 {
+            self.mov_imm(frame_size, "x16");
+            self.ins("sub sp, sp, x16");
+        }
+        else if frame_size > 0 {
+            self.ins(format!("sub sp, sp, #{}", frame_size));
+        }
+
+        for (i, &reg) in self.frame.callee_saved.iter().enumerate() {
+            let to = self.mem(self.save_slot(i), 8);
+            self.ins(format!("str {}, {}", reg, to));
+        }
+
+        let (mut ints, mut floats, mut stacked) = (0, 0, 0);
+
+        for arg in &self.sig.arglist {
+            let t = &arg.typename;
+
+            match reg_class(t) {
+                Some (RegClass::Int) if ints < INT_ARG_REGS.len() => {
+                    let reg = INT_ARG_REGS[ints];
+                    self.ins(format!("mov x16, {}", reg));
+                    self.normalize("x16", t);
+                    self.put(&arg.name, "x16");
+                    ints += 1;
+                }
+                Some (RegClass::Float) if floats < FLOAT_ARG_REGS.len() => {
+                    self.put(&arg.name, FLOAT_ARG_REGS[floats]);
+                    floats += 1;
+                }
+                Some (class) => {
+                    let at = Addr {base: "x29", disp: 16 + 8 * stacked};
+                    let from = self.mem(at, 8);
+
+                    if class == RegClass::Int {
+                        self.ins(format!("ldr x16, {}", from));
+                        self.normalize("x16", t);
+                        self.put(&arg.name, "x16");
+                    }
+                    else {
+                        self.ins(format!("ldr d30, {}", from));
+                        self.put(&arg.name, "d30");
+                    }
+
+                    stacked += 1;
+                }
+                None => {
+                    return Err (CodegenError::Unsupported (
+                        "passing an aggregate by value".to_owned(),
+                        self.sig.loc.clone()
+                    ));
+                }
+            }
+        }
+
+        return Ok (());
+    }
+
+    fn epilogue(&mut self) {
+        for (i, &reg) in self.frame.callee_saved.iter().enumerate() {
+            let from = self.mem(self.save_slot(i), 8);
+            self.ins(format!("ldr {}, {}", reg, from));
+        }
+
+        self.ins("mov sp, x29");
+        self.ins("ldp x29, x30, [sp], #16");
+        self.ins("ret");
+    }
+
+    fn call(
+        &mut self, block: usize, index: usize, stmt: &Stmt, dest: &VarTypePair,
+        callee: &Function, args: &Vec<Operand>, tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        let saved = self.frame.live_across_call(block, index, stmt);
+
+        for &reg in &saved {
+            let slot = Loc::Stack (self.frame.save_slots[reg]);
+            self.mov(reg_class_of(reg), slot, Loc::Reg (reg));
+        }
+
+        let (mut ints, mut floats, mut stacked) = (0, 0, 0);
+        let types = arg_types(&self.frame.types, self.sigs, &callee.name, args);
+
+        for (arg, t) in args.iter().zip(types) {
+            if let Some (var) = arg.var() {
+                if self.home(&var.name).is_none() {
+                    continue;
+                }
+            }
+
+            match reg_class(t) {
+                Some (RegClass::Int) if ints < INT_ARG_REGS.len() => {
+                    self.load_int(arg, t, INT_ARG_REGS[ints]);
+                    ints += 1;
+                }
+                Some (RegClass::Float) if floats < FLOAT_ARG_REGS.len() => {
+                    self.load_float(arg, t, FLOAT_ARG_REGS[floats]);
+                    floats += 1;
+                }
+                Some (class) => {
+                    let scratch = match class {
+                        RegClass::Int => "x16",
+                        RegClass::Float => "d30",
+                    };
+
+                    match class {
+                        RegClass::Int => self.load_int(arg, t, scratch),
+                        RegClass::Float => self.load_float(arg, t, scratch),
+                    }
+
+                    let to = self.mem(Addr {base: "sp", disp: 8 * stacked}, 8);
+                    self.ins(format!("str {}, {}", scratch, to));
+                    stacked += 1;
+                }
+                None => {
+                    return Err (CodegenError::Unsupported (
+                        "passing an aggregate by value".to_owned(), tl.clone()
+                    ));
+                }
+            }
+        }
+
+        self.ins(format!("bl {}", callee.name));
+
+        match reg_class(&dest.typename) {
+            Some (RegClass::Int) => {
+                self.ins("mov x16, x0");
+                self.normalize("x16", &dest.typename);
+                self.put(&dest.name, "x16");
+            }
+            Some (RegClass::Float) => self.put(&dest.name, "d0"),
+            None if is_void(&dest.typename) => {}
+            None => {
+                return Err (CodegenError::Unsupported (
+                    "returning an aggregate".to_owned(), tl.clone()
+                ));
+            }
+        }
+
+        for &reg in &saved {
+            let slot = Loc::Stack (self.frame.save_slots[reg]);
+            self.mov(reg_class_of(reg), Loc::Reg (reg), slot);
+        }
+
+        return Ok (());
+    }
+
+    fn int_arith(&mut self, stmt: &Stmt, t: &Type) {
+        match stmt {
+            &Stmt::AddInst (..) => self.ins("add x16, x16, x17"),
+            &Stmt::SubInst (..) => self.ins("sub x16, x16, x17"),
+            &Stmt::MulInst (..) => self.ins("mul x16, x16, x17"),
+            &Stmt::SDivInst (..) | &Stmt::SRemInst (..) => {
+                self.sign_extend("x16", t);
+                self.sign_extend("x17", t);
+                self.ins("sdiv x14, x16, x17");
+            }
+            &Stmt::UDivInst (..) | &Stmt::URemInst (..) => {
+                self.zero_extend("x16", t);
+                self.zero_extend("x17", t);
+                self.ins("udiv x14, x16, x17");
+            }
+            &Stmt::AndInst (..) => self.ins("and x16, x16, x17"),
+            &Stmt::OrInst (..) => self.ins("orr x16, x16, x17"),
+            &Stmt::XorInst (..) => self.ins("eor x16, x16, x17"),
+            &Stmt::ShlInst (..) => self.ins("lsl x16, x16, x17"),
+            &Stmt::LShrInst (..) => {
+                self.zero_extend("x16", t);
+                self.ins("lsr x16, x16, x17");
+            }
+            &Stmt::AShrInst (..) => {
+                self.sign_extend("x16", t);
+                self.ins("asr x16, x16, x17");
+            }
+            _ => {}
+        }
+
+        // The remainder is what is left of the dividend once the quotient's
+        // worth of divisors is taken away.
+        match stmt {
+            &Stmt::SDivInst (..) | &Stmt::UDivInst (..) => {
+                self.ins("mov x16, x14");
+            }
+            &Stmt::SRemInst (..) | &Stmt::URemInst (..) => {
+                self.ins("msub x16, x14, x17, x16");
+            }
+            _ => {}
+        }
+
+        self.normalize("x16", t);
+    }
+
+    fn fcmp(&mut self, pred: FCmpPred, t: &Type) {
+        // After fcmp an unordered result sets C and V, so each predicate is
+        // one condition, or two for the ones that treat equality and order
+        // differently from unordered.
+        let (cond, or_cond) = match pred {
+            FCmpPred::Oeq => ("eq", None),
+            FCmpPred::Ogt => ("gt", None),
+            FCmpPred::Oge => ("ge", None),
+            FCmpPred::Olt => ("mi", None),
+            FCmpPred::Ole => ("ls", None),
+            FCmpPred::One => ("mi", Some ("gt")),
+            FCmpPred::Ord => ("vc", None),
+            FCmpPred::Ueq => ("eq", Some ("vs")),
+            FCmpPred::Ugt => ("hi", None),
+            FCmpPred::Uge => ("pl", None),
+            FCmpPred::Ult => ("lt", None),
+            FCmpPred::Ule => ("le", None),
+            FCmpPred::Une => ("ne", None),
+            FCmpPred::Uno => ("vs", None),
+        };
+
+        self.ins(format!("fcmp {}, {}", fp("d30", t), fp("d31", t)));
+        self.ins(format!("cset w16, {}", cond));
+
+        if let Some (or_cond) = or_cond {
+            self.ins(format!("cset w17, {}", or_cond));
+            self.ins("orr w16, w16, w17");
+        }
+    }
+
+    fn cast(&mut self, op: CastOp, src: &Variable, dest: &VarTypePair) {
+        let from = self.var_type(src);
+        let to = &dest.typename;
+        let src_op = Operand::OpVariable (src.clone());
+
+        match op {
+            CastOp::Trunc | CastOp::PtrToInt => {
+                self.load_int(&src_op, from, "x16");
+                self.normalize("x16", to);
+                self.put(&dest.name, "x16");
+            }
+            CastOp::ZExt | CastOp::IntToPtr => {
+                self.load_int(&src_op, from, "x16");
+                self.zero_extend("x16", from);
+                self.put(&dest.name, "x16");
+            }
+            CastOp::SExt => {
+                self.load_int(&src_op, from, "x16");
+                self.sign_extend("x16", from);
+                self.put(&dest.name, "x16");
+            }
+            CastOp::FPToSI | CastOp::FPToUI => {
+                let convert = match op {
+                    CastOp::FPToSI => "fcvtzs",
+                    _ => "fcvtzu",
+                };
+
+                self.load_float(&src_op, from, "d30");
+                self.ins(format!("{} x16, {}", convert, fp("d30", from)));
+                self.normalize("x16", to);
+                self.put(&dest.name, "x16");
+            }
+            CastOp::SIToFP | CastOp::UIToFP => {
+                self.load_int(&src_op, from, "x16");
+
+                let convert = match op {
+                    CastOp::SIToFP => {
+                        self.sign_extend("x16", from);
+                        "scvtf"
+                    }
+                    _ => {
+                        self.zero_extend("x16", from);
+                        "ucvtf"
+                    }
+                };
+
+                self.ins(format!("{} {}, x16", convert, fp("d30", to)));
+                self.put(&dest.name, "d30");
+            }
+            CastOp::FPExt => {
+                self.load_float(&src_op, from, "d30");
+                self.ins("fcvt d30, s30");
+                self.put(&dest.name, "d30");
+            }
+            CastOp::FPTrunc => {
+                self.load_float(&src_op, from, "d30");
+                self.ins("fcvt s30, d30");
+                self.put(&dest.name, "d30");
+            }
+            CastOp::Bitcast => {
+                match (reg_class(from), reg_class(to)) {
+                    (Some (RegClass::Float), Some (RegClass::Int)) => {
+                        let int = match from {
+                            &Type::F32 => "w16",
+                            _ => "x16",
+                        };
+
+                        self.load_float(&src_op, from, "d30");
+                        self.ins(format!("fmov {}, {}", int, fp("d30", from)));
+                        self.normalize("x16", to);
+                        self.put(&dest.name, "x16");
+                    }
+                    (Some (RegClass::Int), Some (RegClass::Float)) => {
+                        let int = match to {
+                            &Type::F32 => "w16",
+                            _ => "x16",
+                        };
+
+                        self.load_int(&src_op, from, "x16");
+                        self.ins(format!("fmov {}, {}", fp("d30", to), int));
+                        self.put(&dest.name, "d30");
+                    }
+                    (Some (RegClass::Float), _) => {
+                        self.load_float(&src_op, from, "d30");
+                        self.put(&dest.name, "d30");
+                    }
+                    _ => {
+                        self.load_int(&src_op, from, "x16");
+                        self.put(&dest.name, "x16");
+                    }
+                }
+            }
+        }
+    }
+
+    fn gep(
+        &mut self, dest: &VarTypePair, base: &Variable, indices: &Vec<Operand>,
+        tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        let base_type = self.var_type(base);
+        let mut cur = match base_type {
+            &Type::Ptr (ref pointee) => &**pointee,
+            _ => return Ok (()),
+        };
+
+        self.load_int(&Operand::OpVariable (base.clone()), base_type, "x16");
+
+        // The first index steps over whole pointees, and each one after
+        // that selects an element of the current aggregate.
+        for (i, index) in indices.iter().enumerate() {
+            let (size, next) = match cur {
+                _ if i == 0 => (self.size_of(cur, tl)?, cur),
+                &Type::Array (ref elem, _) => {
+                    (self.size_of(elem, tl)?, &**elem)
+                }
+                &Type::UserType (ref name) => {
+                    let field = match index {
+                        &Operand::OpInteger (field, _, _) => field as usize,
+                        _ => return Ok (()),
+                    };
+                    let offsets = field_offsets(name, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+
+                    self.add_imm("x16", offsets[field] as i64);
+                    cur = &self.structs[name][field];
+                    continue;
+                }
+                _ => return Ok (()),
+            };
+
+            match index {
+                &Operand::OpInteger (i, _, _) => {
+                    self.add_imm("x16", (i as i64).wrapping_mul(size as i64));
+                }
+                &Operand::OpVariable (ref var) => {
+                    let t = self.var_type(var);
+                    self.load_int(index, t, "x17");
+                    self.sign_extend("x17", t);
+                    self.mov_imm(size, "x14");
+                    self.ins("madd x16, x17, x14, x16");
+                }
+                &Operand::OpFloat (..) => {}
+            }
+
+            cur = next;
+        }
+
+        self.put(&dest.name, "x16");
+
+        return Ok (());
+    }
+
+    fn stmt(
+        &mut self, block: &BasicBlock, block_index: usize, index: usize,
+        stmt: &Stmt, tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        match stmt {
+            &Stmt::AddInst (ref dest, ref l, ref r) |
+            &Stmt::SubInst (ref dest, ref l, ref r) |
+            &Stmt::MulInst (ref dest, ref l, ref r) |
+            &Stmt::SDivInst (ref dest, ref l, ref r) |
+            &Stmt::UDivInst (ref dest, ref l, ref r) |
+            &Stmt::SRemInst (ref dest, ref l, ref r) |
+            &Stmt::URemInst (ref dest, ref l, ref r) |
+            &Stmt::AndInst (ref dest, ref l, ref r) |
+            &Stmt::OrInst (ref dest, ref l, ref r) |
+            &Stmt::XorInst (ref dest, ref l, ref r) |
+            &Stmt::ShlInst (ref dest, ref l, ref r) |
+            &Stmt::LShrInst (ref dest, ref l, ref r) |
+            &Stmt::AShrInst (ref dest, ref l, ref r) => {
+                let t = &dest.typename;
+
+                if is_float_type(t) {
+                    let op = match stmt {
+                        &Stmt::AddInst (..) => "fadd",
+                        &Stmt::SubInst (..) => "fsub",
+                        _ => "fmul",
+                    };
+                    let (d30, d31) = (fp("d30", t), fp("d31", t));
+
+                    self.load_float(l, t, "d30");
+                    self.load_float(r, t, "d31");
+                    self.ins(format!("{} {}, {}, {}", op, d30, d30, d31));
+                    self.put(&dest.name, "d30");
+                }
+                else {
+                    self.load_int(l, t, "x16");
+                    self.load_int(r, t, "x17");
+                    self.int_arith(stmt, t);
+                    self.put(&dest.name, "x16");
+                }
+            }
+            &Stmt::NegInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                if is_float_type(t) {
+                    let d30 = fp("d30", t);
+
+                    self.load_float(src, t, "d30");
+                    self.ins(format!("fneg {}, {}", d30, d30));
+                    self.put(&dest.name, "d30");
+                }
+                else {
+                    self.load_int(src, t, "x16");
+                    self.ins("neg x16, x16");
+                    self.normalize("x16", t);
+                    self.put(&dest.name, "x16");
+                }
+            }
+            &Stmt::NotInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                self.load_int(src, t, "x16");
+                self.ins("mvn x16, x16");
+                self.normalize("x16", t);
+                self.put(&dest.name, "x16");
+            }
+            &Stmt::ICmpInst (ref dest, pred, ref l, ref r) => {
+                let t = cmp_type(&self.frame.types, l, r, &Type::I64);
+                let (cond, signed) = match pred {
+                    ICmpPred::Eq => ("eq", false),
+                    ICmpPred::Ne => ("ne", false),
+                    ICmpPred::Slt => ("lt", true),
+                    ICmpPred::Sle => ("le", true),
+                    ICmpPred::Sgt => ("gt", true),
+                    ICmpPred::Sge => ("ge", true),
+                    ICmpPred::Ult => ("lo", false),
+                    ICmpPred::Ule => ("ls", false),
+                    ICmpPred::Ugt => ("hi", false),
+                    ICmpPred::Uge => ("hs", false),
+                };
+
+                self.load_int(l, t, "x16");
+                self.load_int(r, t, "x17");
+
+                // Canonical integers already order correctly as unsigned,
+                // but a true i1 is -1 when read as signed.
+                if signed {
+                    self.sign_extend("x16", t);
+                    self.sign_extend("x17", t);
+                }
+
+                self.ins("cmp x16, x17");
+                self.ins(format!("cset x16, {}", cond));
+                self.put(&dest.name, "x16");
+            }
+            &Stmt::FCmpInst (ref dest, pred, ref l, ref r) => {
+                let t = cmp_type(&self.frame.types, l, r, &Type::F64);
+
+                self.load_float(l, t, "d30");
+                self.load_float(r, t, "d31");
+                self.fcmp(pred, t);
+                self.put(&dest.name, "x16");
+            }
+            &Stmt::AllocaInst (ref dest) => {
+                let offset = self.frame.allocas[dest.name.as_str()];
+                let addr = self.slot(offset);
+
+                self.ins("mov x16, sp");
+                self.add_imm("x16", addr.disp);
+                self.put(&dest.name, "x16");
+            }
+            &Stmt::LoadInst (ref dest, ref ptr) => {
+                self.load_ptr(Some (ptr));
+                self.load_mem(
+                    &dest.typename, Addr {base: "x17", disp: 0}, &dest.name, tl
+                )?;
+            }
+            &Stmt::StoreInst (ref value, ref ptr) => {
+                let pointee = match self.var_type(ptr) {
+                    &Type::Ptr (ref pointee) => &**pointee,
+                    _ => return Ok (()),
+                };
+
+                self.store_mem(
+                    pointee, value, Some (ptr), Addr {base: "x17", disp: 0}, tl
+                )?;
+            }
+            &Stmt::GepInst (ref dest, ref base, ref indices) => {
+                self.gep(dest, base, indices, tl)?;
+            }
+            &Stmt::ExtractValueInst (ref dest, ref agg, ref indices) => {
+                let agg_type = self.var_type(agg);
+                let (offset, elem) =
+                    element_offset(agg_type, indices, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+                let addr = self.aggregate_addr(&agg.name).offset(offset as i64);
+
+                self.load_mem(elem, addr, &dest.name, tl)?;
+            }
+            &Stmt::InsertValueInst (
+                ref dest, ref agg, ref value, ref indices
+            ) => {
+                let agg_type = self.var_type(agg);
+                let (offset, elem) =
+                    element_offset(agg_type, indices, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+                let size = self.size_of(agg_type, tl)?;
+                let dst = self.aggregate_addr(&dest.name);
+                let src = self.aggregate_addr(&agg.name);
+
+                if dst.disp != src.disp {
+                    self.copy_mem(dst, src, size);
+                }
+
+                self.store_mem(
+                    elem, value, None, dst.offset(offset as i64), tl
+                )?;
+            }
+            &Stmt::CastInst (ref dest, op, ref src) => self.cast(op, src, dest),
+            &Stmt::AddrOfInst (ref dest, ref global) => {
+                self.ins(format!("adrp x16, {}", global.name));
+                self.ins(format!("add x16, x16, :lo12:{}", global.name));
+                self.put(&dest.name, "x16");
+            }
+            &Stmt::LetInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                match reg_class(t) {
+                    Some (RegClass::Int) => {
+                        self.load_int(src, t, "x16");
+                        self.put(&dest.name, "x16");
+                    }
+                    Some (RegClass::Float) => {
+                        self.load_float(src, t, "d30");
+                        self.put(&dest.name, "d30");
+                    }
+                    None => {
+                        let size = self.size_of(t, tl)?;
+
+                        if let Some (var) = src.var() {
+                            let dst = self.aggregate_addr(&dest.name);
+                            let from = self.aggregate_addr(&var.name);
+                            self.copy_mem(dst, from, size);
+                        }
+                    }
+                }
+            }
+            &Stmt::RetInst (ref value) => {
+                if let &Some (ref val) = value {
+                    let t = &self.sig.typename;
+
+                    match reg_class(t) {
+                        Some (RegClass::Int) => self.load_int(val, t, "x0"),
+                        Some (RegClass::Float) => {
+                            self.load_float(val, t, "d0");
+                        }
+                        None => {
+                            return Err (CodegenError::Unsupported (
+                                "returning an aggregate".to_owned(), tl.clone()
+                            ));
+                        }
+                    }
+                }
+
+                self.epilogue();
+            }
+            &Stmt::CallInst (ref dest, ref callee, ref args) => {
+                self.call(block_index, index, stmt, dest, callee, args, tl)?;
+            }
+            &Stmt::BrInst (ref target) => {
+                let succ = self.blocks.iter().find(|b| b.label == target.name);
+
+                if let Some (succ) = succ {
+                    let (moves, consts) =
+                        phi_moves(self.frame, succ, &block.label)?;
+                    self.parallel_move(moves, consts);
+                }
+
+                let label = self.block_label(&target.name);
+                self.ins(format!("b {}", label));
+            }
+            &Stmt::CondBrInst (ref cond, ref on_true, ref on_false) => {
+                let on_true = self.edge(block, &on_true.name)?;
+                let on_false = self.edge(block, &on_false.name)?;
+
+                self.load_int(cond, &Type::I1, "x16");
+                self.ins(format!("cbnz x16, {}", on_true));
+                self.ins(format!("b {}", on_false));
+            }
+            &Stmt::SwitchInst (ref val, ref default, ref cases) => {
+                let t = switch_type(&self.frame.types, val);
+
+                self.load_int(val, t, "x16");
+
+                for &(case, ref target) in cases {
+                    let target = self.edge(block, &target.name)?;
+                    let case = wrap_int(case as i64, t);
+
+                    if case >= 0 && case < 4096 {
+                        self.ins(format!("cmp x16, #{}", case));
+                    }
+                    else {
+                        self.mov_imm(case as u64, "x17");
+                        self.ins("cmp x16, x17");
+                    }
+
+                    self.ins(format!("b.eq {}", target));
+                }
+
+                let default = self.edge(block, &default.name)?;
+                self.ins(format!("b {}", default));
+            }
+            // Phis are given their values on the way into their block.
+            &Stmt::PhiInst (..) => {}
+        }
+
+        return Ok (());
+    }
+}
+
+/// Emits assembly for the GNU or LLVM assembler targeting AArch64, following
+/// the AAPCS64 calling convention.
+pub struct AArch64;
+
+impl AsmTarget for AArch64 {
+    fn registers(&self) -> &'static RegisterFile {
+        return &REGISTERS;
+    }
+
+    fn emit_function(
+        &self, sig: &FuncSig, blocks: &Vec<BasicBlock>, frame: &FrameAlloc,
+        structs: &StructTable, sigs: &SigTable, out: &mut String
+    ) -> Result<(), CodegenError>
+    {
+        let out_size = stack_args_size(
+            blocks, frame, sigs, INT_ARG_REGS.len(), FLOAT_ARG_REGS.len()
+        );
+        let mut emitter = Emitter {
+            sig: sig,
+            blocks: blocks,
+            frame: frame,
+            structs: structs,
+            sigs: sigs,
+            out_size: out_size,
+            out: String::new(),
+            trampolines: Vec::new(),
+        };
+
+        emitter.label(&sig.name);
+        emitter.prologue()?;
+
+        for (i, block) in blocks.iter().enumerate() {
+            let label = emitter.block_label(&block.label);
+            emitter.label(&label);
+
+            let stmts = block.stmts.iter().zip(&block.locs);
+
+            for (j, (stmt, tl)) in stmts.enumerate() {
+                emitter.stmt(block, i, j, stmt, tl)?;
+            }
+        }
+
+        let trampolines: Vec<_> = emitter.trampolines.drain(..).collect();
+
+        for (label, moves, consts, target) in trampolines {
+            emitter.label(&label);
+            emitter.parallel_move(moves, consts);
+            emitter.ins(format!("b {}", target));
+        }
+
+        out.push_str(&emitter.out);
+
+        return Ok (());
+    }
+}
+
+/// Lower a typechecked module to AArch64 assembly. The output is marked as
+/// not needing an executable stack, which ELF linkers otherwise assume.
+pub fn generate_aarch64(ast: &Node) -> Result<String, CodegenError> {
+    let mut asm = emit_module(&AArch64, ast)?;
+    asm.push_str("\n    .section .note.GNU-stack,\"\",@progbits\n");
+
+    return Ok (asm);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each sample module with the assembly it is expected to lower to. A
+    /// golden file is regenerated with `compile -f <module> -t aarch64`.
+    const GOLDEN: [(&'static str, &'static str, &'static str); 2] = [
+        (
+            "codegen",
+            include_str!("../testdata/codegen.ir"),
+            include_str!("../testdata/aarch64/codegen.s"),
+        ),
+        (
+            "printf",
+            include_str!("../testdata/printf.ir"),
+            include_str!("../testdata/aarch64/printf.s"),
+        ),
+    ];
+
+    fn compile(source: &str) -> String {
+        let tokens = tokenize(source, FileId (0)).into_result().unwrap();
+        let ast = parse(&tokens).into_result().unwrap();
+
+        assert!(typecheck(&ast).is_ok());
+
+        return generate_aarch64(&ast).unwrap();
+    }
+
+    #[test]
+    fn output_matches_golden_files() {
+        for &(name, source, golden) in &GOLDEN {
+            let asm = compile(source);
+            let lines = asm.lines().zip(golden.lines());
+
+            for (i, (got, want)) in lines.enumerate() {
+                assert_eq!(got, want, "{}.s differs at line {}", name, i + 1);
+            }
+
+            assert_eq!(
+                asm.lines().count(), golden.lines().count(),
+                "{}.s differs in length", name
+            );
+        }
+    }
+}
@@ -0,0 +1,717 @@
+use ir_diagnostic::*;
+use ir_lexer::*;
+use ir_parser::*;
+use ir_reg_allocer::*;
+use ir_type_layout::*;
+use ir_typechecker::*;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The register file a scalar value is kept in.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub enum RegClass {
+    Int,
+    Float,
+}
+
+/// The register class of values of type `t`, or `None` for aggregates, which
+/// always live in memory.
+pub fn reg_class(t: &Type) -> Option<RegClass> {
+    return match t {
+        &Type::I1 | &Type::I8 | &Type::I16 | &Type::I32 | &Type::I64 => {
+            Some (RegClass::Int)
+        }
+        &Type::Ptr (_) => Some (RegClass::Int),
+        &Type::F32 | &Type::F64 => Some (RegClass::Float),
+        _ => None,
+    };
+}
+
+/// Where a value lives: in a register, named as the target's assembler names
+/// it, or at an offset into the local area of the stack frame.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub enum Loc {
+    Reg (&'static str),
+    Stack (u64),
+}
+
+/// The registers of a target that values may be allocated to. Registers the
+/// target needs for argument passing or as scratch space are left out.
+pub struct RegisterFile {
+    /// Registers for integers and pointers, in the order they are handed out.
+    pub int_regs: &'static [&'static str],
+    /// Registers for floats, in the order they are handed out.
+    pub float_regs: &'static [&'static str],
+    /// Those of the registers above that a function must preserve for its
+    /// caller. The rest are saved around calls by the caller.
+    pub callee_saved: &'static [&'static str],
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum CodegenError {
+    Unsupported (String, TokLoc),
+    BadType (LayoutError, TokLoc),
+}
+
+impl CodegenError {
+    pub fn loc(&self) -> &TokLoc {
+        return match self {
+            &CodegenError::Unsupported (_, ref tl) => tl,
+            &CodegenError::BadType (_, ref tl) => tl,
+        };
+    }
+
+    fn message(&self) -> String {
+        return match self {
+            &CodegenError::Unsupported (ref what, _) => {
+                format!("{} is not supported by this backend", what)
+            }
+            &CodegenError::BadType (ref err, _) => format!("{}", err),
+        };
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let code = match self {
+            &CodegenError::Unsupported (..) => "E0400",
+            &CodegenError::BadType (..) => "E0401",
+        };
+
+        return Diagnostic::error(code, self.message())
+            .with_primary(self.loc(), "while generating code for this");
+    }
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tl = self.loc();
+
+        write!(f, "L:{} C:{}: {}", tl.line, tl.col, self.message())
+    }
+}
+
+/// Hands out stack slots in the local area of a frame.
+struct SlotAllocator {
+    size: u64,
+}
+
+impl SlotAllocator {
+    fn slot(&mut self, layout: TypeLayout) -> u64 {
+        let align = layout.align.max(1);
+        let offset = (self.size + align - 1) / align * align;
+
+        self.size = offset + layout.size;
+
+        return offset;
+    }
+}
+
+/// Where every value of a function lives, as decided before any code for it
+/// is emitted.
+pub struct FrameAlloc<'a> {
+    /// The home of each value. Values that are never read have none.
+    pub homes: HashMap<&'a str, Loc>,
+    pub types: HashMap<&'a str, &'a Type>,
+    /// The storage each alloca hands out, as an offset into the local area.
+    pub allocas: HashMap<&'a str, u64>,
+    /// The values live just after each statement, indexed like the blocks.
+    pub live_outs: Vec<Vec<HashSet<&'a str>>>,
+    /// The callee-saved registers the function uses, which it must preserve.
+    pub callee_saved: Vec<&'static str>,
+    /// Where each caller-saved register in use is kept across a call.
+    pub save_slots: HashMap<&'static str, u64>,
+    /// Size of the local area, a multiple of 16.
+    pub local_size: u64,
+}
+
+fn slot_layout(t: &Type, structs: &StructTable, tl: &TokLoc)
+    -> Result<TypeLayout, CodegenError>
+{
+    return layout_of(t, structs)
+        .map_err(|err| CodegenError::BadType (err, tl.clone()));
+}
+
+/// Decide where each value of a function lives. Scalars are colored with the
+/// registers of `regs` using the interference graph, and those that do not
+/// fit are spilled to the stack. Aggregates and alloca storage always get a
+/// slot in the frame.
+pub fn allocate_frame<'a>(
+    sig: &'a FuncSig, blocks: &'a Vec<BasicBlock>, regs: &RegisterFile,
+    structs: &StructTable
+) -> Result<FrameAlloc<'a>, CodegenError>
+{
+    let mut types = HashMap::new();
+    let mut order = Vec::new();
+    let mut def_locs = HashMap::new();
+
+    for arg in &sig.arglist {
+        types.insert(arg.name.as_str(), &arg.typename);
+        order.push(arg.name.as_str());
+        def_locs.insert(arg.name.as_str(), &sig.loc);
+    }
+
+    for block in blocks {
+        for (stmt, tl) in block.stmts.iter().zip(&block.locs) {
+            if let Some (vtp) = stmt.def() {
+                types.insert(vtp.name.as_str(), &vtp.typename);
+                order.push(vtp.name.as_str());
+                def_locs.insert(vtp.name.as_str(), tl);
+            }
+        }
+    }
+
+    let rig = generate_funcdef_rig(blocks);
+    let mut slots = SlotAllocator {size: 0};
+    let mut homes = HashMap::new();
+    let mut used = HashSet::new();
+
+    let classes = [
+        (RegClass::Int, regs.int_regs),
+        (RegClass::Float, regs.float_regs),
+    ];
+
+    for &(class, class_regs) in &classes {
+        let vars: Vec<&str> = order.iter()
+            .cloned()
+            .filter(|var| reg_class(types[var]) == Some (class))
+            .collect();
+
+        let colors = assign_registers(&rig, &vars, class_regs.len() as i64);
+
+        for var in vars {
+            if let Some (&color) = colors.get(var) {
+                homes.insert(var, Loc::Reg (class_regs[color]));
+                used.insert(class_regs[color]);
+            }
+            else if rig.contains_node(var) {
+                let layout = TypeLayout {size: 8, align: 8};
+                homes.insert(var, Loc::Stack (slots.slot(layout)));
+            }
+        }
+    }
+
+    for &var in &order {
+        if reg_class(types[var]).is_none() {
+            let layout = slot_layout(types[var], structs, def_locs[var])?;
+            homes.insert(var, Loc::Stack (slots.slot(layout)));
+        }
+    }
+
+    let mut allocas = HashMap::new();
+
+    for block in blocks {
+        for (stmt, tl) in block.stmts.iter().zip(&block.locs) {
+            if let &Stmt::AllocaInst (ref dest) = stmt {
+                if let Type::Ptr (ref pointee) = dest.typename {
+                    let layout = slot_layout(pointee, structs, tl)?;
+                    allocas.insert(dest.name.as_str(), slots.slot(layout));
+                }
+            }
+        }
+    }
+
+    let all_regs = regs.int_regs.iter().chain(regs.float_regs);
+    let mut callee_saved = Vec::new();
+    let mut save_slots = HashMap::new();
+
+    for &reg in all_regs.filter(|reg| used.contains(*reg)) {
+        if regs.callee_saved.contains(&reg) {
+            callee_saved.push(reg);
+        }
+        else {
+            let layout = TypeLayout {size: 8, align: 8};
+            save_slots.insert(reg, slots.slot(layout));
+        }
+    }
+
+    return Ok (FrameAlloc {
+        homes: homes,
+        types: types,
+        allocas: allocas,
+        live_outs: get_stmt_live_outs(blocks),
+        callee_saved: callee_saved,
+        save_slots: save_slots,
+        local_size: (slots.size + 15) / 16 * 16,
+    });
+}
+
+impl<'a> FrameAlloc<'a> {
+    /// The registers holding values that must survive the call made by
+    /// statement `index` of block `block`, and that the callee may clobber.
+    /// The value the call defines is not among them.
+    pub fn live_across_call(&self, block: usize, index: usize, stmt: &Stmt)
+        -> Vec<&'static str>
+    {
+        let dest = stmt.def().map(|vtp| vtp.name.as_str());
+        let mut regs: Vec<&'static str> = Vec::new();
+
+        for &var in &self.live_outs[block][index] {
+            if Some (var) == dest {
+                continue;
+            }
+
+            if let Some (&Loc::Reg (reg)) = self.homes.get(var) {
+                if self.save_slots.contains_key(reg) && !regs.contains(&reg) {
+                    regs.push(reg);
+                }
+            }
+        }
+
+        regs.sort();
+
+        return regs;
+    }
+}
+
+/// A copy from one home to another, as part of a parallel move.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct Move {
+    pub class: RegClass,
+    pub dst: Loc,
+    pub src: Loc,
+}
+
+/// An immediate of type `t` given to a phi on an edge. These are loaded once
+/// the copies of the parallel move are done, since they read nothing the
+/// copies could overwrite.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub struct ConstMove<'a> {
+    pub class: RegClass,
+    pub dst: Loc,
+    pub imm: &'a Operand,
+    pub t: &'a Type,
+}
+
+pub fn has_phis(block: &BasicBlock) -> bool {
+    return match block.stmts.first() {
+        Some (&Stmt::PhiInst (..)) => true,
+        _ => false,
+    };
+}
+
+/// The copies that give the phis at the head of `succ` their values on the
+/// edge from the block labeled `pred`. The copies are meant to happen all at
+/// once; see `sequence_moves`. Immediate incoming values come separately.
+pub fn phi_moves<'a>(frame: &FrameAlloc, succ: &'a BasicBlock, pred: &str)
+    -> Result<(Vec<Move>, Vec<ConstMove<'a>>), CodegenError>
+{
+    let mut moves = Vec::new();
+    let mut consts = Vec::new();
+
+    for (stmt, tl) in succ.stmts.iter().zip(&succ.locs) {
+        let (dest, incoming) = match stmt {
+            &Stmt::PhiInst (ref dest, ref incoming) => (dest, incoming),
+            _ => break,
+        };
+
+        let class = match reg_class(&dest.typename) {
+            Some (class) => class,
+            None => {
+                return Err (CodegenError::Unsupported (
+                    "a phi of aggregate type".to_owned(), tl.clone()
+                ));
+            }
+        };
+
+        let dst = match frame.homes.get::<str>(&dest.name) {
+            Some (&dst) => dst,
+            None => continue,
+        };
+
+        let val = incoming.iter()
+            .find(|&&(_, ref label)| label.name == pred)
+            .map(|&(ref val, _)| val);
+
+        match val {
+            Some (&Operand::OpVariable (ref var)) => {
+                if let Some (&src) = frame.homes.get::<str>(&var.name) {
+                    moves.push(Move {class: class, dst: dst, src: src});
+                }
+            }
+            Some (imm) => {
+                consts.push(ConstMove {
+                    class: class,
+                    dst: dst,
+                    imm: imm,
+                    t: &dest.typename,
+                });
+            }
+            None => {}
+        }
+    }
+
+    return Ok ((moves, consts));
+}
+
+/// Order a parallel move so that no copy overwrites a value another copy has
+/// yet to read. Cycles are broken by first copying one value to `temp` of
+/// its class, which must be a location none of the moves touch.
+pub fn sequence_moves<F>(moves: Vec<Move>, temp: F) -> Vec<Move>
+    where F: Fn(RegClass) -> Loc
+{
+    let mut pending: Vec<Move> = moves.into_iter()
+        .filter(|mv| mv.dst != mv.src)
+        .collect();
+    let mut sequenced = Vec::new();
+
+    while !pending.is_empty() {
+        let ready = (0..pending.len()).find(|&i| {
+            pending.iter().all(|other| other.src != pending[i].dst)
+        });
+
+        match ready {
+            Some (i) => sequenced.push(pending.remove(i)),
+            None => {
+                // Everything left is in a cycle. Set aside the value one
+                // copy is about to overwrite, and read it from there.
+                let blocked = pending[0].dst;
+                let class = pending[0].class;
+                let saved = temp(class);
+
+                sequenced.push(Move {class: class, dst: saved, src: blocked});
+
+                for mv in pending.iter_mut() {
+                    if mv.src == blocked {
+                        mv.src = saved;
+                    }
+                }
+            }
+        }
+    }
+
+    return sequenced;
+}
+
+/// The byte offset and type of the element of `t` selected by the constant
+/// `indices`.
+pub fn element_offset<'t>(
+    t: &'t Type, indices: &Vec<i64>, structs: &'t StructTable
+) -> Result<(u64, &'t Type), LayoutError>
+{
+    let mut offset = 0;
+    let mut cur = t;
+
+    for &index in indices {
+        cur = match cur {
+            &Type::Array (ref elem, _) => {
+                offset += index as u64 * layout_of(elem, structs)?.size;
+                &**elem
+            }
+            &Type::UserType (ref name) => {
+                offset += field_offsets(name, structs)?[index as usize];
+                &structs[name][index as usize]
+            }
+            _ => return Err (LayoutError::UnsizedType (cur.clone())),
+        };
+    }
+
+    return Ok ((offset, cur));
+}
+
+/// The type a comparison is carried out in, found the same way the
+/// typechecker finds it.
+pub fn cmp_type<'t>(
+    types: &HashMap<&str, &'t Type>, left: &'t Operand, right: &'t Operand,
+    default: &'t Type
+) -> &'t Type
+{
+    for op in &[left, right] {
+        match *op {
+            &Operand::OpVariable (ref var) => {
+                if let Some (t) = types.get::<str>(&var.name) {
+                    return t;
+                }
+            }
+            &Operand::OpInteger (_, Some (ref suffix_type), _) => {
+                return suffix_type;
+            }
+            _ => {}
+        }
+    }
+
+    return default;
+}
+
+/// The type a switch on `val` is carried out in, found the same way the
+/// typechecker finds it.
+pub fn switch_type<'t>(types: &HashMap<&str, &'t Type>, val: &'t Operand)
+    -> &'t Type
+{
+    return cmp_type(types, val, val, &Type::I64);
+}
+
+/// The bits of an immediate of type `t`: integers in canonical form, and
+/// floats as their IEEE encoding at the width of `t`.
+pub fn imm_bits(op: &Operand, t: &Type) -> Option<u64> {
+    return match op {
+        &Operand::OpInteger (i, _, _) => Some (wrap_int(i as i64, t) as u64),
+        &Operand::OpFloat (x, _) => {
+            match t {
+                &Type::F32 => Some ((x as f32).to_bits() as u64),
+                _ => Some (x.to_bits()),
+            }
+        }
+        &Operand::OpVariable (_) => None,
+    };
+}
+
+/// A piece of the initial contents of a global.
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum DataItem {
+    /// A little-endian integer of the given size in bytes.
+    Int (u64, u64),
+    Zero (u64),
+}
+
+fn init_data(
+    init: &Initializer, t: &Type, structs: &StructTable,
+    items: &mut Vec<DataItem>
+) -> Result<(), LayoutError>
+{
+    let size = layout_of(t, structs)?.size;
+
+    match init {
+        &Initializer::InitInteger (i, _, _) => {
+            items.push(DataItem::Int (size, wrap_int(i as i64, t) as u64));
+        }
+        &Initializer::InitFloat (x) => {
+            let bits = match t {
+                &Type::F32 => (x as f32).to_bits() as u64,
+                _ => x.to_bits(),
+            };
+
+            items.push(DataItem::Int (size, bits));
+        }
+        &Initializer::InitZero => items.push(DataItem::Zero (size)),
+        &Initializer::InitString (ref bytes) => {
+            for &b in bytes {
+                items.push(DataItem::Int (1, b as u64));
+            }
+        }
+        &Initializer::InitArray (ref elems) => {
+            if let &Type::Array (ref elem, _) = t {
+                for x in elems {
+                    init_data(x, elem, structs, items)?;
+                }
+            }
+        }
+    }
+
+    return Ok (());
+}
+
+/// The initial contents of `global`, in order.
+pub fn global_items(global: &GlobalDef, structs: &StructTable)
+    -> Result<Vec<DataItem>, LayoutError>
+{
+    let mut items = Vec::new();
+
+    init_data(&global.init, &global.typename, structs, &mut items)?;
+
+    return Ok (items);
+}
+
+/// Assembler directives for the contents of `global`. The directives are
+/// the ones every ELF assembler we target understands.
+pub fn global_data(global: &GlobalDef, structs: &StructTable)
+    -> Result<String, LayoutError>
+{
+    let mut s = String::new();
+
+    for item in global_items(global, structs)? {
+        s.push_str(&match item {
+            DataItem::Int (1, bits) => format!("    .byte {}\n", bits as u8),
+            DataItem::Int (2, bits) => format!("    .short {}\n", bits as u16),
+            DataItem::Int (4, bits) => format!("    .long {}\n", bits as u32),
+            DataItem::Int (_, bits) => format!("    .quad {}\n", bits),
+            DataItem::Zero (0) => continue,
+            DataItem::Zero (size) => format!("    .zero {}\n", size),
+        });
+    }
+
+    return Ok (s);
+}
+
+/// The signatures of the functions a module defines or declares, by name.
+pub type SigTable<'a> = HashMap<&'a str, &'a FuncSig>;
+
+/// Gather the signatures of a module into `sigs`.
+pub fn collect_sigs<'a>(ast: &'a Node, sigs: &mut SigTable<'a>) {
+    match ast {
+        &Node::Module (ref items) => {
+            for item in items {
+                collect_sigs(item, sigs);
+            }
+        }
+        &Node::FuncDecl (ref sig) | &Node::FuncDef (ref sig, _) => {
+            sigs.insert(&sig.name, sig);
+        }
+        _ => {}
+    }
+}
+
+/// The types the arguments of a call to `callee` are passed as. A variable
+/// has a type of its own, while an immediate takes the type of the matching
+/// parameter, or past the parameters of a variadic function is an i64 or an
+/// f64 as the typechecker has it.
+pub fn arg_types<'t>(
+    types: &HashMap<&str, &'t Type>, sigs: &SigTable<'t>, callee: &str,
+    args: &'t [Operand]
+) -> Vec<&'t Type>
+{
+    let params = match sigs.get(callee) {
+        Some (sig) => &sig.arglist[..],
+        None => &[],
+    };
+
+    return args.iter().enumerate().map(|(i, arg)| match arg {
+        &Operand::OpVariable (ref var) => types[var.name.as_str()],
+        _ => match (params.get(i), arg) {
+            (Some (param), _) => &param.typename,
+            (None, &Operand::OpInteger (_, Some (ref suffix_type), _)) => {
+                suffix_type
+            }
+            (None, &Operand::OpFloat (..)) => &Type::F64,
+            (None, _) => &Type::I64,
+        },
+    }).collect();
+}
+
+/// Bytes of stack needed for the arguments of the biggest call that passes
+/// some on the stack, when `int_args` and `float_args` registers carry the
+/// first arguments of each class and the rest take 8 bytes each. Rounded to
+/// keep the stack aligned.
+///
+/// Some targets pass the float arguments that go with `...` as integers, so
+/// those are counted against both classes.
+pub fn stack_args_size(
+    blocks: &Vec<BasicBlock>, frame: &FrameAlloc, sigs: &SigTable,
+    int_args: usize, float_args: usize
+) -> u64
+{
+    let mut size = 0;
+
+    for block in blocks {
+        for stmt in &block.stmts {
+            if let &Stmt::CallInst (_, ref callee, ref args) = stmt {
+                let (mut ints, mut floats) = (0, 0);
+                let fixed = match sigs.get::<str>(&callee.name) {
+                    Some (sig) if sig.variadic => sig.arglist.len(),
+                    _ => args.len(),
+                };
+                let types = arg_types(&frame.types, sigs, &callee.name, args);
+
+                for (i, t) in types.into_iter().enumerate() {
+                    match reg_class(t) {
+                        Some (RegClass::Int) => ints += 1,
+                        Some (RegClass::Float) if i >= fixed => {
+                            ints += 1;
+                            floats += 1;
+                        }
+                        Some (RegClass::Float) => floats += 1,
+                        None => {}
+                    }
+                }
+
+                let stacked = ints.max(int_args) - int_args
+                    + floats.max(float_args) - float_args;
+
+                size = size.max(8 * stacked as u64);
+            }
+        }
+    }
+
+    return (size + 15) / 16 * 16;
+}
+
+/// A target that lowers functions to assembly text.
+pub trait AsmTarget {
+    fn registers(&self) -> &'static RegisterFile;
+
+    /// Append the code for one function, given where its values live.
+    fn emit_function(
+        &self, sig: &FuncSig, blocks: &Vec<BasicBlock>, frame: &FrameAlloc,
+        structs: &StructTable, sigs: &SigTable, out: &mut String
+    ) -> Result<(), CodegenError>;
+}
+
+/// Gather the struct definitions, function definitions and globals of a
+/// module.
+pub fn collect_items<'a>(
+    ast: &'a Node, structs: &mut StructTable,
+    funcs: &mut Vec<(&'a FuncSig, &'a Vec<BasicBlock>)>,
+    globals: &mut Vec<&'a GlobalDef>
+)
+{
+    match ast {
+        &Node::Module (ref items) => {
+            for item in items {
+                collect_items(item, structs, funcs, globals);
+            }
+        }
+        &Node::StructDef (ref name, ref fields, _) => {
+            structs.insert(name.clone(), fields.clone());
+        }
+        &Node::FuncDecl (_) => {}
+        &Node::GlobalDef (ref global) => globals.push(global),
+        &Node::FuncDef (ref sig, ref blocks) => funcs.push((sig, blocks)),
+    }
+}
+
+/// Lower a typechecked module to assembly for `target`: the code of every
+/// function, then the data of every global. Declared functions are left for
+/// the linker to find.
+pub fn emit_module<T: AsmTarget>(target: &T, ast: &Node)
+    -> Result<String, CodegenError>
+{
+    let mut structs = StructTable::new();
+    let mut funcs = Vec::new();
+    let mut globals = Vec::new();
+
+    let mut sigs = SigTable::new();
+
+    collect_items(ast, &mut structs, &mut funcs, &mut globals);
+    collect_sigs(ast, &mut sigs);
+
+    let mut out = String::new();
+
+    if !funcs.is_empty() {
+        out.push_str("    .text\n");
+    }
+
+    for (sig, blocks) in funcs {
+        let frame = allocate_frame(sig, blocks, target.registers(), &structs)?;
+
+        out.push_str(&format!("\n    .globl {}\n", sig.name));
+        target.emit_function(sig, blocks, &frame, &structs, &sigs, &mut out)?;
+    }
+
+    for global in globals {
+        let layout = layout_of(&global.typename, &structs)
+            .map_err(|err| CodegenError::BadType (err, global.loc.clone()))?;
+        let data = global_data(global, &structs)
+            .map_err(|err| CodegenError::BadType (err, global.loc.clone()))?;
+
+        out.push_str(if global.constant {
+            "\n    .section .rodata\n"
+        }
+        else {
+            "\n    .data\n"
+        });
+        out.push_str(&format!(
+            "    .p2align {}\n    .globl {}\n{}:\n{}",
+            layout.align.trailing_zeros(), global.name, global.name, data
+        ));
+    }
+
+    return Ok (out);
+}
@@ -0,0 +1,387 @@
+use ir_lexer::*;
+
+use std::fmt;
+
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Severity::Error => write!(f, "error"),
+            &Severity::Warning => write!(f, "warning"),
+            &Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A span of source text with a message attached. The primary span is where
+/// the problem is; secondary spans point at whatever explains it.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SpanLabel {
+    pub loc: TokLoc,
+    pub message: String,
+    pub primary: bool,
+}
+
+/// A problem found in the input, in a form shared by every stage so that
+/// they can all be reported the same way.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub labels: Vec<SpanLabel>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: String)
+        -> Diagnostic
+    {
+        return Diagnostic {
+            severity: severity,
+            code: code,
+            message: message,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        };
+    }
+
+    pub fn error(code: &'static str, message: String) -> Diagnostic {
+        return Diagnostic::new(Severity::Error, code, message);
+    }
+
+    pub fn with_primary(mut self, loc: &TokLoc, message: &str) -> Diagnostic {
+        self.labels.push(SpanLabel {
+            loc: loc.clone(),
+            message: message.to_owned(),
+            primary: true,
+        });
+
+        return self;
+    }
+
+    pub fn with_secondary(mut self, loc: &TokLoc, message: &str)
+        -> Diagnostic
+    {
+        self.labels.push(SpanLabel {
+            loc: loc.clone(),
+            message: message.to_owned(),
+            primary: false,
+        });
+
+        return self;
+    }
+
+    pub fn with_note(mut self, note: &str) -> Diagnostic {
+        self.notes.push(note.to_owned());
+
+        return self;
+    }
+
+    /// Where the problem is, if the diagnostic points anywhere at all.
+    pub fn primary_loc(&self) -> Option<&TokLoc> {
+        return self.labels.iter()
+            .find(|label| label.primary)
+            .map(|label| &label.loc);
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some (loc) = self.primary_loc() {
+            write!(f, "L:{} C:{}: ", loc.line, loc.col)?;
+        }
+
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+/// The source text behind each `FileId`, so that diagnostics can quote it.
+pub struct SourceMap {
+    files: Vec<(String, String)>,
+}
+
+const RESET: &'static str = "\x1b[0m";
+const BOLD: &'static str = "\x1b[1m";
+const RED: &'static str = "\x1b[1;31m";
+const YELLOW: &'static str = "\x1b[1;33m";
+const BLUE: &'static str = "\x1b[1;34m";
+const GREEN: &'static str = "\x1b[1;32m";
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        return SourceMap {files: Vec::new()};
+    }
+
+    /// Register a file, returning the id its tokens should carry.
+    pub fn add(&mut self, name: &str, source: &str) -> FileId {
+        self.files.push((name.to_owned(), source.to_owned()));
+
+        return FileId (self.files.len() as u32 - 1);
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        return &self.files[file.0 as usize].0;
+    }
+
+    pub fn source(&self, file: FileId) -> &str {
+        return &self.files[file.0 as usize].1;
+    }
+
+    /// Render `diag` the way a compiler would: a header, then each labeled
+    /// line of source with the span underlined, then any notes. ANSI colors
+    /// are used only if `color` is set.
+    pub fn render(&self, diag: &Diagnostic, color: bool) -> String {
+        let paint = |style: &str, text: &str| -> String {
+            if color {
+                format!("{}{}{}", style, text, RESET)
+            }
+            else {
+                text.to_owned()
+            }
+        };
+
+        let severity_style = match diag.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => GREEN,
+        };
+
+        let header = format!("{}[{}]", diag.severity, diag.code);
+        let mut out = format!(
+            "{}{}\n",
+            paint(severity_style, &header),
+            paint(BOLD, &format!(": {}", diag.message))
+        );
+
+        let gutter = diag.labels.iter()
+            .map(|label| label.loc.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pad = " ".repeat(gutter);
+
+        if let Some (loc) = diag.primary_loc() {
+            out.push_str(&format!(
+                "{}{} {}:{}:{}\n",
+                pad, paint(BLUE, "-->"), self.name(loc.file), loc.line, loc.col
+            ));
+        }
+
+        if !diag.labels.is_empty() {
+            out.push_str(&format!("{} {}\n", pad, paint(BLUE, "|")));
+        }
+
+        // Labels are shown in source order, each source line only once
+        // with every label on it underlined beneath.
+        let mut labels: Vec<&SpanLabel> = diag.labels.iter().collect();
+        labels.sort_by_key(|label| (label.loc.file.0, label.loc.start));
+
+        let mut last_line = None;
+
+        for label in labels {
+            let source = self.source(label.loc.file);
+            let line = source.lines()
+                .nth((label.loc.line as usize).saturating_sub(1))
+                .unwrap_or("");
+
+            let this_line = Some ((label.loc.file.0, label.loc.line));
+
+            if this_line != last_line {
+                out.push_str(&format!(
+                    "{} {} {}\n",
+                    paint(BLUE, &format!("{:>1$}", label.loc.line, gutter)),
+                    paint(BLUE, "|"),
+                    line
+                ));
+                last_line = this_line;
+            }
+
+            // Underline to the end of the span, but no further than the end
+            // of its first line. A location that is off the end of the
+            // source, or inside a character, is moved back to where a
+            // character starts.
+            let start = char_boundary(source, label.loc.start);
+            let line_start = source[..start]
+                .rfind('\n')
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            let span_end = char_boundary(
+                source, label.loc.end.min(line_start + line.len())
+            );
+            let width = if span_end > start {
+                source[start..span_end].chars().count()
+            }
+            else {
+                1
+            };
+
+            let (mark, style) = if label.primary {
+                ("^", severity_style)
+            }
+            else {
+                ("-", BLUE)
+            };
+            let underline = format!("{} {}", mark.repeat(width), label.message);
+
+            out.push_str(&format!(
+                "{} {} {}{}\n",
+                pad,
+                paint(BLUE, "|"),
+                " ".repeat((label.loc.col as usize).saturating_sub(1)),
+                paint(style, &underline)
+            ));
+        }
+
+        for note in &diag.notes {
+            out.push_str(&format!(
+                "{} {} note: {}\n", pad, paint(BLUE, "="), note
+            ));
+        }
+
+        return out;
+    }
+}
+
+/// The offset in `source` nearest to `offset`, and not after it, that starts
+/// a character or is the end of the source.
+fn char_boundary(source: &str, offset: usize) -> usize {
+    let mut offset = offset.min(source.len());
+
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+
+    return offset;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(line: u64, col: u64, start: usize, end: usize) -> TokLoc {
+        return TokLoc {
+            file: FileId (0), line: line, col: col, start: start, end: end
+        };
+    }
+
+    fn sources() -> SourceMap {
+        let mut sources = SourceMap::new();
+
+        sources.add("t.ir", "a = 1\nbb + c\n");
+
+        return sources;
+    }
+
+    #[test]
+    fn labels_underline_their_spans() {
+        let diag = Diagnostic::error("E0001", "bad".to_owned())
+            .with_primary(&loc(2, 1, 6, 8), "used here")
+            .with_secondary(&loc(1, 1, 0, 1), "defined here")
+            .with_note("try again");
+
+        assert_eq!(
+            sources().render(&diag, false),
+            "error[E0001]: bad\n \
+             --> t.ir:2:1\n  \
+             |\n\
+             1 | a = 1\n  \
+             | - defined here\n\
+             2 | bb + c\n  \
+             | ^^ used here\n  \
+             = note: try again\n"
+        );
+        assert_eq!(diag.to_string(), "L:2 C:1: error[E0001]: bad");
+    }
+
+    #[test]
+    fn a_line_is_quoted_once() {
+        let diag = Diagnostic::new(Severity::Warning, "E0002", "odd".to_owned())
+            .with_primary(&loc(2, 6, 11, 12), "this")
+            .with_secondary(&loc(2, 1, 6, 8), "and that");
+
+        assert_eq!(
+            sources().render(&diag, false),
+            "warning[E0002]: odd\n \
+             --> t.ir:2:6\n  \
+             |\n\
+             2 | bb + c\n  \
+             | -- and that\n  \
+             |      ^ this\n"
+        );
+    }
+
+    #[test]
+    fn colors_only_when_asked() {
+        let diag = Diagnostic::error("E0003", "eof".to_owned())
+            .with_primary(&loc(2, 7, 12, 12), "here");
+
+        let plain = sources().render(&diag, false);
+        let colored = sources().render(&diag, true);
+
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.ends_with("2 | bb + c\n  |       ^ here\n"));
+        assert!(colored.starts_with(RED));
+        assert!(colored.contains(&format!("{}^ here{}", RED, RESET)));
+    }
+
+    #[test]
+    fn labels_at_the_end_of_the_source_render() {
+        let diag = Diagnostic::error("E0004", "eof".to_owned())
+            .with_primary(&loc(3, 1, 13, 13), "here")
+            .with_secondary(&loc(4, 0, 20, 25), "and past it");
+
+        assert_eq!(
+            sources().render(&diag, false),
+            "error[E0004]: eof\n \
+             --> t.ir:3:1\n  \
+             |\n\
+             3 | \n  \
+             | ^ here\n\
+             4 | \n  \
+             | - and past it\n"
+        );
+
+        let diag = Diagnostic::error("E0005", "nowhere".to_owned())
+            .with_primary(&loc(0, 0, 0, 0), "here");
+
+        assert!(sources().render(&diag, false).contains("| ^ here\n"));
+    }
+
+    #[test]
+    fn labels_after_multibyte_characters_render() {
+        let mut sources = SourceMap::new();
+
+        sources.add("u.ir", "\u{e9} + \u{1f600}x\n");
+
+        let diag = Diagnostic::error("E0006", "wide".to_owned())
+            .with_primary(&loc(1, 6, 9, 10), "x")
+            .with_secondary(&loc(1, 3, 3, 4), "plus");
+
+        assert_eq!(
+            sources.render(&diag, false),
+            "error[E0006]: wide\n \
+             --> u.ir:1:6\n  \
+             |\n\
+             1 | \u{e9} + \u{1f600}x\n  \
+             |   - plus\n  \
+             |      ^ x\n"
+        );
+
+        // A span that starts or ends inside a character is cut back to
+        // the character before.
+        let diag = Diagnostic::error("E0007", "split".to_owned())
+            .with_primary(&loc(1, 2, 1, 7), "odd");
+
+        assert!(sources.render(&diag, false).ends_with("|  ^^^^ odd\n"));
+    }
+}
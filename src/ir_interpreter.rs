@@ -0,0 +1,1399 @@
+use ir_parser::*;
+use ir_type_layout::*;
+use ir_typechecker::*;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A runtime value. Integers are kept sign-extended from the width of their
+/// type, except that an `i1` is always 0 or 1. Pointers are addresses into
+/// the interpreter's memory.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum Value {
+    Int (i64),
+    F32 (f32),
+    F64 (f64),
+    Ptr (u64),
+    Aggregate (Vec<Value>),
+    Void,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Value::Int (i) => write!(f, "{}", i),
+            &Value::F32 (x) => write!(f, "{}", format_float(x as f64)),
+            &Value::F64 (x) => write!(f, "{}", format_float(x)),
+            &Value::Ptr (addr) => write!(f, "0x{:x}", addr),
+            &Value::Aggregate (ref elems) => {
+                write!(f, "[")?;
+                for (i, x) in elems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", x)?;
+                }
+                write!(f, "]")
+            }
+            &Value::Void => write!(f, "void"),
+        }
+    }
+}
+
+/// Read a scalar argument of type `t` from text, as given on a command line.
+pub fn parse_value(text: &str, t: &Type) -> Option<Value> {
+    return match t {
+        &Type::I1 | &Type::I8 | &Type::I16 | &Type::I32 | &Type::I64 => {
+            match text.parse::<i128>() {
+                Ok (i) if int_fits_type(i, t) => {
+                    Some (Value::Int (wrap_int(i as i64, t)))
+                }
+                _ => None,
+            }
+        }
+        &Type::F32 => text.parse::<f32>().ok().map(Value::F32),
+        &Type::F64 => text.parse::<f64>().ok().map(Value::F64),
+        _ => None,
+    };
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum ExecError {
+    UndefinedFunction (String),
+    NoDefinition (String),
+    ArityMismatch (String, usize, usize),
+    UndefinedVariable (String),
+    UnknownLabel (String),
+    NoIncomingValue (String, String),
+    FellOffBlock (String),
+    DivisionByZero,
+    ShiftOutOfRange (u64, Type),
+    OutOfBounds (u64, u64),
+    WriteToConstant (u64),
+    BadValue (Type),
+    BadType (LayoutError),
+    CallDepthExceeded,
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &ExecError::UndefinedFunction (ref name) => {
+                write!(f, "call to undefined function @{}", name)
+            }
+            &ExecError::NoDefinition (ref name) => {
+                write!(f, "function @{} is declared but has no body", name)
+            }
+            &ExecError::ArityMismatch (ref name, expected, found) => {
+                write!(
+                    f, "@{} takes {} arguments but was given {}",
+                    name, expected, found
+                )
+            }
+            &ExecError::UndefinedVariable (ref name) => {
+                write!(f, "read of %{} before it was assigned", name)
+            }
+            &ExecError::UnknownLabel (ref name) => {
+                write!(f, "branch to unknown label {}", name)
+            }
+            &ExecError::NoIncomingValue (ref var, ref pred) => {
+                write!(f, "phi %{} has no value for predecessor {}", var, pred)
+            }
+            &ExecError::FellOffBlock (ref label) => {
+                write!(f, "control reached the end of block {}", label)
+            }
+            &ExecError::DivisionByZero => write!(f, "division by zero"),
+            &ExecError::ShiftOutOfRange (amount, ref t) => {
+                write!(f, "shift of {} by {} bits", t, amount)
+            }
+            &ExecError::OutOfBounds (addr, size) => {
+                write!(
+                    f, "access of {} bytes at 0x{:x} is out of bounds",
+                    size, addr
+                )
+            }
+            &ExecError::WriteToConstant (addr) => {
+                write!(f, "write to constant memory at 0x{:x}", addr)
+            }
+            &ExecError::BadValue (ref t) => {
+                write!(f, "value is not of type {}", t)
+            }
+            &ExecError::BadType (ref err) => write!(f, "{}", err),
+            &ExecError::CallDepthExceeded => {
+                write!(f, "calls nested more than {} deep", MAX_CALL_DEPTH)
+            }
+        }
+    }
+}
+
+/// How deep calls may nest before we give up on the program.
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// Addresses below this are never handed out, so that a null pointer, or one
+/// near it, never points at anything.
+const NULL_GUARD: u64 = 16;
+
+/// The bits of the canonical integer `val` of type `t`, read as signed.
+fn signed(val: i64, t: &Type) -> i64 {
+    return match t {
+        &Type::I1 => -(val & 1),
+        _ => val,
+    };
+}
+
+/// The bits of the canonical integer `val` of type `t`, read as unsigned.
+fn unsigned(val: i64, t: &Type) -> u64 {
+    return match t {
+        &Type::I1 => val as u64 & 1,
+        &Type::I8 => val as u8 as u64,
+        &Type::I16 => val as u16 as u64,
+        &Type::I32 => val as u32 as u64,
+        _ => val as u64,
+    };
+}
+
+fn float_value(x: f64, t: &Type) -> Value {
+    return match t {
+        &Type::F32 => Value::F32 (x as f32),
+        _ => Value::F64 (x),
+    };
+}
+
+fn float_of(val: &Value) -> Option<f64> {
+    return match val {
+        &Value::F32 (x) => Some (x as f64),
+        &Value::F64 (x) => Some (x),
+        _ => None,
+    };
+}
+
+/// A flat, byte-addressed memory. Globals sit at the bottom, and each call
+/// allocates its stack above them, to be released when it returns.
+struct Memory {
+    bytes: Vec<u8>,
+    readonly: Vec<(u64, u64)>,
+}
+
+impl Memory {
+    fn new() -> Memory {
+        return Memory {
+            bytes: vec!(0; NULL_GUARD as usize),
+            readonly: Vec::new(),
+        };
+    }
+
+    /// Reserve `layout.size` zeroed bytes and return their address.
+    fn alloc(&mut self, layout: TypeLayout) -> u64 {
+        let len = self.bytes.len() as u64;
+        let addr = (len + layout.align - 1) / layout.align * layout.align;
+
+        self.bytes.resize((addr + layout.size) as usize, 0);
+
+        return addr;
+    }
+
+    fn range(&self, addr: u64, size: u64) -> Result<usize, ExecError> {
+        return match addr.checked_add(size) {
+            Some (end) if addr >= NULL_GUARD
+                && end <= self.bytes.len() as u64 => Ok (addr as usize),
+            _ => Err (ExecError::OutOfBounds (addr, size)),
+        };
+    }
+
+    /// Read a little-endian quantity of `size` bytes.
+    fn read(&self, addr: u64, size: u64) -> Result<u64, ExecError> {
+        let start = self.range(addr, size)?;
+        let mut bits = 0;
+
+        for i in (0..size as usize).rev() {
+            bits = bits << 8 | self.bytes[start + i] as u64;
+        }
+
+        return Ok (bits);
+    }
+
+    fn write(&mut self, addr: u64, size: u64, bits: u64)
+        -> Result<(), ExecError>
+    {
+        let start = self.range(addr, size)?;
+
+        if self.readonly.iter().any(|&(lo, hi)| addr < hi && addr + size > lo) {
+            return Err (ExecError::WriteToConstant (addr));
+        }
+
+        for i in 0..size as usize {
+            self.bytes[start + i] = (bits >> (8 * i)) as u8;
+        }
+
+        return Ok (());
+    }
+
+    fn load(&self, addr: u64, t: &Type, structs: &StructTable)
+        -> Result<Value, ExecError>
+    {
+        return match t {
+            &Type::I1 | &Type::I8 | &Type::I16 | &Type::I32 | &Type::I64 => {
+                let size = layout(t, structs)?.size;
+                Ok (Value::Int (wrap_int(self.read(addr, size)? as i64, t)))
+            }
+            &Type::F32 => {
+                Ok (Value::F32 (f32::from_bits(self.read(addr, 4)? as u32)))
+            }
+            &Type::F64 => Ok (Value::F64 (f64::from_bits(self.read(addr, 8)?))),
+            &Type::Ptr (_) => Ok (Value::Ptr (self.read(addr, POINTER_SIZE)?)),
+            &Type::Array (ref elem, len) => {
+                let size = layout(elem, structs)?.size;
+                let mut elems = Vec::new();
+
+                for i in 0..len {
+                    elems.push(self.load(addr + i * size, elem, structs)?);
+                }
+
+                Ok (Value::Aggregate (elems))
+            }
+            &Type::UserType (ref name) => {
+                let offsets = field_offsets(name, structs)
+                    .map_err(ExecError::BadType)?;
+                let mut elems = Vec::new();
+
+                for (field, offset) in structs[name].iter().zip(offsets) {
+                    elems.push(self.load(addr + offset, field, structs)?);
+                }
+
+                Ok (Value::Aggregate (elems))
+            }
+            &Type::Void => Err (ExecError::BadValue (t.clone())),
+        };
+    }
+
+    fn store(
+        &mut self, addr: u64, t: &Type, val: &Value, structs: &StructTable
+    ) -> Result<(), ExecError>
+    {
+        return match (t, val) {
+            (&Type::F32, &Value::F32 (x)) => {
+                self.write(addr, 4, x.to_bits() as u64)
+            }
+            (&Type::F64, &Value::F64 (x)) => self.write(addr, 8, x.to_bits()),
+            (&Type::Ptr (_), &Value::Ptr (p)) => {
+                self.write(addr, POINTER_SIZE, p)
+            }
+            (_, &Value::Int (i)) if is_integer_type(t) => {
+                let size = layout(t, structs)?.size;
+                self.write(addr, size, unsigned(i, t))
+            }
+            (&Type::Array (ref elem, len), &Value::Aggregate (ref elems))
+                if elems.len() as u64 == len =>
+            {
+                let size = layout(elem, structs)?.size;
+
+                for (i, x) in elems.iter().enumerate() {
+                    self.store(addr + i as u64 * size, elem, x, structs)?;
+                }
+
+                Ok (())
+            }
+            (&Type::UserType (ref name), &Value::Aggregate (ref elems)) => {
+                let offsets = field_offsets(name, structs)
+                    .map_err(ExecError::BadType)?;
+                let fields = &structs[name];
+
+                if fields.len() != elems.len() {
+                    return Err (ExecError::BadValue (t.clone()));
+                }
+
+                let placed = fields.iter().zip(offsets).zip(elems);
+
+                for ((field, offset), x) in placed {
+                    self.store(addr + offset, field, x, structs)?;
+                }
+
+                Ok (())
+            }
+            _ => Err (ExecError::BadValue (t.clone())),
+        };
+    }
+
+    /// Fill the storage at `addr` with `init`. Memory starts out zeroed, so
+    /// `zeroinit` has nothing to do.
+    fn initialize(
+        &mut self, addr: u64, t: &Type, init: &Initializer,
+        structs: &StructTable
+    ) -> Result<(), ExecError>
+    {
+        return match (init, t) {
+            (&Initializer::InitInteger (i, _, _), _) => {
+                let val = Value::Int (wrap_int(i as i64, t));
+
+                self.store(addr, t, &val, structs)
+            }
+            (&Initializer::InitFloat (x), _) => {
+                self.store(addr, t, &float_value(x, t), structs)
+            }
+            (&Initializer::InitZero, _) => Ok (()),
+            (&Initializer::InitString (ref bytes), _) => {
+                for (i, &b) in bytes.iter().enumerate() {
+                    self.write(addr + i as u64, 1, b as u64)?;
+                }
+
+                Ok (())
+            }
+            (
+                &Initializer::InitArray (ref elems),
+                &Type::Array (ref elem, _)
+            ) => {
+                let size = layout(elem, structs)?.size;
+
+                for (i, x) in elems.iter().enumerate() {
+                    self.initialize(addr + i as u64 * size, elem, x, structs)?;
+                }
+
+                Ok (())
+            }
+            _ => Err (ExecError::BadValue (t.clone())),
+        };
+    }
+}
+
+fn layout(t: &Type, structs: &StructTable) -> Result<TypeLayout, ExecError> {
+    return layout_of(t, structs).map_err(ExecError::BadType);
+}
+
+/// A function body, with the lookups needed to run it worked out up front.
+struct FuncInfo<'a> {
+    sig: &'a FuncSig,
+    blocks: &'a Vec<BasicBlock>,
+    types: HashMap<&'a str, &'a Type>,
+    block_index: HashMap<&'a str, usize>,
+}
+
+impl<'a> FuncInfo<'a> {
+    fn new(sig: &'a FuncSig, blocks: &'a Vec<BasicBlock>) -> FuncInfo<'a> {
+        let mut types = HashMap::new();
+        let mut block_index = HashMap::new();
+
+        for arg in &sig.arglist {
+            types.insert(arg.name.as_str(), &arg.typename);
+        }
+
+        for (i, block) in blocks.iter().enumerate() {
+            block_index.insert(block.label.as_str(), i);
+
+            for stmt in &block.stmts {
+                if let Some (vtp) = stmt.def() {
+                    types.insert(vtp.name.as_str(), &vtp.typename);
+                }
+            }
+        }
+
+        return FuncInfo {
+            sig: sig,
+            blocks: blocks,
+            types: types,
+            block_index: block_index,
+        };
+    }
+}
+
+/// Everything in a module that does not change as it runs.
+struct Program<'a> {
+    structs: StructTable,
+    funcs: HashMap<&'a str, FuncInfo<'a>>,
+    decls: HashSet<&'a str>,
+    globals: HashMap<&'a str, u64>,
+}
+
+/// Where control goes after a statement.
+enum Flow<'a> {
+    Next,
+    Jump (&'a str),
+    Return (Value),
+}
+
+/// Runs the functions of a module. The module is expected to typecheck;
+/// anything the typechecker would reject may still be caught at run time,
+/// but only as far as it gets in the way of running.
+pub struct Interpreter<'a> {
+    program: Program<'a>,
+    memory: Memory,
+}
+
+impl<'a> Interpreter<'a> {
+    /// Load `ast`, which may be a whole module or a single function, and
+    /// lay out and initialize its globals.
+    pub fn new(ast: &'a Node) -> Result<Interpreter<'a>, ExecError> {
+        let mut program = Program {
+            structs: StructTable::new(),
+            funcs: HashMap::new(),
+            decls: HashSet::new(),
+            globals: HashMap::new(),
+        };
+        let mut globals = Vec::new();
+
+        collect(ast, &mut program, &mut globals);
+
+        let mut memory = Memory::new();
+        let mut constants = Vec::new();
+
+        for global in globals {
+            let global_layout = layout(&global.typename, &program.structs)?;
+            let addr = memory.alloc(global_layout);
+
+            memory.initialize(
+                addr, &global.typename, &global.init, &program.structs
+            )?;
+            program.globals.insert(&global.name, addr);
+
+            if global.constant {
+                constants.push((addr, addr + global_layout.size));
+            }
+        }
+
+        memory.readonly = constants;
+
+        return Ok (Interpreter {program: program, memory: memory});
+    }
+
+    /// Call the function `name` with `args`. Integer arguments are wrapped
+    /// to the width of their parameter. Calls made by the program recurse on
+    /// the host stack, so deep recursion wants a thread with room for it.
+    pub fn call(&mut self, name: &str, args: Vec<Value>)
+        -> Result<Value, ExecError>
+    {
+        return call_function(&self.program, &mut self.memory, name, args, 0);
+    }
+
+    /// The signature of the function `name`, if the module defines it.
+    pub fn signature(&self, name: &str) -> Option<&'a FuncSig> {
+        return self.program.funcs.get(name).map(|func| func.sig);
+    }
+}
+
+fn collect<'a>(
+    ast: &'a Node, program: &mut Program<'a>, globals: &mut Vec<&'a GlobalDef>
+)
+{
+    match ast {
+        &Node::Module (ref items) => {
+            for item in items {
+                collect(item, program, globals);
+            }
+        }
+        &Node::StructDef (ref name, ref fields, _) => {
+            program.structs.insert(name.clone(), fields.clone());
+        }
+        &Node::GlobalDef (ref global) => globals.push(global),
+        &Node::FuncDecl (ref sig) => {
+            program.decls.insert(&sig.name);
+        }
+        &Node::FuncDef (ref sig, ref blocks) => {
+            program.funcs.insert(&sig.name, FuncInfo::new(sig, blocks));
+        }
+    }
+}
+
+fn call_function<'a>(
+    prog: &Program<'a>, mem: &mut Memory, name: &str, args: Vec<Value>,
+    depth: usize
+) -> Result<Value, ExecError>
+{
+    if depth >= MAX_CALL_DEPTH {
+        return Err (ExecError::CallDepthExceeded);
+    }
+
+    let func = match prog.funcs.get(name) {
+        Some (func) => func,
+        None if prog.decls.contains(name) => {
+            return Err (ExecError::NoDefinition (name.to_owned()));
+        }
+        None => return Err (ExecError::UndefinedFunction (name.to_owned())),
+    };
+
+    let params = &func.sig.arglist;
+
+    if params.len() != args.len() {
+        return Err (ExecError::ArityMismatch (
+            name.to_owned(), params.len(), args.len()
+        ));
+    }
+
+    let mut env = HashMap::new();
+
+    for (param, arg) in params.iter().zip(args) {
+        let arg = match arg {
+            Value::Int (i) => Value::Int (wrap_int(i, &param.typename)),
+            arg => arg,
+        };
+
+        env.insert(param.name.as_str(), arg);
+    }
+
+    // Everything this call allocates goes when it returns.
+    let stack_mark = mem.bytes.len();
+    let result = run_blocks(prog, mem, func, env, depth);
+    mem.bytes.truncate(stack_mark);
+
+    return result;
+}
+
+fn run_blocks<'a>(
+    prog: &Program<'a>, mem: &mut Memory, func: &FuncInfo<'a>,
+    mut env: HashMap<&'a str, Value>, depth: usize
+) -> Result<Value, ExecError>
+{
+    let mut block = match func.blocks.first() {
+        Some (block) => block,
+        None => return Err (ExecError::FellOffBlock (func.sig.name.clone())),
+    };
+    let mut pred: Option<&str> = None;
+
+    loop {
+        // The phis at the head of a block all read their values as they
+        // were on leaving the predecessor, before any of them is assigned.
+        let mut phi_values = Vec::new();
+
+        for stmt in &block.stmts {
+            let (dest, incoming) = match stmt {
+                &Stmt::PhiInst (ref dest, ref incoming) => (dest, incoming),
+                _ => break,
+            };
+
+            let val = incoming.iter()
+                .find(|&&(_, ref label)| Some (label.name.as_str()) == pred)
+                .map(|&(ref val, _)| val);
+
+            match val {
+                Some (val) => {
+                    let val = operand(&env, val, &dest.typename)?;
+
+                    phi_values.push((dest.name.as_str(), val));
+                }
+                None => {
+                    return Err (ExecError::NoIncomingValue (
+                        dest.name.clone(),
+                        pred.unwrap_or(&block.label).to_owned()
+                    ));
+                }
+            }
+        }
+
+        let phi_count = phi_values.len();
+
+        env.extend(phi_values);
+
+        let mut next = None;
+
+        for stmt in &block.stmts[phi_count..] {
+            match exec_stmt(prog, mem, func, &mut env, stmt, depth)? {
+                Flow::Next => {}
+                Flow::Jump (label) => {
+                    next = Some (label);
+                    break;
+                }
+                Flow::Return (val) => return Ok (val),
+            }
+        }
+
+        let label = match next {
+            Some (label) => label,
+            None => return Err (ExecError::FellOffBlock (block.label.clone())),
+        };
+
+        pred = Some (&block.label);
+        block = match func.block_index.get(label) {
+            Some (&i) => &func.blocks[i],
+            None => return Err (ExecError::UnknownLabel (label.to_owned())),
+        };
+    }
+}
+
+fn get(env: &HashMap<&str, Value>, var: &Variable)
+    -> Result<Value, ExecError>
+{
+    return match env.get::<str>(&var.name) {
+        Some (val) => Ok (val.clone()),
+        None => Err (ExecError::UndefinedVariable (var.name.clone())),
+    };
+}
+
+/// The value of `op`, where an immediate takes the type `t`.
+fn operand(env: &HashMap<&str, Value>, op: &Operand, t: &Type)
+    -> Result<Value, ExecError>
+{
+    return match op {
+        &Operand::OpVariable (ref var) => get(env, var),
+        &Operand::OpInteger (i, _, _) => {
+            Ok (Value::Int (wrap_int(i as i64, t)))
+        }
+        &Operand::OpFloat (x, _) => Ok (float_value(x, t)),
+    };
+}
+
+/// The type a comparison is carried out in, found the same way the
+/// typechecker finds it.
+fn cmp_type<'a>(
+    func: &FuncInfo<'a>, left: &'a Operand, right: &'a Operand,
+    default: &'a Type
+) -> &'a Type
+{
+    for op in &[left, right] {
+        match *op {
+            &Operand::OpVariable (ref var) => {
+                if let Some (t) = func.types.get::<str>(&var.name) {
+                    return t;
+                }
+            }
+            &Operand::OpInteger (_, Some (ref suffix_type), _) => {
+                return suffix_type;
+            }
+            _ => {}
+        }
+    }
+
+    return default;
+}
+
+fn int_arith(stmt: &Stmt, l: i64, r: i64, t: &Type) -> Result<i64, ExecError> {
+    let bits = scalar_bits(t).unwrap_or(64);
+    let shift = || {
+        let amount = unsigned(r, t);
+
+        if amount >= bits {
+            Err (ExecError::ShiftOutOfRange (amount, t.clone()))
+        }
+        else {
+            Ok (amount as u32)
+        }
+    };
+
+    let result = match stmt {
+        &Stmt::AddInst (..) => l.wrapping_add(r),
+        &Stmt::SubInst (..) => l.wrapping_sub(r),
+        &Stmt::MulInst (..) => l.wrapping_mul(r),
+        &Stmt::SDivInst (..) | &Stmt::SRemInst (..) => {
+            let (l, r) = (signed(l, t), signed(r, t));
+
+            if r == 0 {
+                return Err (ExecError::DivisionByZero);
+            }
+
+            match stmt {
+                &Stmt::SDivInst (..) => l.wrapping_div(r),
+                _ => l.wrapping_rem(r),
+            }
+        }
+        &Stmt::UDivInst (..) | &Stmt::URemInst (..) => {
+            let (l, r) = (unsigned(l, t), unsigned(r, t));
+
+            if r == 0 {
+                return Err (ExecError::DivisionByZero);
+            }
+
+            match stmt {
+                &Stmt::UDivInst (..) => (l / r) as i64,
+                _ => (l % r) as i64,
+            }
+        }
+        &Stmt::AndInst (..) => l & r,
+        &Stmt::OrInst (..) => l | r,
+        &Stmt::XorInst (..) => l ^ r,
+        &Stmt::ShlInst (..) => (unsigned(l, t) << shift()?) as i64,
+        &Stmt::LShrInst (..) => (unsigned(l, t) >> shift()?) as i64,
+        &Stmt::AShrInst (..) => signed(l, t) >> shift()?,
+        _ => return Err (ExecError::BadValue (t.clone())),
+    };
+
+    return Ok (wrap_int(result, t));
+}
+
+/// Float arithmetic, carried out at the precision of `t`.
+fn float_arith(stmt: &Stmt, l: f64, r: f64, t: &Type)
+    -> Result<Value, ExecError>
+{
+    return match t {
+        &Type::F32 => {
+            let (l, r) = (l as f32, r as f32);
+
+            match stmt {
+                &Stmt::AddInst (..) => Ok (Value::F32 (l + r)),
+                &Stmt::SubInst (..) => Ok (Value::F32 (l - r)),
+                &Stmt::MulInst (..) => Ok (Value::F32 (l * r)),
+                _ => Err (ExecError::BadValue (t.clone())),
+            }
+        }
+        _ => {
+            match stmt {
+                &Stmt::AddInst (..) => Ok (Value::F64 (l + r)),
+                &Stmt::SubInst (..) => Ok (Value::F64 (l - r)),
+                &Stmt::MulInst (..) => Ok (Value::F64 (l * r)),
+                _ => Err (ExecError::BadValue (t.clone())),
+            }
+        }
+    };
+}
+
+fn icmp(pred: ICmpPred, l: i64, r: i64, t: &Type) -> bool {
+    let (sl, sr) = (signed(l, t), signed(r, t));
+    let (ul, ur) = (unsigned(l, t), unsigned(r, t));
+
+    return match pred {
+        ICmpPred::Eq => l == r,
+        ICmpPred::Ne => l != r,
+        ICmpPred::Slt => sl < sr,
+        ICmpPred::Sle => sl <= sr,
+        ICmpPred::Sgt => sl > sr,
+        ICmpPred::Sge => sl >= sr,
+        ICmpPred::Ult => ul < ur,
+        ICmpPred::Ule => ul <= ur,
+        ICmpPred::Ugt => ul > ur,
+        ICmpPred::Uge => ul >= ur,
+    };
+}
+
+fn fcmp(pred: FCmpPred, l: f64, r: f64) -> bool {
+    let uno = l.is_nan() || r.is_nan();
+
+    return match pred {
+        FCmpPred::Oeq => !uno && l == r,
+        FCmpPred::One => !uno && l != r,
+        FCmpPred::Olt => !uno && l < r,
+        FCmpPred::Ole => !uno && l <= r,
+        FCmpPred::Ogt => !uno && l > r,
+        FCmpPred::Oge => !uno && l >= r,
+        FCmpPred::Ord => !uno,
+        FCmpPred::Ueq => uno || l == r,
+        FCmpPred::Une => uno || l != r,
+        FCmpPred::Ult => uno || l < r,
+        FCmpPred::Ule => uno || l <= r,
+        FCmpPred::Ugt => uno || l > r,
+        FCmpPred::Uge => uno || l >= r,
+        FCmpPred::Uno => uno,
+    };
+}
+
+/// Convert `val` from `src` to `dest`. Floats converted to integers
+/// saturate at the limits of a 64-bit integer before being wrapped to the
+/// width of `dest`.
+fn cast(op: CastOp, val: &Value, src: &Type, dest: &Type)
+    -> Result<Value, ExecError>
+{
+    let bad = || ExecError::BadValue (src.clone());
+
+    return match (op, val) {
+        (CastOp::Trunc, &Value::Int (i)) => Ok (Value::Int (wrap_int(i, dest))),
+        (CastOp::ZExt, &Value::Int (i)) => {
+            Ok (Value::Int (wrap_int(unsigned(i, src) as i64, dest)))
+        }
+        (CastOp::SExt, &Value::Int (i)) => {
+            Ok (Value::Int (wrap_int(signed(i, src), dest)))
+        }
+        (CastOp::FPToSI, _) => {
+            let x = float_of(val).ok_or_else(bad)?;
+            Ok (Value::Int (wrap_int(x as i64, dest)))
+        }
+        (CastOp::FPToUI, _) => {
+            let x = float_of(val).ok_or_else(bad)?;
+            Ok (Value::Int (wrap_int(x as u64 as i64, dest)))
+        }
+        (CastOp::SIToFP, &Value::Int (i)) => {
+            Ok (match dest {
+                &Type::F32 => Value::F32 (signed(i, src) as f32),
+                _ => Value::F64 (signed(i, src) as f64),
+            })
+        }
+        (CastOp::UIToFP, &Value::Int (i)) => {
+            Ok (match dest {
+                &Type::F32 => Value::F32 (unsigned(i, src) as f32),
+                _ => Value::F64 (unsigned(i, src) as f64),
+            })
+        }
+        (CastOp::FPExt, &Value::F32 (x)) => Ok (Value::F64 (x as f64)),
+        (CastOp::FPTrunc, &Value::F64 (x)) => Ok (Value::F32 (x as f32)),
+        (CastOp::Bitcast, &Value::Ptr (p)) => Ok (Value::Ptr (p)),
+        (CastOp::Bitcast, &Value::Int (i)) => {
+            let bits = unsigned(i, src);
+
+            Ok (match dest {
+                &Type::F32 => Value::F32 (f32::from_bits(bits as u32)),
+                &Type::F64 => Value::F64 (f64::from_bits(bits)),
+                _ => Value::Int (wrap_int(bits as i64, dest)),
+            })
+        }
+        (CastOp::Bitcast, &Value::F32 (x)) => {
+            Ok (Value::Int (wrap_int(x.to_bits() as i64, dest)))
+        }
+        (CastOp::Bitcast, &Value::F64 (x)) => {
+            Ok (Value::Int (wrap_int(x.to_bits() as i64, dest)))
+        }
+        (CastOp::PtrToInt, &Value::Ptr (p)) => {
+            Ok (Value::Int (wrap_int(p as i64, dest)))
+        }
+        (CastOp::IntToPtr, &Value::Int (i)) => {
+            Ok (Value::Ptr (unsigned(i, src)))
+        }
+        _ => Err (bad()),
+    };
+}
+
+/// Follow the constant `indices` into the aggregate `val`.
+fn element_mut<'v>(val: &'v mut Value, indices: &[i64])
+    -> Option<&'v mut Value>
+{
+    let mut cur = val;
+
+    for &index in indices {
+        cur = match cur {
+            &mut Value::Aggregate (ref mut elems) if index >= 0 => {
+                elems.get_mut(index as usize)?
+            }
+            _ => return None,
+        };
+    }
+
+    return Some (cur);
+}
+
+fn exec_stmt<'a>(
+    prog: &Program<'a>, mem: &mut Memory, func: &FuncInfo<'a>,
+    env: &mut HashMap<&'a str, Value>, stmt: &'a Stmt, depth: usize
+) -> Result<Flow<'a>, ExecError>
+{
+    let structs = &prog.structs;
+    let var_type = |var: &Variable| match func.types.get::<str>(&var.name) {
+        Some (&t) => Ok (t),
+        None => Err (ExecError::UndefinedVariable (var.name.clone())),
+    };
+    let ptr_of = |val: Value, t: &Type| match val {
+        Value::Ptr (p) => Ok (p),
+        _ => Err (ExecError::BadValue (t.clone())),
+    };
+
+    let result = match stmt {
+        &Stmt::AddInst (ref dest, ref l, ref r) |
+        &Stmt::SubInst (ref dest, ref l, ref r) |
+        &Stmt::MulInst (ref dest, ref l, ref r) |
+        &Stmt::SDivInst (ref dest, ref l, ref r) |
+        &Stmt::UDivInst (ref dest, ref l, ref r) |
+        &Stmt::SRemInst (ref dest, ref l, ref r) |
+        &Stmt::URemInst (ref dest, ref l, ref r) |
+        &Stmt::AndInst (ref dest, ref l, ref r) |
+        &Stmt::OrInst (ref dest, ref l, ref r) |
+        &Stmt::XorInst (ref dest, ref l, ref r) |
+        &Stmt::ShlInst (ref dest, ref l, ref r) |
+        &Stmt::LShrInst (ref dest, ref l, ref r) |
+        &Stmt::AShrInst (ref dest, ref l, ref r) => {
+            let t = &dest.typename;
+            let l = operand(env, l, t)?;
+            let r = operand(env, r, t)?;
+
+            match (&l, &r) {
+                (&Value::Int (l), &Value::Int (r)) => {
+                    Value::Int (int_arith(stmt, l, r, t)?)
+                }
+                _ => match (float_of(&l), float_of(&r)) {
+                    (Some (l), Some (r)) => float_arith(stmt, l, r, t)?,
+                    _ => return Err (ExecError::BadValue (t.clone())),
+                },
+            }
+        }
+        &Stmt::NegInst (ref dest, ref src) => {
+            let t = &dest.typename;
+
+            match operand(env, src, t)? {
+                Value::Int (i) => {
+                    Value::Int (wrap_int(0i64.wrapping_sub(i), t))
+                }
+                Value::F32 (x) => Value::F32 (-x),
+                Value::F64 (x) => Value::F64 (-x),
+                _ => return Err (ExecError::BadValue (t.clone())),
+            }
+        }
+        &Stmt::NotInst (ref dest, ref src) => {
+            let t = &dest.typename;
+
+            match operand(env, src, t)? {
+                Value::Int (i) => Value::Int (wrap_int(!i, t)),
+                _ => return Err (ExecError::BadValue (t.clone())),
+            }
+        }
+        &Stmt::ICmpInst (_, pred, ref l, ref r) => {
+            let t = cmp_type(func, l, r, &Type::I64);
+
+            match (operand(env, l, t)?, operand(env, r, t)?) {
+                (Value::Int (l), Value::Int (r)) => {
+                    Value::Int (icmp(pred, l, r, t) as i64)
+                }
+                _ => return Err (ExecError::BadValue (t.clone())),
+            }
+        }
+        &Stmt::FCmpInst (_, pred, ref l, ref r) => {
+            let t = cmp_type(func, l, r, &Type::F64);
+            let l = operand(env, l, t)?;
+            let r = operand(env, r, t)?;
+
+            match (float_of(&l), float_of(&r)) {
+                (Some (l), Some (r)) => Value::Int (fcmp(pred, l, r) as i64),
+                _ => return Err (ExecError::BadValue (t.clone())),
+            }
+        }
+        &Stmt::AllocaInst (ref dest) => {
+            match dest.typename {
+                Type::Ptr (ref pointee) => {
+                    Value::Ptr (mem.alloc(layout(pointee, structs)?))
+                }
+                ref t => return Err (ExecError::BadValue (t.clone())),
+            }
+        }
+        &Stmt::LoadInst (ref dest, ref ptr) => {
+            let addr = ptr_of(get(env, ptr)?, var_type(ptr)?)?;
+
+            mem.load(addr, &dest.typename, structs)?
+        }
+        &Stmt::StoreInst (ref val, ref ptr) => {
+            let ptr_type = var_type(ptr)?;
+            let pointee = match ptr_type {
+                &Type::Ptr (ref pointee) => pointee,
+                t => return Err (ExecError::BadValue (t.clone())),
+            };
+            let addr = ptr_of(get(env, ptr)?, ptr_type)?;
+            let val = operand(env, val, pointee)?;
+
+            mem.store(addr, pointee, &val, structs)?;
+
+            return Ok (Flow::Next);
+        }
+        &Stmt::GepInst (_, ref base, ref indices) => {
+            let base_type = var_type(base)?;
+            let mut cur = match base_type {
+                &Type::Ptr (ref pointee) => &**pointee,
+                t => return Err (ExecError::BadValue (t.clone())),
+            };
+            let mut addr = ptr_of(get(env, base)?, base_type)?;
+
+            // The first index steps over whole pointees, and each one after
+            // that selects an element of the current aggregate.
+            for (i, index) in indices.iter().enumerate() {
+                let index_val = match index {
+                    &Operand::OpVariable (ref var) => {
+                        match get(env, var)? {
+                            Value::Int (v) => signed(v, var_type(var)?),
+                            _ => return Err (ExecError::BadValue (Type::I64)),
+                        }
+                    }
+                    &Operand::OpInteger (v, _, _) => v as i64,
+                    &Operand::OpFloat (..) => {
+                        return Err (ExecError::BadValue (Type::I64));
+                    }
+                };
+
+                let (offset, next) = match cur {
+                    _ if i == 0 => {
+                        let size = layout(cur, structs)?.size as i64;
+                        (index_val.wrapping_mul(size), cur)
+                    }
+                    &Type::Array (ref elem, _) => {
+                        let size = layout(elem, structs)?.size as i64;
+                        (index_val.wrapping_mul(size), &**elem)
+                    }
+                    &Type::UserType (ref name) => {
+                        let offsets = field_offsets(name, structs)
+                            .map_err(ExecError::BadType)?;
+
+                        match structs[name].get(index_val as usize) {
+                            Some (field) if index_val >= 0 => {
+                                (offsets[index_val as usize] as i64, field)
+                            }
+                            _ => return Err (ExecError::BadValue (cur.clone())),
+                        }
+                    }
+                    t => return Err (ExecError::BadValue (t.clone())),
+                };
+
+                addr = (addr as i64).wrapping_add(offset) as u64;
+                cur = next;
+            }
+
+            Value::Ptr (addr)
+        }
+        &Stmt::ExtractValueInst (ref dest, ref agg, ref indices) => {
+            let mut val = get(env, agg)?;
+
+            match element_mut(&mut val, indices) {
+                Some (elem) => elem.clone(),
+                None => {
+                    return Err (ExecError::BadValue (dest.typename.clone()));
+                }
+            }
+        }
+        &Stmt::InsertValueInst (ref dest, ref agg, ref val, ref indices) => {
+            let agg_type = var_type(agg)?;
+            let elem_type = aggregate_element(agg_type, indices, structs)
+                .ok_or_else(|| ExecError::BadValue (agg_type.clone()))?;
+            let new_elem = operand(env, val, elem_type)?;
+            let mut result = get(env, agg)?;
+
+            match element_mut(&mut result, indices) {
+                Some (elem) => *elem = new_elem,
+                None => {
+                    return Err (ExecError::BadValue (dest.typename.clone()));
+                }
+            }
+
+            result
+        }
+        &Stmt::CastInst (ref dest, op, ref src) => {
+            cast(op, &get(env, src)?, var_type(src)?, &dest.typename)?
+        }
+        &Stmt::AddrOfInst (_, ref global) => {
+            match prog.globals.get::<str>(&global.name) {
+                Some (&addr) => Value::Ptr (addr),
+                None => {
+                    return Err (ExecError::UndefinedVariable (
+                        global.name.clone()
+                    ));
+                }
+            }
+        }
+        &Stmt::LetInst (ref dest, ref src) => {
+            operand(env, src, &dest.typename)?
+        }
+        &Stmt::CallInst (_, ref callee, ref args) => {
+            // Immediate arguments take the types of the callee's parameters.
+            // Without a definition to go by the call fails regardless.
+            let params = match prog.funcs.get::<str>(&callee.name) {
+                Some (info) => &info.sig.arglist[..],
+                None => &[],
+            };
+            let mut arg_vals = Vec::new();
+
+            for (i, arg) in args.iter().enumerate() {
+                let t = params.get(i).map_or(&Type::I64, |p| &p.typename);
+
+                arg_vals.push(operand(env, arg, t)?);
+            }
+
+            call_function(prog, mem, &callee.name, arg_vals, depth + 1)?
+        }
+        &Stmt::RetInst (Some (ref val)) => {
+            return Ok (Flow::Return (operand(env, val, &func.sig.typename)?));
+        }
+        &Stmt::RetInst (None) => return Ok (Flow::Return (Value::Void)),
+        &Stmt::BrInst (ref target) => return Ok (Flow::Jump (&target.name)),
+        &Stmt::CondBrInst (ref cond, ref on_true, ref on_false) => {
+            return match operand(env, cond, &Type::I1)? {
+                Value::Int (0) => Ok (Flow::Jump (&on_false.name)),
+                Value::Int (_) => Ok (Flow::Jump (&on_true.name)),
+                _ => Err (ExecError::BadValue (Type::I1)),
+            };
+        }
+        &Stmt::SwitchInst (ref val, ref default, ref cases) => {
+            // A bare immediate is switched on as an i64, as in the
+            // typechecker.
+            let t = match val {
+                &Operand::OpVariable (ref var) => var_type(var)?,
+                &Operand::OpInteger (_, Some (ref suffix_type), _) => {
+                    suffix_type
+                }
+                _ => &Type::I64,
+            };
+            let v = match operand(env, val, t)? {
+                Value::Int (v) => v,
+                _ => return Err (ExecError::BadValue (t.clone())),
+            };
+
+            let target = cases.iter()
+                .find(|&&(case, _)| wrap_int(case as i64, t) == v)
+                .map(|&(_, ref label)| label)
+                .unwrap_or(default);
+
+            return Ok (Flow::Jump (&target.name));
+        }
+        // Phis are handled on entry to their block.
+        &Stmt::PhiInst (..) => return Ok (Flow::Next),
+    };
+
+    if let Some (dest) = stmt.def() {
+        env.insert(&dest.name, result);
+    }
+
+    return Ok (Flow::Next);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ir_lexer::*;
+
+    use std::thread;
+
+    /// Call `@f` in `source`, which must typecheck, with `args`.
+    fn run(source: &str, args: Vec<Value>) -> Result<Value, ExecError> {
+        let tokens = tokenize(source, FileId (0)).into_result().unwrap();
+        let ast = parse(&tokens).into_result().unwrap();
+
+        typecheck(&ast).unwrap();
+
+        return Interpreter::new(&ast).unwrap().call("f", args);
+    }
+
+    /// Apply the two-operand instruction `op` at type `t` to `l` and `r`.
+    fn binop(op: &str, t: &str, l: Value, r: Value)
+        -> Result<Value, ExecError>
+    {
+        let source = format!(
+            "func @f:{t} (%l:{t}, %r:{t}) {{\n    \
+                 {} %x:{t} %l %r\n    \
+                 ret %x\n\
+             }}",
+            op, t = t
+        );
+
+        return run(&source, vec!(l, r));
+    }
+
+    fn int_binop(op: &str, t: &str, l: i64, r: i64)
+        -> Result<Value, ExecError>
+    {
+        return binop(op, t, Value::Int (l), Value::Int (r));
+    }
+
+    #[test]
+    fn integer_arithmetic_wraps_at_each_width() {
+        let widths = [
+            ("i8", i8::min_value() as i64, i8::max_value() as i64),
+            ("i16", i16::min_value() as i64, i16::max_value() as i64),
+            ("i32", i32::min_value() as i64, i32::max_value() as i64),
+            ("i64", i64::min_value(), i64::max_value()),
+        ];
+
+        for &(t, min, max) in &widths {
+            let cases = [
+                ("add", max, 1, min),
+                ("sub", min, 1, max),
+                ("mul", max, 2, -2),
+                ("mul", min, -1, min),
+            ];
+
+            for &(op, l, r, expected) in &cases {
+                assert_eq!(
+                    int_binop(op, t, l, r).unwrap(), Value::Int (expected),
+                    "{} {} {} {}", op, t, l, r
+                );
+            }
+        }
+
+        assert_eq!(int_binop("add", "i1", 1, 1).unwrap(), Value::Int (0));
+    }
+
+    #[test]
+    fn division_is_signed_or_unsigned() {
+        let cases = [
+            ("sdiv", "i32", -7, 2, -3),
+            ("srem", "i32", -7, 2, -1),
+            ("udiv", "i8", -1, 2, 127),
+            ("urem", "i8", -1, 10, 5),
+            ("udiv", "i64", -2, 2, i64::max_value()),
+            ("sdiv", "i32", i32::min_value() as i64, -1,
+             i32::min_value() as i64),
+            ("srem", "i64", i64::min_value(), -1, 0),
+        ];
+
+        for &(op, t, l, r, expected) in &cases {
+            assert_eq!(
+                int_binop(op, t, l, r).unwrap(), Value::Int (expected),
+                "{} {} {} {}", op, t, l, r
+            );
+        }
+
+        for op in &["sdiv", "udiv", "srem", "urem"] {
+            match int_binop(op, "i16", 5, 0) {
+                Err (ExecError::DivisionByZero) => {}
+                result => panic!("{} by zero gave {:?}", op, result),
+            }
+        }
+    }
+
+    #[test]
+    fn shifts_stay_within_the_type() {
+        let cases = [
+            ("shl", "i8", 1, 7, -128),
+            ("lshr", "i8", -128, 7, 1),
+            ("ashr", "i8", -128, 7, -1),
+            ("lshr", "i64", -1, 63, 1),
+        ];
+
+        for &(op, t, l, r, expected) in &cases {
+            assert_eq!(
+                int_binop(op, t, l, r).unwrap(), Value::Int (expected),
+                "{} {} {} {}", op, t, l, r
+            );
+        }
+
+        let out_of_range = [
+            ("shl", "i8", 8, 8),
+            ("lshr", "i32", 32, 32),
+            ("ashr", "i64", -1, u64::max_value()),
+        ];
+
+        for &(op, t, amount, reported) in &out_of_range {
+            match int_binop(op, t, 1, amount) {
+                Err (ExecError::ShiftOutOfRange (n, _)) => {
+                    assert_eq!(n, reported);
+                }
+                result => {
+                    panic!("{} {} by {} gave {:?}", op, t, amount, result)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn floats_keep_the_precision_of_their_type() {
+        let big = 16777216.0;
+
+        assert_eq!(
+            binop("add", "f32", Value::F32 (big as f32), Value::F32 (1.0))
+                .unwrap(),
+            Value::F32 (big as f32)
+        );
+        assert_eq!(
+            binop("add", "f64", Value::F64 (big), Value::F64 (1.0)).unwrap(),
+            Value::F64 (big + 1.0)
+        );
+
+        let tenth = |t: &str| {
+            run(&format!("func @f:{t} () {{\n    ret 0.1\n}}", t = t), vec!())
+        };
+
+        assert_eq!(tenth("f32").unwrap(), Value::F32 (0.1));
+        assert_eq!(tenth("f64").unwrap(), Value::F64 (0.1));
+    }
+
+    #[test]
+    fn control_follows_branches_phis_and_switches() {
+        let sum = "\
+func @f:i64 (%n:i64) {
+    br head
+head:
+    phi %i:i64 [%n entry, %j body]
+    phi %s:i64 [0 entry, %t body]
+    icmp %c:i1 sgt %i 0
+    br %c body done
+body:
+    add %t:i64 %s %i
+    sub %j:i64 %i 1
+    br head
+done:
+    ret %s
+}";
+
+        assert_eq!(run(sum, vec!(Value::Int (10))).unwrap(), Value::Int (55));
+        assert_eq!(run(sum, vec!(Value::Int (-3))).unwrap(), Value::Int (0));
+
+        let pick = "\
+func @f:i32 (%x:i32) {
+    switch %x other [1 one, 2 two]
+one:
+    ret 10
+two:
+    ret 20
+other:
+    ret 0
+}";
+
+        for &(x, expected) in &[(1, 10), (2, 20), (7, 0), (-1, 0)] {
+            assert_eq!(
+                run(pick, vec!(Value::Int (x))).unwrap(), Value::Int (expected)
+            );
+        }
+    }
+
+    #[test]
+    fn memory_is_reached_through_alloca_gep_and_globals() {
+        let source = "\
+type Pair = {i8, [4 x i32]}
+global @g:[3 x i64] = [1, 2, 3]
+func @f:i64 (%i:i64) {
+    alloca %p:Pair*
+    gep %e:i32* %p 0 1 %i
+    store 7 %e
+    gep %b:i8* %p 0 0
+    store -1 %b
+    load %v:Pair %p
+    extractvalue %w:i32 %v 1 2
+    addrof %q:[3 x i64]* @g
+    gep %r:i64* %q 0 %i
+    load %x:i64 %r
+    store 40 %r
+    load %y:i64 %r
+    sext %z:i64 %w
+    load %c:i8 %b
+    sext %d:i64 %c
+    add %s:i64 %y %z
+    add %u:i64 %s %d
+    add %o:i64 %u %x
+    ret %o
+}";
+
+        assert_eq!(run(source, vec!(Value::Int (2))).unwrap(), Value::Int (49));
+
+        let source = "\
+func @f:i32 () {
+    alloca %p:i32*
+    gep %q:i32* %p 100000
+    load %v:i32 %q
+    ret %v
+}";
+
+        match run(source, vec!()) {
+            Err (ExecError::OutOfBounds (..)) => {}
+            result => panic!("load out of bounds gave {:?}", result),
+        }
+    }
+
+    #[test]
+    fn recursion_stops_at_the_call_depth_limit() {
+        let fact = "\
+func @f:i64 (%n:i64) {
+    icmp %c:i1 sle %n 1
+    br %c base step
+base:
+    ret 1
+step:
+    sub %m:i64 %n 1
+    call %r:i64 @f(%m)
+    mul %p:i64 %n %r
+    ret %p
+}";
+        let forever = "\
+func @f:i64 (%n:i64) {
+    call %r:i64 @f(%n)
+    ret %r
+}";
+
+        // Each call the program makes nests a few calls here, so run with
+        // room for the deepest it may go.
+        let runner = thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(move || {
+                (
+                    run(fact, vec!(Value::Int (10))),
+                    run(forever, vec!(Value::Int (1))),
+                )
+            })
+            .unwrap();
+
+        match runner.join().unwrap() {
+            (
+                Ok (Value::Int (3628800)),
+                Err (ExecError::CallDepthExceeded)
+            ) => {}
+            results => panic!("recursion gave {:?}", results),
+        }
+    }
+}
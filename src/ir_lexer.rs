@@ -1,10 +1,14 @@
-use std::iter::Enumerate;
+use ir_diagnostic::*;
+
+use std::f64;
+use std::fmt;
 use std::iter::Peekable;
-use std::str::Chars;
+use std::str::CharIndices;
 
 #[derive(Debug)]
 #[derive(Clone)]
 pub enum Token {
+    I1Keyword (TokLoc),
     I8Keyword (TokLoc),
     I16Keyword (TokLoc),
     I32Keyword (TokLoc),
@@ -13,11 +17,53 @@ pub enum Token {
     F64Keyword (TokLoc),
     VoidKeyword (TokLoc),
     FuncKeyword (TokLoc),
+    DeclareKeyword (TokLoc),
+    GlobalKeyword (TokLoc),
+    ConstantKeyword (TokLoc),
+    ZeroInitKeyword (TokLoc),
+    AddrOfKeyword (TokLoc),
     AddKeyword (TokLoc),
     SubKeyword (TokLoc),
+    MulKeyword (TokLoc),
+    SDivKeyword (TokLoc),
+    UDivKeyword (TokLoc),
+    SRemKeyword (TokLoc),
+    URemKeyword (TokLoc),
+    AndKeyword (TokLoc),
+    OrKeyword (TokLoc),
+    XorKeyword (TokLoc),
+    ShlKeyword (TokLoc),
+    LShrKeyword (TokLoc),
+    AShrKeyword (TokLoc),
+    NegKeyword (TokLoc),
+    NotKeyword (TokLoc),
+    ICmpKeyword (TokLoc),
+    FCmpKeyword (TokLoc),
+    AllocaKeyword (TokLoc),
+    LoadKeyword (TokLoc),
+    StoreKeyword (TokLoc),
+    GepKeyword (TokLoc),
+    ExtractValueKeyword (TokLoc),
+    InsertValueKeyword (TokLoc),
+    TypeKeyword (TokLoc),
+    TruncKeyword (TokLoc),
+    ZExtKeyword (TokLoc),
+    SExtKeyword (TokLoc),
+    FPToSIKeyword (TokLoc),
+    FPToUIKeyword (TokLoc),
+    SIToFPKeyword (TokLoc),
+    UIToFPKeyword (TokLoc),
+    FPExtKeyword (TokLoc),
+    FPTruncKeyword (TokLoc),
+    BitcastKeyword (TokLoc),
+    PtrToIntKeyword (TokLoc),
+    IntToPtrKeyword (TokLoc),
     LetKeyword (TokLoc),
     RetKeyword (TokLoc),
     CallKeyword (TokLoc),
+    BrKeyword (TokLoc),
+    SwitchKeyword (TokLoc),
+    PhiKeyword (TokLoc),
 
     VarName (String, TokLoc),
     FuncName (String, TokLoc),
@@ -29,7 +75,8 @@ pub enum Token {
     RBrace (TokLoc),
     Asterisk (TokLoc),
 
-    Integer (i64, TokLoc),
+    Integer (i128, Option<String>, TokLoc),
+    Float (f64, TokLoc),
 
 
 
@@ -42,6 +89,7 @@ pub enum Token {
     Comma (TokLoc),
     Dot (TokLoc),
     DotDot (TokLoc),
+    Ellipsis (TokLoc),
     DoubleAmpersand (TokLoc),
     DoubleColon (TokLoc),
     DoubleEquals (TokLoc),
@@ -60,11 +108,296 @@ pub enum Token {
     Pound (TokLoc),
 }
 
+impl Token {
+    pub fn loc(&self) -> &TokLoc {
+        match self {
+            &Token::I1Keyword (ref tl) => tl,
+            &Token::I8Keyword (ref tl) => tl,
+            &Token::I16Keyword (ref tl) => tl,
+            &Token::I32Keyword (ref tl) => tl,
+            &Token::I64Keyword (ref tl) => tl,
+            &Token::F32Keyword (ref tl) => tl,
+            &Token::F64Keyword (ref tl) => tl,
+            &Token::VoidKeyword (ref tl) => tl,
+            &Token::FuncKeyword (ref tl) => tl,
+            &Token::DeclareKeyword (ref tl) => tl,
+            &Token::GlobalKeyword (ref tl) => tl,
+            &Token::ConstantKeyword (ref tl) => tl,
+            &Token::ZeroInitKeyword (ref tl) => tl,
+            &Token::AddrOfKeyword (ref tl) => tl,
+            &Token::AddKeyword (ref tl) => tl,
+            &Token::SubKeyword (ref tl) => tl,
+            &Token::MulKeyword (ref tl) => tl,
+            &Token::SDivKeyword (ref tl) => tl,
+            &Token::UDivKeyword (ref tl) => tl,
+            &Token::SRemKeyword (ref tl) => tl,
+            &Token::URemKeyword (ref tl) => tl,
+            &Token::AndKeyword (ref tl) => tl,
+            &Token::OrKeyword (ref tl) => tl,
+            &Token::XorKeyword (ref tl) => tl,
+            &Token::ShlKeyword (ref tl) => tl,
+            &Token::LShrKeyword (ref tl) => tl,
+            &Token::AShrKeyword (ref tl) => tl,
+            &Token::NegKeyword (ref tl) => tl,
+            &Token::NotKeyword (ref tl) => tl,
+            &Token::ICmpKeyword (ref tl) => tl,
+            &Token::FCmpKeyword (ref tl) => tl,
+            &Token::AllocaKeyword (ref tl) => tl,
+            &Token::LoadKeyword (ref tl) => tl,
+            &Token::StoreKeyword (ref tl) => tl,
+            &Token::GepKeyword (ref tl) => tl,
+            &Token::ExtractValueKeyword (ref tl) => tl,
+            &Token::InsertValueKeyword (ref tl) => tl,
+            &Token::TypeKeyword (ref tl) => tl,
+            &Token::TruncKeyword (ref tl) => tl,
+            &Token::ZExtKeyword (ref tl) => tl,
+            &Token::SExtKeyword (ref tl) => tl,
+            &Token::FPToSIKeyword (ref tl) => tl,
+            &Token::FPToUIKeyword (ref tl) => tl,
+            &Token::SIToFPKeyword (ref tl) => tl,
+            &Token::UIToFPKeyword (ref tl) => tl,
+            &Token::FPExtKeyword (ref tl) => tl,
+            &Token::FPTruncKeyword (ref tl) => tl,
+            &Token::BitcastKeyword (ref tl) => tl,
+            &Token::PtrToIntKeyword (ref tl) => tl,
+            &Token::IntToPtrKeyword (ref tl) => tl,
+            &Token::LetKeyword (ref tl) => tl,
+            &Token::RetKeyword (ref tl) => tl,
+            &Token::CallKeyword (ref tl) => tl,
+            &Token::BrKeyword (ref tl) => tl,
+            &Token::SwitchKeyword (ref tl) => tl,
+            &Token::PhiKeyword (ref tl) => tl,
+            &Token::VarName (_, ref tl) => tl,
+            &Token::FuncName (_, ref tl) => tl,
+            &Token::Colon (ref tl) => tl,
+            &Token::LParen (ref tl) => tl,
+            &Token::RParen (ref tl) => tl,
+            &Token::LBrace (ref tl) => tl,
+            &Token::RBrace (ref tl) => tl,
+            &Token::Asterisk (ref tl) => tl,
+            &Token::Integer (_, _, ref tl) => tl,
+            &Token::Float (_, ref tl) => tl,
+            &Token::CharLit (_, ref tl) => tl,
+            &Token::Ident (_, ref tl) => tl,
+            &Token::StrLit (_, ref tl) => tl,
+            &Token::Ampersand (ref tl) => tl,
+            &Token::Bang (ref tl) => tl,
+            &Token::Comma (ref tl) => tl,
+            &Token::Dot (ref tl) => tl,
+            &Token::DotDot (ref tl) => tl,
+            &Token::Ellipsis (ref tl) => tl,
+            &Token::DoubleAmpersand (ref tl) => tl,
+            &Token::DoubleColon (ref tl) => tl,
+            &Token::DoubleEquals (ref tl) => tl,
+            &Token::DoublePipe (ref tl) => tl,
+            &Token::Equals (ref tl) => tl,
+            &Token::LBracket (ref tl) => tl,
+            &Token::LWakka (ref tl) => tl,
+            &Token::Minus (ref tl) => tl,
+            &Token::Pipe (ref tl) => tl,
+            &Token::QuestionMark (ref tl) => tl,
+            &Token::RBracket (ref tl) => tl,
+            &Token::RFatArrow (ref tl) => tl,
+            &Token::RThinArrow (ref tl) => tl,
+            &Token::RWakka (ref tl) => tl,
+            &Token::Underscore (ref tl) => tl,
+            &Token::Pound (ref tl) => tl,
+        }
+    }
+}
+
+/// Identifies the source file a token came from. Ids are handed out by the
+/// caller of `tokenize`, so tokens from several files can be mixed freely.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq, Hash)]
+pub struct FileId (pub u32);
+
+/// Location of a token. `start` and `end` are byte offsets into the source
+/// (`end` is exclusive), while `line` and `col` are 1-based, with `col`
+/// counted in characters.
 #[derive(Debug)]
 #[derive(Clone)]
 pub struct TokLoc {
-    pub row: u64,
+    pub file: FileId,
+    pub line: u64,
     pub col: u64,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for TokLoc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+/// The result of tokenizing one source file: every token that could be lexed,
+/// plus every lexical error encountered along the way.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct TokenStream {
+    pub file: FileId,
+    pub tokens: Vec<Token>,
+    pub errors: Vec<LexError>,
+}
+
+impl TokenStream {
+    pub fn has_errors(&self) -> bool {
+        return self.errors.len() > 0;
+    }
+
+    pub fn into_result(self) -> Result<Vec<Token>, Vec<LexError>> {
+        return if self.has_errors() {
+            Err (self.errors)
+        }
+        else {
+            Ok (self.tokens)
+        };
+    }
+}
+
+/// Maps byte offsets in a source file back onto lines and 1-based columns.
+/// The first line of the source is numbered `first_line`.
+struct LexCtx<'a> {
+    source: &'a str,
+    file: FileId,
+    first_line: u64,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LexCtx<'a> {
+    fn new(source: &'a str, file: FileId, first_line: u64) -> Self {
+        let mut line_starts = vec!(0);
+
+        for (i, c) in source.char_indices() {
+            if c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+
+        return LexCtx {
+            source: source,
+            file: file,
+            first_line: first_line,
+            line_starts: line_starts,
+        };
+    }
+
+    fn loc(&self, start: usize, end: usize) -> TokLoc {
+        let line = match self.line_starts.binary_search(&start) {
+            Ok (i) => i,
+            Err (i) => i - 1,
+        };
+
+        let line_start = self.line_starts[line];
+        let col = self.source[line_start..start].chars().count();
+
+        return TokLoc {
+            file: self.file,
+            line: self.first_line + line as u64,
+            col: col as u64 + 1,
+            start: start,
+            end: end,
+        };
+    }
+
+    /// Location spanning from `start` up to the cursor's current position.
+    fn loc_to(&self, start: usize, it: &mut Peekable<CharIndices>) -> TokLoc {
+        let end = match it.peek() {
+            Some (&(i, _)) => i,
+            None => self.source.len(),
+        };
+
+        return self.loc(start, end);
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum LexError {
+    BadChar (String, TokLoc),
+    UnterminatedChar (TokLoc),
+    UnterminatedStr (TokLoc),
+    EmptyVarName (TokLoc),
+    EmptyFuncName (TokLoc),
+    IntegerOverflow (String, TokLoc),
+    FloatOverflow (String, TokLoc),
+    MalformedNumber (String, TokLoc),
+    UnexpectedChar (char, TokLoc),
+}
+
+impl LexError {
+    pub fn loc(&self) -> &TokLoc {
+        match self {
+            &LexError::BadChar (_, ref tl) => tl,
+            &LexError::UnterminatedChar (ref tl) => tl,
+            &LexError::UnterminatedStr (ref tl) => tl,
+            &LexError::EmptyVarName (ref tl) => tl,
+            &LexError::EmptyFuncName (ref tl) => tl,
+            &LexError::IntegerOverflow (_, ref tl) => tl,
+            &LexError::FloatOverflow (_, ref tl) => tl,
+            &LexError::MalformedNumber (_, ref tl) => tl,
+            &LexError::UnexpectedChar (_, ref tl) => tl,
+        }
+    }
+
+    fn message(&self) -> String {
+        return match self {
+            &LexError::BadChar (ref s, _) => {
+                format!("bad char literal '{}'", s)
+            }
+            &LexError::UnterminatedChar (_) => {
+                "unterminated char literal".to_owned()
+            }
+            &LexError::UnterminatedStr (_) => {
+                "unterminated string literal".to_owned()
+            }
+            &LexError::EmptyVarName (_) => {
+                "cannot have zero-length varname".to_owned()
+            }
+            &LexError::EmptyFuncName (_) => {
+                "cannot have zero-length funcname".to_owned()
+            }
+            &LexError::IntegerOverflow (ref s, _) => {
+                format!("integer literal {} does not fit in 64 bits", s)
+            }
+            &LexError::FloatOverflow (ref s, _) => {
+                format!("float literal {} is out of range", s)
+            }
+            &LexError::MalformedNumber (ref s, _) => {
+                format!("malformed numeric literal {}", s)
+            }
+            &LexError::UnexpectedChar (c, _) => {
+                format!("unexpected character {:?}", c)
+            }
+        };
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (code, label) = match self {
+            &LexError::BadChar (..) => ("E0100", "not a single character"),
+            &LexError::UnterminatedChar (_) => ("E0101", "literal starts here"),
+            &LexError::UnterminatedStr (_) => ("E0102", "literal starts here"),
+            &LexError::EmptyVarName (_) => ("E0103", "expected a name"),
+            &LexError::EmptyFuncName (_) => ("E0104", "expected a name"),
+            &LexError::IntegerOverflow (..) => ("E0105", "out of range"),
+            &LexError::FloatOverflow (..) => ("E0106", "out of range"),
+            &LexError::MalformedNumber (..) => ("E0107", "not a number"),
+            &LexError::UnexpectedChar (..) => ("E0108", "unexpected"),
+        };
+
+        return Diagnostic::error(code, self.message())
+            .with_primary(self.loc(), label);
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tl = self.loc();
+
+        write!(f, "L:{} C:{}: {}", tl.line, tl.col, self.message())
+    }
 }
 
 pub fn print_tokens(tokens: &Vec<Token>) {
@@ -73,48 +406,63 @@ pub fn print_tokens(tokens: &Vec<Token>) {
     }
 }
 
-fn tokenize_char(it: &mut Peekable<Enumerate<Chars>>, row: u64)
-    -> Option<Token>
+pub fn print_lex_errors(errors: &Vec<LexError>) {
+    for e in errors {
+        println!("{}", e);
+    }
+}
+
+fn tokenize_char(it: &mut Peekable<CharIndices>, ctx: &LexCtx)
+    -> Option<Result<Token, LexError>>
 {
     let mut s = String::new();
 
-    return if let Some (&(col, '\'')) = it.peek() {
+    return if let Some (&(start, '\'')) = it.peek() {
         it.next();
 
         let mut escaped = false;
-        let mut index = 0;
+        let mut terminated = false;
 
-        while let Some ((_, c)) = it.next() {
-            if index > 2 || (c == '\'' && index == 0) {
-                panic!("bad char");
-            }
-            else if c == '\'' && !escaped {
+        while let Some (&(_, c)) = it.peek() {
+            if c == '\n' {
                 break;
             }
-            else if c == '\\' && !escaped {
-                escaped = true;
-            }
-            else {
-                escaped = false;
+
+            it.next();
+
+            if c == '\'' && !escaped {
+                terminated = true;
+                break;
             }
 
+            escaped = c == '\\' && !escaped;
+
             s.push(c);
-            index += 1;
         }
 
-        Some (Token::CharLit(s, TokLoc {row: row, col: col as u64}))
+        let tl = ctx.loc_to(start, it);
+
+        if !terminated {
+            Some (Err (LexError::UnterminatedChar (tl)))
+        }
+        else if s.len() == 0 || s.chars().count() > 3 {
+            Some (Err (LexError::BadChar (s, tl)))
+        }
+        else {
+            Some (Ok (Token::CharLit(s, tl)))
+        }
     }
     else {
         None
     }
 }
 
-fn tokenize_varname(it: &mut Peekable<Enumerate<Chars>>, row: u64)
-    -> Option<Token>
+fn tokenize_varname(it: &mut Peekable<CharIndices>, ctx: &LexCtx)
+    -> Option<Result<Token, LexError>>
 {
     let mut s = String::new();
 
-    return if let Some (&(col, '%')) = it.peek() {
+    return if let Some (&(start, '%')) = it.peek() {
         it.next();
 
         while let Some (&(_, c)) = it.peek() {
@@ -127,23 +475,26 @@ fn tokenize_varname(it: &mut Peekable<Enumerate<Chars>>, row: u64)
             }
         }
 
+        let tl = ctx.loc_to(start, it);
+
         if s.len() == 0 {
-            panic!("Cannot have zero-length varname at L:{} C:{}", row, col);
+            Some (Err (LexError::EmptyVarName (tl)))
+        }
+        else {
+            Some (Ok (Token::VarName(s, tl)))
         }
-
-        Some (Token::VarName(s, TokLoc {row: row, col: col as u64}))
     }
     else {
         None
     }
 }
 
-fn tokenize_funcname(it: &mut Peekable<Enumerate<Chars>>, row: u64)
-    -> Option<Token>
+fn tokenize_funcname(it: &mut Peekable<CharIndices>, ctx: &LexCtx)
+    -> Option<Result<Token, LexError>>
 {
     let mut s = String::new();
 
-    return if let Some (&(col, '@')) = it.peek() {
+    return if let Some (&(start, '@')) = it.peek() {
         it.next();
 
         while let Some (&(_, c)) = it.peek() {
@@ -156,26 +507,29 @@ fn tokenize_funcname(it: &mut Peekable<Enumerate<Chars>>, row: u64)
             }
         }
 
+        let tl = ctx.loc_to(start, it);
+
         if s.len() == 0 {
-            panic!("Cannot have zero-length funcname at L:{} C:{}", row, col);
+            Some (Err (LexError::EmptyFuncName (tl)))
+        }
+        else {
+            Some (Ok (Token::FuncName(s, tl)))
         }
-
-        Some (Token::FuncName(s, TokLoc {row: row, col: col as u64}))
     }
     else {
         None
     }
 }
 
-fn tokenize_ident(it: &mut Peekable<Enumerate<Chars>>, row: u64)
+fn tokenize_ident(it: &mut Peekable<CharIndices>, ctx: &LexCtx)
     -> Option<Token>
 {
     let mut s = String::new();
 
-    let mut col_capture = 0;
+    let mut start = 0;
 
-    if let Some (&(col, _)) = it.peek() {
-        col_capture = col;
+    if let Some (&(i, _)) = it.peek() {
+        start = i;
     }
 
     while let Some (&(_, c)) = it.peek() {
@@ -188,239 +542,588 @@ fn tokenize_ident(it: &mut Peekable<Enumerate<Chars>>, row: u64)
         }
     }
 
-    let keyword = match s.as_ref() {
+    let tl = ctx.loc_to(start, it);
 
-        "i8"       => Some (
-            Token::I8Keyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "i16"       => Some (
-            Token::I16Keyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "i32"       => Some (
-            Token::I32Keyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "i64"       => Some (
-            Token::I64Keyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "f32"       => Some (
-            Token::F32Keyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "f64"       => Some (
-            Token::F64Keyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "void"       => Some (
-            Token::VoidKeyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "func"       => Some (
-            Token::FuncKeyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "add"       => Some (
-            Token::AddKeyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "sub"       => Some (
-            Token::SubKeyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "let"       => Some (
-            Token::LetKeyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "ret"       => Some (
-            Token::RetKeyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        "call"      => Some (
-            Token::CallKeyword (TokLoc {row: row, col: col_capture as u64})
-        ),
-        _          => None,
+    let keyword = match s.as_ref() {
+        "i1"        => Some (Token::I1Keyword (tl.clone())),
+        "bool"      => Some (Token::I1Keyword (tl.clone())),
+        "i8"        => Some (Token::I8Keyword (tl.clone())),
+        "i16"       => Some (Token::I16Keyword (tl.clone())),
+        "i32"       => Some (Token::I32Keyword (tl.clone())),
+        "i64"       => Some (Token::I64Keyword (tl.clone())),
+        "f32"       => Some (Token::F32Keyword (tl.clone())),
+        "f64"       => Some (Token::F64Keyword (tl.clone())),
+        "void"      => Some (Token::VoidKeyword (tl.clone())),
+        "func"      => Some (Token::FuncKeyword (tl.clone())),
+        "declare"   => Some (Token::DeclareKeyword (tl.clone())),
+        "global"    => Some (Token::GlobalKeyword (tl.clone())),
+        "constant"  => Some (Token::ConstantKeyword (tl.clone())),
+        "zeroinit"  => Some (Token::ZeroInitKeyword (tl.clone())),
+        "addrof"    => Some (Token::AddrOfKeyword (tl.clone())),
+        "add"       => Some (Token::AddKeyword (tl.clone())),
+        "sub"       => Some (Token::SubKeyword (tl.clone())),
+        "mul"       => Some (Token::MulKeyword (tl.clone())),
+        "sdiv"      => Some (Token::SDivKeyword (tl.clone())),
+        "udiv"      => Some (Token::UDivKeyword (tl.clone())),
+        "srem"      => Some (Token::SRemKeyword (tl.clone())),
+        "urem"      => Some (Token::URemKeyword (tl.clone())),
+        "and"       => Some (Token::AndKeyword (tl.clone())),
+        "or"        => Some (Token::OrKeyword (tl.clone())),
+        "xor"       => Some (Token::XorKeyword (tl.clone())),
+        "shl"       => Some (Token::ShlKeyword (tl.clone())),
+        "lshr"      => Some (Token::LShrKeyword (tl.clone())),
+        "ashr"      => Some (Token::AShrKeyword (tl.clone())),
+        "neg"       => Some (Token::NegKeyword (tl.clone())),
+        "not"       => Some (Token::NotKeyword (tl.clone())),
+        "icmp"      => Some (Token::ICmpKeyword (tl.clone())),
+        "fcmp"      => Some (Token::FCmpKeyword (tl.clone())),
+        "alloca"    => Some (Token::AllocaKeyword (tl.clone())),
+        "load"      => Some (Token::LoadKeyword (tl.clone())),
+        "store"     => Some (Token::StoreKeyword (tl.clone())),
+        "gep"       => Some (Token::GepKeyword (tl.clone())),
+        "extractvalue" => Some (Token::ExtractValueKeyword (tl.clone())),
+        "insertvalue"  => Some (Token::InsertValueKeyword (tl.clone())),
+        "type"      => Some (Token::TypeKeyword (tl.clone())),
+        "trunc"     => Some (Token::TruncKeyword (tl.clone())),
+        "zext"      => Some (Token::ZExtKeyword (tl.clone())),
+        "sext"      => Some (Token::SExtKeyword (tl.clone())),
+        "fptosi"    => Some (Token::FPToSIKeyword (tl.clone())),
+        "fptoui"    => Some (Token::FPToUIKeyword (tl.clone())),
+        "sitofp"    => Some (Token::SIToFPKeyword (tl.clone())),
+        "uitofp"    => Some (Token::UIToFPKeyword (tl.clone())),
+        "fpext"     => Some (Token::FPExtKeyword (tl.clone())),
+        "fptrunc"   => Some (Token::FPTruncKeyword (tl.clone())),
+        "bitcast"   => Some (Token::BitcastKeyword (tl.clone())),
+        "ptrtoint"  => Some (Token::PtrToIntKeyword (tl.clone())),
+        "inttoptr"  => Some (Token::IntToPtrKeyword (tl.clone())),
+        "let"       => Some (Token::LetKeyword (tl.clone())),
+        "ret"       => Some (Token::RetKeyword (tl.clone())),
+        "call"      => Some (Token::CallKeyword (tl.clone())),
+        "br"        => Some (Token::BrKeyword (tl.clone())),
+        "switch"    => Some (Token::SwitchKeyword (tl.clone())),
+        "phi"       => Some (Token::PhiKeyword (tl.clone())),
+        _           => None,
     };
 
     return if s.len() == 0 {
         None
     }
-    else if let Some (_) = keyword {
+    else if keyword.is_some() {
         keyword
     }
     else if s == "_" {
-        Some (Token::Underscore (TokLoc {row: row, col: col_capture as u64}))
+        Some (Token::Underscore (tl))
     }
     else {
-        Some (Token::Ident(s, TokLoc {row: row, col: col_capture as u64}))
+        Some (Token::Ident(s, tl))
     }
 }
 
-fn tokenize_number(it: &mut Peekable<Enumerate<Chars>>, row: u64)
-    -> Option<Token>
-{
+fn is_ident_char(c: char) -> bool {
+    return c.is_digit(10) || c.is_alphabetic() || c == '_';
+}
+
+/// Consume a run of digits in the given radix, along with any '_' digit
+/// separators mixed in among them.
+fn take_digits(it: &mut Peekable<CharIndices>, s: &mut String, radix: u32) {
+    while let Some (&(_, c)) = it.peek() {
+        if c.is_digit(radix) || c == '_' {
+            s.push(c);
+            it.next();
+        }
+        else {
+            break;
+        }
+    }
+}
+
+/// Yield the character `n` positions past the one currently under the cursor.
+fn peek_nth(it: &Peekable<CharIndices>, n: usize) -> Option<char> {
+    let mut look = it.clone();
+
+    for _ in 0..n {
+        look.next();
+    }
+
+    return look.peek().map(|&(_, c)| c);
+}
+
+/// Scale `val` by 2^`exp` without losing precision to an intermediate
+/// overflow or underflow of the scale factor itself.
+fn scale_by_pow2(mut val: f64, mut exp: i64) -> f64 {
+    let pow2 = |e: i64| f64::from_bits(((e + 1023) as u64) << 52);
+
+    while exp > 1023 {
+        val *= pow2(1023);
+        exp -= 1023;
+    }
+    while exp < -1022 {
+        val *= pow2(-1022);
+        exp += 1022;
+    }
+
+    return val * pow2(exp);
+}
+
+/// Whether the cursor, sitting just past a `0x` prefix, is on a hex float
+/// rather than a hex integer.
+fn at_hex_float(it: &Peekable<CharIndices>) -> bool {
+    let mut look = it.clone();
     let mut s = String::new();
 
-    let mut col_capture = 0;
+    take_digits(&mut look, &mut s, 16);
+
+    return match look.peek() {
+        Some (&(_, c)) => c == '.' || c == 'p' || c == 'P',
+        None => false,
+    };
+}
+
+/// Scan a hex float of the form `0x<hex>[.<hex>]p[+-]<dec>`, with the cursor
+/// sitting just past the `0x` prefix.
+fn tokenize_hex_float(it: &mut Peekable<CharIndices>, s: &mut String)
+    -> Option<f64>
+{
+    let mut mantissa: u64 = 0;
+    let mut frac_digits: i64 = 0;
+    let mut seen_dot = false;
+    let mut seen_digit = false;
+
+    while let Some (&(_, c)) = it.peek() {
+        if c == '_' {}
+        else if let Some (d) = c.to_digit(16) {
+            mantissa = match mantissa.checked_mul(16) {
+                Some (m) => m + d as u64,
+                None => return None,
+            };
+
+            if seen_dot {
+                frac_digits += 1;
+            }
+
+            seen_digit = true;
+        }
+        else if c == '.' && !seen_dot {
+            seen_dot = true;
+        }
+        else {
+            break;
+        }
+
+        s.push(c);
+        it.next();
+    }
+
+    match it.peek() {
+        Some (&(_, 'p')) | Some (&(_, 'P')) if seen_digit => {}
+        _ => return None,
+    }
+
+    s.push('p');
+    it.next();
+
+    let mut exp_str = String::new();
+
+    if let Some (&(_, c)) = it.peek() {
+        if c == '+' || c == '-' {
+            exp_str.push(c);
+            it.next();
+        }
+    }
+
+    take_digits(it, &mut exp_str, 10);
+    s.push_str(&exp_str);
+
+    return match exp_str.parse::<i64>() {
+        Ok (exp) if exp.abs() < 100000 => Some (
+            scale_by_pow2(mantissa as f64, exp - 4 * frac_digits)
+        ),
+        _ => None,
+    };
+}
+
+/// Width suffixes that may trail an integer literal, as in `255i8`.
+const INT_SUFFIXES: [&'static str; 4] = ["i8", "i16", "i32", "i64"];
+
+/// Consume an integer width suffix, if one directly follows the literal.
+fn take_int_suffix(it: &mut Peekable<CharIndices>, ctx: &LexCtx)
+    -> Option<String>
+{
+    let rest = match it.peek() {
+        Some (&(i, _)) => &ctx.source[i..],
+        None => "",
+    };
+
+    for suffix in INT_SUFFIXES.iter() {
+        if !rest.starts_with(suffix) {
+            continue;
+        }
+
+        if let Some (c) = rest[suffix.len()..].chars().next() {
+            if is_ident_char(c) {
+                continue;
+            }
+        }
 
-    if let Some (&(col, _)) = it.peek() {
-        col_capture = col;
+        for _ in 0..suffix.len() {
+            it.next();
+        }
+
+        return Some (suffix.to_string());
     }
 
+    return None;
+}
+
+/// Consume any identifier characters running straight on from a numeric
+/// literal, as in `0b102` or `12abc`. Returns whether there were any.
+fn take_trailing_garbage(it: &mut Peekable<CharIndices>, s: &mut String)
+    -> bool
+{
+    let mut found = false;
+
     while let Some (&(_, c)) = it.peek() {
-        if c.is_digit(10) {
+        if is_ident_char(c) {
             s.push(c);
             it.next();
+            found = true;
         }
         else {
             break;
         }
     }
 
-    if s.len() == 0 {
-        return None;
+    return found;
+}
+
+/// Convert the digits of an integer literal into its value. Decimal literals
+/// must fit in an i64, while prefixed literals spell out a 64-bit pattern and
+/// so may use the full unsigned range. The value is kept exact, so that the
+/// typechecker can hold it against the width of its destination.
+fn int_literal_value(digits: &str, radix: u32, negative: bool)
+    -> Option<i128>
+{
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+
+    let magnitude = match u64::from_str_radix(&digits, radix) {
+        Ok (m) => m as i128,
+        Err (_) => return None,
+    };
+
+    return if negative {
+        if magnitude <= 1 << 63 {
+            Some (-magnitude)
+        }
+        else {
+            None
+        }
     }
+    else if radix == 10 && magnitude > i64::max_value() as i128 {
+        None
+    }
+    else {
+        Some (magnitude)
+    };
+}
 
-    match s.parse::<i64>() {
-        Ok (i) => return Some (
-            Token::Integer(i, TokLoc {row: row, col: col_capture as u64})
-        ),
-        Err (msg) => panic!("Unexpected failure: {}", msg),
+/// Numeric literals are scanned on a lookahead copy of the cursor, so that a
+/// '-' which does not begin a numeric literal is left for `tokenize_op`.
+fn tokenize_number(it: &mut Peekable<CharIndices>, ctx: &LexCtx)
+    -> Option<Result<Token, LexError>>
+{
+    let mut look = it.clone();
+    let mut s = String::new();
+
+    let start = match look.peek() {
+        Some (&(i, _)) => i,
+        None => return None,
+    };
+
+    if let Some (&(_, '-')) = look.peek() {
+        s.push('-');
+        look.next();
+    }
+
+    let negative = s.len() > 0;
+    let rest = &ctx.source[start + s.len()..];
+
+    match look.peek() {
+        Some (&(_, c)) if c.is_digit(10) => {}
+        _ => return None,
+    }
+
+    let radix = match peek_nth(&look, 1) {
+        Some ('x') | Some ('X') if rest.starts_with('0') => 16,
+        Some ('o') | Some ('O') if rest.starts_with('0') => 8,
+        Some ('b') | Some ('B') if rest.starts_with('0') => 2,
+        _ => 10,
+    };
+
+    if radix != 10 {
+        s.push_str(&rest[..2]);
+        look.next();
+        look.next();
+
+        if radix == 16 && at_hex_float(&look) {
+            let val = tokenize_hex_float(&mut look, &mut s);
+            let garbage = take_trailing_garbage(&mut look, &mut s);
+
+            *it = look;
+
+            let tl = ctx.loc_to(start, it);
+
+            return match val {
+                _ if garbage => {
+                    Some (Err (LexError::MalformedNumber (s, tl)))
+                }
+                Some (f) if f.is_infinite() => {
+                    Some (Err (LexError::FloatOverflow (s, tl)))
+                }
+                Some (f) => {
+                    let val = if negative { -f } else { f };
+
+                    Some (Ok (Token::Float (val, tl)))
+                }
+                None => Some (Err (LexError::MalformedNumber (s, tl))),
+            };
+        }
+
+        let mut digits = String::new();
+
+        take_digits(&mut look, &mut digits, radix);
+        s.push_str(&digits);
+
+        let suffix = take_int_suffix(&mut look, ctx);
+        let garbage = take_trailing_garbage(&mut look, &mut s);
+
+        *it = look;
+
+        let tl = ctx.loc_to(start, it);
+
+        return if garbage || digits.chars().all(|c| c == '_') {
+            Some (Err (LexError::MalformedNumber (s, tl)))
+        }
+        else if let Some (i) = int_literal_value(&digits, radix, negative) {
+            Some (Ok (Token::Integer (i, suffix, tl)))
+        }
+        else {
+            Some (Err (LexError::IntegerOverflow (s, tl)))
+        };
+    }
+
+    take_digits(&mut look, &mut s, 10);
+
+    let mut is_float = false;
+
+    if let Some (&(_, '.')) = look.peek() {
+        if let Some (c) = peek_nth(&look, 1) {
+            if c.is_digit(10) {
+                is_float = true;
+
+                s.push('.');
+                look.next();
+
+                take_digits(&mut look, &mut s, 10);
+            }
+        }
+    }
+
+    if let Some (&(_, e)) = look.peek() {
+        if e == 'e' || e == 'E' {
+            let sign = match peek_nth(&look, 1) {
+                Some (c) if c == '+' || c == '-' => Some (c),
+                _ => None,
+            };
+
+            let digit_pos = if sign.is_some() { 2 } else { 1 };
+
+            if let Some (c) = peek_nth(&look, digit_pos) {
+                if c.is_digit(10) {
+                    is_float = true;
+
+                    s.push(e);
+                    look.next();
+
+                    if let Some (c) = sign {
+                        s.push(c);
+                        look.next();
+                    }
+
+                    take_digits(&mut look, &mut s, 10);
+                }
+            }
+        }
+    }
+
+    if is_float {
+        let cleaned: String = s.chars().filter(|&c| c != '_').collect();
+        let garbage = take_trailing_garbage(&mut look, &mut s);
+
+        *it = look;
+
+        let tl = ctx.loc_to(start, it);
+
+        if garbage {
+            return Some (Err (LexError::MalformedNumber (s, tl)));
+        }
+
+        return match cleaned.parse::<f64>() {
+            Ok (f) if !f.is_infinite() => Some (Ok (Token::Float (f, tl))),
+            Ok (_) => Some (Err (LexError::FloatOverflow (s, tl))),
+            Err (_) => Some (Err (LexError::MalformedNumber (s, tl))),
+        };
+    }
+
+    let suffix = take_int_suffix(&mut look, ctx);
+    let digits = if negative { s[1..].to_owned() } else { s.clone() };
+    let garbage = take_trailing_garbage(&mut look, &mut s);
+
+    *it = look;
+
+    let tl = ctx.loc_to(start, it);
+
+    return if garbage {
+        Some (Err (LexError::MalformedNumber (s, tl)))
     }
+    else if let Some (i) = int_literal_value(&digits, 10, negative) {
+        Some (Ok (Token::Integer (i, suffix, tl)))
+    }
+    else {
+        Some (Err (LexError::IntegerOverflow (s, tl)))
+    };
 }
 
-fn tokenize_op(it: &mut Peekable<Enumerate<Chars>>, row: u64) -> Option<Token> {
-    return if let Some (&(col, c)) = it.peek() {
+fn tokenize_op(it: &mut Peekable<CharIndices>, ctx: &LexCtx) -> Option<Token> {
+    return if let Some (&(start, c)) = it.peek() {
         match c {
             '!' => {
                 it.next();
-                Some (Token::Bang (TokLoc {row: row, col: col as u64}))
+                Some (Token::Bang (ctx.loc_to(start, it)))
             }
             '(' => {
                 it.next();
-                Some (Token::LParen (TokLoc {row: row, col: col as u64}))
+                Some (Token::LParen (ctx.loc_to(start, it)))
             }
             ')' => {
                 it.next();
-                Some (Token::RParen (TokLoc {row: row, col: col as u64}))
+                Some (Token::RParen (ctx.loc_to(start, it)))
             }
             '{' => {
                 it.next();
-                Some (Token::LBrace (TokLoc {row: row, col: col as u64}))
+                Some (Token::LBrace (ctx.loc_to(start, it)))
             }
             '}' => {
                 it.next();
-                Some (Token::RBrace (TokLoc {row: row, col: col as u64}))
+                Some (Token::RBrace (ctx.loc_to(start, it)))
             }
             '[' => {
                 it.next();
-                Some (Token::LBracket (TokLoc {row: row, col: col as u64}))
+                Some (Token::LBracket (ctx.loc_to(start, it)))
             }
             ']' => {
                 it.next();
-                Some (Token::RBracket (TokLoc {row: row, col: col as u64}))
+                Some (Token::RBracket (ctx.loc_to(start, it)))
             }
             '<' => {
                 it.next();
-                Some (Token::LWakka (TokLoc {row: row, col: col as u64}))
+                Some (Token::LWakka (ctx.loc_to(start, it)))
             }
             '>' => {
                 it.next();
-                Some (Token::RWakka (TokLoc {row: row, col: col as u64}))
+                Some (Token::RWakka (ctx.loc_to(start, it)))
             }
             '?' => {
                 it.next();
-                Some (Token::QuestionMark (TokLoc {row: row, col: col as u64}))
+                Some (Token::QuestionMark (ctx.loc_to(start, it)))
             }
             ',' => {
                 it.next();
-                Some (Token::Comma (TokLoc {row: row, col: col as u64}))
+                Some (Token::Comma (ctx.loc_to(start, it)))
             }
             '#' => {
                 it.next();
-                Some (Token::Pound (TokLoc {row: row, col: col as u64}))
+                Some (Token::Pound (ctx.loc_to(start, it)))
             }
             '*' => {
                 it.next();
-                Some (Token::Asterisk (TokLoc {row: row, col: col as u64}))
+                Some (Token::Asterisk (ctx.loc_to(start, it)))
             }
             ':' => {
                 it.next();
-                if let Some (&(col, ':')) = it.peek() {
+                if let Some (&(_, ':')) = it.peek() {
                     it.next();
 
-                    Some (
-                        Token::DoubleColon (
-                            TokLoc {row: row, col: col as u64}
-                        )
-                    )
+                    Some (Token::DoubleColon (ctx.loc_to(start, it)))
                 }
                 else {
-                    Some (Token::Colon (TokLoc {row: row, col: col as u64}))
+                    Some (Token::Colon (ctx.loc_to(start, it)))
                 }
             }
             '&' => {
                 it.next();
-                if let Some (&(col, '&')) = it.peek() {
+                if let Some (&(_, '&')) = it.peek() {
                     it.next();
 
-                    Some (
-                        Token::DoubleAmpersand (
-                            TokLoc {row: row, col: col as u64}
-                        )
-                    )
+                    Some (Token::DoubleAmpersand (ctx.loc_to(start, it)))
                 }
                 else {
-                    Some (Token::Ampersand (TokLoc {row: row, col: col as u64}))
+                    Some (Token::Ampersand (ctx.loc_to(start, it)))
                 }
             }
             '|' => {
                 it.next();
-                if let Some (&(col, '|')) = it.peek() {
+                if let Some (&(_, '|')) = it.peek() {
                     it.next();
 
-                    Some (
-                        Token::DoublePipe (
-                            TokLoc {row: row, col: col as u64}
-                        )
-                    )
+                    Some (Token::DoublePipe (ctx.loc_to(start, it)))
                 }
                 else {
-                    Some (Token::Pipe (TokLoc {row: row, col: col as u64}))
+                    Some (Token::Pipe (ctx.loc_to(start, it)))
                 }
             }
             '-' => {
                 it.next();
-                if let Some (&(col, '>')) = it.peek() {
+                if let Some (&(_, '>')) = it.peek() {
                     it.next();
 
-                    Some (
-                        Token::RThinArrow (TokLoc {row: row, col: col as u64})
-                    )
+                    Some (Token::RThinArrow (ctx.loc_to(start, it)))
                 }
                 else {
-                    Some (Token::Minus (TokLoc {row: row, col: col as u64}))
+                    Some (Token::Minus (ctx.loc_to(start, it)))
                 }
             }
             '=' => {
                 it.next();
-                if let Some (&(col, '>')) = it.peek() {
+                if let Some (&(_, '>')) = it.peek() {
                     it.next();
 
-                    Some (Token::RFatArrow (TokLoc {row: row, col: col as u64}))
+                    Some (Token::RFatArrow (ctx.loc_to(start, it)))
                 }
-                else if let Some (&(col, '=')) = it.peek() {
+                else if let Some (&(_, '=')) = it.peek() {
                     it.next();
 
-                    Some (
-                        Token::DoubleEquals (
-                            TokLoc {row: row, col: col as u64}
-                        )
-                    )
+                    Some (Token::DoubleEquals (ctx.loc_to(start, it)))
                 }
                 else {
-                    Some (Token::Equals (TokLoc {row: row, col: col as u64}))
+                    Some (Token::Equals (ctx.loc_to(start, it)))
                 }
             }
             '.' => {
                 it.next();
-                if let Some (&(col, '.')) = it.peek() {
+                if let Some (&(_, '.')) = it.peek() {
                     it.next();
 
-                    Some (Token::DotDot (TokLoc {row: row, col: col as u64}))
+                    if let Some (&(_, '.')) = it.peek() {
+                        it.next();
+
+                        Some (Token::Ellipsis (ctx.loc_to(start, it)))
+                    }
+                    else {
+                        Some (Token::DotDot (ctx.loc_to(start, it)))
+                    }
                 }
                 else {
-                    Some (Token::Dot (TokLoc {row: row, col: col as u64}))
+                    Some (Token::Dot (ctx.loc_to(start, it)))
                 }
             }
             _ => None,
@@ -431,74 +1134,320 @@ fn tokenize_op(it: &mut Peekable<Enumerate<Chars>>, row: u64) -> Option<Token> {
     }
 }
 
-fn tokenize_str(it: &mut Peekable<Enumerate<Chars>>, row: u64)
-    -> Option<Token>
+fn tokenize_str(it: &mut Peekable<CharIndices>, ctx: &LexCtx)
+    -> Option<Result<Token, LexError>>
 {
     let mut s = String::new();
 
-    return if let Some (&(col, '"')) = it.peek() {
+    return if let Some (&(start, '"')) = it.peek() {
         it.next();
 
         let mut escaped = false;
+        let mut terminated = false;
 
-        while let Some ((_, c)) = it.next() {
-            if c == '"' && !escaped {
+        while let Some (&(_, c)) = it.peek() {
+            if c == '\n' {
                 break;
             }
-            if c == '\\' && !escaped {
-                escaped = true;
-            }
-            else {
-                escaped = false;
+
+            it.next();
+
+            if c == '"' && !escaped {
+                terminated = true;
+                break;
             }
+            escaped = c == '\\' && !escaped;
 
             s.push(c);
         }
 
-        Some (Token::StrLit(s, TokLoc {row: row, col: col as u64}))
+        let tl = ctx.loc_to(start, it);
+
+        if terminated {
+            Some (Ok (Token::StrLit(s, tl)))
+        }
+        else {
+            Some (Err (LexError::UnterminatedStr (tl)))
+        }
     }
     else {
         None
     }
 }
 
-pub fn tokenize_line(line: &str, row: u64) -> Vec<Token> {
+/// Tokenize a single line, yielding the first lexical error encountered on
+/// the line if there is one. Tokens are located on line `row` of file 0, and
+/// anything after the first newline is ignored.
+pub fn tokenize_line(line: &str, row: u64) -> Result<Vec<Token>, LexError> {
+    let line = line.lines().next().unwrap_or("");
+    let mut stream = tokenize_from(line, FileId (0), row);
+
+    return if stream.has_errors() {
+        Err (stream.errors.remove(0))
+    }
+    else {
+        Ok (stream.tokens)
+    };
+}
+
+/// Tokenize an entire source file. Lexing continues past bad input, so the
+/// resulting stream holds every lexical error in the file, in source order.
+pub fn tokenize(source: &str, file: FileId) -> TokenStream {
+    return tokenize_from(source, file, 1);
+}
+
+fn tokenize_from(source: &str, file: FileId, first_line: u64) -> TokenStream {
+    let ctx = LexCtx::new(source, file, first_line);
+
     let mut tokens = Vec::new();
+    let mut errors = Vec::new();
 
-    let mut it = line.chars().enumerate().peekable();
+    let mut it = source.char_indices().peekable();
 
-    while let Some (&(_, c)) = it.peek() {
+    while let Some (&(start, c)) = it.peek() {
         if c.is_whitespace() {
             it.next();
+            continue;
         }
         else if c == ';' {
-            break;
+            while let Some (&(_, c)) = it.peek() {
+                if c == '\n' {
+                    break;
+                }
+
+                it.next();
+            }
+
+            continue;
         }
-        else if let Some (str_tok) = tokenize_str(&mut it, row) {
-            tokens.push(str_tok);
+
+        let result = if let Some (str_res) = tokenize_str(&mut it, &ctx) {
+            str_res
         }
-        else if let Some (char_tok) = tokenize_char(&mut it, row) {
-            tokens.push(char_tok);
+        else if let Some (char_res) = tokenize_char(&mut it, &ctx) {
+            char_res
         }
-        else if let Some (op_tok) = tokenize_op(&mut it, row) {
-            tokens.push(op_tok);
+        else if let Some (num_res) = tokenize_number(&mut it, &ctx) {
+            num_res
         }
-        else if let Some (num_tok) = tokenize_number(&mut it, row) {
-            tokens.push(num_tok);
+        else if let Some (op_tok) = tokenize_op(&mut it, &ctx) {
+            Ok (op_tok)
         }
-        else if let Some (varname_tok) = tokenize_varname(&mut it, row) {
-            tokens.push(varname_tok);
+        else if let Some (varname_res) = tokenize_varname(&mut it, &ctx) {
+            varname_res
         }
-        else if let Some (varname_tok) = tokenize_funcname(&mut it, row) {
-            tokens.push(varname_tok);
+        else if let Some (funcname_res) = tokenize_funcname(&mut it, &ctx) {
+            funcname_res
         }
-        else if let Some (ident_tok) = tokenize_ident(&mut it, row) {
-            tokens.push(ident_tok);
+        else if let Some (ident_tok) = tokenize_ident(&mut it, &ctx) {
+            Ok (ident_tok)
         }
         else {
             it.next();
+            Err (LexError::UnexpectedChar (c, ctx.loc_to(start, &mut it)))
+        };
+
+        match result {
+            Ok (tok) => tokens.push(tok),
+            Err (err) => errors.push(err),
+        }
+    }
+
+    return TokenStream {
+        file: file,
+        tokens: tokens,
+        errors: errors,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(source: &str) -> TokenStream {
+        return tokenize(source, FileId (0));
+    }
+
+    #[test]
+    fn bad_input_gives_errors_not_panics() {
+        let stream = lex("'abcd' '' \"open");
+
+        match &stream.errors[..] {
+            &[
+                LexError::BadChar (ref long, _),
+                LexError::BadChar (ref empty, _),
+                LexError::UnterminatedStr (_),
+            ] => assert_eq!((&long[..], &empty[..]), ("abcd", "")),
+            errors => panic!("unexpected errors {:?}", errors),
+        }
+
+        let stream = lex("%: @ $");
+
+        match &stream.errors[..] {
+            &[
+                LexError::EmptyVarName (_),
+                LexError::EmptyFuncName (_),
+                LexError::UnexpectedChar ('$', _),
+            ] => {}
+            errors => panic!("unexpected errors {:?}", errors),
+        }
+
+        match &lex("'x").errors[..] {
+            &[LexError::UnterminatedChar (_)] => {}
+            errors => panic!("unexpected errors {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn lexing_goes_on_after_an_error() {
+        let stream = lex("add $ %x:i32\n$ ret %x");
+
+        assert_eq!(stream.errors.len(), 2);
+        assert_eq!(stream.tokens.len(), 6);
+
+        match stream.tokens.last() {
+            Some (&Token::VarName (ref name, _)) => assert_eq!(name, "x"),
+            tok => panic!("unexpected token {:?}", tok),
+        }
+    }
+
+    #[test]
+    fn errors_carry_codes_and_locations() {
+        let stream = lex("ret\n  $");
+        let err = &stream.errors[0];
+
+        assert_eq!((err.loc().line, err.loc().col), (2, 3));
+        assert_eq!(err.to_string(), "L:2 C:3: unexpected character '$'");
+        assert_eq!(err.to_diagnostic().code, "E0108");
+    }
+
+    #[test]
+    fn tokens_carry_byte_spans_and_file() {
+        let source = "; é\nret  %xé:i64";
+        let stream = tokenize(source, FileId (7));
+
+        assert!(!stream.has_errors());
+        assert_eq!(stream.file, FileId (7));
+
+        let spans: Vec<(u64, u64, &str)> = stream.tokens.iter().map(|tok| {
+            let tl = tok.loc();
+
+            assert_eq!(tl.file, FileId (7));
+            (tl.line, tl.col, &source[tl.start..tl.end])
+        }).collect();
+
+        assert_eq!(spans, vec!(
+            (2, 1, "ret"), (2, 6, "%xé"), (2, 9, ":"), (2, 10, "i64"),
+        ));
+    }
+
+    #[test]
+    fn tokenize_line_numbers_from_row() {
+        let tokens = tokenize_line("br done\nret", 12).unwrap();
+
+        assert_eq!(tokens.len(), 2);
+        assert_eq!((tokens[1].loc().line, tokens[1].loc().col), (12, 4));
+
+        let err = tokenize_line("ret $ $", 3).unwrap_err();
+
+        assert_eq!((err.loc().line, err.loc().col), (3, 5));
+    }
+
+    #[test]
+    fn into_result_keeps_every_error() {
+        assert!(tokenize("ret %x", FileId (0)).into_result().is_ok());
+
+        match tokenize("$\n$", FileId (0)).into_result() {
+            Err (errors) => assert_eq!(errors.len(), 2),
+            Ok (tokens) => panic!("unexpected tokens {:?}", tokens),
+        }
+    }
+
+    fn floats(source: &str) -> Vec<f64> {
+        return lex(source).tokens.iter().map(|tok| match tok {
+            &Token::Float (val, _) => val,
+            tok => panic!("unexpected token {:?}", tok),
+        }).collect();
+    }
+
+    #[test]
+    fn float_literals() {
+        assert_eq!(
+            floats("1.5 -0.25 2e3 1.5E-2 0x1p4 -0x1.8p1 0x.8p0"),
+            vec!(1.5, -0.25, 2000.0, 0.015, 16.0, -3.0, 0.5)
+        );
+        assert!(floats("-0.0")[0].is_sign_negative());
+    }
+
+    #[test]
+    fn float_spellings_lex_as_words() {
+        match &lex("inf nan").tokens[..] {
+            &[Token::Ident (ref a, _), Token::Ident (ref b, _)] => {
+                assert_eq!((&a[..], &b[..]), ("inf", "nan"));
+            }
+            tokens => panic!("unexpected tokens {:?}", tokens),
+        }
+    }
+
+    #[test]
+    fn bad_float_literals() {
+        match &lex("1e999 0x1p 1.5x").errors[..] {
+            &[
+                LexError::FloatOverflow (..),
+                LexError::MalformedNumber (..),
+                LexError::MalformedNumber (..),
+            ] => {}
+            errors => panic!("unexpected errors {:?}", errors),
+        }
+    }
+
+    fn ints(source: &str) -> Vec<(i128, Option<String>)> {
+        return lex(source).tokens.into_iter().map(|tok| match tok {
+            Token::Integer (val, suffix, _) => (val, suffix),
+            tok => panic!("unexpected token {:?}", tok),
+        }).collect();
+    }
+
+    #[test]
+    fn integer_literals() {
+        let vals: Vec<i128> = ints("42 -7 0x2A 0o52 0b10_1010 -0x80 1_000")
+            .into_iter().map(|(val, _)| val).collect();
+
+        assert_eq!(vals, vec!(42, -7, 42, 42, 42, -128, 1000));
+
+        assert_eq!(
+            ints("255i8 -1i64 0xffi16"),
+            vec!(
+                (255, Some ("i8".to_owned())),
+                (-1, Some ("i64".to_owned())),
+                (255, Some ("i16".to_owned())),
+            )
+        );
+    }
+
+    #[test]
+    fn integer_literals_keep_their_exact_value() {
+        assert_eq!(
+            ints("0xffffffffffffffff -9223372036854775808"),
+            vec!((0xffff_ffff_ffff_ffff, None), (-(1 << 63), None))
+        );
+
+        let stream = lex(
+            "9223372036854775808 -9223372036854775809 0x1_0000_0000_0000_0000"
+        );
+
+        assert_eq!(stream.errors.len(), 3);
+
+        for err in &stream.errors {
+            match err {
+                &LexError::IntegerOverflow (..) => {}
+                err => panic!("unexpected error {:?}", err),
+            }
         }
     }
 
-    return tokens;
+    #[test]
+    fn malformed_integer_literals() {
+        assert_eq!(lex("0x 0b2 12ab 0x_").errors.len(), 4);
+    }
 }
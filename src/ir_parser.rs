@@ -1,4 +1,5 @@
 use ir_lexer::*;
+use ir_diagnostic::*;
 use std::fmt;
 
 use std::iter::Peekable;
@@ -7,6 +8,7 @@ use std::slice::Iter;
 #[derive(Debug)]
 #[derive(Clone)]
 pub enum Type {
+    I1,
     I8,
     I16,
     I32,
@@ -15,11 +17,13 @@ pub enum Type {
     F64,
     Void,
     Ptr (Box<Type>),
+    Array (Box<Type>, u64),
     UserType (String),
 }
 
 pub fn is_promotable_to(left: &Type, right: &Type) -> bool {
     return match (left, right) {
+        (&Type::I1, &Type::I1) => true,
         (&Type::I8, &Type::I8) => true,
         (&Type::I16, &Type::I16) => true,
         (&Type::I32, &Type::I32) => true,
@@ -27,9 +31,13 @@ pub fn is_promotable_to(left: &Type, right: &Type) -> bool {
         (&Type::F32, &Type::F32) => true,
         (&Type::F64, &Type::F64) => true,
         (&Type::Void, &Type::Void) => true,
-        // (&Type::Ptr (ref t_l), &Type::Ptr (ref t_r)) => {
-        //     is_promotable_to(&*t_l, &*t_r)
-        // }
+        (&Type::Ptr (ref t_l), &Type::Ptr (ref t_r)) => {
+            is_promotable_to(t_l, t_r)
+        }
+        (&Type::Array (ref t_l, n_l), &Type::Array (ref t_r, n_r)) => {
+            n_l == n_r && is_promotable_to(t_l, t_r)
+        }
+        (&Type::UserType (ref s_l), &Type::UserType (ref s_r)) => s_l == s_r,
         _ => false
     }
 }
@@ -37,6 +45,7 @@ pub fn is_promotable_to(left: &Type, right: &Type) -> bool {
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            &Type::I1 => write!(f, "i1"),
             &Type::I8 => write!(f, "i8"),
             &Type::I16 => write!(f, "i16"),
             &Type::I32 => write!(f, "i32"),
@@ -45,6 +54,7 @@ impl fmt::Display for Type {
             &Type::F64 => write!(f, "f64"),
             &Type::Void => write!(f, "void"),
             &Type::Ptr (ref t) => write!(f, "{}*", t),
+            &Type::Array (ref t, n) => write!(f, "[{} x {}]", n, t),
             &Type::UserType (ref s) => write!(f, "{}", s),
         }
     }
@@ -74,6 +84,30 @@ impl fmt::Display for Function {
     }
 }
 
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Global {
+    pub name: String,
+}
+
+impl fmt::Display for Global {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "@{}", self.name)
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Label {
+    pub name: String,
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
 #[derive(Debug)]
 #[derive(Clone)]
 pub struct VarTypePair {
@@ -93,6 +127,10 @@ pub struct FuncSig {
     pub name: String,
     pub typename: Type,
     pub arglist: Vec<VarTypePair>,
+    /// Whether further arguments may follow the listed ones, as with `...`
+    /// in C. Only declarations can be variadic.
+    pub variadic: bool,
+    pub loc: TokLoc,
 }
 
 impl fmt::Display for FuncSig {
@@ -111,524 +149,3405 @@ impl fmt::Display for FuncSig {
             );
         }
 
-        write!(f, "func @{}:{} ({})", self.name, self.typename, farglist)
+        if self.variadic {
+            if !self.arglist.is_empty() {
+                farglist.push_str(", ");
+            }
+
+            farglist.push_str("...");
+        }
+
+        write!(f, "@{}:{} ({})", self.name, self.typename, farglist)
     }
 }
 
+/// An instruction operand: either a variable or an immediate. Immediates take
+/// their type from the instruction they appear in.
 #[derive(Debug)]
 #[derive(Clone)]
-pub enum LetValue {
-    LetVariable (Variable),
-    LetInteger (i64),
+pub enum Operand {
+    OpVariable (Variable),
+    OpInteger (i128, Option<Type>, TokLoc),
+    OpFloat (f64, TokLoc),
+}
+
+impl Operand {
+    /// The variable this operand reads, or `None` for an immediate.
+    pub fn var(&self) -> Option<&Variable> {
+        return match self {
+            &Operand::OpVariable (ref v) => Some (v),
+            _ => None,
+        };
+    }
+}
+
+/// Format an integer constant such that the lexer reads back the exact same
+/// value. Decimal literals must fit in an i64, so larger 64-bit patterns are
+/// spelled in hex.
+pub fn format_int(val: i128) -> String {
+    return if val > i64::max_value() as i128 {
+        format!("{:#x}", val)
+    }
+    else {
+        format!("{}", val)
+    };
+}
+
+/// Format a float constant such that the parser reads back the exact same
+/// value. Finite values always carry a '.' or an exponent, so that they are
+/// never mistaken for integer literals, and a NaN other than the default one
+/// spells out its sign and significand bits.
+pub fn format_float(val: f64) -> String {
+    return if val.is_nan() {
+        let bits = val.to_bits();
+        let sign = if bits >> 63 != 0 { "-" } else { "" };
+        let payload = bits & NAN_PAYLOAD_MASK;
+
+        if payload == f64::NAN.to_bits() & NAN_PAYLOAD_MASK {
+            format!("{}nan", sign)
+        }
+        else {
+            format!("{}nan(0x{:x})", sign, payload)
+        }
+    }
+    else if val.is_infinite() {
+        if val < 0.0 { "-inf".to_owned() } else { "inf".to_owned() }
+    }
+    else {
+        format!("{:?}", val)
+    };
 }
 
-impl fmt::Display for LetValue {
+impl fmt::Display for Operand {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &LetValue::LetVariable (ref v) => write!(f, "%{}", v.name),
-            &LetValue::LetInteger (i) => write!(f, "{}", i),
+            &Operand::OpVariable (ref v) => write!(f, "%{}", v.name),
+            &Operand::OpInteger (i, Some (ref t), _) => {
+                write!(f, "{}{}", format_int(i), t)
+            }
+            &Operand::OpInteger (i, None, _) => {
+                write!(f, "{}", format_int(i))
+            }
+            &Operand::OpFloat (val, _) => write!(f, "{}", format_float(val)),
         }
     }
 }
 
+/// The initial contents of a global.
 #[derive(Debug)]
 #[derive(Clone)]
-pub enum Stmt {
-    AddInst  (VarTypePair, Variable, Variable),
-    SubInst  (VarTypePair, Variable, Variable),
-    LetInst  (VarTypePair, LetValue),
-    RetInst  (Option<Variable>),
-    CallInst (VarTypePair, Function, Vec<Variable>)
+pub enum Initializer {
+    InitInteger (i128, Option<Type>, TokLoc),
+    InitFloat (f64),
+    InitZero,
+    InitString (Vec<u8>),
+    InitArray (Vec<Initializer>),
 }
 
-#[derive(Debug)]
-#[derive(Clone)]
-pub enum Node {
-    FuncDef (FuncSig, Vec<Stmt>),
+/// Quote `bytes` as a string literal, escaping anything that is not
+/// printable ASCII so that the parser reads back the same bytes.
+pub fn format_str_bytes(bytes: &[u8]) -> String {
+    let mut s = String::from("\"");
+
+    for &b in bytes {
+        match b {
+            b'"' => s.push_str("\\\""),
+            b'\\' => s.push_str("\\\\"),
+            b'\n' => s.push_str("\\n"),
+            b'\t' => s.push_str("\\t"),
+            0x20..=0x7e => s.push(b as char),
+            _ => s.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+
+    s.push('"');
+
+    return s;
 }
 
-pub fn print_ast(node: &Node) {
-    match node {
-        &Node::FuncDef (ref sig, ref stmt_list) => {
-            println!("{} {{", sig);
-            for stmt in stmt_list {
-                match stmt {
-                    &Stmt::AddInst (ref vtp, ref v2, ref v3) => {
-                        println!("    add   {} {} {}", vtp, v2, v3);
-                    }
-                    &Stmt::SubInst (ref vtp, ref v2, ref v3) => {
-                        println!("    sub   {} {} {}", vtp, v2, v3);
-                    }
-                    &Stmt::LetInst (ref vtp, ref v2) => {
-                        println!("    let   {} {}", vtp, v2);
-                    }
-                    &Stmt::RetInst (ref opt) => {
-                        if let &Some (ref var) = opt {
-                            println!("    ret   {}", var);
-                        }
-                        else {
-                            println!("    ret   void");
-                        }
-                    }
-                    &Stmt::CallInst (ref vtp, ref f, ref vars) => {
-                        print!("    call   {} {}(", vtp, f);
-                        for x in 0..vars.len() {
-                            if x > 0 {
-                                print!(", ");
-                            }
-                            print!("{}", vars[x]);
-                        }
-                        println!(")");
+impl fmt::Display for Initializer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Initializer::InitInteger (i, Some (ref t), _) => {
+                write!(f, "{}{}", format_int(i), t)
+            }
+            &Initializer::InitInteger (i, None, _) => {
+                write!(f, "{}", format_int(i))
+            }
+            &Initializer::InitFloat (val) => {
+                write!(f, "{}", format_float(val))
+            }
+            &Initializer::InitZero => write!(f, "zeroinit"),
+            &Initializer::InitString (ref bytes) => {
+                write!(f, "{}", format_str_bytes(bytes))
+            }
+            &Initializer::InitArray (ref elems) => {
+                write!(f, "[")?;
+                for (i, x) in elems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
                     }
+                    write!(f, "{}", x)?;
                 }
+                write!(f, "]")
             }
-            println!("}}");
         }
     }
 }
 
-fn parse_type(it: &mut Peekable<Iter<Token>>) -> Option<Type> {
-    return if let Some (&&Token::I8Keyword (_)) = it.peek() {
-        it.next();
+/// Module-level storage. A constant global may not be written to.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct GlobalDef {
+    pub name: String,
+    pub typename: Type,
+    pub constant: bool,
+    pub init: Initializer,
+    pub loc: TokLoc,
+}
 
-        Some (Type::I8)
+impl fmt::Display for GlobalDef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "{} @{}:{} = {}",
+            if self.constant { "constant" } else { "global" },
+            self.name, self.typename, self.init
+        )
     }
-    else if let Some (&&Token::I16Keyword (_)) = it.peek() {
-        it.next();
+}
 
-        Some (Type::I16)
-    }
-    else if let Some (&&Token::I32Keyword (_)) = it.peek() {
-        it.next();
+/// Integer comparison predicates. The signed and unsigned orderings read the
+/// same bits differently, so there is one of each.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum ICmpPred {
+    Eq,
+    Ne,
+    Slt,
+    Sle,
+    Sgt,
+    Sge,
+    Ult,
+    Ule,
+    Ugt,
+    Uge,
+}
 
-        Some (Type::I32)
+impl ICmpPred {
+    pub fn from_name(name: &str) -> Option<ICmpPred> {
+        return match name {
+            "eq" => Some (ICmpPred::Eq),
+            "ne" => Some (ICmpPred::Ne),
+            "slt" => Some (ICmpPred::Slt),
+            "sle" => Some (ICmpPred::Sle),
+            "sgt" => Some (ICmpPred::Sgt),
+            "sge" => Some (ICmpPred::Sge),
+            "ult" => Some (ICmpPred::Ult),
+            "ule" => Some (ICmpPred::Ule),
+            "ugt" => Some (ICmpPred::Ugt),
+            "uge" => Some (ICmpPred::Uge),
+            _ => None,
+        };
     }
-    else if let Some (&&Token::I64Keyword (_)) = it.peek() {
-        it.next();
+}
 
-        Some (Type::I64)
+impl fmt::Display for ICmpPred {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &ICmpPred::Eq => write!(f, "eq"),
+            &ICmpPred::Ne => write!(f, "ne"),
+            &ICmpPred::Slt => write!(f, "slt"),
+            &ICmpPred::Sle => write!(f, "sle"),
+            &ICmpPred::Sgt => write!(f, "sgt"),
+            &ICmpPred::Sge => write!(f, "sge"),
+            &ICmpPred::Ult => write!(f, "ult"),
+            &ICmpPred::Ule => write!(f, "ule"),
+            &ICmpPred::Ugt => write!(f, "ugt"),
+            &ICmpPred::Uge => write!(f, "uge"),
+        }
     }
-    else if let Some (&&Token::F32Keyword (_)) = it.peek() {
-        it.next();
+}
 
-        Some (Type::F32)
-    }
-    else if let Some (&&Token::F64Keyword (_)) = it.peek() {
-        it.next();
+/// Float comparison predicates. The ordered forms are false if either operand
+/// is NaN, the unordered forms are true; `ord` and `uno` test for NaN alone.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum FCmpPred {
+    Oeq,
+    One,
+    Olt,
+    Ole,
+    Ogt,
+    Oge,
+    Ord,
+    Ueq,
+    Une,
+    Ult,
+    Ule,
+    Ugt,
+    Uge,
+    Uno,
+}
 
-        Some (Type::F64)
+impl FCmpPred {
+    pub fn from_name(name: &str) -> Option<FCmpPred> {
+        return match name {
+            "oeq" => Some (FCmpPred::Oeq),
+            "one" => Some (FCmpPred::One),
+            "olt" => Some (FCmpPred::Olt),
+            "ole" => Some (FCmpPred::Ole),
+            "ogt" => Some (FCmpPred::Ogt),
+            "oge" => Some (FCmpPred::Oge),
+            "ord" => Some (FCmpPred::Ord),
+            "ueq" => Some (FCmpPred::Ueq),
+            "une" => Some (FCmpPred::Une),
+            "ult" => Some (FCmpPred::Ult),
+            "ule" => Some (FCmpPred::Ule),
+            "ugt" => Some (FCmpPred::Ugt),
+            "uge" => Some (FCmpPred::Uge),
+            "uno" => Some (FCmpPred::Uno),
+            _ => None,
+        };
     }
-    else {
-        None
-    };
 }
 
-fn parse_var_type_pair(mut it: &mut Peekable<Iter<Token>>)
-    -> Option<VarTypePair>
-{
-    return if let Some (&&Token::VarName (ref varname, ref tl)) = it.peek() {
-        it.next();
-
-        if let Some (&&Token::Colon (ref tl)) = it.peek() {
-            it.next();
-
-            if let Some (type_node) = parse_type(&mut it) {
-                Some (
-                    VarTypePair {name: varname.to_owned(), typename: type_node}
-                )
-            }
-            else {
-                panic!("Expected type, got trash: {:?}", tl);
-            }
-        }
-        else {
-            panic!("Expected ':', got trash: {:?}", tl);
+impl fmt::Display for FCmpPred {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &FCmpPred::Oeq => write!(f, "oeq"),
+            &FCmpPred::One => write!(f, "one"),
+            &FCmpPred::Olt => write!(f, "olt"),
+            &FCmpPred::Ole => write!(f, "ole"),
+            &FCmpPred::Ogt => write!(f, "ogt"),
+            &FCmpPred::Oge => write!(f, "oge"),
+            &FCmpPred::Ord => write!(f, "ord"),
+            &FCmpPred::Ueq => write!(f, "ueq"),
+            &FCmpPred::Une => write!(f, "une"),
+            &FCmpPred::Ult => write!(f, "ult"),
+            &FCmpPred::Ule => write!(f, "ule"),
+            &FCmpPred::Ugt => write!(f, "ugt"),
+            &FCmpPred::Uge => write!(f, "uge"),
+            &FCmpPred::Uno => write!(f, "uno"),
         }
     }
-    else {
-        None
-    };
 }
 
-fn parse_func_type_pair(mut it: &mut Peekable<Iter<Token>>)
-    -> Option<(String, Type)>
-{
-    return if let Some (&&Token::FuncName (ref funcname, ref tl)) = it.peek() {
-        it.next();
-
-        if let Some (&&Token::Colon (ref tl)) = it.peek() {
-            it.next();
+/// Conversions between types. Each names the one instruction that performs
+/// it, with the same mnemonic.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum CastOp {
+    Trunc,
+    ZExt,
+    SExt,
+    FPToSI,
+    FPToUI,
+    SIToFP,
+    UIToFP,
+    FPExt,
+    FPTrunc,
+    Bitcast,
+    PtrToInt,
+    IntToPtr,
+}
 
-            if let Some (type_node) = parse_type(&mut it) {
-                Some ((
-                    funcname.to_owned(), type_node
-                ))
-            }
-            else {
-                panic!("Expected type, got trash: {:?}", tl);
-            }
-        }
-        else {
-            panic!("Expected ':', got trash: {:?}", tl);
-        }
+impl CastOp {
+    pub fn mnemonic(&self) -> &'static str {
+        return match self {
+            &CastOp::Trunc => "trunc",
+            &CastOp::ZExt => "zext",
+            &CastOp::SExt => "sext",
+            &CastOp::FPToSI => "fptosi",
+            &CastOp::FPToUI => "fptoui",
+            &CastOp::SIToFP => "sitofp",
+            &CastOp::UIToFP => "uitofp",
+            &CastOp::FPExt => "fpext",
+            &CastOp::FPTrunc => "fptrunc",
+            &CastOp::Bitcast => "bitcast",
+            &CastOp::PtrToInt => "ptrtoint",
+            &CastOp::IntToPtr => "inttoptr",
+        };
     }
-    else {
-        None
-    };
 }
 
-fn parse_binary_input_vars(mut it: &mut Peekable<Iter<Token>>)
-    -> Option<(Variable, Variable)>
-{
-    return if let Some (&&Token::VarName (ref left_src, ref tl)) = it.peek() {
-        it.next();
-
-        if let Some (&&Token::VarName (ref right_src, _)) = it.peek() {
-            it.next();
-
-            Some ((
-                Variable {name: left_src.to_owned()},
-                Variable {name: right_src.to_owned()}
-            ))
-        }
-        else {
-            panic!("Expected ':', got trash: {:?}", tl);
-        }
+impl fmt::Display for CastOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mnemonic())
     }
-    else {
-        None
-    };
 }
 
-fn parse_let_value(mut it: &mut Peekable<Iter<Token>>)
-    -> Option<LetValue>
-{
-    return if let Some (&&Token::Integer (i, _)) = it.peek() {
-        it.next();
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum Stmt {
+    AddInst  (VarTypePair, Operand, Operand),
+    SubInst  (VarTypePair, Operand, Operand),
+    MulInst  (VarTypePair, Operand, Operand),
+    SDivInst (VarTypePair, Operand, Operand),
+    UDivInst (VarTypePair, Operand, Operand),
+    SRemInst (VarTypePair, Operand, Operand),
+    URemInst (VarTypePair, Operand, Operand),
+    AndInst  (VarTypePair, Operand, Operand),
+    OrInst   (VarTypePair, Operand, Operand),
+    XorInst  (VarTypePair, Operand, Operand),
+    ShlInst  (VarTypePair, Operand, Operand),
+    LShrInst (VarTypePair, Operand, Operand),
+    AShrInst (VarTypePair, Operand, Operand),
+    NegInst  (VarTypePair, Operand),
+    NotInst  (VarTypePair, Operand),
+    ICmpInst (VarTypePair, ICmpPred, Operand, Operand),
+    FCmpInst (VarTypePair, FCmpPred, Operand, Operand),
+    AllocaInst (VarTypePair),
+    LoadInst   (VarTypePair, Variable),
+    StoreInst  (Operand, Variable),
+    GepInst    (VarTypePair, Variable, Vec<Operand>),
+    ExtractValueInst (VarTypePair, Variable, Vec<i64>),
+    InsertValueInst  (VarTypePair, Variable, Operand, Vec<i64>),
+    CastInst   (VarTypePair, CastOp, Variable),
+    AddrOfInst (VarTypePair, Global),
+    LetInst  (VarTypePair, Operand),
+    RetInst  (Option<Operand>),
+    CallInst (VarTypePair, Function, Vec<Operand>),
+    BrInst     (Label),
+    CondBrInst (Operand, Label, Label),
+    SwitchInst (Operand, Label, Vec<(i128, Label)>),
+    PhiInst    (VarTypePair, Vec<(Operand, Label)>),
+}
 
-        Some (LetValue::LetInteger (i))
+impl Stmt {
+    /// The variable defined by this statement, if any.
+    pub fn def(&self) -> Option<&VarTypePair> {
+        return match self {
+            &Stmt::AddInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::SubInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::MulInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::SDivInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::UDivInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::SRemInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::URemInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::AndInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::OrInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::XorInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::ShlInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::LShrInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::AShrInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::NegInst (ref vtp, _) => Some (vtp),
+            &Stmt::NotInst (ref vtp, _) => Some (vtp),
+            &Stmt::ICmpInst (ref vtp, _, _, _) => Some (vtp),
+            &Stmt::FCmpInst (ref vtp, _, _, _) => Some (vtp),
+            &Stmt::AllocaInst (ref vtp) => Some (vtp),
+            &Stmt::LoadInst (ref vtp, _) => Some (vtp),
+            &Stmt::StoreInst (..) => None,
+            &Stmt::GepInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::ExtractValueInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::InsertValueInst (ref vtp, _, _, _) => Some (vtp),
+            &Stmt::CastInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::AddrOfInst (ref vtp, _) => Some (vtp),
+            &Stmt::LetInst (ref vtp, _) => Some (vtp),
+            &Stmt::CallInst (ref vtp, _, _) => Some (vtp),
+            &Stmt::PhiInst (ref vtp, _) => Some (vtp),
+            &Stmt::RetInst (_) => None,
+            &Stmt::BrInst (_) => None,
+            &Stmt::CondBrInst (..) => None,
+            &Stmt::SwitchInst (..) => None,
+        };
     }
-    else if let Some (&&Token::VarName (ref varname, _)) = it.peek() {
-        it.next();
 
-        Some (LetValue::LetVariable (Variable {name: varname.to_owned()}))
+    /// The keyword that introduces this statement in IR text.
+    pub fn mnemonic(&self) -> &'static str {
+        return match self {
+            &Stmt::AddInst (..) => "add",
+            &Stmt::SubInst (..) => "sub",
+            &Stmt::MulInst (..) => "mul",
+            &Stmt::SDivInst (..) => "sdiv",
+            &Stmt::UDivInst (..) => "udiv",
+            &Stmt::SRemInst (..) => "srem",
+            &Stmt::URemInst (..) => "urem",
+            &Stmt::AndInst (..) => "and",
+            &Stmt::OrInst (..) => "or",
+            &Stmt::XorInst (..) => "xor",
+            &Stmt::ShlInst (..) => "shl",
+            &Stmt::LShrInst (..) => "lshr",
+            &Stmt::AShrInst (..) => "ashr",
+            &Stmt::NegInst (..) => "neg",
+            &Stmt::NotInst (..) => "not",
+            &Stmt::ICmpInst (..) => "icmp",
+            &Stmt::FCmpInst (..) => "fcmp",
+            &Stmt::AllocaInst (_) => "alloca",
+            &Stmt::LoadInst (..) => "load",
+            &Stmt::StoreInst (..) => "store",
+            &Stmt::GepInst (..) => "gep",
+            &Stmt::ExtractValueInst (..) => "extractvalue",
+            &Stmt::InsertValueInst (..) => "insertvalue",
+            &Stmt::CastInst (_, op, _) => op.mnemonic(),
+            &Stmt::AddrOfInst (..) => "addrof",
+            &Stmt::LetInst (..) => "let",
+            &Stmt::RetInst (_) => "ret",
+            &Stmt::CallInst (..) => "call",
+            &Stmt::BrInst (_) => "br",
+            &Stmt::CondBrInst (..) => "br",
+            &Stmt::SwitchInst (..) => "switch",
+            &Stmt::PhiInst (..) => "phi",
+        };
     }
-    else {
-        None
-    };
-}
 
-fn parse_let(mut it: &mut Peekable<Iter<Token>>) -> Option<Stmt> {
-    return if let Some (&&Token::LetKeyword (ref tl)) = it.peek() {
-        it.next();
-
-        if let Some (dest_var_type_pair) = parse_var_type_pair(&mut it) {
-            if let Some (let_value) = parse_let_value(&mut it)
-            {
-                Some (Stmt::LetInst (
-                    dest_var_type_pair,
-                    let_value,
-                ))
+    /// The variables read by this statement. For a phi these are the incoming
+    /// values, which are really read at the end of each predecessor.
+    pub fn uses(&self) -> Vec<&Variable> {
+        return match self {
+            &Stmt::AddInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::SubInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::MulInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::SDivInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::UDivInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::SRemInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::URemInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::AndInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::OrInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::XorInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::ShlInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::LShrInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::AShrInst (_, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::NegInst (_, ref v) => operand_vars(vec!(v)),
+            &Stmt::NotInst (_, ref v) => operand_vars(vec!(v)),
+            &Stmt::ICmpInst (_, _, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::FCmpInst (_, _, ref l, ref r) => operand_vars(vec!(l, r)),
+            &Stmt::AllocaInst (_) => vec!(),
+            &Stmt::LoadInst (_, ref ptr) => vec!(ptr),
+            &Stmt::StoreInst (ref val, ref ptr) => {
+                let mut vars = operand_vars(vec!(val));
+                vars.push(ptr);
+                vars
             }
-            else {
-                panic!("Expected unary input var, got trash: {:?}", tl);
+            &Stmt::GepInst (_, ref base, ref indices) => {
+                let mut vars = vec!(base);
+                vars.extend(operand_vars(indices.iter().collect()));
+                vars
             }
-        }
-        else {
-            panic!("Expected <var>:<type> pair, got trash: {:?}", tl);
-        }
+            &Stmt::ExtractValueInst (_, ref agg, _) => vec!(agg),
+            &Stmt::CastInst (_, _, ref src) => vec!(src),
+            &Stmt::AddrOfInst (..) => vec!(),
+            &Stmt::InsertValueInst (_, ref agg, ref val, _) => {
+                let mut vars = vec!(agg);
+                vars.extend(operand_vars(vec!(val)));
+                vars
+            }
+            &Stmt::LetInst (_, ref v) => operand_vars(vec!(v)),
+            &Stmt::CallInst (_, _, ref args) => {
+                operand_vars(args.iter().collect())
+            }
+            &Stmt::PhiInst (_, ref incoming) => {
+                operand_vars(incoming.iter().map(|&(ref v, _)| v).collect())
+            }
+            &Stmt::RetInst (Some (ref v)) => operand_vars(vec!(v)),
+            &Stmt::RetInst (None) => vec!(),
+            &Stmt::BrInst (_) => vec!(),
+            &Stmt::CondBrInst (ref cond, _, _) => operand_vars(vec!(cond)),
+            &Stmt::SwitchInst (ref val, _, _) => operand_vars(vec!(val)),
+        };
     }
-    else {
-        None
-    };
 }
 
-fn parse_ret_value(mut it: &mut Peekable<Iter<Token>>)
-    -> Option<Option<Variable>>
-{
-    return if let Some (&&Token::VarName (ref varname, _)) = it.peek() {
-        it.next();
-
-        Some (Some (Variable {name: varname.to_owned()}))
-    }
-    else if let Some (&&Token::VoidKeyword (_)) = it.peek() {
-        it.next();
+/// The variables among `ops`, skipping immediates.
+pub fn operand_vars(ops: Vec<&Operand>) -> Vec<&Variable> {
+    return ops.into_iter().filter_map(|op| op.var()).collect();
+}
 
-        Some (None)
-    }
-    else {
-        None
+pub fn is_terminator(stmt: &Stmt) -> bool {
+    return match stmt {
+        &Stmt::RetInst (_) => true,
+        &Stmt::BrInst (_) => true,
+        &Stmt::CondBrInst (..) => true,
+        &Stmt::SwitchInst (..) => true,
+        _ => false,
     };
 }
 
-
-fn parse_ret(mut it: &mut Peekable<Iter<Token>>) -> Option<Stmt> {
-    return if let Some (&&Token::RetKeyword (ref tl)) = it.peek() {
-        it.next();
-
-        if let Some (ret_value) = parse_ret_value(&mut it)
-        {
-            Some (Stmt::RetInst (
-                ret_value
-            ))
-        }
-        else {
-            panic!("Expected variable, got trash: {:?}", tl);
-        }
-    }
-    else {
-        None
-    }
+/// A labeled run of statements. The parser guarantees that the last
+/// statement, and only the last statement, is a terminator.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct BasicBlock {
+    pub label: String,
+    pub stmts: Vec<Stmt>,
+    /// Where the block starts: its label, or the function's '{' for an
+    /// unlabeled entry block.
+    pub loc: TokLoc,
+    /// Where each statement starts, in step with `stmts`.
+    pub locs: Vec<TokLoc>,
 }
 
-fn parse_add(mut it: &mut Peekable<Iter<Token>>) -> Option<Stmt> {
-    return if let Some (&&Token::AddKeyword (ref tl)) = it.peek() {
-        it.next();
+impl BasicBlock {
+    pub fn terminator(&self) -> Option<&Stmt> {
+        return match self.stmts.last() {
+            Some (stmt) if is_terminator(stmt) => Some (stmt),
+            _ => None,
+        };
+    }
 
-        if let Some (target_var_type_pair) = parse_var_type_pair(&mut it) {
-            if let Some ((left_src, right_src))
-                = parse_binary_input_vars(&mut it)
-            {
-                Some (Stmt::AddInst (
-                    target_var_type_pair,
-                    left_src,
-                    right_src,
-                ))
-            }
-            else {
-                panic!("Expected binary input vars, got trash: {:?}", tl);
+    /// Labels of the blocks control may transfer to from this one, in the
+    /// order they appear in the terminator. Duplicates are kept.
+    pub fn successors(&self) -> Vec<&str> {
+        return match self.terminator() {
+            Some (&Stmt::BrInst (ref target)) => vec!(&target.name),
+            Some (&Stmt::CondBrInst (_, ref on_true, ref on_false)) => {
+                vec!(&on_true.name, &on_false.name)
             }
-        }
-        else {
-            panic!("Expected <var>:<type> pair, got trash: {:?}", tl);
-        }
-    }
-    else {
-        None
-    };
-}
+            Some (&Stmt::SwitchInst (_, ref default, ref cases)) => {
+                let mut succs: Vec<&str> = vec!(&default.name);
 
-fn parse_sub(mut it: &mut Peekable<Iter<Token>>) -> Option<Stmt> {
-    return if let Some (&&Token::SubKeyword (ref tl)) = it.peek() {
-        it.next();
+                for &(_, ref target) in cases {
+                    succs.push(&target.name);
+                }
 
-        if let Some (target_var_type_pair) = parse_var_type_pair(&mut it) {
-            if let Some ((left_src, right_src))
-                = parse_binary_input_vars(&mut it)
-            {
-                Some (Stmt::SubInst (
-                    target_var_type_pair,
-                    left_src,
-                    right_src,
-                ))
+                succs
             }
-            else {
-                panic!("Expected binary input vars: {:?}", tl);
-            }
-        }
-        else {
-            panic!("Expected <var>:<type> pair: {:?}", tl);
-        }
+            _ => vec!(),
+        };
     }
-    else {
-        None
-    };
 }
 
-fn parse_arg_list (mut it: &mut Peekable<Iter<Token>>)
-    -> Vec<VarTypePair>
-{
-    let mut arg_list = Vec::new();
+/// Label given to a function's first block when the source leaves it
+/// unlabeled.
+pub const ENTRY_LABEL: &'static str = "entry";
 
-    while let Some (target_var_type_pair) = parse_var_type_pair(&mut it) {
-        arg_list.push(target_var_type_pair);
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum Node {
+    Module (Vec<Node>),
+    StructDef (String, Vec<Type>, TokLoc),
+    FuncDecl (FuncSig),
+    GlobalDef (GlobalDef),
+    FuncDef (FuncSig, Vec<BasicBlock>),
+}
 
-        if let Some (&&Token::Comma(_)) = it.peek() {
-            it.next();
-        }
-        else {
-            break;
+/// Write `items` separated by `sep`.
+fn write_sep<T: fmt::Display>(f: &mut fmt::Formatter, items: &[T], sep: &str)
+    -> fmt::Result
+{
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, "{}", sep)?;
         }
+        write!(f, "{}", item)?;
     }
 
-    return arg_list;
+    return Ok (());
 }
 
-fn parse_func_sig(mut it: &mut Peekable<Iter<Token>>)
-    -> Option<FuncSig>
-{
-    return if let Some ((func_name, func_type))
-        = parse_func_type_pair(&mut it)
-    {
-
-        if let Some (&&Token::LParen (ref tl)) = it.peek() {
-            it.next();
-
-            let arg_list = parse_arg_list(it);
+/// Statements print in canonical form: the mnemonic, then each operand
+/// separated by a single space, exactly as the parser reads them back.
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = self.mnemonic();
 
-            if let Some (&&Token::RParen (_)) = it.peek() {
-                it.next();
+        match self {
+            &Stmt::AddInst (ref vtp, ref l, ref r) |
+            &Stmt::SubInst (ref vtp, ref l, ref r) |
+            &Stmt::MulInst (ref vtp, ref l, ref r) |
+            &Stmt::SDivInst (ref vtp, ref l, ref r) |
+            &Stmt::UDivInst (ref vtp, ref l, ref r) |
+            &Stmt::SRemInst (ref vtp, ref l, ref r) |
+            &Stmt::URemInst (ref vtp, ref l, ref r) |
+            &Stmt::AndInst (ref vtp, ref l, ref r) |
+            &Stmt::OrInst (ref vtp, ref l, ref r) |
+            &Stmt::XorInst (ref vtp, ref l, ref r) |
+            &Stmt::ShlInst (ref vtp, ref l, ref r) |
+            &Stmt::LShrInst (ref vtp, ref l, ref r) |
+            &Stmt::AShrInst (ref vtp, ref l, ref r) => {
+                write!(f, "{} {} {} {}", op, vtp, l, r)
+            }
+            &Stmt::NegInst (ref vtp, ref v) |
+            &Stmt::NotInst (ref vtp, ref v) |
+            &Stmt::LetInst (ref vtp, ref v) => {
+                write!(f, "{} {} {}", op, vtp, v)
+            }
+            &Stmt::ICmpInst (ref vtp, pred, ref l, ref r) => {
+                write!(f, "{} {} {} {} {}", op, vtp, pred, l, r)
+            }
+            &Stmt::FCmpInst (ref vtp, pred, ref l, ref r) => {
+                write!(f, "{} {} {} {} {}", op, vtp, pred, l, r)
+            }
+            &Stmt::AllocaInst (ref vtp) => write!(f, "{} {}", op, vtp),
+            &Stmt::LoadInst (ref vtp, ref ptr) => {
+                write!(f, "{} {} {}", op, vtp, ptr)
+            }
+            &Stmt::StoreInst (ref val, ref ptr) => {
+                write!(f, "{} {} {}", op, val, ptr)
+            }
+            &Stmt::GepInst (ref vtp, ref base, ref indices) => {
+                write!(f, "{} {} {}", op, vtp, base)?;
+                for index in indices {
+                    write!(f, " {}", index)?;
+                }
+                Ok (())
+            }
+            &Stmt::ExtractValueInst (ref vtp, ref agg, ref indices) => {
+                write!(f, "{} {} {}", op, vtp, agg)?;
+                for index in indices {
+                    write!(f, " {}", index)?;
+                }
+                Ok (())
+            }
+            &Stmt::InsertValueInst (ref vtp, ref agg, ref val, ref indices) => {
+                write!(f, "{} {} {} {}", op, vtp, agg, val)?;
+                for index in indices {
+                    write!(f, " {}", index)?;
+                }
+                Ok (())
+            }
+            &Stmt::CastInst (ref vtp, _, ref src) => {
+                write!(f, "{} {} {}", op, vtp, src)
+            }
+            &Stmt::AddrOfInst (ref vtp, ref global) => {
+                write!(f, "{} {} {}", op, vtp, global)
+            }
+            &Stmt::RetInst (Some (ref val)) => write!(f, "{} {}", op, val),
+            &Stmt::RetInst (None) => write!(f, "{} void", op),
+            &Stmt::CallInst (ref vtp, ref func, ref args) => {
+                write!(f, "{} {} {}(", op, vtp, func)?;
+                write_sep(f, args, ", ")?;
+                write!(f, ")")
+            }
+            &Stmt::BrInst (ref target) => write!(f, "{} {}", op, target),
+            &Stmt::CondBrInst (ref cond, ref on_true, ref on_false) => {
+                write!(f, "{} {} {} {}", op, cond, on_true, on_false)
+            }
+            &Stmt::SwitchInst (ref val, ref default, ref cases) => {
+                write!(f, "{} {} {} [", op, val, default)?;
+                for (i, &(case, ref target)) in cases.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} {}", format_int(case), target)?;
+                }
+                write!(f, "]")
+            }
+            &Stmt::PhiInst (ref vtp, ref incoming) => {
+                write!(f, "{} {} [", op, vtp)?;
+                for (i, &(ref val, ref pred)) in incoming.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} {}", val, pred)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
 
-                Some (
-                    FuncSig {
-                        name: func_name,
-                        typename: func_type,
-                        arglist: arg_list,
+/// A block always prints its label, even the entry block, which reads back
+/// with the same name whether or not the source spelled it out.
+impl fmt::Display for BasicBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}:", self.label)?;
+        for stmt in &self.stmts {
+            writeln!(f, "    {}", stmt)?;
+        }
+
+        return Ok (());
+    }
+}
+
+/// The canonical text of a module: items in order, separated by a blank
+/// line. Parsing this text gives back the same tree, apart from locations.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Node::Module (ref items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
                     }
-                )
+                    write!(f, "{}", item)?;
+                }
+                Ok (())
             }
-            else {
-                panic!("Expected ')', got trash: {:?} {:?}", it.peek(), tl);
+            &Node::StructDef (ref name, ref fields, _) => {
+                write!(f, "type {} = {{", name)?;
+                write_sep(f, fields, ", ")?;
+                writeln!(f, "}}")
             }
+            &Node::FuncDecl (ref sig) => writeln!(f, "declare {}", sig),
+            &Node::GlobalDef (ref global) => writeln!(f, "{}", global),
+            &Node::FuncDef (ref sig, ref blocks) => {
+                writeln!(f, "func {} {{", sig)?;
+                for block in blocks {
+                    write!(f, "{}", block)?;
+                }
+                writeln!(f, "}}")
+            }
+        }
+    }
+}
+
+pub fn print_ast(node: &Node) {
+    print!("{}", node);
+}
+
+/// Print `node`, parse the text back and print that again, returning the
+/// second printing if it differs from the first. Since the printer shows
+/// every part of the tree but its locations, equal text means equal trees.
+pub fn check_round_trip(node: &Node) -> Result<(), String> {
+    let text = node.to_string();
+
+    let tokens = match tokenize(&text, FileId (0)).into_result() {
+        Ok (tokens) => tokens,
+        Err (errors) => {
+            return Err (errors.iter().map(|e| format!("{}\n", e)).collect());
+        }
+    };
+
+    let reparsed = match parse(&tokens).into_result() {
+        Ok (node) => node,
+        Err (errors) => {
+            return Err (errors.iter().map(|e| format!("{}\n", e)).collect());
+        }
+    };
+
+    let again = reparsed.to_string();
+
+    return if again == text { Ok (()) } else { Err (again) };
+}
+
+/// What a parse function produces: `Ok (None)` if the input does not start
+/// with the construct it looks for, `Ok (Some (..))` if it parsed one, and an
+/// error if the construct started but then went wrong.
+pub type ParseResult<T> = Result<Option<T>, Diagnostic>;
+
+/// Complain that `what` was expected where `found` is. `context` is the start
+/// of the construct being parsed, which is also where we point if the input
+/// ends early.
+fn expected(what: &str, found: Option<&&Token>, context: &TokLoc)
+    -> Diagnostic
+{
+    let diag = Diagnostic::error("E0200", format!("expected {}", what));
+
+    return match found {
+        Some (tok) if tok.loc().start != context.start => {
+            diag.with_primary(tok.loc(), &format!("expected {}", what))
+                .with_secondary(context, "while parsing this")
+        }
+        Some (tok) => {
+            diag.with_primary(tok.loc(), &format!("expected {}", what))
+        }
+        None => {
+            diag.with_primary(context, "input ends after this")
+        }
+    };
+}
+
+fn parse_base_type(it: &mut Peekable<Iter<Token>>) -> ParseResult<Type> {
+    return Ok (if let Some (&&Token::I1Keyword (_)) = it.peek() {
+        it.next();
+
+        Some (Type::I1)
+    }
+    else if let Some (&&Token::I8Keyword (_)) = it.peek() {
+        it.next();
+
+        Some (Type::I8)
+    }
+    else if let Some (&&Token::I16Keyword (_)) = it.peek() {
+        it.next();
+
+        Some (Type::I16)
+    }
+    else if let Some (&&Token::I32Keyword (_)) = it.peek() {
+        it.next();
+
+        Some (Type::I32)
+    }
+    else if let Some (&&Token::I64Keyword (_)) = it.peek() {
+        it.next();
+
+        Some (Type::I64)
+    }
+    else if let Some (&&Token::F32Keyword (_)) = it.peek() {
+        it.next();
+
+        Some (Type::F32)
+    }
+    else if let Some (&&Token::F64Keyword (_)) = it.peek() {
+        it.next();
+
+        Some (Type::F64)
+    }
+    else if let Some (&&Token::LBracket (ref tl)) = it.peek() {
+        it.next();
+
+        let len = match it.peek() {
+            Some (&&Token::Integer (n, None, _)) if n >= 0 => n as u64,
+            tok => return Err (expected("array length", tok, tl)),
+        };
+
+        it.next();
+
+        match it.peek() {
+            Some (&&Token::Ident (ref x, _)) if x == "x" => {}
+            tok => return Err (expected("'x'", tok, tl)),
+        }
+
+        it.next();
+
+        let elem = match parse_type(it)? {
+            Some (t) => t,
+            None => return Err (expected("element type", it.peek(), tl)),
+        };
+
+        if let Some (&&Token::RBracket (_)) = it.peek() {
+            it.next();
         }
         else {
-            panic!("Expected '(', got trash somewhere");
+            return Err (expected("']'", it.peek(), tl));
         }
+
+        Some (Type::Array (Box::new(elem), len))
+    }
+    else if let Some (&&Token::Ident (ref name, _)) = it.peek() {
+        it.next();
+
+        Some (Type::UserType (name.to_owned()))
     }
     else {
         None
-    };
+    });
+}
+
+/// A base type followed by any number of '*', each adding a level of
+/// indirection, so `i8**` is a pointer to a pointer to an `i8`.
+fn parse_type(it: &mut Peekable<Iter<Token>>) -> ParseResult<Type> {
+    return Ok (if let Some (mut t) = parse_base_type(it)? {
+        while let Some (&&Token::Asterisk (_)) = it.peek() {
+            it.next();
+
+            t = Type::Ptr (Box::new(t));
+        }
+
+        Some (t)
+    }
+    else {
+        None
+    });
 }
 
-fn parse_func(mut it: &mut Peekable<Iter<Token>>) -> Option<Node> {
-    return if let Some (&&Token::FuncKeyword (ref tl)) = it.peek() {
+/// The type of a function's result: any type, or `void` for none. This is
+/// the one place `void` may be written.
+fn parse_ret_type(it: &mut Peekable<Iter<Token>>) -> ParseResult<Type> {
+    return if let Some (&&Token::VoidKeyword (_)) = it.peek() {
         it.next();
 
-        if let Some (target_func_sig) = parse_func_sig(&mut it) {
+        Ok (Some (Type::Void))
+    }
+    else {
+        parse_type(it)
+    };
+}
 
-            if let Some (&&Token::LBrace (ref tl)) = it.peek() {
-                it.next();
+type TypeParser = fn(&mut Peekable<Iter<Token>>) -> ParseResult<Type>;
 
-                let stmt_list = parse_statements(&mut it);
+fn parse_var_type_pair(it: &mut Peekable<Iter<Token>>)
+    -> ParseResult<VarTypePair>
+{
+    return parse_var_type_pair_of(it, parse_type);
+}
 
-                if let Some (&&Token::RBrace (_)) = it.peek() {
-                    it.next();
+fn parse_var_type_pair_of(
+    it: &mut Peekable<Iter<Token>>, parse_ty: TypeParser
+) -> ParseResult<VarTypePair>
+{
+    return Ok (if let Some (&&Token::VarName (ref varname, ref tl))
+        = it.peek()
+    {
+        it.next();
 
-                    Some (
-                        Node::FuncDef (
-                            target_func_sig,
-                            stmt_list,
-                        )
-                    )
-                }
-                else {
-                    panic!("Expected '}}', got trash: {:?}", tl);
-                }
+        if let Some (&&Token::Colon (_)) = it.peek() {
+            it.next();
+
+            if let Some (type_node) = parse_ty(it)? {
+                Some (
+                    VarTypePair {name: varname.to_owned(), typename: type_node}
+                )
             }
             else {
-                panic!("Expected '{{', got trash: {:?}", tl);
+                return Err (expected("type", it.peek(), tl));
             }
         }
         else {
-            panic!("Expected 'FuncSig'");
+            return Err (expected("':'", it.peek(), tl));
         }
     }
     else {
         None
-    };
+    });
 }
 
-fn parse_param_list (mut it: &mut Peekable<Iter<Token>>)
-    -> Vec<Variable>
+fn parse_func_type_pair(
+    it: &mut Peekable<Iter<Token>>, parse_ty: TypeParser
+) -> ParseResult<(String, Type)>
 {
-    let mut param_list = Vec::new();
-
-    while let Some (&&Token::VarName (ref var_name, _)) = it.peek() {
+    return Ok (if let Some (&&Token::FuncName (ref funcname, ref tl))
+        = it.peek()
+    {
         it.next();
 
-        param_list.push(Variable {name: var_name.to_owned()});
-
-        if let Some (&&Token::Comma(_)) = it.peek() {
+        if let Some (&&Token::Colon (_)) = it.peek() {
             it.next();
+
+            if let Some (type_node) = parse_ty(it)? {
+                Some ((
+                    funcname.to_owned(), type_node
+                ))
+            }
+            else {
+                return Err (expected("type", it.peek(), tl));
+            }
         }
         else {
-            break;
+            return Err (expected("':'", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+fn parse_binary_operands(it: &mut Peekable<Iter<Token>>)
+    -> ParseResult<(Operand, Operand)>
+{
+    let tl = match it.peek() {
+        Some (tok) => tok.loc(),
+        None => return Ok (None),
+    };
+
+    return Ok (if let Some (left_src) = parse_operand(it)? {
+        if let Some (right_src) = parse_operand(it)? {
+            Some ((left_src, right_src))
+        }
+        else {
+            return Err (expected("second operand", it.peek(), tl));
         }
     }
+    else {
+        None
+    });
+}
 
-    return param_list;
+fn int_suffix_type(suffix: &str, tl: &TokLoc) -> Result<Type, Diagnostic> {
+    return match suffix {
+        "i8" => Ok (Type::I8),
+        "i16" => Ok (Type::I16),
+        "i32" => Ok (Type::I32),
+        "i64" => Ok (Type::I64),
+        _ => Err (
+            Diagnostic::error(
+                "E0201", format!("unknown integer suffix {}", suffix)
+            ).with_primary(tl, "expected one of i8, i16, i32 or i64")
+        ),
+    };
 }
 
-fn parse_func_call(mut it: &mut Peekable<Iter<Token>>) -> Option<Stmt> {
-    return if let Some (&&Token::CallKeyword (_)) = it.peek() {
-        it.next();
+/// Non-numeric spellings accepted for float literals. These lex as plain
+/// identifiers, so they only stand for a float where a literal is expected.
+const FLOAT_SPELLINGS: [(&'static str, f64); 3] = [
+    ("inf", f64::INFINITY),
+    ("nan", f64::NAN),
+    ("NaN", f64::NAN),
+];
+
+/// The significand bits of an `f64`, which hold the payload of a NaN.
+const NAN_PAYLOAD_MASK: u64 = (1 << 52) - 1;
+
+/// Parse one of the `FLOAT_SPELLINGS`, optionally negated. A NaN may give its
+/// significand bits in parentheses, as in `nan(0x1)`. A spelling followed by
+/// ':' is the label of the next block instead. The location spans the whole
+/// literal, from the sign to the closing parenthesis of the payload.
+fn parse_float_word(it: &mut Peekable<Iter<Token>>)
+    -> ParseResult<(f64, TokLoc)>
+{
+    let mut look = it.clone();
 
-        if let Some (target_var_type_pair) = parse_var_type_pair(&mut it) {
+    let minus_tl = if let Some (&&Token::Minus (ref tl)) = look.peek() {
+        look.next();
+        Some (tl.clone())
+    }
+    else {
+        None
+    };
 
-            if let Some (&&Token::FuncName (ref funcname, _)) = it.peek() {
-                it.next();
+    if at_label(&look) {
+        return Ok (None);
+    }
 
-                if let Some (&&Token::LParen (_)) = it.peek() {
-                    it.next();
+    let (value, tl) = match look.peek() {
+        Some (&&Token::Ident (ref word, ref tl)) => {
+            match FLOAT_SPELLINGS.iter().find(|&&(s, _)| s == word) {
+                Some (&(_, value)) => (value, tl),
+                None => return Ok (None),
+            }
+        }
+        _ => return Ok (None),
+    };
 
-                    let param_list = parse_param_list(it);
+    look.next();
+    *it = look;
 
-                    if let Some (&&Token::RParen (_)) = it.peek() {
-                        it.next();
+    let mut bits = value.to_bits();
+    let mut end = tl.end;
 
-                        Some (
-                            Stmt::CallInst (
-                                target_var_type_pair,
-                                Function {name: funcname.to_owned()},
-                                param_list,
-                            )
-                        )
-                    }
-                    else {
-                        panic!("Expected ')', got trash somewhere");
-                    }
-                }
-                else {
-                    panic!("Expected '(', got trash somewhere");
+    if let (true, Some (&&Token::LParen (_))) = (value.is_nan(), it.peek()) {
+        it.next();
+
+        let payload = match it.next() {
+            Some (&Token::Integer (n, None, ref ptl)) => {
+                if n <= 0 || n as u64 > NAN_PAYLOAD_MASK {
+                    let msg = format!("NaN payload {:#x} out of range", n);
+
+                    return Err (
+                        Diagnostic::error("E0206", msg)
+                            .with_primary(ptl, "not a NaN payload")
+                            .with_note("the payload must be between 0x1 and \
+                                        0xfffffffffffff")
+                    );
                 }
+
+                n as u64
             }
-            else {
-                panic!("Expected FuncName");
-            }
-        }
-        else {
-            panic!("Expected return VarTypePair");
+            found => return Err (expected("NaN payload", found.as_ref(), tl)),
+        };
+
+        match it.next() {
+            Some (&Token::RParen (ref rtl)) => end = rtl.end,
+            found => return Err (expected("')'", found.as_ref(), tl)),
         }
+
+        bits = bits & !NAN_PAYLOAD_MASK | payload;
     }
-    else {
-        None
+
+    let start_tl = match minus_tl {
+        Some (mtl) => {
+            bits |= 1 << 63;
+            mtl
+        }
+        None => tl.clone(),
     };
+
+    return Ok (Some ((f64::from_bits(bits), TokLoc {end: end, ..start_tl})));
 }
 
+fn parse_operand(it: &mut Peekable<Iter<Token>>)
+    -> ParseResult<Operand>
+{
+    return Ok (if let Some (&&Token::Integer (i, ref suffix, ref tl))
+        = it.peek()
+    {
+        it.next();
+
+        let suffix_type = match suffix {
+            &Some (ref s) => Some (int_suffix_type(s, tl)?),
+            &None => None,
+        };
 
-fn parse_statement(mut it: &mut Peekable<Iter<Token>>) -> Option<Stmt> {
-    return if let Some (node) = parse_add(&mut it) {
-        Some (node)
-    }
-    else if let Some (node) = parse_sub(&mut it) {
-        Some (node)
+        Some (Operand::OpInteger (i, suffix_type, tl.clone()))
     }
-    else if let Some (node) = parse_let(&mut it) {
-        Some (node)
+    else if let Some (&&Token::Float (val, ref tl)) = it.peek() {
+        it.next();
+
+        Some (Operand::OpFloat (val, tl.clone()))
     }
-    else if let Some (node) = parse_ret(&mut it) {
-        Some (node)
+    else if let Some ((val, tl)) = parse_float_word(it)? {
+        Some (Operand::OpFloat (val, tl))
     }
-    else if let Some (node) = parse_func_call(&mut it) {
-        Some (node)
+    else if let Some (&&Token::VarName (ref varname, _)) = it.peek() {
+        it.next();
+
+        Some (Operand::OpVariable (Variable {name: varname.to_owned()}))
     }
     else {
         None
-    };
+    });
 }
 
-fn parse_statements(mut it: &mut Peekable<Iter<Token>>) -> Vec<Stmt> {
-    let mut stmts = Vec::new();
+fn parse_let(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::LetKeyword (ref tl)) = it.peek() {
+        it.next();
 
-    while let Some (node) = parse_statement(&mut it) {
-        stmts.push(node);
+        if let Some (dest_var_type_pair) = parse_var_type_pair(it)? {
+            if let Some (let_value) = parse_operand(it)?
+            {
+                Some (Stmt::LetInst (
+                    dest_var_type_pair,
+                    let_value,
+                ))
+            }
+            else {
+                return Err (expected("operand", it.peek(), tl));
+            }
+        }
+        else {
+            return Err (expected("<var>:<type> pair", it.peek(), tl));
+        }
     }
-
-    return stmts;
+    else {
+        None
+    });
 }
 
-pub fn parse(tokens: &Vec<Token>) -> Option<Node> {
-    let mut it = tokens.iter().peekable();
-
-    return parse_func(&mut it);
+fn parse_ret_value(it: &mut Peekable<Iter<Token>>)
+    -> ParseResult<Option<Operand>>
+{
+    return if let Some (&&Token::VoidKeyword (_)) = it.peek() {
+        it.next();
+
+        Ok (Some (None))
+    }
+    else {
+        Ok (parse_operand(it)?.map(Some))
+    };
+}
+
+
+fn parse_ret(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::RetKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (ret_value) = parse_ret_value(it)?
+        {
+            Some (Stmt::RetInst (
+                ret_value
+            ))
+        }
+        else {
+            return Err (expected("operand or 'void'", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+fn parse_binary_inst(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    let inst: fn(VarTypePair, Operand, Operand) -> Stmt = match it.peek() {
+        Some (&&Token::AddKeyword (_)) => Stmt::AddInst,
+        Some (&&Token::SubKeyword (_)) => Stmt::SubInst,
+        Some (&&Token::MulKeyword (_)) => Stmt::MulInst,
+        Some (&&Token::SDivKeyword (_)) => Stmt::SDivInst,
+        Some (&&Token::UDivKeyword (_)) => Stmt::UDivInst,
+        Some (&&Token::SRemKeyword (_)) => Stmt::SRemInst,
+        Some (&&Token::URemKeyword (_)) => Stmt::URemInst,
+        Some (&&Token::AndKeyword (_)) => Stmt::AndInst,
+        Some (&&Token::OrKeyword (_)) => Stmt::OrInst,
+        Some (&&Token::XorKeyword (_)) => Stmt::XorInst,
+        Some (&&Token::ShlKeyword (_)) => Stmt::ShlInst,
+        Some (&&Token::LShrKeyword (_)) => Stmt::LShrInst,
+        Some (&&Token::AShrKeyword (_)) => Stmt::AShrInst,
+        _ => return Ok (None),
+    };
+
+    let tl = it.next().unwrap().loc();
+
+    return if let Some (target_var_type_pair) = parse_var_type_pair(it)? {
+        if let Some ((left_src, right_src))
+            = parse_binary_operands(it)?
+        {
+            Ok (Some (inst(
+                target_var_type_pair,
+                left_src,
+                right_src,
+            )))
+        }
+        else {
+            Err (expected("binary operands", it.peek(), tl))
+        }
+    }
+    else {
+        Err (expected("<var>:<type> pair", it.peek(), tl))
+    };
+}
+
+fn parse_unary_inst(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    let inst: fn(VarTypePair, Operand) -> Stmt = match it.peek() {
+        Some (&&Token::NegKeyword (_)) => Stmt::NegInst,
+        Some (&&Token::NotKeyword (_)) => Stmt::NotInst,
+        _ => return Ok (None),
+    };
+
+    let tl = it.next().unwrap().loc();
+
+    return if let Some (target_var_type_pair) = parse_var_type_pair(it)? {
+        if let Some (src) = parse_operand(it)? {
+            Ok (Some (inst(target_var_type_pair, src)))
+        }
+        else {
+            Err (expected("unary operand", it.peek(), tl))
+        }
+    }
+    else {
+        Err (expected("<var>:<type> pair", it.peek(), tl))
+    };
+}
+
+fn parse_cmp_pred(it: &mut Peekable<Iter<Token>>)
+    -> Option<(String, TokLoc)>
+{
+    return if let Some (&&Token::Ident (ref name, ref tl)) = it.peek() {
+        it.next();
+
+        Some ((name.to_owned(), tl.clone()))
+    }
+    else {
+        None
+    };
+}
+
+fn parse_cmp(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    let is_icmp = match it.peek() {
+        Some (&&Token::ICmpKeyword (_)) => true,
+        Some (&&Token::FCmpKeyword (_)) => false,
+        _ => return Ok (None),
+    };
+
+    let tl = it.next().unwrap().loc();
+
+    let dest = match parse_var_type_pair(it)? {
+        Some (vtp) => vtp,
+        None => return Err (expected("<var>:<type> pair", it.peek(), tl)),
+    };
+
+    let (pred_name, pred_tl) = match parse_cmp_pred(it) {
+        Some (pred) => pred,
+        None => {
+            return Err (expected("comparison predicate", it.peek(), tl));
+        }
+    };
+
+    let (left_src, right_src) = match parse_binary_operands(it)? {
+        Some (vars) => vars,
+        None => return Err (expected("binary operands", it.peek(), tl)),
+    };
+
+    let unknown_pred = |kind: &str| {
+        Diagnostic::error(
+            "E0202", format!("unknown {} predicate {}", kind, pred_name)
+        ).with_primary(&pred_tl, "not a predicate")
+            .with_secondary(tl, "in this comparison")
+    };
+
+    return if is_icmp {
+        match ICmpPred::from_name(&pred_name) {
+            Some (pred) => {
+                Ok (Some (Stmt::ICmpInst (dest, pred, left_src, right_src)))
+            }
+            None => {
+                Err (unknown_pred("icmp").with_note(
+                    "icmp takes eq, ne, slt, sle, sgt, sge, ult, ule, ugt \
+                     or uge"
+                ))
+            }
+        }
+    }
+    else {
+        match FCmpPred::from_name(&pred_name) {
+            Some (pred) => {
+                Ok (Some (Stmt::FCmpInst (dest, pred, left_src, right_src)))
+            }
+            None => {
+                Err (unknown_pred("fcmp").with_note(
+                    "fcmp takes an ordered (o..) or unordered (u..) \
+                     predicate, or ord/uno"
+                ))
+            }
+        }
+    };
+}
+
+fn parse_alloca(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::AllocaKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (dest_var_type_pair) = parse_var_type_pair(it)? {
+            Some (Stmt::AllocaInst (dest_var_type_pair))
+        }
+        else {
+            return Err (expected("<var>:<type> pair", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+fn parse_load(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::LoadKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (dest_var_type_pair) = parse_var_type_pair(it)? {
+            if let Some (&&Token::VarName (ref ptr, _)) = it.peek() {
+                it.next();
+
+                Some (Stmt::LoadInst (
+                    dest_var_type_pair,
+                    Variable {name: ptr.to_owned()},
+                ))
+            }
+            else {
+                return Err (expected("pointer variable", it.peek(), tl));
+            }
+        }
+        else {
+            return Err (expected("<var>:<type> pair", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+fn parse_store(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::StoreKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (value) = parse_operand(it)? {
+            if let Some (&&Token::VarName (ref ptr, _)) = it.peek() {
+                it.next();
+
+                Some (Stmt::StoreInst (
+                    value,
+                    Variable {name: ptr.to_owned()},
+                ))
+            }
+            else {
+                return Err (expected("pointer variable", it.peek(), tl));
+            }
+        }
+        else {
+            return Err (expected("operand to store", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+/// Zero or more constant aggregate indices, as used by extractvalue and
+/// insertvalue. An index is never negative, and must fit an i64.
+fn parse_const_indices(it: &mut Peekable<Iter<Token>>)
+    -> Result<Vec<i64>, Diagnostic>
+{
+    let mut indices = Vec::new();
+
+    while let Some (&&Token::Integer (i, None, ref tl)) = it.peek() {
+        it.next();
+
+        if i < 0 || i > i64::max_value() as i128 {
+            return Err (
+                Diagnostic::error(
+                    "E0207", format!("index {} is out of range", format_int(i))
+                ).with_primary(tl, "not a valid index")
+                    .with_note("indices are between 0 and 0x7fffffffffffffff")
+            );
+        }
+
+        indices.push(i as i64);
+    }
+
+    return Ok (indices);
+}
+
+fn parse_gep(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::GepKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        let dest = match parse_var_type_pair(it)? {
+            Some (vtp) => vtp,
+            None => return Err (expected("<var>:<type> pair", it.peek(), tl)),
+        };
+
+        let base = match it.peek() {
+            Some (&&Token::VarName (ref base, _)) => {
+                Variable {name: base.to_owned()}
+            }
+            tok => return Err (expected("pointer variable", tok, tl)),
+        };
+
+        it.next();
+
+        let mut indices = Vec::new();
+
+        while let Some (index) = parse_operand(it)? {
+            indices.push(index);
+        }
+
+        if indices.is_empty() {
+            return Err (expected("at least one index", it.peek(), tl));
+        }
+
+        Some (Stmt::GepInst (dest, base, indices))
+    }
+    else {
+        None
+    });
+}
+
+fn parse_extractvalue(it: &mut Peekable<Iter<Token>>)
+    -> ParseResult<Stmt>
+{
+    return Ok (if let Some (&&Token::ExtractValueKeyword (ref tl))
+        = it.peek()
+    {
+        it.next();
+
+        let dest = match parse_var_type_pair(it)? {
+            Some (vtp) => vtp,
+            None => return Err (expected("<var>:<type> pair", it.peek(), tl)),
+        };
+
+        let agg = match it.peek() {
+            Some (&&Token::VarName (ref agg, _)) => {
+                Variable {name: agg.to_owned()}
+            }
+            tok => return Err (expected("aggregate variable", tok, tl)),
+        };
+
+        it.next();
+
+        let indices = parse_const_indices(it)?;
+
+        if indices.is_empty() {
+            return Err (expected("at least one index", it.peek(), tl));
+        }
+
+        Some (Stmt::ExtractValueInst (dest, agg, indices))
+    }
+    else {
+        None
+    });
+}
+
+fn parse_insertvalue(it: &mut Peekable<Iter<Token>>)
+    -> ParseResult<Stmt>
+{
+    return Ok (if let Some (&&Token::InsertValueKeyword (ref tl))
+        = it.peek()
+    {
+        it.next();
+
+        let dest = match parse_var_type_pair(it)? {
+            Some (vtp) => vtp,
+            None => return Err (expected("<var>:<type> pair", it.peek(), tl)),
+        };
+
+        let agg = match it.peek() {
+            Some (&&Token::VarName (ref agg, _)) => {
+                Variable {name: agg.to_owned()}
+            }
+            tok => return Err (expected("aggregate variable", tok, tl)),
+        };
+
+        it.next();
+
+        let value = match parse_operand(it)? {
+            Some (value) => value,
+            None => return Err (expected("operand to insert", it.peek(), tl)),
+        };
+
+        let indices = parse_const_indices(it)?;
+
+        if indices.is_empty() {
+            return Err (expected("at least one index", it.peek(), tl));
+        }
+
+        Some (Stmt::InsertValueInst (dest, agg, value, indices))
+    }
+    else {
+        None
+    });
+}
+
+fn parse_cast(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    let op = match it.peek() {
+        Some (&&Token::TruncKeyword (_)) => CastOp::Trunc,
+        Some (&&Token::ZExtKeyword (_)) => CastOp::ZExt,
+        Some (&&Token::SExtKeyword (_)) => CastOp::SExt,
+        Some (&&Token::FPToSIKeyword (_)) => CastOp::FPToSI,
+        Some (&&Token::FPToUIKeyword (_)) => CastOp::FPToUI,
+        Some (&&Token::SIToFPKeyword (_)) => CastOp::SIToFP,
+        Some (&&Token::UIToFPKeyword (_)) => CastOp::UIToFP,
+        Some (&&Token::FPExtKeyword (_)) => CastOp::FPExt,
+        Some (&&Token::FPTruncKeyword (_)) => CastOp::FPTrunc,
+        Some (&&Token::BitcastKeyword (_)) => CastOp::Bitcast,
+        Some (&&Token::PtrToIntKeyword (_)) => CastOp::PtrToInt,
+        Some (&&Token::IntToPtrKeyword (_)) => CastOp::IntToPtr,
+        _ => return Ok (None),
+    };
+
+    let tl = it.next().unwrap().loc();
+
+    return if let Some (target_var_type_pair) = parse_var_type_pair(it)? {
+        if let Some (&&Token::VarName (ref src, _)) = it.peek() {
+            it.next();
+
+            Ok (Some (Stmt::CastInst (
+                target_var_type_pair,
+                op,
+                Variable {name: src.to_owned()},
+            )))
+        }
+        else {
+            Err (expected("cast source variable", it.peek(), tl))
+        }
+    }
+    else {
+        Err (expected("<var>:<type> pair", it.peek(), tl))
+    };
+}
+
+fn parse_addrof(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::AddrOfKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (dest_var_type_pair) = parse_var_type_pair(it)? {
+            if let Some (&&Token::FuncName (ref name, _)) = it.peek() {
+                it.next();
+
+                Some (Stmt::AddrOfInst (
+                    dest_var_type_pair,
+                    Global {name: name.to_owned()},
+                ))
+            }
+            else {
+                return Err (expected("global name", it.peek(), tl));
+            }
+        }
+        else {
+            return Err (expected("<var>:<type> pair", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+fn parse_arg_list (it: &mut Peekable<Iter<Token>>)
+    -> Result<Vec<VarTypePair>, Diagnostic>
+{
+    let mut arg_list = Vec::new();
+
+    while let Some (target_var_type_pair) = parse_var_type_pair(it)? {
+        arg_list.push(target_var_type_pair);
+
+        if let Some (&&Token::Comma(_)) = it.peek() {
+            it.next();
+        }
+        else {
+            break;
+        }
+    }
+
+    return Ok (arg_list);
+}
+
+/// A function signature, `@<name>:<type> (<args>)`. With `variadic_ok` the
+/// arguments may end in `...`.
+fn parse_func_sig(it: &mut Peekable<Iter<Token>>, variadic_ok: bool)
+    -> ParseResult<FuncSig>
+{
+    let name_tl = match it.peek() {
+        Some (tok) => tok.loc(),
+        None => return Ok (None),
+    };
+
+    return Ok (if let Some ((func_name, func_type))
+        = parse_func_type_pair(it, parse_ret_type)?
+    {
+
+        if let Some (&&Token::LParen (ref tl)) = it.peek() {
+            it.next();
+
+            let arg_list = parse_arg_list(it)?;
+            let mut variadic = false;
+
+            if let Some (&&Token::Ellipsis (_)) = it.peek() {
+                if variadic_ok {
+                    it.next();
+                    variadic = true;
+                }
+            }
+
+            if let Some (&&Token::RParen (_)) = it.peek() {
+                it.next();
+
+                Some (
+                    FuncSig {
+                        name: func_name,
+                        typename: func_type,
+                        arglist: arg_list,
+                        variadic: variadic,
+                        loc: name_tl.clone(),
+                    }
+                )
+            }
+            else {
+                return Err (expected("')'", it.peek(), tl));
+            }
+        }
+        else {
+            return Err (expected("'('", it.peek(), name_tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+/// A function definition. Errors in its body are added to `errors` and the
+/// rest of the body still parsed, so a body in error gives a partial function.
+fn parse_func(
+    it: &mut Peekable<Iter<Token>>, errors: &mut Vec<Diagnostic>
+) -> ParseResult<Node>
+{
+    return Ok (if let Some (&&Token::FuncKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (target_func_sig) = parse_func_sig(it, false)? {
+
+            if let Some (&&Token::LBrace (ref tl)) = it.peek() {
+                it.next();
+
+                let block_list = parse_blocks(it, tl, errors);
+
+                if let Some (&&Token::RBrace (_)) = it.peek() {
+                    it.next();
+                }
+                else {
+                    errors.push(expected("'}'", it.peek(), tl));
+                }
+
+                Some (
+                    Node::FuncDef (
+                        target_func_sig,
+                        block_list,
+                    )
+                )
+            }
+            else {
+                return Err (expected("'{'", it.peek(), tl));
+            }
+        }
+        else {
+            return Err (expected("function signature", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+/// Decode the escapes in the body of a string literal: `\n`, `\t`, `\0`,
+/// `\\`, `\"` and `\xHH`. Other characters stand for their UTF-8 bytes.
+fn unescape_str(s: &str, tl: &TokLoc) -> Result<Vec<u8>, Diagnostic> {
+    let mut bytes = Vec::new();
+    let mut chars = s.chars();
+
+    let bad_escape = |escape: String| {
+        Diagnostic::error(
+            "E0203", format!("unknown escape \\{} in string", escape)
+        ).with_primary(tl, "in this string")
+            .with_note("strings take \\n, \\t, \\0, \\\\, \\\" and \\xHH")
+    };
+
+    while let Some (c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        match chars.next() {
+            Some ('n') => bytes.push(b'\n'),
+            Some ('t') => bytes.push(b'\t'),
+            Some ('0') => bytes.push(0),
+            Some ('\\') => bytes.push(b'\\'),
+            Some ('"') => bytes.push(b'"'),
+            Some ('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+
+                match u8::from_str_radix(&hex, 16) {
+                    Ok (b) if hex.len() == 2 => bytes.push(b),
+                    _ => return Err (bad_escape(format!("x{}", hex))),
+                }
+            }
+            Some (c) => return Err (bad_escape(c.to_string())),
+            None => return Err (bad_escape(String::new())),
+        }
+    }
+
+    return Ok (bytes);
+}
+
+fn parse_initializer(it: &mut Peekable<Iter<Token>>)
+    -> ParseResult<Initializer>
+{
+    return Ok (if let Some (&&Token::Integer (i, ref suffix, ref tl))
+        = it.peek()
+    {
+        it.next();
+
+        let suffix_type = match suffix {
+            &Some (ref s) => Some (int_suffix_type(s, tl)?),
+            &None => None,
+        };
+
+        Some (Initializer::InitInteger (i, suffix_type, tl.clone()))
+    }
+    else if let Some (&&Token::Float (val, _)) = it.peek() {
+        it.next();
+
+        Some (Initializer::InitFloat (val))
+    }
+    else if let Some ((val, _)) = parse_float_word(it)? {
+        Some (Initializer::InitFloat (val))
+    }
+    else if let Some (&&Token::ZeroInitKeyword (_)) = it.peek() {
+        it.next();
+
+        Some (Initializer::InitZero)
+    }
+    else if let Some (&&Token::StrLit (ref s, ref tl)) = it.peek() {
+        it.next();
+
+        Some (Initializer::InitString (unescape_str(s, tl)?))
+    }
+    else if let Some (&&Token::LBracket (ref tl)) = it.peek() {
+        it.next();
+
+        let mut elems = Vec::new();
+
+        while let Some (elem) = parse_initializer(it)? {
+            elems.push(elem);
+
+            if let Some (&&Token::Comma (_)) = it.peek() {
+                it.next();
+            }
+            else {
+                break;
+            }
+        }
+
+        if let Some (&&Token::RBracket (_)) = it.peek() {
+            it.next();
+        }
+        else {
+            return Err (expected("']'", it.peek(), tl));
+        }
+
+        Some (Initializer::InitArray (elems))
+    }
+    else {
+        None
+    });
+}
+
+/// A global definition, `global @<name>:<type> = <init>`, or the same with
+/// `constant` for read-only data.
+fn parse_global(it: &mut Peekable<Iter<Token>>) -> ParseResult<Node> {
+    let (constant, tl) = match it.peek() {
+        Some (&&Token::GlobalKeyword (ref tl)) => (false, tl),
+        Some (&&Token::ConstantKeyword (ref tl)) => (true, tl),
+        _ => return Ok (None),
+    };
+
+    it.next();
+
+    let (name, typename) = match parse_func_type_pair(it, parse_type)? {
+        Some (pair) => pair,
+        None => return Err (expected("@<name>:<type> pair", it.peek(), tl)),
+    };
+
+    match it.peek() {
+        Some (&&Token::Equals (_)) => {}
+        tok => return Err (expected("'='", tok, tl)),
+    }
+
+    it.next();
+
+    let init = match parse_initializer(it)? {
+        Some (init) => init,
+        None => return Err (expected("initializer", it.peek(), tl)),
+    };
+
+    return Ok (Some (Node::GlobalDef (GlobalDef {
+        name: name,
+        typename: typename,
+        constant: constant,
+        init: init,
+        loc: tl.clone(),
+    })));
+}
+
+/// A function declared here and defined elsewhere, `declare <sig>`.
+fn parse_func_decl(it: &mut Peekable<Iter<Token>>) -> ParseResult<Node> {
+    return Ok (if let Some (&&Token::DeclareKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (target_func_sig) = parse_func_sig(it, true)? {
+            Some (Node::FuncDecl (target_func_sig))
+        }
+        else {
+            return Err (expected("function signature", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+fn parse_param_list (it: &mut Peekable<Iter<Token>>)
+    -> Result<Vec<Operand>, Diagnostic>
+{
+    let mut param_list = Vec::new();
+
+    while let Some (arg) = parse_operand(it)? {
+        param_list.push(arg);
+
+        if let Some (&&Token::Comma(_)) = it.peek() {
+            it.next();
+        }
+        else {
+            break;
+        }
+    }
+
+    return Ok (param_list);
+}
+
+fn parse_func_call(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::CallKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (target_var_type_pair)
+            = parse_var_type_pair_of(it, parse_ret_type)?
+        {
+            if let Some (&&Token::FuncName (ref funcname, _)) = it.peek() {
+                it.next();
+
+                if let Some (&&Token::LParen (ref tl)) = it.peek() {
+                    it.next();
+
+                    let param_list = parse_param_list(it)?;
+
+                    if let Some (&&Token::RParen (_)) = it.peek() {
+                        it.next();
+
+                        Some (
+                            Stmt::CallInst (
+                                target_var_type_pair,
+                                Function {name: funcname.to_owned()},
+                                param_list,
+                            )
+                        )
+                    }
+                    else {
+                        return Err (expected("')'", it.peek(), tl));
+                    }
+                }
+                else {
+                    return Err (expected("'('", it.peek(), tl));
+                }
+            }
+            else {
+                return Err (expected("function name", it.peek(), tl));
+            }
+        }
+        else {
+            return Err (expected("<var>:<type> pair", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+
+fn parse_label(it: &mut Peekable<Iter<Token>>) -> Option<Label> {
+    return if let Some (&&Token::Ident (ref name, _)) = it.peek() {
+        it.next();
+
+        Some (Label {name: name.to_owned()})
+    }
+    else {
+        None
+    };
+}
+
+/// The condition of a `br`. Only variables and integers are taken, since a
+/// float spelling such as `inf` would be the label of an unconditional `br`.
+fn parse_br_cond(it: &mut Peekable<Iter<Token>>) -> ParseResult<Operand> {
+    return match it.peek() {
+        Some (&&Token::VarName (..)) | Some (&&Token::Integer (..)) => {
+            parse_operand(it)
+        }
+        _ => Ok (None),
+    };
+}
+
+fn parse_br(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::BrKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (cond) = parse_br_cond(it)? {
+            if let Some (on_true) = parse_label(it) {
+                if let Some (on_false) = parse_label(it) {
+                    Some (Stmt::CondBrInst (
+                        cond,
+                        on_true,
+                        on_false,
+                    ))
+                }
+                else {
+                    return Err (
+                        expected("false branch label", it.peek(), tl)
+                    );
+                }
+            }
+            else {
+                return Err (expected("true branch label", it.peek(), tl));
+            }
+        }
+        else if let Some (target) = parse_label(it) {
+            Some (Stmt::BrInst (target))
+        }
+        else {
+            return Err (
+                expected("branch label or condition", it.peek(), tl)
+            );
+        }
+    }
+    else {
+        None
+    });
+}
+
+fn parse_switch_cases(it: &mut Peekable<Iter<Token>>)
+    -> Result<Vec<(i128, Label)>, Diagnostic>
+{
+    let mut cases = Vec::new();
+
+    while let Some (&&Token::Integer (i, ref suffix, ref tl)) = it.peek() {
+        it.next();
+
+        if let &Some (ref s) = suffix {
+            return Err (
+                Diagnostic::error(
+                    "E0204", format!("switch case value has suffix {}", s)
+                ).with_primary(tl, "remove the suffix")
+                    .with_note("case values take the type of the switch value")
+            );
+        }
+
+        if let Some (target) = parse_label(it) {
+            cases.push((i, target));
+        }
+        else {
+            return Err (expected("case label", it.peek(), tl));
+        }
+
+        if let Some (&&Token::Comma(_)) = it.peek() {
+            it.next();
+        }
+        else {
+            break;
+        }
+    }
+
+    return Ok (cases);
+}
+
+fn parse_switch(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::SwitchKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (val) = parse_operand(it)? {
+            if let Some (default) = parse_label(it) {
+                if let Some (&&Token::LBracket (ref tl)) = it.peek() {
+                    it.next();
+
+                    let cases = parse_switch_cases(it)?;
+
+                    if let Some (&&Token::RBracket (_)) = it.peek() {
+                        it.next();
+
+                        Some (Stmt::SwitchInst (
+                            val,
+                            default,
+                            cases,
+                        ))
+                    }
+                    else {
+                        return Err (expected("']'", it.peek(), tl));
+                    }
+                }
+                else {
+                    return Err (expected("'['", it.peek(), tl));
+                }
+            }
+            else {
+                return Err (expected("default label", it.peek(), tl));
+            }
+        }
+        else {
+            return Err (expected("switch value", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+fn parse_phi_incoming(it: &mut Peekable<Iter<Token>>)
+    -> Result<Vec<(Operand, Label)>, Diagnostic>
+{
+    let mut incoming = Vec::new();
+
+    while let Some (&tok) = it.peek() {
+        let val = match parse_operand(it)? {
+            Some (val) => val,
+            None => break,
+        };
+
+        if let Some (pred) = parse_label(it) {
+            incoming.push((val, pred));
+        }
+        else {
+            return Err (expected("predecessor label", it.peek(), tok.loc()));
+        }
+
+        if let Some (&&Token::Comma(_)) = it.peek() {
+            it.next();
+        }
+        else {
+            break;
+        }
+    }
+
+    return Ok (incoming);
+}
+
+fn parse_phi(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (&&Token::PhiKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        if let Some (target_var_type_pair) = parse_var_type_pair(it)? {
+            if let Some (&&Token::LBracket (ref tl)) = it.peek() {
+                it.next();
+
+                let incoming = parse_phi_incoming(it)?;
+
+                if let Some (&&Token::RBracket (_)) = it.peek() {
+                    it.next();
+
+                    Some (Stmt::PhiInst (target_var_type_pair, incoming))
+                }
+                else {
+                    return Err (expected("']'", it.peek(), tl));
+                }
+            }
+            else {
+                return Err (expected("'['", it.peek(), tl));
+            }
+        }
+        else {
+            return Err (expected("<var>:<type> pair", it.peek(), tl));
+        }
+    }
+    else {
+        None
+    });
+}
+
+fn parse_statement(it: &mut Peekable<Iter<Token>>) -> ParseResult<Stmt> {
+    return Ok (if let Some (node) = parse_binary_inst(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_unary_inst(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_cmp(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_cast(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_addrof(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_alloca(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_load(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_store(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_gep(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_extractvalue(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_insertvalue(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_let(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_ret(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_func_call(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_br(it)? {
+        Some (node)
+    }
+    else if let Some (node) = parse_switch(it)? {
+        Some (node)
+    }
+    else { parse_phi(it)? });
+}
+
+/// Whether `tok` can only start a statement.
+fn starts_statement(tok: &Token) -> bool {
+    return match tok {
+        &Token::AddKeyword (_) | &Token::SubKeyword (_) |
+        &Token::MulKeyword (_) | &Token::SDivKeyword (_) |
+        &Token::UDivKeyword (_) | &Token::SRemKeyword (_) |
+        &Token::URemKeyword (_) | &Token::AndKeyword (_) |
+        &Token::OrKeyword (_) | &Token::XorKeyword (_) |
+        &Token::ShlKeyword (_) | &Token::LShrKeyword (_) |
+        &Token::AShrKeyword (_) | &Token::NegKeyword (_) |
+        &Token::NotKeyword (_) | &Token::ICmpKeyword (_) |
+        &Token::FCmpKeyword (_) | &Token::AllocaKeyword (_) |
+        &Token::LoadKeyword (_) | &Token::StoreKeyword (_) |
+        &Token::GepKeyword (_) | &Token::ExtractValueKeyword (_) |
+        &Token::InsertValueKeyword (_) | &Token::TruncKeyword (_) |
+        &Token::ZExtKeyword (_) | &Token::SExtKeyword (_) |
+        &Token::FPToSIKeyword (_) | &Token::FPToUIKeyword (_) |
+        &Token::SIToFPKeyword (_) | &Token::UIToFPKeyword (_) |
+        &Token::FPExtKeyword (_) | &Token::FPTruncKeyword (_) |
+        &Token::BitcastKeyword (_) | &Token::PtrToIntKeyword (_) |
+        &Token::IntToPtrKeyword (_) | &Token::AddrOfKeyword (_) |
+        &Token::LetKeyword (_) | &Token::RetKeyword (_) |
+        &Token::CallKeyword (_) | &Token::BrKeyword (_) |
+        &Token::SwitchKeyword (_) | &Token::PhiKeyword (_) => true,
+        _ => false,
+    };
+}
+
+/// Whether `tok` can only start a module item.
+fn starts_item(tok: &Token) -> bool {
+    return match tok {
+        &Token::FuncKeyword (_) | &Token::DeclareKeyword (_) |
+        &Token::GlobalKeyword (_) | &Token::ConstantKeyword (_) |
+        &Token::TypeKeyword (_) => true,
+        _ => false,
+    };
+}
+
+/// Whether the next tokens are a block label, `<name>:`.
+fn at_label(it: &Peekable<Iter<Token>>) -> bool {
+    let mut look = it.clone();
+
+    return match (look.next(), look.next()) {
+        (Some (&Token::Ident (..)), Some (&Token::Colon (_))) => true,
+        _ => false,
+    };
+}
+
+/// Whether a function body may pick up again at the next token: a block
+/// label, or the end of the body or of the input.
+fn at_block_boundary(it: &mut Peekable<Iter<Token>>) -> bool {
+    let tok: &Token = match it.peek() {
+        Some (tok) => tok,
+        None => return true,
+    };
+
+    return match tok {
+        &Token::RBrace (_) => true,
+        _ => starts_item(tok) || at_label(it),
+    };
+}
+
+/// After an error in a function body, skip ahead to where parsing can pick up
+/// again: the next statement, or a block boundary.
+fn skip_to_statement(it: &mut Peekable<Iter<Token>>) {
+    loop {
+        let tok: &Token = match it.peek() {
+            Some (tok) => tok,
+            None => return,
+        };
+
+        if starts_statement(tok) || at_block_boundary(it) {
+            return;
+        }
+
+        it.next();
+    }
+}
+
+/// After an error between blocks, skip ahead to the next block boundary.
+fn skip_to_block(it: &mut Peekable<Iter<Token>>) {
+    while !at_block_boundary(it) {
+        it.next();
+    }
+}
+
+/// After an error at module scope, skip ahead to the next module item.
+fn skip_to_item(it: &mut Peekable<Iter<Token>>) {
+    loop {
+        match it.peek() {
+            Some (tok) if !starts_item(tok) => {}
+            _ => return,
+        }
+
+        it.next();
+    }
+}
+
+/// Parse statements up to and including the block's terminator, along with
+/// where each one starts. `tl` is where the block starts. A statement in
+/// error is left out, and parsing picks up again at the next statement; the
+/// block ends early at a block boundary, terminated or not.
+fn parse_block_statements(
+    it: &mut Peekable<Iter<Token>>, label: &str, tl: &TokLoc,
+    errors: &mut Vec<Diagnostic>
+) -> (Vec<Stmt>, Vec<TokLoc>)
+{
+    let mut stmts = Vec::new();
+    let mut locs = Vec::new();
+
+    // Set after an error, so that a block whose terminator was the statement
+    // in error is not reported again as unterminated.
+    let mut recovering = false;
+
+    loop {
+        let stmt_tl = match it.peek() {
+            Some (tok) => tok.loc().clone(),
+            None => tl.clone(),
+        };
+
+        match parse_statement(it) {
+            Ok (Some (node)) => {
+                let terminated = is_terminator(&node);
+
+                stmts.push(node);
+                locs.push(stmt_tl);
+                recovering = false;
+
+                if terminated {
+                    break;
+                }
+            }
+            Ok (None) if at_block_boundary(it) => {
+                if !recovering {
+                    let diag = Diagnostic::error(
+                        "E0205",
+                        format!(
+                            "block '{}' does not end in a terminator", label
+                        )
+                    );
+                    let diag = match it.peek() {
+                        Some (tok) => {
+                            diag.with_primary(tok.loc(), "expected a statement")
+                                .with_secondary(tl, "block starts here")
+                        }
+                        None => diag.with_primary(tl, "block starts here"),
+                    };
+
+                    errors.push(
+                        diag.with_note("a block must end in ret, br or switch")
+                    );
+                }
+
+                break;
+            }
+            Ok (None) => {
+                errors.push(expected("a statement", it.peek(), &stmt_tl));
+                recovering = true;
+
+                it.next();
+                skip_to_statement(it);
+            }
+            Err (diag) => {
+                errors.push(diag);
+                recovering = true;
+
+                skip_to_statement(it);
+            }
+        }
+    }
+
+    return (stmts, locs);
+}
+
+/// Parse the blocks of a function body, up to but not including its '}'.
+fn parse_blocks(
+    it: &mut Peekable<Iter<Token>>, brace_tl: &TokLoc,
+    errors: &mut Vec<Diagnostic>
+) -> Vec<BasicBlock>
+{
+    let mut blocks = Vec::new();
+
+    loop {
+        let (label, tl) = if at_label(it) {
+            let tl = it.peek().unwrap().loc();
+            let name = match it.next() {
+                Some (&Token::Ident (ref name, _)) => name.to_owned(),
+                _ => unreachable!(),
+            };
+
+            it.next();
+
+            (name, tl)
+        }
+        else if blocks.len() == 0 {
+            (ENTRY_LABEL.to_owned(), brace_tl)
+        }
+        else if at_block_boundary(it) {
+            break;
+        }
+        else {
+            errors.push(
+                expected("label or '}' after terminator", it.peek(), brace_tl)
+            );
+
+            skip_to_block(it);
+            continue;
+        };
+
+        let (stmts, locs) = parse_block_statements(it, &label, tl, errors);
+
+        blocks.push(BasicBlock {
+            label: label,
+            stmts: stmts,
+            loc: tl.clone(),
+            locs: locs,
+        });
+    }
+
+    return blocks;
+}
+
+/// A struct type declaration, `type <name> = {<type>, ...}`.
+fn parse_struct_def(it: &mut Peekable<Iter<Token>>) -> ParseResult<Node> {
+    return Ok (if let Some (&&Token::TypeKeyword (ref tl)) = it.peek() {
+        it.next();
+
+        let name = match it.peek() {
+            Some (&&Token::Ident (ref name, _)) => name.to_owned(),
+            tok => return Err (expected("type name", tok, tl)),
+        };
+
+        it.next();
+
+        match it.peek() {
+            Some (&&Token::Equals (_)) => {}
+            tok => return Err (expected("'='", tok, tl)),
+        }
+
+        it.next();
+
+        match it.peek() {
+            Some (&&Token::LBrace (_)) => {}
+            tok => return Err (expected("'{'", tok, tl)),
+        }
+
+        it.next();
+
+        let mut fields = Vec::new();
+
+        while let Some (field) = parse_type(it)? {
+            fields.push(field);
+
+            if let Some (&&Token::Comma (_)) = it.peek() {
+                it.next();
+            }
+            else {
+                break;
+            }
+        }
+
+        match it.peek() {
+            Some (&&Token::RBrace (_)) => {}
+            tok => return Err (expected("'}'", tok, tl)),
+        }
+
+        it.next();
+
+        Some (Node::StructDef (name, fields, tl.clone()))
+    }
+    else {
+        None
+    });
+}
+
+fn parse_item(
+    it: &mut Peekable<Iter<Token>>, errors: &mut Vec<Diagnostic>
+) -> ParseResult<Node>
+{
+    return Ok (if let Some (def) = parse_struct_def(it)? {
+        Some (def)
+    }
+    else if let Some (global) = parse_global(it)? {
+        Some (global)
+    }
+    else if let Some (decl) = parse_func_decl(it)? {
+        Some (decl)
+    }
+    else { parse_func(it, errors)? });
+}
+
+/// A parsed module, along with every syntax error found in it. After an
+/// error the parser picks up again at the next statement, block or module
+/// item, so the module holds whatever could be parsed around the errors.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct ParsedModule {
+    pub ast: Node,
+    pub errors: Vec<Diagnostic>,
+}
+
+impl ParsedModule {
+    pub fn has_errors(&self) -> bool {
+        return self.errors.len() > 0;
+    }
+
+    pub fn into_result(self) -> Result<Node, Vec<Diagnostic>> {
+        return if self.has_errors() {
+            Err (self.errors)
+        }
+        else {
+            Ok (self.ast)
+        };
+    }
+}
+
+/// Parse a whole module, consuming every token. Items are kept in source
+/// order.
+pub fn parse(tokens: &Vec<Token>) -> ParsedModule {
+    let mut it = tokens.iter().peekable();
+
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+
+    while let Some (&tok) = it.peek() {
+        match parse_item(&mut it, &mut errors) {
+            Ok (Some (item)) => items.push(item),
+            Ok (None) => {
+                errors.push(
+                    expected("a module item", Some (&tok), tok.loc())
+                        .with_note("a module holds type, global, constant, \
+                                    declare and func items")
+                );
+
+                it.next();
+                skip_to_item(&mut it);
+            }
+            Err (diag) => {
+                errors.push(diag);
+
+                skip_to_item(&mut it);
+            }
+        }
+    }
+
+    return ParsedModule {ast: Node::Module (items), errors: errors};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(source: &str) -> ParsedModule {
+        return parse(&tokenize(source, FileId (0)).into_result().unwrap());
+    }
+
+    /// The statements of the only function in `source`, which must parse.
+    fn stmts(source: &str) -> Vec<Stmt> {
+        return match parse_str(source).into_result() {
+            Ok (Node::Module (ref items)) => match &items[..] {
+                &[Node::FuncDef (_, ref blocks)] => {
+                    blocks.iter().flat_map(|b| b.stmts.clone()).collect()
+                }
+                items => panic!("unexpected items {:?}", items),
+            },
+            result => panic!("unexpected result {:?}", result),
+        };
+    }
+
+    #[test]
+    fn float_words_are_literals() {
+        let source =
+            "func @f:f64 () {\n\
+                 let %a:f64 inf\n\
+                 let %b:f64 -inf\n\
+                 let %c:f64 -nan(0x1)\n\
+                 br nan\n\
+             nan:\n\
+                 ret %c\n\
+             }";
+        let stmts = stmts(source);
+
+        let vals: Vec<(f64, &str)> = stmts[..3].iter().map(|stmt| match stmt {
+            &Stmt::LetInst (_, Operand::OpFloat (val, ref tl)) => {
+                (val, &source[tl.start..tl.end])
+            }
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }).collect();
+
+        assert_eq!(vals[0], (f64::INFINITY, "inf"));
+        assert_eq!(vals[1], (f64::NEG_INFINITY, "-inf"));
+        assert_eq!(vals[2].0.to_bits(), 0xfff0_0000_0000_0001);
+        assert_eq!(vals[2].1, "-nan(0x1)");
+
+        match &stmts[3] {
+            &Stmt::BrInst (ref target) => assert_eq!(target.name, "nan"),
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn bad_nan_payloads() {
+        for payload in &["0", "0x10000000000000"] {
+            let source = format!(
+                "func @f:f64 () {{\n    ret nan({})\n}}", payload
+            );
+            let errors = parse_str(&source).errors;
+
+            assert_eq!(errors[0].code, "E0206", "payload {}", payload);
+        }
+    }
+
+    #[test]
+    fn constant_indices_fit_an_i64() {
+        for index in &["-1", "0x8000000000000000"] {
+            let source = format!(
+                "func @f:i8 (%a:[2 x i8]) {{\n    \
+                     extractvalue %b:i8 %a 0 {}\n    \
+                     ret %b\n}}",
+                index
+            );
+            let errors = parse_str(&source).errors;
+            let loc = errors[0].primary_loc().unwrap();
+
+            assert_eq!(errors[0].code, "E0207", "index {}", index);
+            assert_eq!(&source[loc.start..loc.end], *index);
+        }
+    }
+
+    #[test]
+    fn floats_format_to_what_they_parse_from() {
+        let vals = [
+            0.0, -0.0, 1.5, 1e300, 5e-324, f64::INFINITY, f64::NEG_INFINITY,
+            f64::NAN, -f64::NAN, f64::from_bits(0x7ff0_0000_0000_0001),
+        ];
+
+        for &val in &vals {
+            let text = format_float(val);
+            let source = format!("func @f:f64 () {{\n    ret {}\n}}", text);
+
+            match &stmts(&source)[0] {
+                &Stmt::RetInst (Some (Operand::OpFloat (back, _))) => {
+                    assert_eq!(back.to_bits(), val.to_bits(), "{}", text);
+                }
+                stmt => panic!("unexpected statement {:?}", stmt),
+            }
+        }
+    }
+
+    #[test]
+    fn integers_format_to_what_they_lex_from() {
+        assert_eq!(format_int(-5), "-5");
+        assert_eq!(format_int(i64::max_value() as i128), "9223372036854775807");
+        assert_eq!(format_int(1 << 63), "0x8000000000000000");
+
+        for &val in &[0, -1, -(1 << 63), i64::max_value() as i128, 1 << 63] {
+            let text = format_int(val);
+
+            match &tokenize(&text, FileId (0)).tokens[..] {
+                &[Token::Integer (back, None, _)] => assert_eq!(back, val),
+                tokens => panic!("unexpected tokens {:?}", tokens),
+            }
+        }
+    }
+
+    #[test]
+    fn modules_hold_every_function_in_order() {
+        let module = parse_str(
+            "func @a:i64 () {\n    ret 1\n}\n\
+             func @b:i64 (%x:i64) {\n    ret %x\n}\n\
+             func @c:void () {\n    ret void\n}\n"
+        );
+
+        assert!(!module.has_errors());
+
+        let names: Vec<&str> = match &module.ast {
+            &Node::Module (ref items) => items.iter().map(|item| match item {
+                &Node::FuncDef (ref sig, _) => sig.name.as_str(),
+                item => panic!("unexpected item {:?}", item),
+            }).collect(),
+            node => panic!("unexpected node {:?}", node),
+        };
+
+        assert_eq!(names, vec!("a", "b", "c"));
+    }
+
+    /// The blocks of the only function in `source`, which must parse.
+    fn blocks(source: &str) -> Vec<BasicBlock> {
+        return match parse_str(source).into_result() {
+            Ok (Node::Module (ref items)) => match &items[..] {
+                &[Node::FuncDef (_, ref blocks)] => blocks.clone(),
+                items => panic!("unexpected items {:?}", items),
+            },
+            result => panic!("unexpected result {:?}", result),
+        };
+    }
+
+    #[test]
+    fn bodies_split_into_labeled_blocks() {
+        let blocks = blocks(
+            "func @f:i64 (%c:i1, %x:i64) {\n\
+                 br %c one two\n\
+             one:\n\
+                 switch %x two [1 one, -2 done]\n\
+             two:\n\
+                 br done\n\
+             done:\n\
+                 ret %x\n\
+             }"
+        );
+
+        let labels: Vec<&str> = blocks.iter().map(|b| b.label.as_str())
+            .collect();
+
+        assert_eq!(labels, vec!(ENTRY_LABEL, "one", "two", "done"));
+        assert_eq!(blocks[0].successors(), vec!("one", "two"));
+        assert_eq!(blocks[1].successors(), vec!("two", "one", "done"));
+        assert_eq!(blocks[2].successors(), vec!("done"));
+        assert!(blocks[3].successors().is_empty());
+
+        for block in &blocks {
+            assert!(block.terminator().is_some());
+            assert_eq!(block.stmts.len(), block.locs.len());
+        }
+    }
+
+    #[test]
+    fn blocks_must_end_in_a_terminator() {
+        let errors = parse_str(
+            "func @f:i64 () {\n    let %a:i64 1\nnext:\n    ret %a\n}"
+        ).errors;
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "E0205");
+
+        let errors = parse_str(
+            "func @f:i64 () {\n    ret 0\n    ret 1\n}"
+        ).errors;
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("label or '}' after terminator"));
+    }
+
+    const INT_OPS: [&'static str; 13] = [
+        "add", "sub", "mul", "sdiv", "udiv", "srem", "urem", "and", "or", "xor",
+        "shl", "lshr", "ashr",
+    ];
+
+    #[test]
+    fn arithmetic_statements_print_as_written() {
+        let mut lines: Vec<String> = INT_OPS.iter()
+            .map(|op| format!("{} %{}:i32 %a -3", op, op))
+            .collect();
+
+        lines.push("neg %n:i32 %a".to_owned());
+        lines.push("not %m:i32 7".to_owned());
+        lines.push("ret void".to_owned());
+
+        let source = format!(
+            "func @f:void (%a:i32) {{\n    {}\n}}", lines.join("\n    ")
+        );
+        let printed: Vec<String> = stmts(&source).iter()
+            .map(|stmt| stmt.to_string())
+            .collect();
+
+        assert_eq!(printed, lines);
+    }
+
+    #[test]
+    fn every_comparison_predicate_parses() {
+        let icmp = [
+            "eq", "ne", "slt", "sle", "sgt", "sge", "ult", "ule", "ugt", "uge",
+        ];
+        let fcmp = [
+            "oeq", "one", "olt", "ole", "ogt", "oge", "ord", "ueq", "une",
+            "ult", "ule", "ugt", "uge", "uno",
+        ];
+
+        let mut lines: Vec<String> = icmp.iter()
+            .map(|pred| format!("icmp %i{}:i1 {} %a 0", pred, pred))
+            .collect();
+
+        lines.extend(fcmp.iter()
+            .map(|pred| format!("fcmp %f{}:i1 {} %x 0.5", pred, pred)));
+        lines.push("ret void".to_owned());
+
+        let source = format!(
+            "func @f:void (%a:i32, %x:f32) {{\n    {}\n}}",
+            lines.join("\n    ")
+        );
+        let printed: Vec<String> = stmts(&source).iter()
+            .map(|stmt| stmt.to_string())
+            .collect();
+
+        assert_eq!(printed, lines);
+
+        let errors = parse_str(
+            "func @f:void (%a:i32) {\n    icmp %c:i1 oeq %a 0\n    ret void\n}"
+        ).errors;
+
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn immediates_are_operands_everywhere() {
+        let lines = [
+            "add %a:i64 1 2", "call %c:i64 @g(5, -1.5, %a)",
+            "br 1 next next", "next:",
+            "phi %p:i64 [7 entry]", "switch 2i8 done [1 done]", "done:",
+            "ret 0x8000000000000000",
+        ];
+        let source = format!(
+            "func @f:i64 () {{\n{}\n}}", lines.join("\n")
+        );
+        let stmts = stmts(&source);
+        let printed: Vec<String> = stmts.iter()
+            .map(|stmt| stmt.to_string())
+            .collect();
+        let expected: Vec<&str> = lines.iter().cloned()
+            .filter(|line| !line.ends_with(':'))
+            .collect();
+
+        assert_eq!(printed, expected);
+
+        let uses: Vec<&str> = stmts.iter()
+            .flat_map(|stmt| stmt.uses())
+            .map(|var| var.name.as_str())
+            .collect();
+
+        assert_eq!(uses, vec!("a"));
+    }
+
+    #[test]
+    fn branch_conditions_are_not_float_words() {
+        let errors = parse_str(
+            "func @f:i64 () {\n    br inf a b\na:\n    ret 0\nb:\n    ret 1\n}"
+        ).errors;
+
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn pointer_types_nest() {
+        let source = "func @f:void () {\n    alloca %p:i8***\n    ret void\n}";
+
+        match &stmts(source)[0] {
+            &Stmt::AllocaInst (ref vtp) => {
+                let i8_ptr = Type::Ptr (Box::new(Type::I8));
+                let i8_ptr_ptr = Type::Ptr (Box::new(i8_ptr));
+
+                assert!(is_promotable_to(
+                    &vtp.typename, &Type::Ptr (Box::new(i8_ptr_ptr))
+                ));
+                assert_eq!(vtp.to_string(), "%p:i8***");
+            }
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn casts_print_as_written() {
+        let lines = [
+            "trunc %a:i8 %i", "zext %b:i64 %a", "sext %c:i64 %a",
+            "fptosi %d:i32 %x", "fptoui %e:i32 %x", "sitofp %f:f64 %i",
+            "uitofp %g:f32 %i", "fpext %h:f64 %y", "fptrunc %j:f32 %x",
+            "bitcast %k:f64 %c", "inttoptr %p:i8* %c", "ptrtoint %l:i64 %p",
+            "ret void",
+        ];
+        let source = format!(
+            "func @f:void (%i:i32, %x:f64, %y:f32) {{\n    {}\n}}",
+            lines.join("\n    ")
+        );
+        let printed: Vec<String> = stmts(&source).iter()
+            .map(|stmt| stmt.to_string())
+            .collect();
+
+        assert_eq!(printed, lines);
+        assert_eq!(
+            parse_str("func @f:void () {\n    zext %b:i64 3\n}").errors.len(), 1
+        );
+    }
+
+    #[test]
+    fn declarations_have_no_body() {
+        let source = "declare @printf:i32 (%fmt:i8*, ...)\n\
+                      declare @free:void (%p:i8*)\n";
+
+        match parse_str(source).into_result() {
+            Ok (Node::Module (ref items)) => {
+                let printed: Vec<String> = items.iter()
+                    .map(|item| match item {
+                        &Node::FuncDecl (ref sig) => sig.to_string(),
+                        item => panic!("unexpected item {:?}", item),
+                    })
+                    .collect();
+
+                assert_eq!(
+                    printed,
+                    vec!("@printf:i32 (%fmt:i8*, ...)", "@free:void (%p:i8*)")
+                );
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn void_is_only_a_result_type() {
+        let bad = [
+            "declare @f:i32 (%v:void)",
+            "declare @f:i32 (%v:void*)",
+            "func @f:i32 (%a:i32, ...) {\n    ret 0\n}",
+            "func @f:void () {\n    alloca %p:void*\n    ret void\n}",
+            "global @g:void = zeroinit",
+        ];
+
+        for source in &bad {
+            assert_eq!(parse_str(source).errors.len(), 1, "{}", source);
+        }
+
+        assert!(parse_str(
+            "func @f:void () {\n    call %r:void @f()\n    ret void\n}"
+        ).errors.is_empty());
+    }
+
+    #[test]
+    fn globals_print_as_written() {
+        let lines = [
+            "global @n:i32 = -7",
+            "constant @x:f64 = 2.5",
+            "global @z:[4 x i64] = zeroinit",
+            "constant @s:[4 x i8] = \"hi\\n\\x00\"",
+            "global @m:[2 x [2 x i8]] = [[1, 2], [3, 255i8]]",
+        ];
+        let source = lines.join("\n");
+
+        match parse_str(&source).into_result() {
+            Ok (Node::Module (ref items)) => {
+                let printed: Vec<String> = items.iter()
+                    .map(|item| match item {
+                        &Node::GlobalDef (ref global) => global.to_string(),
+                        item => panic!("unexpected item {:?}", item),
+                    })
+                    .collect();
+
+                assert_eq!(printed, lines);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        assert_eq!(parse_str("global @n:i32").errors.len(), 1);
+        assert_eq!(parse_str("constant @n:i32 = %x").errors.len(), 1);
+    }
+
+    #[test]
+    fn errors_do_not_hide_later_errors() {
+        let source = "\
+func @f:i32 (%a:i32) {
+    add %x:i32 %a
+    sub %y:i32 %a 1
+    mul %z:i32 , 2
+    ret %y
+}
+what is this
+global @g:i32 = 3
+func @h:void () {
+entry:
+    br 5
+next:
+    ret void
+}
+";
+        let parsed = parse_str(source);
+        let lines: Vec<u64> = parsed.errors.iter()
+            .map(|e| e.primary_loc().unwrap().line)
+            .collect();
+
+        assert_eq!(lines, vec!(3, 4, 7, 12));
+
+        match parsed.ast {
+            Node::Module (ref items) => {
+                let printed: Vec<String> = items.iter()
+                    .map(|item| match item {
+                        &Node::FuncDef (ref sig, ref blocks) => {
+                            let stmts: Vec<String> = blocks.iter()
+                                .flat_map(|b| b.stmts.iter())
+                                .map(|stmt| stmt.to_string())
+                                .collect();
+
+                            format!("{}: {}", sig.name, stmts.join("; "))
+                        }
+                        &Node::GlobalDef (ref global) => global.to_string(),
+                        item => panic!("unexpected item {:?}", item),
+                    })
+                    .collect();
+
+                assert_eq!(printed, vec!(
+                    "f: sub %y:i32 %a 1; ret %y",
+                    "global @g:i32 = 3",
+                    "h: ret void",
+                ));
+            }
+            ref ast => panic!("unexpected tree {:?}", ast),
+        }
+    }
+
+    fn no_loc() -> TokLoc {
+        return TokLoc {file: FileId (0), line: 0, col: 0, start: 0, end: 0};
+    }
+
+    /// The Debug form of a tree with every `TokLoc` cut out, so that trees
+    /// parsed from different text can be compared.
+    fn without_locs(tree: &str) -> String {
+        let mut out = String::new();
+        let mut rest = tree;
+
+        while let Some (start) = rest.find("TokLoc {") {
+            let end = start + rest[start..].find('}').unwrap();
+
+            out.push_str(&rest[..start]);
+            rest = &rest[end + 1..];
+        }
+
+        out.push_str(rest);
+
+        return out;
+    }
+
+    const ICMP_PREDS: [ICmpPred; 10] = [
+        ICmpPred::Eq, ICmpPred::Ne, ICmpPred::Slt, ICmpPred::Sle,
+        ICmpPred::Sgt, ICmpPred::Sge, ICmpPred::Ult, ICmpPred::Ule,
+        ICmpPred::Ugt, ICmpPred::Uge,
+    ];
+
+    const FCMP_PREDS: [FCmpPred; 14] = [
+        FCmpPred::Oeq, FCmpPred::One, FCmpPred::Olt, FCmpPred::Ole,
+        FCmpPred::Ogt, FCmpPred::Oge, FCmpPred::Ord, FCmpPred::Ueq,
+        FCmpPred::Une, FCmpPred::Ult, FCmpPred::Ule, FCmpPred::Ugt,
+        FCmpPred::Uge, FCmpPred::Uno,
+    ];
+
+    const CAST_OPS: [CastOp; 12] = [
+        CastOp::Trunc, CastOp::ZExt, CastOp::SExt, CastOp::FPToSI,
+        CastOp::FPToUI, CastOp::SIToFP, CastOp::UIToFP, CastOp::FPExt,
+        CastOp::FPTrunc, CastOp::Bitcast, CastOp::PtrToInt, CastOp::IntToPtr,
+    ];
+
+    /// Random modules for the round-trip test. They only need to parse, so
+    /// names and types are picked with no regard for whether they typecheck.
+    /// Numbers come from a xorshift generator on `state`, which must not
+    /// start at zero.
+    struct ModuleGen {
+        state: u64,
+    }
+
+    impl ModuleGen {
+        fn next(&mut self) -> u64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+
+            return self.state;
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            return (self.next() % n as u64) as usize;
+        }
+
+        fn flip(&mut self) -> bool {
+            return self.next() & 1 == 1;
+        }
+
+        fn name(&mut self, prefix: &str) -> String {
+            return format!("{}{}", prefix, self.below(8));
+        }
+
+        fn int_type(&mut self) -> Type {
+            return match self.below(5) {
+                0 => Type::I1,
+                1 => Type::I8,
+                2 => Type::I16,
+                3 => Type::I32,
+                _ => Type::I64,
+            };
+        }
+
+        /// A type other than void, nested at most `depth` deep.
+        fn ty(&mut self, depth: u32) -> Type {
+            return match self.below(10) {
+                0..=4 => self.int_type(),
+                5 => Type::F32,
+                6 => Type::F64,
+                7 if depth > 0 => Type::Ptr (Box::new(self.ty(depth - 1))),
+                8 if depth > 0 => {
+                    let len = self.below(5) as u64;
+
+                    Type::Array (Box::new(self.ty(depth - 1)), len)
+                }
+                _ => Type::UserType (self.name("T")),
+            };
+        }
+
+        fn ret_type(&mut self) -> Type {
+            return if self.below(4) == 0 { Type::Void } else { self.ty(2) };
+        }
+
+        fn int(&mut self) -> i128 {
+            return match self.below(4) {
+                0 => self.below(10) as i128,
+                1 => -(self.below(1000) as i128),
+                2 => self.next() as i64 as i128,
+                _ => self.next() as i128,
+            };
+        }
+
+        /// A width suffix for an integer literal. There is no `i1` suffix.
+        fn suffix(&mut self) -> Option<Type> {
+            return match self.below(5) {
+                0 => Some (Type::I8),
+                1 => Some (Type::I16),
+                2 => Some (Type::I32),
+                3 => Some (Type::I64),
+                _ => None,
+            };
+        }
+
+        fn float(&mut self) -> f64 {
+            return match self.below(5) {
+                0 => f64::INFINITY,
+                1 => f64::NEG_INFINITY,
+                2 => {
+                    let sign = (self.flip() as u64) << 63;
+                    let payload = self.below(1 << 20) as u64;
+
+                    f64::from_bits(f64::NAN.to_bits() | sign | payload)
+                }
+                3 => self.below(100) as f64 / 8.0 - 6.0,
+                _ => f64::from_bits(self.next()),
+            };
+        }
+
+        fn var(&mut self) -> Variable {
+            return Variable {name: self.name("v")};
+        }
+
+        fn vtp(&mut self) -> VarTypePair {
+            return VarTypePair {name: self.name("v"), typename: self.ty(2)};
+        }
+
+        fn label(&mut self) -> Label {
+            return Label {name: self.name("b")};
+        }
+
+        fn int_operand(&mut self) -> Operand {
+            return if self.flip() {
+                Operand::OpVariable (self.var())
+            }
+            else {
+                Operand::OpInteger (self.int(), self.suffix(), no_loc())
+            };
+        }
+
+        fn operand(&mut self) -> Operand {
+            return if self.below(3) == 0 {
+                Operand::OpFloat (self.float(), no_loc())
+            }
+            else {
+                self.int_operand()
+            };
+        }
+
+        fn operands(&mut self, min: usize) -> Vec<Operand> {
+            let mut ops = Vec::new();
+
+            for _ in 0..min + self.below(3) {
+                ops.push(self.operand());
+            }
+
+            return ops;
+        }
+
+        fn indices(&mut self) -> Vec<i64> {
+            let mut indices = Vec::new();
+
+            for _ in 0..1 + self.below(3) {
+                indices.push(self.below(10) as i64);
+            }
+
+            return indices;
+        }
+
+        fn binary(&mut self) -> Stmt {
+            let (dest, l, r) = (self.vtp(), self.operand(), self.operand());
+
+            return match self.below(13) {
+                0 => Stmt::AddInst (dest, l, r),
+                1 => Stmt::SubInst (dest, l, r),
+                2 => Stmt::MulInst (dest, l, r),
+                3 => Stmt::SDivInst (dest, l, r),
+                4 => Stmt::UDivInst (dest, l, r),
+                5 => Stmt::SRemInst (dest, l, r),
+                6 => Stmt::URemInst (dest, l, r),
+                7 => Stmt::AndInst (dest, l, r),
+                8 => Stmt::OrInst (dest, l, r),
+                9 => Stmt::XorInst (dest, l, r),
+                10 => Stmt::ShlInst (dest, l, r),
+                11 => Stmt::LShrInst (dest, l, r),
+                _ => Stmt::AShrInst (dest, l, r),
+            };
+        }
+
+        fn stmt(&mut self) -> Stmt {
+            return match self.below(17) {
+                0 => self.binary(),
+                1 => Stmt::NegInst (self.vtp(), self.operand()),
+                2 => Stmt::NotInst (self.vtp(), self.operand()),
+                3 => {
+                    let pred = ICMP_PREDS[self.below(ICMP_PREDS.len())];
+
+                    Stmt::ICmpInst (
+                        self.vtp(), pred, self.operand(), self.operand()
+                    )
+                }
+                4 => {
+                    let pred = FCMP_PREDS[self.below(FCMP_PREDS.len())];
+
+                    Stmt::FCmpInst (
+                        self.vtp(), pred, self.operand(), self.operand()
+                    )
+                }
+                5 => Stmt::AllocaInst (self.vtp()),
+                6 => Stmt::LoadInst (self.vtp(), self.var()),
+                7 => Stmt::StoreInst (self.operand(), self.var()),
+                8 => Stmt::GepInst (self.vtp(), self.var(), self.operands(1)),
+                9 => {
+                    Stmt::ExtractValueInst (
+                        self.vtp(), self.var(), self.indices()
+                    )
+                }
+                10 => {
+                    Stmt::InsertValueInst (
+                        self.vtp(), self.var(), self.operand(), self.indices()
+                    )
+                }
+                11 => {
+                    let op = CAST_OPS[self.below(CAST_OPS.len())];
+
+                    Stmt::CastInst (self.vtp(), op, self.var())
+                }
+                12 => {
+                    Stmt::AddrOfInst (self.vtp(), Global {name: self.name("g")})
+                }
+                13 => Stmt::LetInst (self.vtp(), self.operand()),
+                14 => {
+                    let dest = VarTypePair {
+                        name: self.name("v"), typename: self.ret_type()
+                    };
+                    let func = Function {name: self.name("f")};
+
+                    Stmt::CallInst (dest, func, self.operands(0))
+                }
+                _ => {
+                    let mut incoming = Vec::new();
+
+                    for _ in 0..1 + self.below(3) {
+                        incoming.push((self.operand(), self.label()));
+                    }
+
+                    Stmt::PhiInst (self.vtp(), incoming)
+                }
+            };
+        }
+
+        fn terminator(&mut self) -> Stmt {
+            return match self.below(5) {
+                0 => Stmt::RetInst (None),
+                1 => Stmt::RetInst (Some (self.operand())),
+                2 => Stmt::BrInst (self.label()),
+                3 => {
+                    Stmt::CondBrInst (
+                        self.int_operand(), self.label(), self.label()
+                    )
+                }
+                _ => {
+                    let mut cases = Vec::new();
+
+                    for _ in 0..self.below(4) {
+                        cases.push((self.int(), self.label()));
+                    }
+
+                    Stmt::SwitchInst (self.int_operand(), self.label(), cases)
+                }
+            };
+        }
+
+        fn block(&mut self) -> BasicBlock {
+            let mut stmts = Vec::new();
+
+            for _ in 0..self.below(6) {
+                stmts.push(self.stmt());
+            }
+
+            stmts.push(self.terminator());
+
+            return BasicBlock {
+                label: self.name("b"),
+                locs: vec!(no_loc(); stmts.len()),
+                stmts: stmts,
+                loc: no_loc(),
+            };
+        }
+
+        fn sig(&mut self, variadic: bool) -> FuncSig {
+            let mut arglist = Vec::new();
+
+            for _ in 0..self.below(4) {
+                arglist.push(self.vtp());
+            }
+
+            return FuncSig {
+                name: self.name("f"),
+                typename: self.ret_type(),
+                arglist: arglist,
+                variadic: variadic,
+                loc: no_loc(),
+            };
+        }
+
+        fn init(&mut self, depth: u32) -> Initializer {
+            return match self.below(if depth > 0 { 5 } else { 3 }) {
+                0 => {
+                    Initializer::InitInteger (
+                        self.int(), self.suffix(), no_loc()
+                    )
+                }
+                1 => Initializer::InitFloat (self.float()),
+                2 => Initializer::InitZero,
+                3 => {
+                    let mut bytes = Vec::new();
+
+                    for _ in 0..self.below(6) {
+                        bytes.push(self.next() as u8);
+                    }
+
+                    Initializer::InitString (bytes)
+                }
+                _ => {
+                    let mut elems = Vec::new();
+
+                    for _ in 0..self.below(4) {
+                        elems.push(self.init(depth - 1));
+                    }
+
+                    Initializer::InitArray (elems)
+                }
+            };
+        }
+
+        fn item(&mut self) -> Node {
+            return match self.below(4) {
+                0 => {
+                    let mut fields = Vec::new();
+
+                    for _ in 0..1 + self.below(3) {
+                        fields.push(self.ty(2));
+                    }
+
+                    Node::StructDef (self.name("T"), fields, no_loc())
+                }
+                1 => {
+                    let variadic = self.flip();
+
+                    Node::FuncDecl (self.sig(variadic))
+                }
+                2 => {
+                    Node::GlobalDef (GlobalDef {
+                        name: self.name("g"),
+                        typename: self.ty(2),
+                        constant: self.flip(),
+                        init: self.init(2),
+                        loc: no_loc(),
+                    })
+                }
+                _ => {
+                    let sig = self.sig(false);
+                    let mut blocks = Vec::new();
+
+                    for _ in 0..1 + self.below(3) {
+                        blocks.push(self.block());
+                    }
+
+                    Node::FuncDef (sig, blocks)
+                }
+            };
+        }
+
+        fn module(&mut self) -> Node {
+            let mut items = Vec::new();
+
+            for _ in 0..self.below(6) {
+                items.push(self.item());
+            }
+
+            return Node::Module (items);
+        }
+    }
+
+    #[test]
+    fn printed_modules_parse_back_the_same() {
+        let mut gen = ModuleGen {state: 0x2f05_6b11_e9c3_001d};
+
+        for _ in 0..500 {
+            let module = gen.module();
+            let text = module.to_string();
+
+            if let Err (err) = check_round_trip(&module) {
+                panic!("{}\ndid not print back the same:\n{}", text, err);
+            }
+
+            let reparsed = parse_str(&text).into_result().unwrap();
+
+            assert_eq!(
+                without_locs(&format!("{:?}", reparsed)),
+                without_locs(&format!("{:?}", module)),
+                "{}", text
+            );
+        }
+    }
 }
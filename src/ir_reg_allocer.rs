@@ -1,11 +1,15 @@
 use ir_parser::*;
 
-use petgraph::*;
+use petgraph::graphmap::UnGraphMap;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 use rand::*;
 
+/// An interference graph: an undirected graph of the variables of a
+/// function, with an edge between any two that are live at the same time.
+pub type GraphMap<N, E> = UnGraphMap<N, E>;
+
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 struct Color {
     red: u8,
@@ -23,58 +27,200 @@ impl Color {
     }
 }
 
+/// The names of the variables among `ops`. Immediates are never live.
+fn operand_names(ops: Vec<&Operand>) -> Vec<&str> {
+    return operand_vars(ops).into_iter().map(|v| &v.name[..]).collect();
+}
+
 // Left Vec<String> is remove list
 // Right Vec<String> is add list
 fn stmt_liveness<'a, 'b>(stmt: &'a Stmt)
     -> (Vec<&'b str>, Vec<&'b str>)
     where 'a: 'b
 {
-    return match stmt {
+    match stmt {
         &Stmt::AddInst (
             VarTypePair {ref name, typename: _},
-            Variable {name: ref l_rval},
-            Variable {name: ref r_rval},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::SubInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::MulInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::SDivInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::UDivInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::SRemInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::URemInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::AndInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::OrInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::XorInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::ShlInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::LShrInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::AShrInst (
+            VarTypePair {ref name, typename: _},
+            ref l_rval,
+            ref r_rval,
         ) => {
             return (
                 vec!(&name),
-                vec!(&l_rval, &r_rval)
+                operand_names(vec!(l_rval, r_rval))
             )
         }
-        &Stmt::SubInst (
+        &Stmt::ICmpInst (
+            VarTypePair {ref name, typename: _},
+            _,
+            ref l_rval,
+            ref r_rval,
+        ) |
+        &Stmt::FCmpInst (
+            VarTypePair {ref name, typename: _},
+            _,
+            ref l_rval,
+            ref r_rval,
+        ) => {
+            return (
+                vec!(&name),
+                operand_names(vec!(l_rval, r_rval))
+            )
+        }
+        &Stmt::NegInst (
+            VarTypePair {ref name, typename: _},
+            ref rval,
+        ) |
+        &Stmt::NotInst (
+            VarTypePair {ref name, typename: _},
+            ref rval,
+        ) => {
+            return (
+                vec!(&name),
+                operand_names(vec!(rval))
+            )
+        }
+        &Stmt::AllocaInst (VarTypePair {ref name, typename: _}) |
+        &Stmt::AddrOfInst (VarTypePair {ref name, typename: _}, _) => {
+            return (
+                vec!(&name),
+                vec!()
+            )
+        }
+        &Stmt::LoadInst (
+            VarTypePair {ref name, typename: _},
+            Variable {name: ref ptr},
+        ) => {
+            return (
+                vec!(&name),
+                vec!(&ptr)
+            )
+        }
+        &Stmt::StoreInst (ref val, Variable {name: ref ptr}) => {
+            let mut add_list = operand_names(vec!(val));
+            add_list.push(ptr);
+
+            return (
+                vec!(),
+                add_list
+            )
+        }
+        &Stmt::GepInst (
             VarTypePair {ref name, typename: _},
-            Variable {name: ref l_rval},
-            Variable {name: ref r_rval},
+            Variable {name: ref base},
+            ref indices,
         ) => {
+            let mut add_list = operand_names(indices.iter().collect());
+            add_list.push(base);
+
             return (
                 vec!(&name),
-                vec!(&l_rval, &r_rval)
+                add_list
+            )
+        }
+        &Stmt::ExtractValueInst (
+            VarTypePair {ref name, typename: _},
+            Variable {name: ref src},
+            _,
+        ) |
+        &Stmt::CastInst (
+            VarTypePair {ref name, typename: _},
+            _,
+            Variable {name: ref src},
+        ) => {
+            return (
+                vec!(&name),
+                vec!(&src)
+            )
+        }
+        &Stmt::InsertValueInst (
+            VarTypePair {ref name, typename: _},
+            Variable {name: ref agg},
+            ref val,
+            _,
+        ) => {
+            let mut add_list = operand_names(vec!(val));
+            add_list.push(agg);
+
+            return (
+                vec!(&name),
+                add_list
             )
         }
         &Stmt::LetInst (
             VarTypePair {ref name, typename: _},
-            ref let_val,
+            ref rval,
         ) => {
-            match let_val {
-                &LetValue::LetVariable (Variable {name: ref rval}) => {
-                    return (
-                        vec!(&name),
-                        vec!(&rval)
-                    )
-                }
-                &LetValue::LetInteger (_) => {
-                    return (
-                        vec!(&name),
-                        vec!()
-                    )
-                }
-            }
+            return (
+                vec!(&name),
+                operand_names(vec!(rval))
+            )
         }
         &Stmt::RetInst (ref ret_opt) => {
             match ret_opt {
-                &Some (Variable {ref name}) => {
+                &Some (ref rval) => {
                     return (
                         vec!(),
-                        vec!(&name)
+                        operand_names(vec!(rval))
                     )
                 }
                 &None => {
@@ -90,47 +236,181 @@ fn stmt_liveness<'a, 'b>(stmt: &'a Stmt)
             Function {name: _},
             ref vars_rval,
         ) => {
-            let mut rvars: Vec<&str> = Vec::new();
-            for var_rval in vars_rval {
-                rvars.push(&var_rval.name);
-            }
             return (
                 vec!(&name),
-                rvars
+                operand_names(vars_rval.iter().collect())
+            )
+        }
+        &Stmt::BrInst (_) => {
+            return (
+                vec!(),
+                vec!()
+            )
+        }
+        &Stmt::CondBrInst (ref rval, _, _) |
+        &Stmt::SwitchInst (ref rval, _, _) => {
+            return (
+                vec!(),
+                operand_names(vec!(rval))
+            )
+        }
+        // Phi operands are live out of the matching predecessor rather than
+        // live into the phi's block; see block_live_out.
+        &Stmt::PhiInst (VarTypePair {ref name, typename: _}, _) => {
+            return (
+                vec!(&name),
+                vec!()
             )
         }
     }
 }
 
-fn get_funcdef_liveness_ranges<'a, 'b>(stmts: &'a Vec<Stmt>)
-    -> Vec<HashSet<&'b str>>
+/// Walk a block's statements backwards from the set of variables live on exit
+/// from the block, yielding the liveset after each statement (in reverse
+/// order) and the set of variables live on entry to the block.
+fn get_block_liveness_ranges<'a, 'b>(
+    block: &'a BasicBlock, live_out: &HashSet<&'b str>
+)
+    -> (Vec<HashSet<&'b str>>, HashSet<&'b str>)
     where 'a: 'b
 {
     let mut livesets = Vec::new();
-    let mut prev_liveset: HashSet<&str> = HashSet::new();
+    let mut prev_liveset: HashSet<&str> = live_out.clone();
+
+    livesets.push(prev_liveset.clone());
 
-    for stmt in stmts.iter().rev() {
-        let (remove_list, add_list) = stmt_liveness(&stmt);
+    for stmt in block.stmts.iter().rev() {
+        let (remove_list, add_list) = stmt_liveness(stmt);
 
         let mut liveset: HashSet<&str> = HashSet::new();
 
         for prev in prev_liveset.drain() {
-            liveset.insert(&prev);
-        }
-        for add in add_list {
-            liveset.insert(&add);
+            liveset.insert(prev);
         }
         for remove in remove_list {
             liveset.remove(remove);
         }
+        for add in add_list {
+            liveset.insert(add);
+        }
 
         livesets.push(liveset.clone());
         prev_liveset = liveset;
     }
 
+    return (livesets, prev_liveset);
+}
+
+/// Compute the set of variables live on entry to each block, iterating to a
+/// fixed point across the control-flow graph.
+fn get_block_live_ins(blocks: &Vec<BasicBlock>)
+    -> HashMap<&str, HashSet<&str>>
+{
+    let mut live_in: HashMap<&str, HashSet<&str>> = HashMap::new();
+
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for block in blocks.iter().rev() {
+            let live_out = block_live_out(block, &live_in, blocks);
+
+            let (_, block_live_in)
+                = get_block_liveness_ranges(block, &live_out);
+
+            if live_in.get::<str>(&block.label) != Some (&block_live_in) {
+                live_in.insert(&block.label, block_live_in);
+                changed = true;
+            }
+        }
+    }
+
+    return live_in;
+}
+
+/// Compute the livesets of every block in the function, iterating the
+/// per-block live-in sets to a fixed point across the control-flow graph.
+fn get_funcdef_liveness_ranges<'a, 'b>(blocks: &'a Vec<BasicBlock>)
+    -> Vec<HashSet<&'b str>>
+    where 'a: 'b
+{
+    let live_in = get_block_live_ins(blocks);
+    let mut livesets = Vec::new();
+
+    for block in blocks {
+        let live_out = block_live_out(block, &live_in, blocks);
+
+        let (block_livesets, _) = get_block_liveness_ranges(block, &live_out);
+
+        livesets.extend(block_livesets);
+    }
+
     return livesets;
 }
 
+/// The variables live just after each statement of each block, indexed
+/// like the blocks and their statements.
+pub fn get_stmt_live_outs(blocks: &Vec<BasicBlock>)
+    -> Vec<Vec<HashSet<&str>>>
+{
+    let live_in = get_block_live_ins(blocks);
+    let mut live_outs = Vec::new();
+
+    for block in blocks {
+        let live_out = block_live_out(block, &live_in, blocks);
+
+        // The ranges run backwards from the end of the block, and end with
+        // the live-in set, which belongs to no statement.
+        let (mut block_livesets, _)
+            = get_block_liveness_ranges(block, &live_out);
+
+        block_livesets.pop();
+        block_livesets.reverse();
+
+        live_outs.push(block_livesets);
+    }
+
+    return live_outs;
+}
+
+/// The variables live on exit from `block`: everything live into one of its
+/// successors, plus the values the successors' phis take along this edge.
+fn block_live_out<'b>(
+    block: &BasicBlock, live_in: &HashMap<&str, HashSet<&'b str>>,
+    blocks: &'b Vec<BasicBlock>
+)
+    -> HashSet<&'b str>
+{
+    let mut live_out = HashSet::new();
+
+    for succ in block.successors() {
+        if let Some (succ_live_in) = live_in.get(succ) {
+            for var in succ_live_in {
+                live_out.insert(*var);
+            }
+        }
+
+        for succ_block in blocks.iter().filter(|b| b.label == succ) {
+            for stmt in &succ_block.stmts {
+                if let &Stmt::PhiInst (_, ref incoming) = stmt {
+                    for &(ref val, ref pred) in incoming {
+                        if pred.name != block.label {
+                            continue;
+                        }
+
+                        if let Some (var) = val.var() {
+                            live_out.insert(&var.name);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    return live_out;
+}
+
 fn add_liveset_to_rig<'a>(
     liveset: HashSet<&'a str>, rig: &mut GraphMap<&'a str, i64>
 ) {
@@ -165,7 +445,7 @@ fn active_edges(rig: &GraphMap<&str, i64>, active_node: &str) -> i64 {
             continue;
         }
 
-        for (_, &i) in rig.edges(node) {
+        for (_, _, &i) in rig.edges(node) {
             if i > 0 {
                 active_count += 1;
             }
@@ -184,7 +464,7 @@ fn disconnect_k_connected_nodes<'a>(rig: &mut GraphMap<&'a str, i64>, k: i64)
 
     let mut iter = nodes.iter();
     for node in nodes.iter() {
-        let active_count = active_edges(&rig, node);
+        let active_count = active_edges(rig, node);
 
         if k != active_count {
             continue;
@@ -235,7 +515,7 @@ fn reconnect_edges(rig: &mut GraphMap<&str, i64>) {
 /// attempt to iteratively disconnect nodes from the graph to generate a
 /// coloring stack, then (regardless of success or failure) reconnect any
 /// disconnected edges, then return either Some(coloring stack) or None
-fn generate_coloring_stack<'a>(mut rig: &mut GraphMap<&'a str, i64>, k: i64)
+fn generate_coloring_stack<'a>(rig: &mut GraphMap<&'a str, i64>, k: i64)
     -> Option<Vec<&'a str>>
 {
     reconnect_edges(rig);
@@ -247,13 +527,12 @@ fn generate_coloring_stack<'a>(mut rig: &mut GraphMap<&'a str, i64>, k: i64)
     let mut ignore = HashSet::new();
 
     while k_minus > 0 {
-        let mut sub_stack = disconnect_k_connected_nodes(&mut rig, k_minus);
+        let mut sub_stack = disconnect_k_connected_nodes(rig, k_minus);
 
         if sub_stack.len() > 0 {
             for node in sub_stack.drain(..) {
-                if ignore.get(node).is_none() {
+                if ignore.insert(node) {
                     stack.push(node);
-                    ignore.insert(node);
                 }
             }
         }
@@ -265,12 +544,11 @@ fn generate_coloring_stack<'a>(mut rig: &mut GraphMap<&'a str, i64>, k: i64)
     let mut failure = false;
 
     for node in rig.nodes() {
-        let active_count = active_edges(&rig, node);
+        let active_count = active_edges(rig, node);
 
         if active_count == 0 {
-            if ignore.get(node).is_none() {
+            if ignore.insert(node) {
                 stack.push(node);
-                ignore.insert(node);
             }
         }
         else {
@@ -289,49 +567,48 @@ fn generate_coloring_stack<'a>(mut rig: &mut GraphMap<&'a str, i64>, k: i64)
 
 }
 
-fn color_mappings<'a>(
-    rig: &mut GraphMap<&'a str, i64>, stack: &Vec<&'a str>, k: i64
+/// Pop the coloring stack, giving each node the lowest color numbered below
+/// `k` that none of its already colored neighbors has. A stack found by
+/// `generate_coloring_stack` always leaves such a color; any node that
+/// somehow finds none is left uncolored.
+fn color_indices<'a>(
+    rig: &GraphMap<&'a str, i64>, stack: &Vec<&'a str>, k: i64
 )
-    -> HashMap<&'a str, Color>
+    -> HashMap<&'a str, usize>
 {
-    let mut colors = HashSet::new();
-
-    for _ in 0..k {
-        colors.insert(Color::new());
-    }
-
-    let mut color_map: HashMap<&'a str, Color> = HashMap::new();
+    let mut color_map: HashMap<&'a str, usize> = HashMap::new();
 
-    for val in stack {
-        for node in rig.nodes() {
-            if *val == node {
-                let mut colors_taken = HashSet::new();
+    for val in stack.iter().rev() {
+        let mut colors_taken = HashSet::new();
 
-                for neighbor in rig.neighbors(node) {
-                    if let Some (color) = color_map.get(neighbor) {
-                        colors_taken.insert(color.clone());
-                    }
-                }
-
-                let mut assigned = false;
+        for neighbor in rig.neighbors(val) {
+            if let Some (&color) = color_map.get(neighbor) {
+                colors_taken.insert(color);
+            }
+        }
 
-                for color in colors.iter() {
-                    if !colors_taken.contains(color) {
-                        color_map.insert(val, color.clone());
-                        assigned = true;
-                    }
-                }
+        let free = (0..k as usize).find(|c| !colors_taken.contains(c));
 
-                if !assigned {
-                    panic!("Could not assign color to node {}", val);
-                }
-            }
+        if let Some (color) = free {
+            color_map.insert(val, color);
         }
     }
 
     return color_map;
 }
 
+fn color_mappings<'a>(
+    rig: &mut GraphMap<&'a str, i64>, stack: &Vec<&'a str>, k: i64
+)
+    -> HashMap<&'a str, Color>
+{
+    let palette: Vec<Color> = (0..k).map(|_| Color::new()).collect();
+
+    return color_indices(rig, stack, k).into_iter()
+        .map(|(val, color)| (val, palette[color].clone()))
+        .collect();
+}
+
 pub fn dump_colored_graph(rig: &mut GraphMap<&str, i64>) -> String {
     if let Some((stack, used_k)) = find_minimum_k(rig, 16) {
         let color_map = color_mappings(rig, &stack, used_k);
@@ -360,7 +637,7 @@ pub fn dump_colored_graph(rig: &mut GraphMap<&str, i64>) -> String {
         let mut already_linked = HashSet::new();
 
         for node in rig.nodes() {
-            for (connected, &edge_val) in rig.edges(node) {
+            for (_, connected, &edge_val) in rig.edges(node) {
                 if edge_val == 0 {
                     continue;
                 }
@@ -386,7 +663,7 @@ pub fn dump_colored_graph(rig: &mut GraphMap<&str, i64>) -> String {
             }
         }
 
-        s.push_str("}");
+        s.push('}');
 
         return s;
     }
@@ -398,12 +675,12 @@ pub fn dump_colored_graph(rig: &mut GraphMap<&str, i64>) -> String {
 /// Iteratively increase K until a sufficient K is found such that the graph can
 /// be K-colored. Yield both the coloring stack and the determined-sufficient K
 /// on success. bound is one past the maximum K to test.
-pub fn find_minimum_k<'a>(mut rig: &mut GraphMap<&'a str, i64>, bound: i64)
+pub fn find_minimum_k<'a>(rig: &mut GraphMap<&'a str, i64>, bound: i64)
     -> Option<(Vec<&'a str>, i64)>
 {
     let mut k = 2;
     loop {
-        if let Some (stack) = generate_coloring_stack(&mut rig, k) {
+        if let Some (stack) = generate_coloring_stack(rig, k) {
             return Some((stack, k));
         }
         else {
@@ -416,6 +693,47 @@ pub fn find_minimum_k<'a>(mut rig: &mut GraphMap<&'a str, i64>, bound: i64)
     }
 }
 
+/// Give each of `vars` one of `k` registers, numbered from 0, such that no
+/// two variables that interfere in `rig` share one. If `k` registers are not
+/// enough, the variables with the most interference are spilled until they
+/// are. Spilled variables, and any not in `rig` at all, are left out.
+///
+/// Only the interference among `vars` is considered, and they are colored in
+/// the order given, so the same order always gives the same registers.
+pub fn assign_registers<'a>(
+    rig: &GraphMap<&'a str, i64>, vars: &[&'a str], k: i64
+)
+    -> HashMap<&'a str, usize>
+{
+    let mut graph: GraphMap<&'a str, i64> = GraphMap::new();
+
+    for &var in vars.iter().filter(|var| rig.contains_node(var)) {
+        graph.add_node(var);
+
+        for &other in vars {
+            if graph.contains_node(other) && rig.contains_edge(var, other) {
+                graph.add_edge(var, other, 1);
+            }
+        }
+    }
+
+    loop {
+        if graph.node_count() == 0 {
+            return HashMap::new();
+        }
+
+        if let Some ((stack, used_k)) = find_minimum_k(&mut graph, k + 1) {
+            return color_indices(&graph, &stack, used_k);
+        }
+
+        let spilled = graph.nodes()
+            .max_by_key(|node| graph.neighbors(node).count())
+            .unwrap();
+
+        graph.remove_node(spilled);
+    }
+}
+
 pub fn dump_dot_format(rig: &GraphMap<&str, i64>) -> String {
     let mut s = String::new();
     s.push_str("graph {\n");
@@ -431,7 +749,7 @@ pub fn dump_dot_format(rig: &GraphMap<&str, i64>) -> String {
     let mut already_linked = HashSet::new();
 
     for node in rig.nodes() {
-        for (connected, &edge_val) in rig.edges(node) {
+        for (_, connected, &edge_val) in rig.edges(node) {
             if edge_val == 0 {
                 continue;
             }
@@ -457,25 +775,108 @@ pub fn dump_dot_format(rig: &GraphMap<&str, i64>) -> String {
         }
     }
 
-    s.push_str("}");
+    s.push('}');
 
     return s;
 }
 
-pub fn generate_rig(ast: &Node) -> GraphMap<&str, i64> {
-    let rig = match ast {
-        &Node::FuncDef (_, ref stmts) => {
-            let mut rig: GraphMap<&str, i64> = GraphMap::new();
+pub fn generate_funcdef_rig(blocks: &Vec<BasicBlock>) -> GraphMap<&str, i64> {
+    let mut rig: GraphMap<&str, i64> = GraphMap::new();
 
-            let mut liveness_ranges = get_funcdef_liveness_ranges(&stmts);
+    let mut liveness_ranges = get_funcdef_liveness_ranges(blocks);
 
-            for liveset in liveness_ranges.drain(..) {
-                add_liveset_to_rig(liveset, &mut rig);
-            }
+    for liveset in liveness_ranges.drain(..) {
+        add_liveset_to_rig(liveset, &mut rig);
+    }
 
-            rig
+    return rig;
+}
+
+/// Generate one register interference graph per function in the AST, paired
+/// with the name of the function it belongs to, in source order.
+pub fn generate_rigs(ast: &Node) -> Vec<(&str, GraphMap<&str, i64>)> {
+    let mut rigs = Vec::new();
+
+    match ast {
+        &Node::Module (ref items) => {
+            for item in items {
+                rigs.extend(generate_rigs(item));
+            }
         }
-    };
+        &Node::StructDef (..) | &Node::FuncDecl (_) | &Node::GlobalDef (_) => {}
+        &Node::FuncDef (ref sig, ref blocks) => {
+            rigs.push((sig.name.as_ref(), generate_funcdef_rig(blocks)));
+        }
+    }
 
-    return rig;
+    return rigs;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ir_lexer::*;
+
+    fn parse_str(source: &str) -> Node {
+        let tokens = tokenize(source, FileId (0)).into_result().unwrap();
+
+        return parse(&tokens).into_result().unwrap();
+    }
+
+    #[test]
+    fn one_graph_per_function() {
+        let ast = parse_str(
+            "declare @ext:i64 ()\n\
+             func @a:i64 (%x:i64) {\n    add %y:i64 %x 1\n    ret %y\n}\n\
+             global @g:i64 = 0\n\
+             func @b:i64 () {\n    ret 0\n}\n"
+        );
+
+        let rigs = generate_rigs(&ast);
+        let names: Vec<&str> = rigs.iter().map(|&(name, _)| name).collect();
+
+        assert_eq!(names, vec!("a", "b"));
+        assert!(rigs[0].1.contains_node("x"));
+        assert_eq!(rigs[1].1.node_count(), 0);
+    }
+
+    fn names<'a>(set: &HashSet<&'a str>) -> Vec<&'a str> {
+        let mut names: Vec<&str> = set.iter().cloned().collect();
+
+        names.sort();
+
+        return names;
+    }
+
+    #[test]
+    fn immediates_are_never_live() {
+        let ast = parse_str(
+            "func @f:i64 (%a:i64, %b:i64) {\n\
+                 call %r:i64 @f(%a, 5)\n\
+                 br 1 x y\n\
+             x:\n\
+                 br y\n\
+             y:\n\
+                 phi %p:i64 [3 entry, %b x]\n\
+                 switch 4 z [4 z]\n\
+             z:\n\
+                 ret %p\n\
+             }"
+        );
+
+        let blocks = match ast {
+            Node::Module (ref items) => match &items[..] {
+                &[Node::FuncDef (_, ref blocks)] => blocks.clone(),
+                items => panic!("unexpected items {:?}", items),
+            },
+            node => panic!("unexpected node {:?}", node),
+        };
+
+        let live_outs = get_stmt_live_outs(&blocks);
+
+        assert_eq!(names(&live_outs[0][0]), vec!("b"));
+        assert_eq!(names(&live_outs[0][1]), vec!("b"));
+        assert_eq!(names(&live_outs[2][0]), vec!("p"));
+        assert!(live_outs[3][0].is_empty());
+    }
 }
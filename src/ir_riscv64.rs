@@ -0,0 +1,1292 @@
+use ir_codegen::*;
+use ir_lexer::*;
+use ir_parser::*;
+use ir_type_layout::*;
+use ir_typechecker::*;
+
+use std::fmt;
+
+/// Integer arguments, in the order the psABI assigns them.
+const INT_ARG_REGS: [&'static str; 8] = [
+    "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7",
+];
+
+/// Float arguments, in the order the psABI assigns them.
+const FLOAT_ARG_REGS: [&'static str; 8] = [
+    "fa0", "fa1", "fa2", "fa3", "fa4", "fa5", "fa6", "fa7",
+];
+
+/// Values live in the callee-saved registers first, so that calls rarely
+/// need to save anything. Argument registers are never used, and `t0` to
+/// `t2`, `t5` and `t6` along with `ft8` to `ft11` are left free as scratch.
+/// `s0` is the frame pointer.
+static REGISTERS: RegisterFile = RegisterFile {
+    int_regs: &[
+        "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11",
+        "t3", "t4",
+    ],
+    float_regs: &[
+        "fs0", "fs1", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9",
+        "fs10", "fs11", "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7",
+    ],
+    callee_saved: &[
+        "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11",
+        "fs0", "fs1", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9",
+        "fs10", "fs11",
+    ],
+};
+
+/// Scratch registers that break cycles in parallel moves. Nothing else is
+/// kept in them while a parallel move runs.
+fn cycle_temp(class: RegClass) -> Loc {
+    return match class {
+        RegClass::Int => Loc::Reg ("t6"),
+        RegClass::Float => Loc::Reg ("ft9"),
+    };
+}
+
+fn reg_class_of(reg: &str) -> RegClass {
+    return if reg.starts_with("f") {
+        RegClass::Float
+    }
+    else {
+        RegClass::Int
+    };
+}
+
+/// The suffix of a float instruction working on `t`.
+fn fmt_suffix(t: &Type) -> &'static str {
+    return match t {
+        &Type::F32 => "s",
+        _ => "d",
+    };
+}
+
+fn fits_i12(val: i64) -> bool {
+    return val >= -2048 && val < 2048;
+}
+
+/// A memory operand: a base register plus a displacement.
+#[derive(Clone, Copy)]
+struct Addr {
+    base: &'static str,
+    disp: i64,
+}
+
+impl Addr {
+    fn offset(&self, by: i64) -> Addr {
+        return Addr {base: self.base, disp: self.disp + by};
+    }
+}
+
+impl fmt::Display for Addr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}({})", self.disp, self.base)
+    }
+}
+
+struct Emitter<'e> {
+    sig: &'e FuncSig,
+    blocks: &'e Vec<BasicBlock>,
+    frame: &'e FrameAlloc<'e>,
+    structs: &'e StructTable,
+    sigs: &'e SigTable<'e>,
+    /// Size of the area at the bottom of the frame where arguments are put
+    /// for calls that pass some on the stack.
+    out_size: u64,
+    out: String,
+    next_label: usize,
+    /// Blocks that carry the phi moves of an edge out of a block with more
+    /// than one successor: their label, their moves and where they go.
+    trampolines: Vec<(String, Vec<Move>, Vec<ConstMove<'e>>, String)>,
+}
+
+impl<'e> Emitter<'e> {
+    fn ins<S: AsRef<str>>(&mut self, text: S) {
+        self.out.push_str("    ");
+        self.out.push_str(text.as_ref());
+        self.out.push('\n');
+    }
+
+    fn label(&mut self, label: &str) {
+        self.out.push_str(label);
+        self.out.push_str(":\n");
+    }
+
+    fn block_label(&self, label: &str) -> String {
+        return format!(".L{}.{}", self.sig.name, label);
+    }
+
+    fn fresh_label(&mut self) -> String {
+        self.next_label += 1;
+
+        return format!(".L{}.{}", self.sig.name, self.next_label);
+    }
+
+    /// Jump to `target` when the integer in `reg` is not zero. A branch only
+    /// reaches 4KiB either way, so it just skips over a jump that can reach
+    /// anywhere in the function.
+    fn jump_if(&mut self, reg: &str, target: &str) {
+        let skip = self.fresh_label();
+
+        self.ins(format!("beqz {}, {}", reg, skip));
+        self.ins(format!("j {}", target));
+        self.label(&skip);
+    }
+
+    /// Where the stack slot at `offset` into the local area is.
+    fn slot(&self, offset: u64) -> Addr {
+        return Addr {base: "sp", disp: (self.out_size + offset) as i64};
+    }
+
+    /// Where callee-saved register `i` is kept while the function runs.
+    fn save_slot(&self, i: usize) -> Addr {
+        let base = self.out_size + self.frame.local_size;
+
+        return Addr {base: "sp", disp: (base + 8 * i as u64) as i64};
+    }
+
+    /// The operand for an access at `addr`. Offsets that do not fit in the
+    /// 12 bits of an instruction are added up in t5 first.
+    fn mem(&mut self, addr: Addr) -> String {
+        if fits_i12(addr.disp) {
+            return format!("{}", addr);
+        }
+
+        self.ins(format!("li t5, {}", addr.disp));
+        self.ins(format!("add t5, {}, t5", addr.base));
+
+        return "0(t5)".to_owned();
+    }
+
+    fn home(&self, name: &str) -> Option<Loc> {
+        return self.frame.homes.get(name).cloned();
+    }
+
+    fn var_type(&self, var: &Variable) -> &'e Type {
+        return self.frame.types[var.name.as_str()];
+    }
+
+    fn mov(&mut self, class: RegClass, dst: Loc, src: Loc) {
+        match (class, dst, src) {
+            _ if dst == src => {}
+            (_, Loc::Stack (d), Loc::Stack (s)) => {
+                let from = self.mem(self.slot(s));
+                self.ins(format!("ld t0, {}", from));
+                let to = self.mem(self.slot(d));
+                self.ins(format!("sd t0, {}", to));
+            }
+            (RegClass::Int, Loc::Reg (d), Loc::Reg (s)) => {
+                self.ins(format!("mv {}, {}", d, s));
+            }
+            (RegClass::Float, Loc::Reg (d), Loc::Reg (s)) => {
+                self.ins(format!("fmv.d {}, {}", d, s));
+            }
+            (_, Loc::Reg (d), Loc::Stack (s)) => {
+                let load = match class {
+                    RegClass::Int => "ld",
+                    RegClass::Float => "fld",
+                };
+                let from = self.mem(self.slot(s));
+                self.ins(format!("{} {}, {}", load, d, from));
+            }
+            (_, Loc::Stack (d), Loc::Reg (s)) => {
+                let store = match class {
+                    RegClass::Int => "sd",
+                    RegClass::Float => "fsd",
+                };
+                let to = self.mem(self.slot(d));
+                self.ins(format!("{} {}, {}", store, s, to));
+            }
+        }
+    }
+
+    /// Store the scalar in `reg` to the home of `name`, if it has one.
+    fn put(&mut self, name: &str, reg: &'static str) {
+        if let Some (home) = self.home(name) {
+            self.mov(reg_class_of(reg), home, Loc::Reg (reg));
+        }
+    }
+
+    fn add_imm(&mut self, reg: &str, val: i64) {
+        if val == 0 {
+            return;
+        }
+
+        if fits_i12(val) {
+            self.ins(format!("addi {}, {}, {}", reg, reg, val));
+        }
+        else {
+            self.ins(format!("li t2, {}", val));
+            self.ins(format!("add {}, {}, t2", reg, reg));
+        }
+    }
+
+    /// Load an integer or pointer operand of type `t` into `reg`.
+    fn load_int(&mut self, op: &Operand, t: &Type, reg: &'static str) {
+        if let Some (var) = op.var() {
+            if let Some (home) = self.home(&var.name) {
+                self.mov(RegClass::Int, Loc::Reg (reg), home);
+            }
+        }
+        else if let Some (bits) = imm_bits(op, t) {
+            self.ins(format!("li {}, {}", reg, bits as i64));
+        }
+    }
+
+    /// Load a float operand of type `t` into the float register `reg`.
+    fn load_float(&mut self, op: &Operand, t: &Type, reg: &'static str) {
+        if let Some (var) = op.var() {
+            if let Some (home) = self.home(&var.name) {
+                self.mov(RegClass::Float, Loc::Reg (reg), home);
+            }
+        }
+        else if let Some (bits) = imm_bits(op, t) {
+            let suffix = match t {
+                &Type::F32 => "w",
+                _ => "d",
+            };
+
+            self.ins(format!("li t5, {}", bits as i64));
+            self.ins(format!("fmv.{}.x {}, t5", suffix, reg));
+        }
+    }
+
+    /// Bring the integer in `reg` back to canonical form for `t` after an
+    /// operation that may have disturbed the bits above its width.
+    fn normalize(&mut self, reg: &str, t: &Type) {
+        let shift = match t {
+            &Type::I1 => {
+                self.ins(format!("andi {}, {}, 1", reg, reg));
+                return;
+            }
+            &Type::I32 => {
+                self.ins(format!("sext.w {}, {}", reg, reg));
+                return;
+            }
+            &Type::I8 => 56,
+            &Type::I16 => 48,
+            _ => return,
+        };
+
+        self.ins(format!("slli {}, {}, {}", reg, reg, shift));
+        self.ins(format!("srai {}, {}, {}", reg, reg, shift));
+    }
+
+    /// Turn the canonical integer in `reg` into its value read as unsigned.
+    fn zero_extend(&mut self, reg: &str, t: &Type) {
+        let shift = match t {
+            &Type::I1 => {
+                self.ins(format!("andi {}, {}, 1", reg, reg));
+                return;
+            }
+            &Type::I8 => {
+                self.ins(format!("andi {}, {}, 255", reg, reg));
+                return;
+            }
+            &Type::I16 => 48,
+            &Type::I32 => 32,
+            _ => return,
+        };
+
+        self.ins(format!("slli {}, {}, {}", reg, reg, shift));
+        self.ins(format!("srli {}, {}, {}", reg, reg, shift));
+    }
+
+    /// Turn the canonical integer in `reg` into its value read as signed.
+    /// Only an `i1` is not already kept that way.
+    fn sign_extend(&mut self, reg: &str, t: &Type) {
+        if let &Type::I1 = t {
+            self.ins(format!("neg {}, {}", reg, reg));
+        }
+    }
+
+    fn load_ptr(&mut self, ptr: Option<&Variable>) {
+        if let Some (ptr) = ptr {
+            let t = self.var_type(ptr);
+            self.load_int(&Operand::OpVariable (ptr.clone()), t, "t1");
+        }
+    }
+
+    fn copy_mem(&mut self, dst: Addr, src: Addr, size: u64) {
+        let mut done = 0;
+
+        for &(width, suffix) in &[(8, "d"), (4, "w"), (2, "h"), (1, "b")] {
+            while size - done >= width {
+                let at = done as i64;
+                let from = self.mem(src.offset(at));
+                self.ins(format!("l{} t0, {}", suffix, from));
+                let to = self.mem(dst.offset(at));
+                self.ins(format!("s{} t0, {}", suffix, to));
+                done += width;
+            }
+        }
+    }
+
+    fn size_of(&self, t: &Type, tl: &TokLoc) -> Result<u64, CodegenError> {
+        return layout_of(t, self.structs)
+            .map(|layout| layout.size)
+            .map_err(|err| CodegenError::BadType (err, tl.clone()));
+    }
+
+    /// The slot holding the aggregate `name`.
+    fn aggregate_addr(&self, name: &str) -> Addr {
+        return match self.home(name) {
+            Some (Loc::Stack (offset)) => self.slot(offset),
+            _ => Addr {base: "sp", disp: 0},
+        };
+    }
+
+    /// Load a value of type `t` from `addr` into the home of `dest`.
+    fn load_mem(&mut self, t: &Type, addr: Addr, dest: &str, tl: &TokLoc)
+        -> Result<(), CodegenError>
+    {
+        let load = match t {
+            &Type::I1 => "lbu t0",
+            &Type::I8 => "lb t0",
+            &Type::I16 => "lh t0",
+            &Type::I32 => "lw t0",
+            &Type::I64 | &Type::Ptr (_) => "ld t0",
+            &Type::F32 => "flw ft10",
+            &Type::F64 => "fld ft10",
+            _ => {
+                let size = self.size_of(t, tl)?;
+                let dst = self.aggregate_addr(dest);
+                self.copy_mem(dst, addr, size);
+                return Ok (());
+            }
+        };
+
+        let from = self.mem(addr);
+        self.ins(format!("{}, {}", load, from));
+
+        match reg_class(t) {
+            Some (RegClass::Float) => self.put(dest, "ft10"),
+            _ => self.put(dest, "t0"),
+        }
+
+        return Ok (());
+    }
+
+    /// Store `value`, of type `t`, to `addr`. When `ptr` is given, it is
+    /// loaded into t1 once the value is in hand, for `addr` to be based on.
+    fn store_mem(
+        &mut self, t: &Type, value: &Operand, ptr: Option<&Variable>,
+        addr: Addr, tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        let store = match t {
+            &Type::I1 | &Type::I8 => "sb t0",
+            &Type::I16 => "sh t0",
+            &Type::I32 => "sw t0",
+            &Type::I64 | &Type::Ptr (_) => "sd t0",
+            &Type::F32 => "fsw ft10",
+            &Type::F64 => "fsd ft10",
+            _ => {
+                let size = self.size_of(t, tl)?;
+                let src = match value.var() {
+                    Some (var) => self.aggregate_addr(&var.name),
+                    None => return Ok (()),
+                };
+
+                self.load_ptr(ptr);
+                self.copy_mem(addr, src, size);
+                return Ok (());
+            }
+        };
+
+        match reg_class(t) {
+            Some (RegClass::Float) => self.load_float(value, t, "ft10"),
+            _ => self.load_int(value, t, "t0"),
+        }
+
+        self.load_ptr(ptr);
+
+        let to = self.mem(addr);
+        self.ins(format!("{}, {}", store, to));
+
+        return Ok (());
+    }
+
+    /// Where control should go to take the edge from `pred` to the block
+    /// labeled `target`: the block itself, or a trampoline that first sets
+    /// the block's phis.
+    fn edge(&mut self, pred: &BasicBlock, target: &str)
+        -> Result<String, CodegenError>
+    {
+        let succ = self.blocks.iter().find(|b| b.label == target);
+
+        return match succ {
+            Some (succ) if has_phis(succ) => {
+                let label = format!(
+                    ".L{}.{}.{}", self.sig.name, pred.label, target
+                );
+
+                if !self.trampolines.iter().any(|t| t.0 == label) {
+                    let (moves, consts) =
+                        phi_moves(self.frame, succ, &pred.label)?;
+                    let to = self.block_label(target);
+                    self.trampolines.push((label.clone(), moves, consts, to));
+                }
+
+                Ok (label)
+            }
+            _ => Ok (self.block_label(target)),
+        };
+    }
+
+    fn parallel_move(&mut self, moves: Vec<Move>, consts: Vec<ConstMove>) {
+        for mv in sequence_moves(moves, cycle_temp) {
+            self.mov(mv.class, mv.dst, mv.src);
+        }
+
+        for mv in consts {
+            let reg = match (mv.dst, cycle_temp(mv.class)) {
+                (Loc::Reg (reg), _) | (_, Loc::Reg (reg)) => reg,
+                _ => continue,
+            };
+
+            match mv.class {
+                RegClass::Int => self.load_int(mv.imm, mv.t, reg),
+                RegClass::Float => self.load_float(mv.imm, mv.t, reg),
+            }
+
+            self.mov(mv.class, mv.dst, Loc::Reg (reg));
+        }
+    }
+
+    fn save_reg(&mut self, reg: &str, addr: Addr) {
+        let store = match reg_class_of(reg) {
+            RegClass::Int => "sd",
+            RegClass::Float => "fsd",
+        };
+        let to = self.mem(addr);
+
+        self.ins(format!("{} {}, {}", store, reg, to));
+    }
+
+    fn restore_reg(&mut self, reg: &str, addr: Addr) {
+        let load = match reg_class_of(reg) {
+            RegClass::Int => "ld",
+            RegClass::Float => "fld",
+        };
+        let from = self.mem(addr);
+
+        self.ins(format!("{} {}, {}", load, reg, from));
+    }
+
+    fn prologue(&mut self) -> Result<(), CodegenError> {
+        let save_size = (8 * self.frame.callee_saved.len() as u64 + 15)
+            / 16 * 16;
+        let frame_size = self.out_size + self.frame.local_size + save_size;
+
+        self.ins("addi sp, sp, -16");
+        self.ins("sd ra, 8(sp)");
+        self.ins("sd s0, 0(sp)");
+        self.ins("addi s0, sp, 16");
+        self.add_imm("sp", -(frame_size as i64));
+
+        for (i, &reg) in self.frame.callee_saved.iter().enumerate() {
+            let addr = self.save_slot(i);
+            self.save_reg(reg, addr);
+        }
+
+        let (mut ints, mut floats, mut stacked) = (0, 0, 0);
+
+        for arg in &self.sig.arglist {
+            let t = &arg.typename;
+            let suffix = match t {
+                &Type::F32 => "w",
+                _ => "d",
+            };
+
+            // A float that finds the float registers taken goes in the next
+            // integer register, and only then on the stack.
+            match reg_class(t) {
+                Some (RegClass::Int) if ints < INT_ARG_REGS.len() => {
+                    self.ins(format!("mv t0, {}", INT_ARG_REGS[ints]));
+                    self.normalize("t0", t);
+                    self.put(&arg.name, "t0");
+                    ints += 1;
+                }
+                Some (RegClass::Float) if floats < FLOAT_ARG_REGS.len() => {
+                    self.put(&arg.name, FLOAT_ARG_REGS[floats]);
+                    floats += 1;
+                }
+                Some (RegClass::Float) if ints < INT_ARG_REGS.len() => {
+                    let reg = INT_ARG_REGS[ints];
+                    self.ins(format!("fmv.{}.x ft10, {}", suffix, reg));
+                    self.put(&arg.name, "ft10");
+                    ints += 1;
+                }
+                Some (class) => {
+                    let at = Addr {base: "s0", disp: 8 * stacked};
+                    let from = self.mem(at);
+
+                    if class == RegClass::Int {
+                        self.ins(format!("ld t0, {}", from));
+                        self.normalize("t0", t);
+                        self.put(&arg.name, "t0");
+                    }
+                    else {
+                        self.ins(format!("fl{} ft10, {}", suffix, from));
+                        self.put(&arg.name, "ft10");
+                    }
+
+                    stacked += 1;
+                }
+                None => {
+                    return Err (CodegenError::Unsupported (
+                        "passing an aggregate by value".to_owned(),
+                        self.sig.loc.clone()
+                    ));
+                }
+            }
+        }
+
+        return Ok (());
+    }
+
+    fn epilogue(&mut self) {
+        for (i, &reg) in self.frame.callee_saved.iter().enumerate() {
+            let addr = self.save_slot(i);
+            self.restore_reg(reg, addr);
+        }
+
+        self.ins("addi sp, s0, -16");
+        self.ins("ld ra, 8(sp)");
+        self.ins("ld s0, 0(sp)");
+        self.ins("addi sp, sp, 16");
+        self.ins("ret");
+    }
+
+    fn call(
+        &mut self, block: usize, index: usize, stmt: &Stmt, dest: &VarTypePair,
+        callee: &Function, args: &Vec<Operand>, tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        let saved = self.frame.live_across_call(block, index, stmt);
+
+        for &reg in &saved {
+            let slot = Loc::Stack (self.frame.save_slots[reg]);
+            self.mov(reg_class_of(reg), slot, Loc::Reg (reg));
+        }
+
+        let (mut ints, mut floats, mut stacked) = (0, 0, 0);
+        let types = arg_types(&self.frame.types, self.sigs, &callee.name, args);
+
+        // The arguments that go with `...` are passed as integers would be,
+        // floats included.
+        let fixed = match self.sigs.get::<str>(&callee.name) {
+            Some (sig) if sig.variadic => sig.arglist.len(),
+            _ => args.len(),
+        };
+
+        for (i, (arg, t)) in args.iter().zip(types).enumerate() {
+            if let Some (var) = arg.var() {
+                if self.home(&var.name).is_none() {
+                    continue;
+                }
+            }
+
+            let suffix = match t {
+                &Type::F32 => "w",
+                _ => "d",
+            };
+
+            match reg_class(t) {
+                Some (RegClass::Int) if ints < INT_ARG_REGS.len() => {
+                    self.load_int(arg, t, INT_ARG_REGS[ints]);
+                    ints += 1;
+                }
+                Some (RegClass::Float)
+                    if i < fixed && floats < FLOAT_ARG_REGS.len() =>
+                {
+                    self.load_float(arg, t, FLOAT_ARG_REGS[floats]);
+                    floats += 1;
+                }
+                Some (RegClass::Float) if ints < INT_ARG_REGS.len() => {
+                    let reg = INT_ARG_REGS[ints];
+                    self.load_float(arg, t, "ft10");
+                    self.ins(format!("fmv.x.{} {}, ft10", suffix, reg));
+                    ints += 1;
+                }
+                Some (class) => {
+                    let to = self.mem(Addr {base: "sp", disp: 8 * stacked});
+
+                    if class == RegClass::Int {
+                        self.load_int(arg, t, "t0");
+                        self.ins(format!("sd t0, {}", to));
+                    }
+                    else {
+                        self.load_float(arg, t, "ft10");
+                        self.ins(format!("fs{} ft10, {}", suffix, to));
+                    }
+
+                    stacked += 1;
+                }
+                None => {
+                    return Err (CodegenError::Unsupported (
+                        "passing an aggregate by value".to_owned(), tl.clone()
+                    ));
+                }
+            }
+        }
+
+        self.ins(format!("call {}", callee.name));
+
+        match reg_class(&dest.typename) {
+            Some (RegClass::Int) => {
+                self.ins("mv t0, a0");
+                self.normalize("t0", &dest.typename);
+                self.put(&dest.name, "t0");
+            }
+            Some (RegClass::Float) => self.put(&dest.name, "fa0"),
+            None if is_void(&dest.typename) => {}
+            None => {
+                return Err (CodegenError::Unsupported (
+                    "returning an aggregate".to_owned(), tl.clone()
+                ));
+            }
+        }
+
+        for &reg in &saved {
+            let slot = Loc::Stack (self.frame.save_slots[reg]);
+            self.mov(reg_class_of(reg), Loc::Reg (reg), slot);
+        }
+
+        return Ok (());
+    }
+
+    fn int_arith(&mut self, stmt: &Stmt, t: &Type) {
+        match stmt {
+            &Stmt::AddInst (..) => self.ins("add t0, t0, t1"),
+            &Stmt::SubInst (..) => self.ins("sub t0, t0, t1"),
+            &Stmt::MulInst (..) => self.ins("mul t0, t0, t1"),
+            &Stmt::SDivInst (..) | &Stmt::SRemInst (..) => {
+                let op = match stmt {
+                    &Stmt::SDivInst (..) => "div",
+                    _ => "rem",
+                };
+
+                self.sign_extend("t0", t);
+                self.sign_extend("t1", t);
+                self.ins(format!("{} t0, t0, t1", op));
+            }
+            &Stmt::UDivInst (..) | &Stmt::URemInst (..) => {
+                let op = match stmt {
+                    &Stmt::UDivInst (..) => "divu",
+                    _ => "remu",
+                };
+
+                self.zero_extend("t0", t);
+                self.zero_extend("t1", t);
+                self.ins(format!("{} t0, t0, t1", op));
+            }
+            &Stmt::AndInst (..) => self.ins("and t0, t0, t1"),
+            &Stmt::OrInst (..) => self.ins("or t0, t0, t1"),
+            &Stmt::XorInst (..) => self.ins("xor t0, t0, t1"),
+            &Stmt::ShlInst (..) => self.ins("sll t0, t0, t1"),
+            &Stmt::LShrInst (..) => {
+                self.zero_extend("t0", t);
+                self.ins("srl t0, t0, t1");
+            }
+            &Stmt::AShrInst (..) => {
+                self.sign_extend("t0", t);
+                self.ins("sra t0, t0, t1");
+            }
+            _ => {}
+        }
+
+        self.normalize("t0", t);
+    }
+
+    fn fcmp(&mut self, pred: FCmpPred, t: &Type) {
+        let s = fmt_suffix(t);
+
+        // The comparisons are all false for NaN, so each unordered
+        // predicate is the negation of the opposite ordered one.
+        let (cmp, negate) = match pred {
+            FCmpPred::Oeq => (format!("feq.{} t0, ft10, ft11", s), false),
+            FCmpPred::Olt => (format!("flt.{} t0, ft10, ft11", s), false),
+            FCmpPred::Ole => (format!("fle.{} t0, ft10, ft11", s), false),
+            FCmpPred::Ogt => (format!("flt.{} t0, ft11, ft10", s), false),
+            FCmpPred::Oge => (format!("fle.{} t0, ft11, ft10", s), false),
+            FCmpPred::Une => (format!("feq.{} t0, ft10, ft11", s), true),
+            FCmpPred::Uge => (format!("flt.{} t0, ft10, ft11", s), true),
+            FCmpPred::Ugt => (format!("fle.{} t0, ft10, ft11", s), true),
+            FCmpPred::Ule => (format!("flt.{} t0, ft11, ft10", s), true),
+            FCmpPred::Ult => (format!("fle.{} t0, ft11, ft10", s), true),
+            FCmpPred::Ord | FCmpPred::Uno => {
+                self.ins(format!("feq.{} t0, ft10, ft10", s));
+                self.ins(format!("feq.{} t1, ft11, ft11", s));
+                ("and t0, t0, t1".to_owned(), pred == FCmpPred::Uno)
+            }
+            FCmpPred::One | FCmpPred::Ueq => {
+                self.ins(format!("flt.{} t0, ft10, ft11", s));
+                self.ins(format!("flt.{} t1, ft11, ft10", s));
+                ("or t0, t0, t1".to_owned(), pred == FCmpPred::Ueq)
+            }
+        };
+
+        self.ins(cmp);
+
+        if negate {
+            self.ins("xori t0, t0, 1");
+        }
+    }
+
+    fn cast(&mut self, op: CastOp, src: &Variable, dest: &VarTypePair) {
+        let from = self.var_type(src);
+        let to = &dest.typename;
+        let src_op = Operand::OpVariable (src.clone());
+
+        match op {
+            CastOp::Trunc | CastOp::PtrToInt => {
+                self.load_int(&src_op, from, "t0");
+                self.normalize("t0", to);
+                self.put(&dest.name, "t0");
+            }
+            CastOp::ZExt | CastOp::IntToPtr => {
+                self.load_int(&src_op, from, "t0");
+                self.zero_extend("t0", from);
+                self.put(&dest.name, "t0");
+            }
+            CastOp::SExt => {
+                self.load_int(&src_op, from, "t0");
+                self.sign_extend("t0", from);
+                self.put(&dest.name, "t0");
+            }
+            CastOp::FPToSI | CastOp::FPToUI => {
+                let int = match op {
+                    CastOp::FPToSI => "l",
+                    _ => "lu",
+                };
+
+                // Conversions round to nearest unless told otherwise.
+                self.load_float(&src_op, from, "ft10");
+                self.ins(format!(
+                    "fcvt.{}.{} t0, ft10, rtz", int, fmt_suffix(from)
+                ));
+                self.normalize("t0", to);
+                self.put(&dest.name, "t0");
+            }
+            CastOp::SIToFP | CastOp::UIToFP => {
+                self.load_int(&src_op, from, "t0");
+
+                let int = match op {
+                    CastOp::SIToFP => {
+                        self.sign_extend("t0", from);
+                        "l"
+                    }
+                    _ => {
+                        self.zero_extend("t0", from);
+                        "lu"
+                    }
+                };
+
+                self.ins(format!("fcvt.{}.{} ft10, t0", fmt_suffix(to), int));
+                self.put(&dest.name, "ft10");
+            }
+            CastOp::FPExt => {
+                self.load_float(&src_op, from, "ft10");
+                self.ins("fcvt.d.s ft10, ft10");
+                self.put(&dest.name, "ft10");
+            }
+            CastOp::FPTrunc => {
+                self.load_float(&src_op, from, "ft10");
+                self.ins("fcvt.s.d ft10, ft10");
+                self.put(&dest.name, "ft10");
+            }
+            CastOp::Bitcast => {
+                match (reg_class(from), reg_class(to)) {
+                    (Some (RegClass::Float), Some (RegClass::Int)) => {
+                        let suffix = match from {
+                            &Type::F32 => "w",
+                            _ => "d",
+                        };
+
+                        self.load_float(&src_op, from, "ft10");
+                        self.ins(format!("fmv.x.{} t0, ft10", suffix));
+                        self.normalize("t0", to);
+                        self.put(&dest.name, "t0");
+                    }
+                    (Some (RegClass::Int), Some (RegClass::Float)) => {
+                        let suffix = match to {
+                            &Type::F32 => "w",
+                            _ => "d",
+                        };
+
+                        self.load_int(&src_op, from, "t0");
+                        self.ins(format!("fmv.{}.x ft10, t0", suffix));
+                        self.put(&dest.name, "ft10");
+                    }
+                    (Some (RegClass::Float), _) => {
+                        self.load_float(&src_op, from, "ft10");
+                        self.put(&dest.name, "ft10");
+                    }
+                    _ => {
+                        self.load_int(&src_op, from, "t0");
+                        self.put(&dest.name, "t0");
+                    }
+                }
+            }
+        }
+    }
+
+    fn gep(
+        &mut self, dest: &VarTypePair, base: &Variable, indices: &Vec<Operand>,
+        tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        let base_type = self.var_type(base);
+        let mut cur = match base_type {
+            &Type::Ptr (ref pointee) => &**pointee,
+            _ => return Ok (()),
+        };
+
+        self.load_int(&Operand::OpVariable (base.clone()), base_type, "t0");
+
+        // The first index steps over whole pointees, and each one after
+        // that selects an element of the current aggregate.
+        for (i, index) in indices.iter().enumerate() {
+            let (size, next) = match cur {
+                _ if i == 0 => (self.size_of(cur, tl)?, cur),
+                &Type::Array (ref elem, _) => {
+                    (self.size_of(elem, tl)?, &**elem)
+                }
+                &Type::UserType (ref name) => {
+                    let field = match index {
+                        &Operand::OpInteger (field, _, _) => field as usize,
+                        _ => return Ok (()),
+                    };
+                    let offsets = field_offsets(name, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+
+                    self.add_imm("t0", offsets[field] as i64);
+                    cur = &self.structs[name][field];
+                    continue;
+                }
+                _ => return Ok (()),
+            };
+
+            match index {
+                &Operand::OpInteger (i, _, _) => {
+                    self.add_imm("t0", (i as i64).wrapping_mul(size as i64));
+                }
+                &Operand::OpVariable (ref var) => {
+                    let t = self.var_type(var);
+                    self.load_int(index, t, "t1");
+                    self.sign_extend("t1", t);
+                    self.ins(format!("li t2, {}", size));
+                    self.ins("mul t1, t1, t2");
+                    self.ins("add t0, t0, t1");
+                }
+                &Operand::OpFloat (..) => {}
+            }
+
+            cur = next;
+        }
+
+        self.put(&dest.name, "t0");
+
+        return Ok (());
+    }
+
+    fn stmt(
+        &mut self, block: &BasicBlock, block_index: usize, index: usize,
+        stmt: &Stmt, tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        match stmt {
+            &Stmt::AddInst (ref dest, ref l, ref r) |
+            &Stmt::SubInst (ref dest, ref l, ref r) |
+            &Stmt::MulInst (ref dest, ref l, ref r) |
+            &Stmt::SDivInst (ref dest, ref l, ref r) |
+            &Stmt::UDivInst (ref dest, ref l, ref r) |
+            &Stmt::SRemInst (ref dest, ref l, ref r) |
+            &Stmt::URemInst (ref dest, ref l, ref r) |
+            &Stmt::AndInst (ref dest, ref l, ref r) |
+            &Stmt::OrInst (ref dest, ref l, ref r) |
+            &Stmt::XorInst (ref dest, ref l, ref r) |
+            &Stmt::ShlInst (ref dest, ref l, ref r) |
+            &Stmt::LShrInst (ref dest, ref l, ref r) |
+            &Stmt::AShrInst (ref dest, ref l, ref r) => {
+                let t = &dest.typename;
+
+                if is_float_type(t) {
+                    let op = match stmt {
+                        &Stmt::AddInst (..) => "fadd",
+                        &Stmt::SubInst (..) => "fsub",
+                        _ => "fmul",
+                    };
+
+                    self.load_float(l, t, "ft10");
+                    self.load_float(r, t, "ft11");
+                    self.ins(format!(
+                        "{}.{} ft10, ft10, ft11", op, fmt_suffix(t)
+                    ));
+                    self.put(&dest.name, "ft10");
+                }
+                else {
+                    self.load_int(l, t, "t0");
+                    self.load_int(r, t, "t1");
+                    self.int_arith(stmt, t);
+                    self.put(&dest.name, "t0");
+                }
+            }
+            &Stmt::NegInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                if is_float_type(t) {
+                    self.load_float(src, t, "ft10");
+                    self.ins(format!("fneg.{} ft10, ft10", fmt_suffix(t)));
+                    self.put(&dest.name, "ft10");
+                }
+                else {
+                    self.load_int(src, t, "t0");
+                    self.ins("neg t0, t0");
+                    self.normalize("t0", t);
+                    self.put(&dest.name, "t0");
+                }
+            }
+            &Stmt::NotInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                self.load_int(src, t, "t0");
+                self.ins("not t0, t0");
+                self.normalize("t0", t);
+                self.put(&dest.name, "t0");
+            }
+            &Stmt::ICmpInst (ref dest, pred, ref l, ref r) => {
+                let t = cmp_type(&self.frame.types, l, r, &Type::I64);
+
+                // Every predicate is a set-less-than, with the operands
+                // swapped for greater-than and the result flipped for the
+                // ones that also hold on equality.
+                let (cmp, negate) = match pred {
+                    ICmpPred::Eq | ICmpPred::Ne => ("sub t0, t0, t1", false),
+                    ICmpPred::Slt => ("slt t0, t0, t1", false),
+                    ICmpPred::Sge => ("slt t0, t0, t1", true),
+                    ICmpPred::Sgt => ("slt t0, t1, t0", false),
+                    ICmpPred::Sle => ("slt t0, t1, t0", true),
+                    ICmpPred::Ult => ("sltu t0, t0, t1", false),
+                    ICmpPred::Uge => ("sltu t0, t0, t1", true),
+                    ICmpPred::Ugt => ("sltu t0, t1, t0", false),
+                    ICmpPred::Ule => ("sltu t0, t1, t0", true),
+                };
+                let signed = match pred {
+                    ICmpPred::Slt | ICmpPred::Sle |
+                    ICmpPred::Sgt | ICmpPred::Sge => true,
+                    _ => false,
+                };
+
+                self.load_int(l, t, "t0");
+                self.load_int(r, t, "t1");
+
+                // Canonical integers already order correctly as unsigned,
+                // but a true i1 is -1 when read as signed.
+                if signed {
+                    self.sign_extend("t0", t);
+                    self.sign_extend("t1", t);
+                }
+
+                self.ins(cmp);
+
+                match pred {
+                    ICmpPred::Eq => self.ins("seqz t0, t0"),
+                    ICmpPred::Ne => self.ins("snez t0, t0"),
+                    _ if negate => self.ins("xori t0, t0, 1"),
+                    _ => {}
+                }
+
+                self.put(&dest.name, "t0");
+            }
+            &Stmt::FCmpInst (ref dest, pred, ref l, ref r) => {
+                let t = cmp_type(&self.frame.types, l, r, &Type::F64);
+
+                self.load_float(l, t, "ft10");
+                self.load_float(r, t, "ft11");
+                self.fcmp(pred, t);
+                self.put(&dest.name, "t0");
+            }
+            &Stmt::AllocaInst (ref dest) => {
+                let offset = self.frame.allocas[dest.name.as_str()];
+                let addr = self.slot(offset);
+
+                self.ins("mv t0, sp");
+                self.add_imm("t0", addr.disp);
+                self.put(&dest.name, "t0");
+            }
+            &Stmt::LoadInst (ref dest, ref ptr) => {
+                self.load_ptr(Some (ptr));
+                self.load_mem(
+                    &dest.typename, Addr {base: "t1", disp: 0}, &dest.name, tl
+                )?;
+            }
+            &Stmt::StoreInst (ref value, ref ptr) => {
+                let pointee = match self.var_type(ptr) {
+                    &Type::Ptr (ref pointee) => &**pointee,
+                    _ => return Ok (()),
+                };
+
+                self.store_mem(
+                    pointee, value, Some (ptr), Addr {base: "t1", disp: 0}, tl
+                )?;
+            }
+            &Stmt::GepInst (ref dest, ref base, ref indices) => {
+                self.gep(dest, base, indices, tl)?;
+            }
+            &Stmt::ExtractValueInst (ref dest, ref agg, ref indices) => {
+                let agg_type = self.var_type(agg);
+                let (offset, elem) =
+                    element_offset(agg_type, indices, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+                let addr = self.aggregate_addr(&agg.name).offset(offset as i64);
+
+                self.load_mem(elem, addr, &dest.name, tl)?;
+            }
+            &Stmt::InsertValueInst (
+                ref dest, ref agg, ref value, ref indices
+            ) => {
+                let agg_type = self.var_type(agg);
+                let (offset, elem) =
+                    element_offset(agg_type, indices, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+                let size = self.size_of(agg_type, tl)?;
+                let dst = self.aggregate_addr(&dest.name);
+                let src = self.aggregate_addr(&agg.name);
+
+                if dst.disp != src.disp {
+                    self.copy_mem(dst, src, size);
+                }
+
+                self.store_mem(
+                    elem, value, None, dst.offset(offset as i64), tl
+                )?;
+            }
+            &Stmt::CastInst (ref dest, op, ref src) => self.cast(op, src, dest),
+            &Stmt::AddrOfInst (ref dest, ref global) => {
+                self.ins(format!("lla t0, {}", global.name));
+                self.put(&dest.name, "t0");
+            }
+            &Stmt::LetInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                match reg_class(t) {
+                    Some (RegClass::Int) => {
+                        self.load_int(src, t, "t0");
+                        self.put(&dest.name, "t0");
+                    }
+                    Some (RegClass::Float) => {
+                        self.load_float(src, t, "ft10");
+                        self.put(&dest.name, "ft10");
+                    }
+                    None => {
+                        let size = self.size_of(t, tl)?;
+
+                        if let Some (var) = src.var() {
+                            let dst = self.aggregate_addr(&dest.name);
+                            let from = self.aggregate_addr(&var.name);
+                            self.copy_mem(dst, from, size);
+                        }
+                    }
+                }
+            }
+            &Stmt::RetInst (ref value) => {
+                if let &Some (ref val) = value {
+                    let t = &self.sig.typename;
+
+                    match reg_class(t) {
+                        Some (RegClass::Int) => self.load_int(val, t, "a0"),
+                        Some (RegClass::Float) => {
+                            self.load_float(val, t, "fa0");
+                        }
+                        None => {
+                            return Err (CodegenError::Unsupported (
+                                "returning an aggregate".to_owned(), tl.clone()
+                            ));
+                        }
+                    }
+                }
+
+                self.epilogue();
+            }
+            &Stmt::CallInst (ref dest, ref callee, ref args) => {
+                self.call(block_index, index, stmt, dest, callee, args, tl)?;
+            }
+            &Stmt::BrInst (ref target) => {
+                let succ = self.blocks.iter().find(|b| b.label == target.name);
+
+                if let Some (succ) = succ {
+                    let (moves, consts) =
+                        phi_moves(self.frame, succ, &block.label)?;
+                    self.parallel_move(moves, consts);
+                }
+
+                let label = self.block_label(&target.name);
+                self.ins(format!("j {}", label));
+            }
+            &Stmt::CondBrInst (ref cond, ref on_true, ref on_false) => {
+                let on_true = self.edge(block, &on_true.name)?;
+                let on_false = self.edge(block, &on_false.name)?;
+                self.load_int(cond, &Type::I1, "t0");
+                self.jump_if("t0", &on_true);
+                self.ins(format!("j {}", on_false));
+            }
+            &Stmt::SwitchInst (ref val, ref default, ref cases) => {
+                let t = switch_type(&self.frame.types, val);
+
+                self.load_int(val, t, "t0");
+
+                for &(case, ref target) in cases {
+                    let target = self.edge(block, &target.name)?;
+
+                    self.ins(format!("li t1, {}", wrap_int(case as i64, t)));
+                    self.ins("sub t1, t0, t1");
+                    self.ins("seqz t1, t1");
+                    self.jump_if("t1", &target);
+                }
+
+                let default = self.edge(block, &default.name)?;
+                self.ins(format!("j {}", default));
+            }
+            // Phis are given their values on the way into their block.
+            &Stmt::PhiInst (..) => {}
+        }
+
+        return Ok (());
+    }
+}
+
+/// Emits assembly for the GNU or LLVM assembler targeting RV64IMFD, following
+/// the LP64D variant of the standard RISC-V calling convention.
+pub struct RiscV64;
+
+impl AsmTarget for RiscV64 {
+    fn registers(&self) -> &'static RegisterFile {
+        return &REGISTERS;
+    }
+
+    fn emit_function(
+        &self, sig: &FuncSig, blocks: &Vec<BasicBlock>, frame: &FrameAlloc,
+        structs: &StructTable, sigs: &SigTable, out: &mut String
+    ) -> Result<(), CodegenError>
+    {
+        let out_size = stack_args_size(
+            blocks, frame, sigs, INT_ARG_REGS.len(), FLOAT_ARG_REGS.len()
+        );
+        let mut emitter = Emitter {
+            sig: sig,
+            blocks: blocks,
+            frame: frame,
+            structs: structs,
+            sigs: sigs,
+            out_size: out_size,
+            out: String::new(),
+            next_label: 0,
+            trampolines: Vec::new(),
+        };
+
+        emitter.label(&sig.name);
+        emitter.prologue()?;
+
+        for (i, block) in blocks.iter().enumerate() {
+            let label = emitter.block_label(&block.label);
+            emitter.label(&label);
+
+            let stmts = block.stmts.iter().zip(&block.locs);
+
+            for (j, (stmt, tl)) in stmts.enumerate() {
+                emitter.stmt(block, i, j, stmt, tl)?;
+            }
+        }
+
+        let trampolines: Vec<_> = emitter.trampolines.drain(..).collect();
+
+        for (label, moves, consts, target) in trampolines {
+            emitter.label(&label);
+            emitter.parallel_move(moves, consts);
+            emitter.ins(format!("j {}", target));
+        }
+
+        out.push_str(&emitter.out);
+
+        return Ok (());
+    }
+}
+
+/// Lower a typechecked module to RV64 assembly. The output is marked as not
+/// needing an executable stack, which ELF linkers otherwise assume.
+pub fn generate_riscv64(ast: &Node) -> Result<String, CodegenError> {
+    let mut asm = emit_module(&RiscV64, ast)?;
+    asm.push_str("\n    .section .note.GNU-stack,\"\",@progbits\n");
+
+    return Ok (asm);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The module `testdata/riscv64/<name>.ir` with the assembly it lowers
+    /// to, from `<name>.s` beside it. Regenerate a golden file with
+    /// `compile -f <name>.ir -t riscv64`.
+    macro_rules! golden {
+        ($($name:ident,)*) => {
+            [$((
+                stringify!($name),
+                include_str!(concat!(
+                    "../testdata/riscv64/", stringify!($name), ".ir"
+                )),
+                include_str!(concat!(
+                    "../testdata/riscv64/", stringify!($name), ".s"
+                )),
+            )),*]
+        }
+    }
+
+    /// One file for each kind of statement, named for its mnemonic, with
+    /// `condbr` for a conditional `br` and `cast` for every conversion.
+    const GOLDEN: [(&'static str, &'static str, &'static str); 32] = golden!(
+        add, sub, mul, sdiv, udiv,
+        srem, urem, and, or, xor,
+        shl, lshr, ashr, neg, not,
+        icmp, fcmp, alloca, load, store,
+        gep, extractvalue, insertvalue, cast, addrof,
+        let, ret, call, br, condbr,
+        switch, phi,
+    );
+
+    fn compile(source: &str) -> String {
+        let tokens = tokenize(source, FileId (0)).into_result().unwrap();
+        let ast = parse(&tokens).into_result().unwrap();
+
+        assert!(typecheck(&ast).is_ok());
+
+        return generate_riscv64(&ast).unwrap();
+    }
+
+    #[test]
+    fn every_statement_matches_its_golden_file() {
+        for &(name, source, golden) in &GOLDEN {
+            let asm = compile(source);
+            let lines = asm.lines().zip(golden.lines());
+
+            for (i, (got, want)) in lines.enumerate() {
+                assert_eq!(got, want, "{}.s differs at line {}", name, i + 1);
+            }
+
+            assert_eq!(
+                asm.lines().count(), golden.lines().count(),
+                "{}.s differs in length", name
+            );
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use ir_parser::*;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Field types of every struct declared in a module, by name.
+pub type StructTable = HashMap<String, Vec<Type>>;
+
+/// Pointers are 64 bits wide on every target we generate code for.
+pub const POINTER_SIZE: u64 = 8;
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum LayoutError {
+    UnknownType (String),
+    RecursiveType (String),
+    UnsizedType (Type),
+    /// The size of the type does not fit in 64 bits.
+    TooLarge (Type),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &LayoutError::UnknownType (ref name) => {
+                write!(f, "use of undeclared type {}", name)
+            }
+            &LayoutError::RecursiveType (ref name) => {
+                write!(f, "type {} contains itself by value", name)
+            }
+            &LayoutError::UnsizedType (ref t) => {
+                write!(f, "type {} has no size", t)
+            }
+            &LayoutError::TooLarge (ref t) => {
+                write!(f, "type {} is too large", t)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub struct TypeLayout {
+    pub size: u64,
+    pub align: u64,
+}
+
+fn align_to(offset: u64, align: u64) -> Option<u64> {
+    return offset.checked_add(align - 1).map(|end| end / align * align);
+}
+
+/// Scalars are aligned to their own size.
+fn scalar(size: u64) -> TypeLayout {
+    return TypeLayout {size: size, align: size};
+}
+
+fn layout_of_inner<'a>(
+    t: &'a Type, structs: &'a StructTable, visiting: &mut Vec<&'a str>
+) -> Result<TypeLayout, LayoutError>
+{
+    return match t {
+        &Type::I1 | &Type::I8 => Ok (scalar(1)),
+        &Type::I16 => Ok (scalar(2)),
+        &Type::I32 | &Type::F32 => Ok (scalar(4)),
+        &Type::I64 | &Type::F64 => Ok (scalar(8)),
+        &Type::Ptr (_) => Ok (scalar(POINTER_SIZE)),
+        &Type::Void => Err (LayoutError::UnsizedType (t.clone())),
+        &Type::Array (ref elem, len) => {
+            let elem_layout = layout_of_inner(elem, structs, visiting)?;
+
+            match elem_layout.size.checked_mul(len) {
+                Some (size) => Ok (TypeLayout {
+                    size: size,
+                    align: elem_layout.align,
+                }),
+                None => Err (LayoutError::TooLarge (t.clone())),
+            }
+        }
+        &Type::UserType (ref name) => {
+            let fields = match structs.get(name) {
+                Some (fields) => fields,
+                None => return Err (LayoutError::UnknownType (name.clone())),
+            };
+
+            if visiting.contains(&name.as_str()) {
+                return Err (LayoutError::RecursiveType (name.clone()));
+            }
+
+            visiting.push(name);
+            let layout =
+                struct_layout_inner(name, fields, structs, visiting);
+            visiting.pop();
+
+            layout.map(|(layout, _)| layout)
+        }
+    };
+}
+
+fn struct_layout_inner<'a>(
+    name: &str, fields: &'a Vec<Type>, structs: &'a StructTable,
+    visiting: &mut Vec<&'a str>
+) -> Result<(TypeLayout, Vec<u64>), LayoutError>
+{
+    let too_large = || LayoutError::TooLarge (Type::UserType (name.to_owned()));
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+    let mut align = 1;
+
+    for field in fields {
+        let field_layout = layout_of_inner(field, structs, visiting)?;
+
+        offset = align_to(offset, field_layout.align).ok_or_else(too_large)?;
+        offsets.push(offset);
+        offset = offset.checked_add(field_layout.size).ok_or_else(too_large)?;
+
+        if field_layout.align > align {
+            align = field_layout.align;
+        }
+    }
+
+    let size = align_to(offset, align).ok_or_else(too_large)?;
+
+    return Ok ((TypeLayout {size: size, align: align}, offsets));
+}
+
+/// Size and alignment of `t`, with structs laid out as C would: each field
+/// at the next offset aligned for it, and the whole padded out to a multiple
+/// of its strictest field alignment.
+pub fn layout_of(t: &Type, structs: &StructTable)
+    -> Result<TypeLayout, LayoutError>
+{
+    return layout_of_inner(t, structs, &mut Vec::new());
+}
+
+/// Byte offset of each field of the struct `name`.
+pub fn field_offsets(name: &str, structs: &StructTable)
+    -> Result<Vec<u64>, LayoutError>
+{
+    let fields = match structs.get(name) {
+        Some (fields) => fields,
+        None => return Err (LayoutError::UnknownType (name.to_owned())),
+    };
+
+    let mut visiting = vec!(name);
+
+    return struct_layout_inner(name, fields, structs, &mut visiting)
+        .map(|(_, offsets)| offsets);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structs(defs: &[(&str, Vec<Type>)]) -> StructTable {
+        return defs.iter()
+            .map(|&(ref name, ref fields)| (name.to_string(), fields.clone()))
+            .collect();
+    }
+
+    fn array(elem: Type, len: u64) -> Type {
+        return Type::Array (Box::new(elem), len);
+    }
+
+    fn user(name: &str) -> Type {
+        return Type::UserType (name.to_owned());
+    }
+
+    fn size_align(t: &Type, structs: &StructTable) -> (u64, u64) {
+        let layout = layout_of(t, structs).unwrap();
+
+        return (layout.size, layout.align);
+    }
+
+    #[test]
+    fn scalars_are_aligned_to_their_size() {
+        let none = StructTable::new();
+
+        assert_eq!(size_align(&Type::I1, &none), (1, 1));
+        assert_eq!(size_align(&Type::I16, &none), (2, 2));
+        assert_eq!(size_align(&Type::F32, &none), (4, 4));
+        assert_eq!(size_align(&Type::I64, &none), (8, 8));
+        assert_eq!(
+            size_align(&Type::Ptr (Box::new(Type::I8)), &none),
+            (POINTER_SIZE, POINTER_SIZE)
+        );
+
+        match layout_of(&Type::Void, &none) {
+            Err (LayoutError::UnsizedType (Type::Void)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn structs_are_laid_out_as_c_would() {
+        let table = structs(&[
+            ("Pair", vec!(Type::I8, Type::I64)),
+            ("Outer", vec!(Type::I16, user("Pair"), array(Type::I8, 3))),
+        ]);
+
+        assert_eq!(size_align(&user("Pair"), &table), (16, 8));
+        assert_eq!(field_offsets("Pair", &table).unwrap(), vec!(0, 8));
+        assert_eq!(size_align(&user("Outer"), &table), (32, 8));
+        assert_eq!(field_offsets("Outer", &table).unwrap(), vec!(0, 8, 24));
+        assert_eq!(
+            size_align(&array(user("Pair"), 3), &table), (48, 8)
+        );
+        assert_eq!(size_align(&array(Type::I16, 0), &table), (0, 2));
+    }
+
+    #[test]
+    fn bad_struct_types() {
+        let table = structs(&[
+            ("Loop", vec!(Type::I8, user("Via"))),
+            ("Via", vec!(array(user("Loop"), 2))),
+            ("Linked", vec!(Type::Ptr (Box::new(user("Linked"))))),
+        ]);
+
+        match layout_of(&user("Loop"), &table) {
+            Err (LayoutError::RecursiveType (ref name)) => {
+                assert_eq!(name, "Loop");
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        match layout_of(&user("Nope"), &table) {
+            Err (LayoutError::UnknownType (ref name)) => {
+                assert_eq!(name, "Nope");
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        assert_eq!(size_align(&user("Linked"), &table), (8, 8));
+    }
+
+    #[test]
+    fn sizes_that_overflow_are_errors() {
+        let table = structs(&[
+            ("Big", vec!(Type::I8, array(Type::I64, (1 << 61) - 1))),
+        ]);
+
+        match layout_of(&array(Type::I32, 1 << 62), &table) {
+            Err (LayoutError::TooLarge (_)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        match layout_of(&user("Big"), &table) {
+            Err (LayoutError::TooLarge (ref t)) => {
+                assert_eq!(t.to_string(), "Big");
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        assert_eq!(
+            size_align(&array(Type::I64, (1 << 61) - 1), &table),
+            (u64::max_value() - 7, 8)
+        );
+    }
+}
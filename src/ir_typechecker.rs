@@ -1,155 +1,2168 @@
+use ir_diagnostic::*;
+use ir_lexer::*;
 use ir_parser::*;
+use ir_type_layout::*;
 
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
 
-fn typecheck_stmts<'a, 'b>(
-    stmts: &'a Vec<Stmt>, ret_type: &'a Type,
-    sym_tab: &'b mut HashMap<&'b str, &'b Type>
-) -> bool
-where 'a: 'b
-{
-    for stmt in stmts {
-        match stmt {
-            &Stmt::AddInst (ref dest_lval, ref left_rval, ref right_rval) => {
-                if let Some (left_type)
-                    = sym_tab.get::<str>(&&left_rval.name)
-                {
-                    if !is_promotable_to(&left_type, &dest_lval.typename) {
-                        return false;
+/// A problem found by the typechecker, tied to where in the source it was
+/// found: the statement at fault, or the item for module-level problems.
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum TypeError {
+    DuplicateArg (VarTypePair, TokLoc),
+    DuplicateLabel (String, TokLoc),
+    UnknownLabel (String, TokLoc),
+    UndefinedVariable (String, TokLoc),
+    UndefinedFunction (String, TokLoc),
+    DuplicateFunction (String, TokLoc),
+    ConflictingDeclaration (String, TokLoc),
+    DuplicateGlobal (String, TokLoc),
+    UndefinedGlobal (String, TokLoc),
+    BadInitializer (String, TokLoc),
+    Redefinition (String, TokLoc),
+    MisplacedPhi (String, String, TokLoc),
+    PhiPredecessorMismatch (String, String, TokLoc),
+    UseNotDominated (String, String, TokLoc),
+    IntegerOutOfRange (i128, Type, TokLoc),
+    SuffixMismatch (Type, Type, TokLoc),
+    DuplicateType (String, TokLoc),
+    BadType (LayoutError, TokLoc),
+    TypeMismatch (Type, Type, TokLoc),
+    ReturnTypeMismatch (Type, Type, TokLoc),
+    /// The callee, how many arguments it takes, whether it takes more after
+    /// those, and how many it was given.
+    ArityMismatch (String, usize, bool, usize, TokLoc),
+    /// A store through a pointer into the named constant global.
+    StoreToConstant (String, TokLoc),
+    IllegalCast (CastOp, Type, Type, TokLoc),
+    /// An "integer" or "float" literal, the type it was used as, where the
+    /// literal is, and the statement it is in.
+    OperandTypeMismatch (&'static str, Type, TokLoc, TokLoc),
+    /// A variable used as a pointer, and its actual type.
+    NotAPointer (String, Type, TokLoc),
+    /// A switch case value, and the type switched on.
+    SwitchCaseOutOfRange (i128, Type, TokLoc),
+    DuplicateSwitchCase (i128, TokLoc),
+    /// The mnemonic of an instruction, the type it was given, and the kind
+    /// of type it takes instead.
+    InvalidOperandType (&'static str, Type, &'static str, TokLoc),
+    /// An aggregate type, and the index that does not select an element
+    /// of it.
+    InvalidIndex (Type, String, TokLoc),
+}
+
+impl TypeError {
+    pub fn loc(&self) -> &TokLoc {
+        match self {
+            &TypeError::DuplicateArg (_, ref tl) => tl,
+            &TypeError::DuplicateLabel (_, ref tl) => tl,
+            &TypeError::UnknownLabel (_, ref tl) => tl,
+            &TypeError::UndefinedVariable (_, ref tl) => tl,
+            &TypeError::UndefinedFunction (_, ref tl) => tl,
+            &TypeError::DuplicateFunction (_, ref tl) => tl,
+            &TypeError::ConflictingDeclaration (_, ref tl) => tl,
+            &TypeError::DuplicateGlobal (_, ref tl) => tl,
+            &TypeError::UndefinedGlobal (_, ref tl) => tl,
+            &TypeError::BadInitializer (_, ref tl) => tl,
+            &TypeError::Redefinition (_, ref tl) => tl,
+            &TypeError::MisplacedPhi (_, _, ref tl) => tl,
+            &TypeError::PhiPredecessorMismatch (_, _, ref tl) => tl,
+            &TypeError::UseNotDominated (_, _, ref tl) => tl,
+            &TypeError::IntegerOutOfRange (_, _, ref tl) => tl,
+            &TypeError::SuffixMismatch (_, _, ref tl) => tl,
+            &TypeError::DuplicateType (_, ref tl) => tl,
+            &TypeError::BadType (_, ref tl) => tl,
+            &TypeError::TypeMismatch (_, _, ref tl) => tl,
+            &TypeError::ReturnTypeMismatch (_, _, ref tl) => tl,
+            &TypeError::ArityMismatch (_, _, _, _, ref tl) => tl,
+            &TypeError::StoreToConstant (_, ref tl) => tl,
+            &TypeError::IllegalCast (_, _, _, ref tl) => tl,
+            &TypeError::OperandTypeMismatch (_, _, ref tl, _) => tl,
+            &TypeError::NotAPointer (_, _, ref tl) => tl,
+            &TypeError::SwitchCaseOutOfRange (_, _, ref tl) => tl,
+            &TypeError::DuplicateSwitchCase (_, ref tl) => tl,
+            &TypeError::InvalidOperandType (_, _, _, ref tl) => tl,
+            &TypeError::InvalidIndex (_, _, ref tl) => tl,
+        }
+    }
+
+    fn message(&self) -> String {
+        return match self {
+            &TypeError::DuplicateArg (ref arg, _) => {
+                format!("duplicate argument {}", arg)
+            }
+            &TypeError::DuplicateLabel (ref label, _) => {
+                format!("duplicate block label {}", label)
+            }
+            &TypeError::UnknownLabel (ref label, _) => {
+                format!("branch to undefined block label {}", label)
+            }
+            &TypeError::UndefinedVariable (ref name, _) => {
+                format!("use of undefined variable %{}", name)
+            }
+            &TypeError::UndefinedFunction (ref name, _) => {
+                format!("call to undeclared function @{}", name)
+            }
+            &TypeError::DuplicateFunction (ref name, _) => {
+                format!("function @{} is defined more than once", name)
+            }
+            &TypeError::ConflictingDeclaration (ref name, _) => {
+                format!("conflicting signatures for function @{}", name)
+            }
+            &TypeError::DuplicateGlobal (ref name, _) => {
+                format!("global @{} is defined more than once", name)
+            }
+            &TypeError::UndefinedGlobal (ref name, _) => {
+                format!("use of undefined global @{}", name)
+            }
+            &TypeError::BadInitializer (ref name, _) => {
+                format!("initializer of @{} does not match its type", name)
+            }
+            &TypeError::Redefinition (ref name, _) => {
+                format!("variable %{} is defined more than once", name)
+            }
+            &TypeError::MisplacedPhi (ref name, ref label, _) => {
+                format!(
+                    "phi %{} in block {} is not at the head of the block",
+                    name, label
+                )
+            }
+            &TypeError::PhiPredecessorMismatch (ref name, ref label, _) => {
+                format!(
+                    "phi %{} in block {} does not name each predecessor once",
+                    name, label
+                )
+            }
+            &TypeError::UseNotDominated (ref name, ref label, _) => {
+                format!(
+                    "use of %{} in block {} is not dominated by its definition",
+                    name, label
+                )
+            }
+            &TypeError::IntegerOutOfRange (i, ref t, _) => {
+                format!(
+                    "integer literal {} is out of range for {}",
+                    format_int(i), t
+                )
+            }
+            &TypeError::SuffixMismatch (ref suffix, ref t, _) => {
+                format!("{} literal assigned to {} destination", suffix, t)
+            }
+            &TypeError::DuplicateType (ref name, _) => {
+                format!("type {} is declared more than once", name)
+            }
+            &TypeError::BadType (ref err, _) => format!("{}", err),
+            &TypeError::TypeMismatch (ref expected, ref found, _) => {
+                format!(
+                    "mismatched types: expected {}, found {}", expected, found
+                )
+            }
+            &TypeError::ReturnTypeMismatch (ref expected, ref found, _) => {
+                format!(
+                    "function returns {}, but {} is returned", expected, found
+                )
+            }
+            &TypeError::ArityMismatch (ref name, expected, more, found, _) => {
+                format!(
+                    "@{} takes {}{} argument(s), but {} were given",
+                    name, if more { "at least " } else { "" }, expected, found
+                )
+            }
+            &TypeError::StoreToConstant (ref name, _) => {
+                format!("store into constant global @{}", name)
+            }
+            &TypeError::IllegalCast (op, ref from, ref to, _) => {
+                format!("cannot {} {} to {}", op, from, to)
+            }
+            &TypeError::OperandTypeMismatch (kind, ref t, _, _) => {
+                format!("{} literal used where {} is expected", kind, t)
+            }
+            &TypeError::NotAPointer (ref name, ref t, _) => {
+                format!("%{} is used as a pointer, but has type {}", name, t)
+            }
+            &TypeError::SwitchCaseOutOfRange (val, ref t, _) => {
+                format!(
+                    "switch case {} is out of range for {}", format_int(val), t
+                )
+            }
+            &TypeError::DuplicateSwitchCase (val, _) => {
+                format!("switch case {} is given twice", format_int(val))
+            }
+            &TypeError::InvalidOperandType (mnemonic, ref t, takes, _) => {
+                format!("{} takes {}, not {}", mnemonic, takes, t)
+            }
+            &TypeError::InvalidIndex (ref t, ref index, _) => {
+                format!("index {} does not select an element of {}", index, t)
+            }
+        };
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (code, label) = match self {
+            &TypeError::DuplicateArg (..) => {
+                ("E0301", "in this signature".to_owned())
+            }
+            &TypeError::DuplicateLabel (..) => {
+                ("E0302", "label used again here".to_owned())
+            }
+            &TypeError::UnknownLabel (ref label, _) => {
+                ("E0303", format!("no block named {}", label))
+            }
+            &TypeError::UndefinedVariable (..) => {
+                ("E0304", "not defined in this function".to_owned())
+            }
+            &TypeError::UndefinedFunction (..) => {
+                ("E0305", "not declared in this module".to_owned())
+            }
+            &TypeError::DuplicateFunction (..) => {
+                ("E0306", "defined again here".to_owned())
+            }
+            &TypeError::ConflictingDeclaration (..) => {
+                ("E0307", "differs from an earlier signature".to_owned())
+            }
+            &TypeError::DuplicateGlobal (..) => {
+                ("E0308", "name already taken".to_owned())
+            }
+            &TypeError::UndefinedGlobal (..) => {
+                ("E0309", "not defined in this module".to_owned())
+            }
+            &TypeError::BadInitializer (..) => {
+                ("E0310", "initializer does not fit".to_owned())
+            }
+            &TypeError::Redefinition (..) => {
+                ("E0311", "defined again here".to_owned())
+            }
+            &TypeError::MisplacedPhi (..) => {
+                ("E0312", "follows a non-phi statement".to_owned())
+            }
+            &TypeError::PhiPredecessorMismatch (..) => {
+                ("E0313", "in this phi".to_owned())
+            }
+            &TypeError::UseNotDominated (..) => {
+                ("E0314", "used here".to_owned())
+            }
+            &TypeError::IntegerOutOfRange (_, ref t, _) => {
+                ("E0315", format!("does not fit in {}", t))
+            }
+            &TypeError::SuffixMismatch (_, ref t, _) => {
+                ("E0316", format!("expected {}", t))
+            }
+            &TypeError::DuplicateType (..) => {
+                ("E0317", "declared again here".to_owned())
+            }
+            &TypeError::BadType (..) => ("E0318", "in this type".to_owned()),
+            &TypeError::TypeMismatch (ref expected, _, _) => {
+                ("E0319", format!("expected {}", expected))
+            }
+            &TypeError::ReturnTypeMismatch (_, ref found, _) => {
+                ("E0320", format!("returns {}", found))
+            }
+            &TypeError::ArityMismatch (_, expected, more, _, _) => {
+                let at_least = if more { "at least " } else { "" };
+
+                ("E0321", format!(
+                    "expected {}{} argument(s)", at_least, expected
+                ))
+            }
+            &TypeError::StoreToConstant (..) => {
+                ("E0322", "pointer is into read-only data".to_owned())
+            }
+            &TypeError::IllegalCast (..) => {
+                ("E0323", "illegal cast".to_owned())
+            }
+            &TypeError::OperandTypeMismatch (kind, _, _, _) => {
+                ("E0324", format!("{} literal", kind))
+            }
+            &TypeError::NotAPointer (..) => {
+                ("E0325", "expected a pointer".to_owned())
+            }
+            &TypeError::SwitchCaseOutOfRange (_, ref t, _) => {
+                ("E0326", format!("switches on {}", t))
+            }
+            &TypeError::DuplicateSwitchCase (..) => {
+                ("E0327", "in this switch".to_owned())
+            }
+            &TypeError::InvalidOperandType (_, ref t, _, _) => {
+                ("E0328", format!("found {}", t))
+            }
+            &TypeError::InvalidIndex (..) => {
+                ("E0329", "in this statement".to_owned())
+            }
+        };
+
+        let diag = Diagnostic::error(code, self.message())
+            .with_primary(self.loc(), &label);
+
+        return match self {
+            &TypeError::IllegalCast (op, _, _, _) => {
+                diag.with_note(cast_rule(op))
+            }
+            &TypeError::OperandTypeMismatch (_, ref t, _, ref stmt_tl) => {
+                diag.with_secondary(stmt_tl, &format!("expects {}", t))
+            }
+            &TypeError::InvalidIndex (..) => {
+                diag.with_note("arrays are indexed below their length, and \
+                                structs by a constant below their number of \
+                                fields")
+            }
+            _ => diag,
+        };
+    }
+}
+
+/// What each kind of cast converts, for telling why a cast is illegal.
+fn cast_rule(op: CastOp) -> &'static str {
+    return match op {
+        CastOp::Trunc => "trunc narrows an integer to a smaller integer type",
+        CastOp::ZExt | CastOp::SExt => {
+            "zext and sext widen an integer to a larger integer type"
+        }
+        CastOp::FPToSI | CastOp::FPToUI => {
+            "fptosi and fptoui convert a float to an integer"
+        }
+        CastOp::SIToFP | CastOp::UIToFP => {
+            "sitofp and uitofp convert an integer to a float"
+        }
+        CastOp::FPExt => "fpext widens an f32 to an f64",
+        CastOp::FPTrunc => "fptrunc narrows an f64 to an f32",
+        CastOp::Bitcast => {
+            "bitcast keeps the width of a value, and turns pointers only \
+             into other pointers"
+        }
+        CastOp::PtrToInt => "ptrtoint converts a pointer to an integer",
+        CastOp::IntToPtr => "inttoptr converts an integer to a pointer",
+    };
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tl = self.loc();
+
+        write!(f, "L:{} C:{}: {}", tl.line, tl.col, self.message())
+    }
+}
+
+pub fn is_void(t: &Type) -> bool {
+    return match t {
+        &Type::Void => true,
+        _ => false,
+    };
+}
+
+pub fn is_float_type(t: &Type) -> bool {
+    return match t {
+        &Type::F32 | &Type::F64 => true,
+        _ => false,
+    };
+}
+
+pub fn is_integer_type(t: &Type) -> bool {
+    return match t {
+        &Type::I1 | &Type::I8 | &Type::I16 | &Type::I32 | &Type::I64 => true,
+        _ => false,
+    };
+}
+
+fn is_ptr_type(t: &Type) -> bool {
+    return match t {
+        &Type::Ptr (_) => true,
+        _ => false,
+    };
+}
+
+/// Width in bits of a scalar type, or `None` for anything else.
+pub fn scalar_bits(t: &Type) -> Option<u64> {
+    return match t {
+        &Type::I1 => Some (1),
+        &Type::I8 => Some (8),
+        &Type::I16 => Some (16),
+        &Type::I32 | &Type::F32 => Some (32),
+        &Type::I64 | &Type::F64 => Some (64),
+        &Type::Ptr (_) => Some (POINTER_SIZE * 8),
+        _ => None,
+    };
+}
+
+/// Whether `op` may convert a `src` value to a `dest` value. Truncations must
+/// narrow and extensions must widen; a bitcast keeps the width, and only
+/// reinterprets pointers as other pointers.
+pub fn is_legal_cast(op: CastOp, src: &Type, dest: &Type) -> bool {
+    let narrows = || match (scalar_bits(src), scalar_bits(dest)) {
+        (Some (s), Some (d)) => s > d,
+        _ => false,
+    };
+    let widens = || match (scalar_bits(src), scalar_bits(dest)) {
+        (Some (s), Some (d)) => s < d,
+        _ => false,
+    };
+
+    return match op {
+        CastOp::Trunc => {
+            is_integer_type(src) && is_integer_type(dest) && narrows()
+        }
+        CastOp::ZExt | CastOp::SExt => {
+            is_integer_type(src) && is_integer_type(dest) && widens()
+        }
+        CastOp::FPToSI | CastOp::FPToUI => {
+            is_float_type(src) && is_integer_type(dest)
+        }
+        CastOp::SIToFP | CastOp::UIToFP => {
+            is_integer_type(src) && is_float_type(dest)
+        }
+        CastOp::FPExt => {
+            is_float_type(src) && is_float_type(dest) && widens()
+        }
+        CastOp::FPTrunc => {
+            is_float_type(src) && is_float_type(dest) && narrows()
+        }
+        CastOp::Bitcast => {
+            if is_ptr_type(src) || is_ptr_type(dest) {
+                is_ptr_type(src) && is_ptr_type(dest)
+            }
+            else {
+                match (scalar_bits(src), scalar_bits(dest)) {
+                    (Some (s), Some (d)) => s == d,
+                    _ => false,
+                }
+            }
+        }
+        CastOp::PtrToInt => is_ptr_type(src) && is_integer_type(dest),
+        CastOp::IntToPtr => is_integer_type(src) && is_ptr_type(dest),
+    };
+}
+
+/// Whether `val` is representable in the integer type `t`, read either as a
+/// signed or as an unsigned quantity, so both `-1` and `255` fit an `i8`.
+/// An `i1` only takes `0` and `1`.
+pub fn int_fits_type(val: i128, t: &Type) -> bool {
+    let bits = match t {
+        &Type::I1 => return val == 0 || val == 1,
+        &Type::I8 => 8,
+        &Type::I16 => 16,
+        &Type::I32 => 32,
+        &Type::I64 => 64,
+        _ => return false,
+    };
+
+    let min = -(1i128 << (bits - 1));
+    let max = (1i128 << bits) - 1;
+
+    return val >= min && val <= max;
+}
+
+/// Put `val` in the canonical form for a value of the integer type `t`:
+/// sign-extended from the width of `t`, except that an `i1` is 0 or 1.
+pub fn wrap_int(val: i64, t: &Type) -> i64 {
+    return match t {
+        &Type::I1 => val & 1,
+        &Type::I8 => val as i8 as i64,
+        &Type::I16 => val as i16 as i64,
+        &Type::I32 => val as i32 as i64,
+        _ => val,
+    };
+}
+
+fn var_type<'a>(
+    var: &Variable, sym_tab: &HashMap<&'a str, &'a Type>, tl: &TokLoc
+) -> Result<&'a Type, TypeError>
+{
+    return match sym_tab.get::<str>(&var.name) {
+        Some (t) => Ok (t),
+        None => {
+            Err (TypeError::UndefinedVariable (var.name.clone(), tl.clone()))
+        }
+    };
+}
+
+fn check_label(target: &Label, labels: &HashSet<&str>, tl: &TokLoc)
+    -> Result<(), TypeError>
+{
+    return if labels.contains::<str>(&target.name) {
+        Ok (())
+    }
+    else {
+        Err (TypeError::UnknownLabel (target.name.clone(), tl.clone()))
+    };
+}
+
+/// Check that `op` can be used where a value of type `t` is expected. An
+/// immediate has no type of its own, so it only has to fit `t`.
+fn check_operand(
+    op: &Operand, t: &Type, sym_tab: &HashMap<&str, &Type>, stmt_tl: &TokLoc
+) -> Result<(), TypeError>
+{
+    match op {
+        &Operand::OpVariable (ref var) => {
+            let var_t = var_type(var, sym_tab, stmt_tl)?;
+
+            if !is_promotable_to(var_t, t) {
+                return Err (TypeError::TypeMismatch (
+                    t.clone(), var_t.clone(), stmt_tl.clone(),
+                ));
+            }
+        }
+        &Operand::OpInteger (i, ref suffix, ref tl) => {
+            if !is_integer_type(t) {
+                return Err (TypeError::OperandTypeMismatch (
+                    "integer", t.clone(), tl.clone(), stmt_tl.clone(),
+                ));
+            }
+
+            if let &Some (ref suffix_type) = suffix {
+                if !is_promotable_to(suffix_type, t) {
+                    return Err (TypeError::SuffixMismatch (
+                        suffix_type.clone(), t.clone(), tl.clone(),
+                    ));
+                }
+            }
+
+            if !int_fits_type(i, t) {
+                return Err (TypeError::IntegerOutOfRange (
+                    i, t.clone(), tl.clone(),
+                ));
+            }
+        }
+        &Operand::OpFloat (_, ref tl) => {
+            if !is_float_type(t) {
+                return Err (TypeError::OperandTypeMismatch (
+                    "float", t.clone(), tl.clone(), stmt_tl.clone(),
+                ));
+            }
+        }
+    }
+
+    return Ok (());
+}
+
+/// The type a comparison is carried out in. A comparison has no destination
+/// of the operand type to go by, so this is taken from whichever operand has
+/// a type of its own, falling back to `default` for two bare immediates.
+fn cmp_operand_type(
+    left: &Operand, right: &Operand, default: Type,
+    sym_tab: &HashMap<&str, &Type>, tl: &TokLoc
+) -> Result<Type, TypeError>
+{
+    for op in &[left, right] {
+        match *op {
+            &Operand::OpVariable (ref var) => {
+                return Ok (var_type(var, sym_tab, tl)?.clone());
+            }
+            &Operand::OpInteger (_, Some (ref suffix_type), _) => {
+                return Ok (suffix_type.clone());
+            }
+            _ => {}
+        }
+    }
+
+    return Ok (default);
+}
+
+/// A gep array index may be an integer of any width.
+fn check_index(op: &Operand, sym_tab: &HashMap<&str, &Type>, tl: &TokLoc)
+    -> Result<(), TypeError>
+{
+    let t = match op {
+        &Operand::OpVariable (ref var) => var_type(var, sym_tab, tl)?,
+        &Operand::OpInteger (..) => return Ok (()),
+        &Operand::OpFloat (..) => &Type::F64,
+    };
+
+    return if is_integer_type(t) {
+        Ok (())
+    }
+    else {
+        Err (TypeError::InvalidOperandType (
+            "gep", t.clone(), "integer indices", tl.clone()
+        ))
+    };
+}
+
+fn struct_field<'a>(name: &str, index: i64, structs: &'a StructTable)
+    -> Option<&'a Type>
+{
+    return match structs.get(name) {
+        Some (fields) if index >= 0 => fields.get(index as usize),
+        _ => None,
+    };
+}
+
+/// The type reached by following the constant `indices` into `t`, or `None`
+/// if an index does not select an element.
+pub fn aggregate_element<'a>(
+    t: &'a Type, indices: &Vec<i64>, structs: &'a StructTable
+) -> Option<&'a Type>
+{
+    let mut cur = t;
+
+    for &index in indices {
+        cur = match cur {
+            &Type::Array (ref elem, len) => {
+                if index < 0 || index as u64 >= len {
+                    return None;
+                }
+
+                &**elem
+            }
+            &Type::UserType (ref name) => struct_field(name, index, structs)?,
+            _ => return None,
+        };
+    }
+
+    return Some (cur);
+}
+
+/// Follow the constant `indices` into `t` like `aggregate_element`, naming
+/// the first index that does not select an element.
+fn select_element<'a>(
+    t: &'a Type, indices: &Vec<i64>, structs: &'a StructTable, tl: &TokLoc
+) -> Result<&'a Type, TypeError>
+{
+    let mut cur = t;
+
+    for &index in indices {
+        cur = match aggregate_element(cur, &vec!(index), structs) {
+            Some (elem) => elem,
+            None => {
+                return Err (TypeError::InvalidIndex (
+                    cur.clone(), index.to_string(), tl.clone()
+                ));
+            }
+        };
+    }
+
+    return Ok (cur);
+}
+
+/// Check that every struct named in `t` is declared. Structs behind a pointer
+/// need not be sized, so only the types held by value are laid out.
+fn check_type(t: &Type, structs: &StructTable, tl: &TokLoc)
+    -> Result<(), TypeError>
+{
+    return match t {
+        &Type::Ptr (ref pointee) => check_type(pointee, structs, tl),
+        &Type::Array (..) | &Type::UserType (_) => {
+            layout_of(t, structs)
+                .map(|_| ())
+                .map_err(|err| TypeError::BadType (err, tl.clone()))
+        }
+        _ => Ok (()),
+    };
+}
+
+/// Typecheck a statement, found at `tl`, against the types of every variable
+/// defined in the function. Whether definitions reach their uses is left to
+/// `verify_ssa`.
+fn typecheck_stmt<'a>(
+    stmt: &'a Stmt, tl: &TokLoc, ret_type: &'a Type, labels: &HashSet<&str>,
+    sym_tab: &HashMap<&'a str, &'a Type>, syms: &ModuleSymbols
+) -> Result<(), TypeError>
+{
+    let structs = &syms.structs;
+    let invalid = |takes: &'static str, t: &Type| {
+        TypeError::InvalidOperandType (
+            stmt.mnemonic(), t.clone(), takes, tl.clone()
+        )
+    };
+    let not_a_pointer = |var: &Variable, t: &Type| {
+        TypeError::NotAPointer (var.name.clone(), t.clone(), tl.clone())
+    };
+    let mismatch = |expected: &Type, found: &Type| {
+        TypeError::TypeMismatch (expected.clone(), found.clone(), tl.clone())
+    };
+
+    match stmt {
+        &Stmt::AddInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::SubInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::MulInst (ref dest_lval, ref left_rval, ref right_rval) => {
+            let t = &dest_lval.typename;
+
+            if !is_integer_type(t) && !is_float_type(t) {
+                return Err (invalid("integer or float operands", t));
+            }
+
+            check_operand(left_rval, t, sym_tab, tl)?;
+            check_operand(right_rval, t, sym_tab, tl)?;
+        }
+        &Stmt::SDivInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::UDivInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::SRemInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::URemInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::AndInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::OrInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::XorInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::ShlInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::LShrInst (ref dest_lval, ref left_rval, ref right_rval) |
+        &Stmt::AShrInst (ref dest_lval, ref left_rval, ref right_rval) => {
+            let t = &dest_lval.typename;
+
+            if !is_integer_type(t) {
+                return Err (invalid("integer operands", t));
+            }
+
+            check_operand(left_rval, t, sym_tab, tl)?;
+            check_operand(right_rval, t, sym_tab, tl)?;
+        }
+        &Stmt::NegInst (ref dest_lval, ref src_rval) => {
+            let t = &dest_lval.typename;
+
+            if !is_integer_type(t) && !is_float_type(t) {
+                return Err (invalid("an integer or float operand", t));
+            }
+
+            check_operand(src_rval, t, sym_tab, tl)?;
+        }
+        &Stmt::NotInst (ref dest_lval, ref src_rval) => {
+            let t = &dest_lval.typename;
+
+            if !is_integer_type(t) {
+                return Err (invalid("an integer operand", t));
+            }
+
+            check_operand(src_rval, t, sym_tab, tl)?;
+        }
+        &Stmt::ICmpInst (
+            ref dest_lval, _, ref left_rval, ref right_rval
+        ) => {
+            if !is_promotable_to(&dest_lval.typename, &Type::I1) {
+                return Err (mismatch(&Type::I1, &dest_lval.typename));
+            }
+
+            let cmp_type = cmp_operand_type(
+                left_rval, right_rval, Type::I64, sym_tab, tl
+            )?;
+
+            if !is_integer_type(&cmp_type) {
+                return Err (invalid("integer operands", &cmp_type));
+            }
+
+            check_operand(left_rval, &cmp_type, sym_tab, tl)?;
+            check_operand(right_rval, &cmp_type, sym_tab, tl)?;
+        }
+        &Stmt::FCmpInst (
+            ref dest_lval, _, ref left_rval, ref right_rval
+        ) => {
+            if !is_promotable_to(&dest_lval.typename, &Type::I1) {
+                return Err (mismatch(&Type::I1, &dest_lval.typename));
+            }
+
+            let cmp_type = cmp_operand_type(
+                left_rval, right_rval, Type::F64, sym_tab, tl
+            )?;
+
+            if !is_float_type(&cmp_type) {
+                return Err (invalid("float operands", &cmp_type));
+            }
+
+            check_operand(left_rval, &cmp_type, sym_tab, tl)?;
+            check_operand(right_rval, &cmp_type, sym_tab, tl)?;
+        }
+        &Stmt::AllocaInst (ref dest_lval) => {
+            match dest_lval.typename {
+                Type::Ptr (ref pointee) if is_void(pointee) => {
+                    return Err (TypeError::BadType (
+                        LayoutError::UnsizedType (Type::Void), tl.clone()
+                    ));
+                }
+                Type::Ptr (_) => {}
+                ref t => {
+                    return Err (TypeError::NotAPointer (
+                        dest_lval.name.clone(), t.clone(), tl.clone()
+                    ));
+                }
+            }
+        }
+        &Stmt::LoadInst (ref dest_lval, ref ptr) => {
+            match var_type(ptr, sym_tab, tl)? {
+                &Type::Ptr (ref pointee) => {
+                    if !is_promotable_to(pointee, &dest_lval.typename) {
+                        return Err (mismatch(&dest_lval.typename, pointee));
+                    }
+                }
+                t => return Err (not_a_pointer(ptr, t)),
+            }
+        }
+        &Stmt::StoreInst (ref value, ref ptr) => {
+            match var_type(ptr, sym_tab, tl)? {
+                &Type::Ptr (ref pointee) => {
+                    check_operand(value, pointee, sym_tab, tl)?;
+                }
+                t => return Err (not_a_pointer(ptr, t)),
+            }
+        }
+        &Stmt::GepInst (ref dest_lval, ref base, ref indices) => {
+            let mut cur = match var_type(base, sym_tab, tl)? {
+                &Type::Ptr (ref pointee) => &**pointee,
+                t => return Err (not_a_pointer(base, t)),
+            };
+
+            // The first index steps over whole pointees, and each one
+            // after that selects an element of the current aggregate.
+            check_index(&indices[0], sym_tab, tl)?;
+
+            for index in &indices[1..] {
+                cur = match (cur, index) {
+                    (&Type::Array (ref elem, _), _) => {
+                        check_index(index, sym_tab, tl)?;
+                        &**elem
+                    }
+                    (
+                        &Type::UserType (ref name),
+                        &Operand::OpInteger (i, _, _)
+                    ) if i >= 0 && i <= i64::max_value() as i128 => {
+                        match struct_field(name, i as i64, structs) {
+                            Some (field) => field,
+                            None => {
+                                return Err (TypeError::InvalidIndex (
+                                    cur.clone(), format_int(i), tl.clone()
+                                ));
+                            }
+                        }
+                    }
+                    _ => {
+                        return Err (TypeError::InvalidIndex (
+                            cur.clone(), index.to_string(), tl.clone()
+                        ));
+                    }
+                };
+            }
+
+            let result = Type::Ptr (Box::new(cur.clone()));
+
+            if !is_promotable_to(&result, &dest_lval.typename) {
+                return Err (mismatch(&dest_lval.typename, &result));
+            }
+        }
+        &Stmt::ExtractValueInst (ref dest_lval, ref agg, ref indices) => {
+            let agg_type = var_type(agg, sym_tab, tl)?;
+            let elem = select_element(agg_type, indices, structs, tl)?;
+
+            if !is_promotable_to(elem, &dest_lval.typename) {
+                return Err (mismatch(&dest_lval.typename, elem));
+            }
+        }
+        &Stmt::InsertValueInst (
+            ref dest_lval, ref agg, ref value, ref indices
+        ) => {
+            let agg_type = var_type(agg, sym_tab, tl)?;
+
+            if !is_promotable_to(agg_type, &dest_lval.typename) {
+                return Err (mismatch(&dest_lval.typename, agg_type));
+            }
+
+            let elem = select_element(agg_type, indices, structs, tl)?;
+
+            check_operand(value, elem, sym_tab, tl)?;
+        }
+        &Stmt::AddrOfInst (ref dest_lval, ref global) => {
+            let def = match syms.globals.get::<str>(&global.name) {
+                Some (def) => def,
+                None => {
+                    return Err (TypeError::UndefinedGlobal (
+                        global.name.clone(), tl.clone()
+                    ));
+                }
+            };
+
+            let ptr_type = Type::Ptr (Box::new(def.typename.clone()));
+
+            if !is_promotable_to(&ptr_type, &dest_lval.typename) {
+                return Err (mismatch(&dest_lval.typename, &ptr_type));
+            }
+        }
+        &Stmt::CastInst (ref dest_lval, op, ref src) => {
+            let src_type = var_type(src, sym_tab, tl)?;
+
+            if !is_legal_cast(op, src_type, &dest_lval.typename) {
+                return Err (TypeError::IllegalCast (
+                    op, src_type.clone(), dest_lval.typename.clone(),
+                    tl.clone()
+                ));
+            }
+        }
+        &Stmt::LetInst (ref dest_lval, ref src_rval) => {
+            check_operand(src_rval, &dest_lval.typename, sym_tab, tl)?;
+        }
+        &Stmt::RetInst (ref rval) => {
+            let val_type = match rval {
+                &Some (Operand::OpVariable (ref var)) => {
+                    var_type(var, sym_tab, tl)?
+                }
+                &Some (ref imm) => {
+                    check_operand(imm, ret_type, sym_tab, tl)?;
+                    ret_type
+                }
+                &None => &Type::Void,
+            };
+
+            if !is_promotable_to(val_type, ret_type) {
+                return Err (TypeError::ReturnTypeMismatch (
+                    ret_type.clone(), val_type.clone(), tl.clone()
+                ));
+            }
+        }
+        &Stmt::CallInst (ref dest_lval, ref func_rval, ref vars_rval) => {
+            let callee = match syms.funcs.get::<str>(&func_rval.name) {
+                Some (callee) => callee,
+                None => {
+                    return Err (TypeError::UndefinedFunction (
+                        func_rval.name.clone(), tl.clone()
+                    ));
+                }
+            };
+
+            let fixed = callee.arglist.len();
+
+            if vars_rval.len() < fixed
+                || vars_rval.len() > fixed && !callee.variadic
+            {
+                return Err (TypeError::ArityMismatch (
+                    func_rval.name.clone(), fixed, callee.variadic,
+                    vars_rval.len(), tl.clone()
+                ));
+            }
+
+            for (param, arg) in callee.arglist.iter().zip(vars_rval) {
+                check_operand(arg, &param.typename, sym_tab, tl)?;
+            }
+
+            // Arguments beyond the parameters of a variadic function have
+            // no parameter to take a type from, so they go as their own
+            // type, and bare immediates as an i64 or an f64.
+            for arg in &vars_rval[fixed..] {
+                let arg_type = match arg {
+                    &Operand::OpVariable (ref var) => {
+                        var_type(var, sym_tab, tl)?
+                    }
+                    &Operand::OpInteger (_, Some (ref suffix_type), _) => {
+                        suffix_type
+                    }
+                    &Operand::OpInteger (..) => &Type::I64,
+                    &Operand::OpFloat (..) => &Type::F64,
+                };
+
+                check_operand(arg, arg_type, sym_tab, tl)?;
+            }
+
+            if !is_promotable_to(&callee.typename, &dest_lval.typename) {
+                return Err (mismatch(&dest_lval.typename, &callee.typename));
+            }
+        }
+        &Stmt::BrInst (ref target) => {
+            check_label(target, labels, tl)?;
+        }
+        &Stmt::CondBrInst (ref cond, ref on_true, ref on_false) => {
+            check_operand(cond, &Type::I1, sym_tab, tl)?;
+
+            check_label(on_true, labels, tl)?;
+            check_label(on_false, labels, tl)?;
+        }
+        &Stmt::SwitchInst (ref val, ref default, ref cases) => {
+            // A bare immediate has no type to go by, so it is switched on as
+            // an i64.
+            let val_type = match val {
+                &Operand::OpVariable (ref var) => var_type(var, sym_tab, tl)?,
+                &Operand::OpInteger (_, Some (ref suffix_type), _) => {
+                    suffix_type
+                }
+                _ => &Type::I64,
+            };
+
+            if !is_integer_type(val_type) {
+                return Err (invalid("an integer value", val_type));
+            }
+
+            check_operand(val, val_type, sym_tab, tl)?;
+
+            let mut seen = HashSet::new();
+
+            for &(case_val, _) in cases {
+                if !int_fits_type(case_val, val_type) {
+                    return Err (TypeError::SwitchCaseOutOfRange (
+                        case_val, val_type.clone(), tl.clone()
+                    ));
+                }
+
+                if !seen.insert(case_val) {
+                    return Err (TypeError::DuplicateSwitchCase (
+                        case_val, tl.clone()
+                    ));
+                }
+            }
+
+            check_label(default, labels, tl)?;
+
+            for &(_, ref target) in cases {
+                check_label(target, labels, tl)?;
+            }
+        }
+        &Stmt::PhiInst (ref dest_lval, ref incoming) => {
+            for &(ref val, ref pred) in incoming {
+                check_operand(val, &dest_lval.typename, sym_tab, tl)?;
+                check_label(pred, labels, tl)?;
+            }
+        }
+    }
+
+    return Ok (());
+}
+
+fn predecessors(blocks: &Vec<BasicBlock>) -> HashMap<&str, HashSet<&str>> {
+    let mut preds: HashMap<&str, HashSet<&str>> = HashMap::new();
+
+    for block in blocks {
+        preds.insert(&block.label, HashSet::new());
+    }
+
+    for block in blocks {
+        for succ in block.successors() {
+            if let Some (succ_preds) = preds.get_mut(succ) {
+                succ_preds.insert(&block.label);
+            }
+        }
+    }
+
+    return preds;
+}
+
+/// Compute the dominator set of every block reachable from the entry block,
+/// iterating to a fixed point. Unreachable blocks are left out.
+fn dominators<'a>(
+    blocks: &'a Vec<BasicBlock>, preds: &HashMap<&'a str, HashSet<&'a str>>
+)
+    -> HashMap<&'a str, HashSet<&'a str>>
+{
+    let mut reachable: HashSet<&str> = HashSet::new();
+    let mut worklist: Vec<&BasicBlock> = blocks.iter().take(1).collect();
+
+    while let Some (block) = worklist.pop() {
+        if !reachable.insert(&block.label) {
+            continue;
+        }
+
+        for succ in block.successors() {
+            for b in blocks {
+                if b.label == succ {
+                    worklist.push(b);
+                }
+            }
+        }
+    }
+
+    let mut doms: HashMap<&str, HashSet<&str>> = HashMap::new();
+
+    for (i, block) in blocks.iter().enumerate() {
+        if !reachable.contains::<str>(&block.label) {
+            continue;
+        }
+
+        if i == 0 {
+            let mut entry_dom = HashSet::new();
+            entry_dom.insert(block.label.as_ref());
+            doms.insert(&block.label, entry_dom);
+        }
+        else {
+            doms.insert(&block.label, reachable.clone());
+        }
+    }
+
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for block in blocks.iter().skip(1) {
+            if !reachable.contains::<str>(&block.label) {
+                continue;
+            }
+
+            let mut new_dom: Option<HashSet<&str>> = None;
+
+            for pred in &preds[block.label.as_str()] {
+                if let Some (pred_dom) = doms.get(pred) {
+                    new_dom = Some (match new_dom {
+                        Some (d) => d.intersection(pred_dom).cloned().collect(),
+                        None => pred_dom.clone(),
+                    });
+                }
+            }
+
+            let mut new_dom = new_dom.unwrap_or_default();
+            new_dom.insert(&block.label);
+
+            if doms.get::<str>(&block.label) != Some (&new_dom) {
+                doms.insert(&block.label, new_dom);
+                changed = true;
+            }
+        }
+    }
+
+    return doms;
+}
+
+/// Report stores through pointers that are known to point into a constant
+/// global: the result of its `addrof`, and what is derived from that by
+/// `gep`, `bitcast`, `let`, or a phi all of whose incoming values are such
+/// pointers. A pointer that makes its way through memory, an integer or a
+/// call is not followed, and neither is a statement in `failed`.
+fn check_constant_stores(
+    blocks: &Vec<BasicBlock>, syms: &ModuleSymbols,
+    failed: &HashSet<(usize, usize)>, errors: &mut Vec<TypeError>
+)
+{
+    // The constant global each such pointer points into.
+    let mut into = HashMap::<&str, &str>::new();
+    let mut changed = true;
+
+    // Phis may take values defined further down, so go until nothing new
+    // is found.
+    while changed {
+        changed = false;
+
+        for (b, block) in blocks.iter().enumerate() {
+            for (i, stmt) in block.stmts.iter().enumerate() {
+                if failed.contains(&(b, i)) {
+                    continue;
+                }
+
+                let (dest, global) = match stmt {
+                    &Stmt::AddrOfInst (ref dest, ref global) => {
+                        match syms.globals.get::<str>(&global.name) {
+                            Some (def) if def.constant => {
+                                (dest, def.name.as_str())
+                            }
+                            _ => continue,
+                        }
+                    }
+                    &Stmt::GepInst (ref dest, ref base, _) |
+                    &Stmt::CastInst (ref dest, CastOp::Bitcast, ref base) => {
+                        match into.get::<str>(&base.name) {
+                            Some (&global) => (dest, global),
+                            None => continue,
+                        }
                     }
+                    &Stmt::LetInst (ref dest, ref src) => {
+                        match src.var().and_then(|var| into.get(&*var.name)) {
+                            Some (&global) => (dest, global),
+                            None => continue,
+                        }
+                    }
+                    &Stmt::PhiInst (ref dest, ref incoming) => {
+                        let mut globals = incoming.iter().map(|&(ref val, _)| {
+                            val.var().and_then(|var| {
+                                into.get::<str>(&var.name).cloned()
+                            })
+                        });
+
+                        match globals.next() {
+                            Some (Some (global))
+                                if globals.all(|g| g == Some (global)) =>
+                            {
+                                (dest, global)
+                            }
+                            _ => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+
+                if into.insert(&dest.name, global).is_none() {
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    for (b, block) in blocks.iter().enumerate() {
+        let stmts = block.stmts.iter().zip(&block.locs);
+
+        for (i, (stmt, tl)) in stmts.enumerate() {
+            if failed.contains(&(b, i)) {
+                continue;
+            }
+
+            if let &Stmt::StoreInst (_, ref ptr) = stmt {
+                if let Some (&global) = into.get::<str>(&ptr.name) {
+                    errors.push(TypeError::StoreToConstant (
+                        global.to_owned(), tl.clone()
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Check that the function is in valid SSA form: phis sit at the head of
+/// their block and name each predecessor exactly once, and every use of a
+/// variable is dominated by its single definition. Function arguments are
+/// defined on entry. Uses in unreachable blocks are not checked, and neither
+/// are the statements in `failed`, which did not typecheck; a variable
+/// defined more than once is taken to be defined where it first is.
+fn verify_ssa(
+    sig: &FuncSig, blocks: &Vec<BasicBlock>,
+    failed: &HashSet<(usize, usize)>, errors: &mut Vec<TypeError>
+)
+{
+    let preds = predecessors(blocks);
+    let doms = dominators(blocks, &preds);
+
+    // Block and statement index of each variable's definition. Arguments
+    // have no entry, as they dominate everything.
+    let mut def_sites: HashMap<&str, (&str, usize)> = HashMap::new();
+
+    for block in blocks {
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            if let Some (vtp) = stmt.def() {
+                def_sites.entry(&vtp.name).or_insert((&block.label, i));
+            }
+        }
+    }
+
+    let is_arg = |name: &str| sig.arglist.iter().any(|arg| arg.name == name);
+
+    // Whether the definition of `name` dominates the point just before
+    // statement `index` of block `label`.
+    let dominates = |name: &str, label: &str, index: usize| -> bool {
+        if is_arg(name) {
+            return true;
+        }
+
+        return match (def_sites.get(name), doms.get(label)) {
+            (Some (&(def_label, def_index)), Some (block_doms)) => {
+                if def_label == label {
+                    def_index < index
                 }
                 else {
-                    return false;
-                }
-                if let Some (right_type)
-                    = sym_tab.get::<str>(&&right_rval.name)
-                {
-                    if !is_promotable_to(&right_type, &dest_lval.typename) {
-                        return false;
-                    }
+                    block_doms.contains(def_label)
                 }
+            }
+            (_, None) => true,
+            (None, _) => false,
+        };
+    };
+
+    for (b, block) in blocks.iter().enumerate() {
+        let mut in_phi_head = true;
+        let stmts = block.stmts.iter().zip(&block.locs);
+
+        for (i, (stmt, tl)) in stmts.enumerate() {
+            if failed.contains(&(b, i)) {
+                if let &Stmt::PhiInst (..) = stmt {}
                 else {
-                    return false;
+                    in_phi_head = false;
                 }
 
-                if sym_tab.contains_key::<str>(&&dest_lval.name) {
-                    return false;
+                continue;
+            }
+
+            if let &Stmt::PhiInst (ref dest_lval, ref incoming) = stmt {
+                if !in_phi_head {
+                    errors.push(TypeError::MisplacedPhi (
+                        dest_lval.name.clone(), block.label.clone(), tl.clone()
+                    ));
                 }
 
-                sym_tab.insert(&dest_lval.name, &dest_lval.typename);
-            }
-            &Stmt::SubInst (ref dest_lval, ref left_rval, ref right_rval) => {
-                if let Some (left_type)
-                    = sym_tab.get::<str>(&&left_rval.name)
-                {
-                    if !is_promotable_to(&left_type, &dest_lval.typename) {
-                        return false;
+                let mut named = HashSet::new();
+                let mut each_once = true;
+
+                for &(ref val, ref pred) in incoming {
+                    each_once &= named.insert(pred.name.as_str());
+
+                    let var = match val.var() {
+                        Some (var) => var,
+                        None => continue,
+                    };
+
+                    // The incoming value is read at the end of the
+                    // predecessor, so its definition must reach there.
+                    if !dominates(&var.name, &pred.name, usize::max_value()) {
+                        errors.push(TypeError::UseNotDominated (
+                            var.name.clone(), pred.name.clone(), tl.clone()
+                        ));
                     }
                 }
-                else {
-                    return false;
+
+                if !each_once || named != preds[block.label.as_str()] {
+                    errors.push(TypeError::PhiPredecessorMismatch (
+                        dest_lval.name.clone(), block.label.clone(), tl.clone()
+                    ));
                 }
-                if let Some (right_type)
-                    = sym_tab.get::<str>(&&right_rval.name)
-                {
-                    if !is_promotable_to(&right_type, &dest_lval.typename) {
-                        return false;
-                    }
+
+                continue;
+            }
+
+            in_phi_head = false;
+
+            for var in stmt.uses() {
+                if !dominates(&var.name, &block.label, i) {
+                    errors.push(TypeError::UseNotDominated (
+                        var.name.clone(), block.label.clone(), tl.clone()
+                    ));
                 }
-                else {
-                    return false;
+            }
+        }
+    }
+}
+
+fn typecheck_funcdef(
+    sig: &FuncSig, blocks: &Vec<BasicBlock>, syms: &ModuleSymbols,
+    errors: &mut Vec<TypeError>
+)
+{
+    let structs = &syms.structs;
+    let mut sym_tab = HashMap::new();
+    // Block and statement index of each statement that is in error.
+    let mut failed = HashSet::new();
+
+    errors.extend(check_type(&sig.typename, structs, &sig.loc).err());
+
+    for arg in &sig.arglist {
+        if sym_tab.contains_key::<str>(&arg.name) {
+            errors.push(TypeError::DuplicateArg (arg.clone(), sig.loc.clone()));
+        }
+
+        errors.extend(check_type(&arg.typename, structs, &sig.loc).err());
+
+        sym_tab.insert(arg.name.as_str(), &arg.typename);
+    }
+
+    let mut labels = HashSet::new();
+
+    for block in blocks {
+        if !labels.insert(block.label.as_ref()) {
+            errors.push(TypeError::DuplicateLabel (
+                block.label.clone(), block.loc.clone()
+            ));
+        }
+    }
+
+    for (b, block) in blocks.iter().enumerate() {
+        let stmts = block.stmts.iter().zip(&block.locs);
+
+        for (i, (stmt, tl)) in stmts.enumerate() {
+            if let Some (dest_lval) = stmt.def() {
+                let t = &dest_lval.typename;
+
+                if let Err (err) = check_type(t, structs, tl) {
+                    errors.push(err);
+                    failed.insert((b, i));
                 }
 
-                if sym_tab.contains_key::<str>(&&dest_lval.name) {
-                    return false;
+                // Uses go by the first definition.
+                if sym_tab.contains_key::<str>(&dest_lval.name) {
+                    errors.push(TypeError::Redefinition (
+                        dest_lval.name.clone(), tl.clone()
+                    ));
+                    failed.insert((b, i));
                 }
+                else {
+                    sym_tab.insert(&dest_lval.name, &dest_lval.typename);
+                }
+            }
+        }
+    }
+
+    for (b, block) in blocks.iter().enumerate() {
+        let stmts = block.stmts.iter().zip(&block.locs);
 
-                sym_tab.insert(&dest_lval.name, &dest_lval.typename);
+        for (i, (stmt, tl)) in stmts.enumerate() {
+            if let Err (err) = typecheck_stmt(
+                stmt, tl, &sig.typename, &labels, &sym_tab, syms
+            ) {
+                errors.push(err);
+                failed.insert((b, i));
             }
-            &Stmt::LetInst (ref dest_lval, ref src_rval) => {
-                match src_rval {
-                    &LetValue::LetInteger (_) => {
-                        match dest_lval.typename {
-                            Type::Ptr (_) => {
-                                return false;
-                            }
-                            Type::UserType (_) => {
-                                return false;
-                            }
-                            _ => {}
-                        }
-                    }
-                    &LetValue::LetVariable (ref var) => {
-                        if let Some (val_type)
-                            = sym_tab.get::<str>(&&var.name)
-                        {
-                            if !is_promotable_to(
-                                &val_type, &dest_lval.typename
-                            ) {
-                                return false;
-                            }
-                        }
-                    }
-                }
+        }
+    }
 
-                if sym_tab.contains_key::<str>(&&dest_lval.name) {
-                    return false;
-                }
+    verify_ssa(sig, blocks, &failed, errors);
+    check_constant_stores(blocks, syms, &failed, errors);
+}
+
+/// Everything declared at module scope, gathered before any function body is
+/// checked so that calls and types may refer forward.
+pub struct ModuleSymbols<'a> {
+    pub structs: StructTable,
+    pub funcs: HashMap<&'a str, &'a FuncSig>,
+    pub globals: HashMap<&'a str, &'a GlobalDef>,
+}
+
+/// Whether `init` can fill storage of type `t`. Any sized type may be
+/// zero-initialized; strings fill byte arrays of exactly their length.
+fn initializer_fits(init: &Initializer, t: &Type) -> bool {
+    return match (init, t) {
+        (&Initializer::InitZero, _) => true,
+        (&Initializer::InitInteger (i, ref suffix, _), _) => {
+            let suffix_ok = match suffix {
+                &Some (ref suffix_type) => is_promotable_to(suffix_type, t),
+                &None => true,
+            };
+
+            is_integer_type(t) && suffix_ok && int_fits_type(i, t)
+        }
+        (&Initializer::InitFloat (_), _) => is_float_type(t),
+        (
+            &Initializer::InitString (ref bytes),
+            &Type::Array (ref elem, len)
+        ) => {
+            is_promotable_to(elem, &Type::I8) && bytes.len() as u64 == len
+        }
+        (
+            &Initializer::InitArray (ref elems),
+            &Type::Array (ref elem, len)
+        ) => {
+            elems.len() as u64 == len
+                && elems.iter().all(|e| initializer_fits(e, elem))
+        }
+        _ => false,
+    };
+}
+
+/// Whether two signatures for the same function agree, ignoring the names
+/// given to the arguments.
+fn same_signature(left: &FuncSig, right: &FuncSig) -> bool {
+    return is_promotable_to(&left.typename, &right.typename)
+        && left.variadic == right.variadic
+        && left.arglist.len() == right.arglist.len()
+        && left.arglist.iter().zip(&right.arglist).all(|(l, r)| {
+            is_promotable_to(&l.typename, &r.typename)
+        });
+}
 
-                sym_tab.insert(&dest_lval.name, &dest_lval.typename);
+fn collect_symbols<'a>(
+    ast: &'a Node, syms: &mut ModuleSymbols<'a>,
+    defined: &mut HashSet<&'a str>, errors: &mut Vec<TypeError>
+)
+{
+    match ast {
+        &Node::Module (ref items) => {
+            for item in items {
+                collect_symbols(item, syms, defined, errors);
             }
-            &Stmt::RetInst (ref rval) => {
-                if let &Some (ref var) = rval {
-                    if let Some (val_type)
-                        = sym_tab.get::<str>(&&var.name)
-                    {
-                        if !is_promotable_to(&val_type, &ret_type) {
-                            return false;
-                        }
-                    }
-                    else {
-                        if !is_promotable_to(&Type::Void, &ret_type) {
-                            return false;
-                        }
-                    }
+        }
+        &Node::StructDef (ref name, ref fields, ref tl) => {
+            if syms.structs.contains_key(name) {
+                errors.push(
+                    TypeError::DuplicateType (name.clone(), tl.clone())
+                );
+                return;
+            }
+
+            syms.structs.insert(name.clone(), fields.clone());
+        }
+        &Node::GlobalDef (ref global) => {
+            if syms.globals.contains_key::<str>(&global.name)
+                || syms.funcs.contains_key::<str>(&global.name)
+            {
+                errors.push(TypeError::DuplicateGlobal (
+                    global.name.clone(), global.loc.clone()
+                ));
+                return;
+            }
+
+            syms.globals.insert(&global.name, global);
+        }
+        &Node::FuncDecl (ref sig) | &Node::FuncDef (ref sig, _) => {
+            if syms.globals.contains_key::<str>(&sig.name) {
+                errors.push(TypeError::DuplicateGlobal (
+                    sig.name.clone(), sig.loc.clone()
+                ));
+                return;
+            }
+
+            if let &Node::FuncDef (..) = ast {
+                if !defined.insert(&sig.name) {
+                    errors.push(TypeError::DuplicateFunction (
+                        sig.name.clone(), sig.loc.clone()
+                    ));
+                    return;
                 }
             }
-            &Stmt::CallInst (ref dest_lval, ref func_rval, ref vars_rval) => {
-                return true;
+
+            if let Some (prev) = syms.funcs.get::<str>(&sig.name) {
+                if !same_signature(prev, sig) {
+                    errors.push(TypeError::ConflictingDeclaration (
+                        sig.name.clone(), sig.loc.clone()
+                    ));
+                    return;
+                }
             }
+
+            syms.funcs.insert(&sig.name, sig);
         }
     }
+}
+
+/// Gather the structs and functions of a module, rejecting a struct or
+/// function body given twice and declarations that disagree on a signature.
+/// Symbols in error are left out, and the rest still gathered.
+fn gather_symbols<'a>(ast: &'a Node, errors: &mut Vec<TypeError>)
+    -> ModuleSymbols<'a>
+{
+    let mut syms = ModuleSymbols {
+        structs: StructTable::new(),
+        funcs: HashMap::new(),
+        globals: HashMap::new(),
+    };
+
+    collect_symbols(ast, &mut syms, &mut HashSet::new(), errors);
 
-    return true;
+    return syms;
 }
 
-fn typecheck_funcdef<'a, 'b>(
-    sig: &'a FuncSig, stmts: &'a Vec<Stmt>,
-    sym_tab: &'b mut HashMap<&'b str, &'b Type>
-) -> bool
-where 'a: 'b
+pub fn build_symbol_table<'a>(ast: &'a Node)
+    -> Result<ModuleSymbols<'a>, Vec<TypeError>>
 {
-    for arg in &sig.arglist {
-        if sym_tab.contains_key::<str>(&&arg.name) {
-            return false;
+    let mut errors = Vec::new();
+    let syms = gather_symbols(ast, &mut errors);
+
+    return if errors.is_empty() {
+        Ok (syms)
+    }
+    else {
+        Err (errors)
+    };
+}
+
+fn typecheck_node(
+    ast: &Node, syms: &ModuleSymbols, errors: &mut Vec<TypeError>
+)
+{
+    let structs = &syms.structs;
+
+    match ast {
+        &Node::Module (ref items) => {
+            for item in items {
+                typecheck_node(item, syms, errors);
+            }
+        }
+        &Node::StructDef (_, ref fields, ref tl) => {
+            for field in fields {
+                errors.extend(check_type(field, structs, tl).err());
+            }
         }
+        &Node::FuncDecl (ref sig) => {
+            let tl = &sig.loc;
+
+            errors.extend(check_type(&sig.typename, structs, tl).err());
 
-        sym_tab.insert(&arg.name, &arg.typename);
+            for arg in &sig.arglist {
+                errors.extend(check_type(&arg.typename, structs, tl).err());
+            }
+        }
+        &Node::GlobalDef (ref global) => {
+            if let Err (err) = layout_of(&global.typename, structs) {
+                errors.push(TypeError::BadType (err, global.loc.clone()));
+            }
+            else if !initializer_fits(&global.init, &global.typename) {
+                errors.push(TypeError::BadInitializer (
+                    global.name.clone(), global.loc.clone()
+                ));
+            }
+        }
+        &Node::FuncDef (ref sig, ref blocks) => {
+            typecheck_funcdef(sig, blocks, syms, errors);
+        }
     }
+}
+
+/// Typecheck a whole module, carrying on past errors so that every one is
+/// reported. Errors come back in source order.
+pub fn typecheck(ast: &Node) -> Result<(), Vec<TypeError>> {
+    let mut errors = Vec::new();
+    let syms = gather_symbols(ast, &mut errors);
+
+    typecheck_node(ast, &syms, &mut errors);
+
+    errors.sort_by_key(|err| (err.loc().file.0, err.loc().start));
 
-    return typecheck_stmts(stmts, &sig.typename, sym_tab);
+    return if errors.is_empty() {
+        Ok (())
+    }
+    else {
+        Err (errors)
+    };
 }
 
-pub fn typecheck(ast: &Node) -> bool {
-    let mut sym_tab = HashMap::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    match ast {
-        &Node::FuncDef (ref sig, ref stmts) => {
-            if !typecheck_funcdef(sig, stmts, &mut sym_tab) {
-                return false;
+    /// The codes of the type errors in `source`, which must parse.
+    fn codes(source: &str) -> Vec<&'static str> {
+        let tokens = tokenize(source, FileId (0)).into_result().unwrap();
+        let ast = parse(&tokens).into_result().unwrap();
+
+        return match typecheck(&ast) {
+            Ok (()) => vec!(),
+            Err (errors) => {
+                errors.iter().map(|e| e.to_diagnostic().code).collect()
             }
+        };
+    }
+
+    fn named_func(name: &str, body: &str) -> String {
+        return format!(
+            "func @{}:i64 (%i:i64, %d:f64) {{\n{}\n}}\n", name, body
+        );
+    }
+
+    fn func(body: &str) -> String {
+        return named_func("f", body);
+    }
+
+    /// The codes of the type errors in `stmt`, put in a function by itself.
+    fn stmt_codes(stmt: &str) -> Vec<&'static str> {
+        return codes(&func(&format!("{}\nret 0", stmt)));
+    }
+
+    #[test]
+    fn floats_and_integers_do_not_mix() {
+        assert!(stmt_codes("add %x:f64 %d 1.5").is_empty());
+        assert!(stmt_codes("add %x:f64 %d -inf").is_empty());
+        assert_eq!(stmt_codes("add %x:i64 %i 1.5"), vec!("E0324"));
+        assert_eq!(stmt_codes("add %x:f64 %d 1"), vec!("E0324"));
+        assert_eq!(stmt_codes("add %x:f64 %i %d"), vec!("E0319"));
+    }
+
+    #[test]
+    fn integer_literals_must_fit_their_type() {
+        assert!(stmt_codes("let %a:i8 -128").is_empty());
+        assert!(stmt_codes("let %a:i8 255").is_empty());
+        assert!(stmt_codes("let %a:i64 0xffffffffffffffff").is_empty());
+        assert_eq!(stmt_codes("let %a:i8 256"), vec!("E0315"));
+        assert_eq!(stmt_codes("let %a:i8 -129"), vec!("E0315"));
+        assert_eq!(stmt_codes("let %a:i8 0xffffffffffffffff"), vec!("E0315"));
+        assert_eq!(stmt_codes("let %a:i1 2"), vec!("E0315"));
+    }
+
+    #[test]
+    fn integer_suffixes_must_match() {
+        assert!(stmt_codes("add %a:i64 %i 3i64").is_empty());
+        assert_eq!(stmt_codes("let %a:i64 3i8"), vec!("E0316"));
+        assert_eq!(stmt_codes("let %a:i8 3i64"), vec!("E0316"));
+    }
+
+    #[test]
+    fn every_function_is_checked() {
+        let source = format!(
+            "{}{}{}",
+            named_func("a", "ret 0"),
+            named_func("b", "let %a:i8 300\nret 0"),
+            named_func("c", "ret 1.5")
+        );
+
+        assert_eq!(codes(&source), vec!("E0315", "E0324"));
+    }
+
+    #[test]
+    fn branch_targets_must_exist_once() {
+        assert!(codes(&func("br a\na:\nret 0")).is_empty());
+        assert_eq!(codes(&func("br b\na:\nret 0")), vec!("E0303"));
+        assert_eq!(
+            codes(&func("switch %i a [1 a, 2 b]\na:\nret 0")),
+            vec!("E0303")
+        );
+        assert_eq!(
+            codes(&func("br a\na:\nbr a\na:\nret 0")),
+            vec!("E0302")
+        );
+    }
+
+    const LOOP: &'static str = "\
+        br head
+    head:
+        phi %n:i64 [%i entry, %m body]
+        icmp %c:i1 sgt %n 0
+        br %c body done
+    body:
+        sub %m:i64 %n 1
+        br head
+    done:
+        ret %n";
+
+    #[test]
+    fn loops_in_ssa_form_pass() {
+        assert!(codes(&func(LOOP)).is_empty());
+    }
+
+    #[test]
+    fn variables_are_defined_once() {
+        assert_eq!(
+            codes(&func("let %a:i64 1\nlet %a:i64 2\nret %a")),
+            vec!("E0311")
+        );
+        assert_eq!(codes(&func("let %i:i64 1\nret %i")), vec!("E0311"));
+    }
+
+    #[test]
+    fn phis_head_their_block_and_name_each_predecessor() {
+        let misplaced = LOOP.replace(
+            "phi %n:i64 [%i entry, %m body]\n        icmp",
+            "let %z:i64 0\nphi %n:i64 [%i entry, %m body]\nicmp"
+        );
+
+        assert_eq!(codes(&func(&misplaced)), vec!("E0312"));
+
+        for incoming in &[
+            "[%i entry]",
+            "[%i entry, %m body, %m body]",
+            "[%i entry, %m body, %i done]",
+        ] {
+            let source = LOOP.replace("[%i entry, %m body]", incoming);
+
+            assert_eq!(codes(&func(&source)), vec!("E0313"), "{}", incoming);
+        }
+    }
+
+    #[test]
+    fn uses_are_dominated_by_their_definition() {
+        let source = "\
+            let %c:i1 1
+            br %c a b
+        a:
+            let %x:i64 1
+            br b
+        b:
+            ret %x";
+
+        assert_eq!(codes(&func(source)), vec!("E0314"));
+
+        // A phi's incoming value need only be available at the end of the
+        // predecessor it comes from.
+        let source = LOOP.replace("ret %n", "ret %m");
+
+        assert_eq!(codes(&func(&source)), vec!("E0314"));
+
+        // Blocks that cannot be reached are not checked.
+        let source = "br b\ndead:\nret %y\nb:\nlet %y:i64 1\nret %y";
+
+        assert!(codes(&func(source)).is_empty());
+    }
+
+    #[test]
+    fn bitwise_and_division_want_integers() {
+        for op in &["sdiv", "urem", "and", "xor", "shl", "ashr"] {
+            let ints = format!("{} %x:i64 %i 3", op);
+            let floats = format!("{} %x:f64 %d 3.0", op);
+
+            assert!(stmt_codes(&ints).is_empty(), "{}", ints);
+            assert_eq!(stmt_codes(&floats), vec!("E0328"), "{}", floats);
+        }
+
+        assert!(stmt_codes("mul %x:f64 %d 3.0").is_empty());
+        assert!(stmt_codes("neg %x:f64 %d").is_empty());
+        assert!(stmt_codes("not %x:i64 %i").is_empty());
+        assert_eq!(stmt_codes("not %x:f64 %d"), vec!("E0328"));
+        assert_eq!(stmt_codes("and %x:i32 %i 1"), vec!("E0319"));
+    }
+
+    #[test]
+    fn arithmetic_wants_numbers() {
+        let bad = [
+            "alloca %p:i32*\nadd %q:i32* %p %p",
+            "alloca %p:i32*\nneg %q:i32* %p",
+            "alloca %p:Pair*\nload %v:Pair %p\nsub %w:Pair %v %v",
+            "alloca %p:[2 x i8]*\nload %v:[2 x i8] %p\nmul %w:[2 x i8] %v %v",
+        ];
+
+        for stmts in &bad {
+            let body = format!("{}\nret 0", stmts);
+            let source = format!("{}{}", PAIR, func(&body));
+
+            assert_eq!(codes(&source), vec!("E0328"), "{}", stmts);
+        }
+    }
+
+    #[test]
+    fn comparisons_give_an_i1() {
+        assert!(stmt_codes("icmp %c:i1 slt %i 10").is_empty());
+        assert!(stmt_codes("fcmp %c:i1 olt %d 1.0").is_empty());
+        assert_eq!(stmt_codes("icmp %c:i64 slt %i 10"), vec!("E0319"));
+        assert_eq!(stmt_codes("icmp %c:i1 eq %d 1.0"), vec!("E0328"));
+        assert_eq!(stmt_codes("fcmp %c:i1 oeq %i 1"), vec!("E0328"));
+        assert_eq!(stmt_codes("icmp %c:i1 eq %i %d"), vec!("E0319"));
+    }
+
+    #[test]
+    fn branch_conditions_are_i1() {
+        let branch = |cond: &str| {
+            codes(&func(&format!("br {} a b\na:\nret 0\nb:\nret 1", cond)))
+        };
+
+        assert!(branch("1").is_empty());
+        assert_eq!(branch("%i"), vec!("E0319"));
+        assert_eq!(branch("2"), vec!("E0315"));
+    }
+
+    #[test]
+    fn immediates_take_the_type_they_are_used_as() {
+        assert!(stmt_codes("add %x:i8 -128 127").is_empty());
+        assert_eq!(stmt_codes("add %x:i8 1 256"), vec!("E0315"));
+        assert_eq!(codes(&func("ret 1.5")), vec!("E0324"));
+        assert_eq!(codes(&func("ret 0x1p64")), vec!("E0324"));
+
+        let phi = |incoming: &str| {
+            codes(&func(&format!("br a\na:\nphi %p:i8 [{}]\nret 0", incoming)))
+        };
+
+        assert!(phi("-1 entry").is_empty());
+        assert_eq!(phi("256 entry"), vec!("E0315"));
+
+        let switch = |val: &str| {
+            codes(&func(&format!("switch {} a [300 a]\na:\nret 0", val)))
+        };
+
+        assert!(switch("5").is_empty());
+        assert!(switch("5i16").is_empty());
+        assert_eq!(switch("5i8"), vec!("E0326"));
+        assert_eq!(
+            codes(&func("switch %i a [1 a, 1 a]\na:\nret 0")),
+            vec!("E0327")
+        );
+        assert_eq!(
+            codes(&func("switch %d a [1 a]\na:\nret 0")),
+            vec!("E0328")
+        );
+    }
+
+    #[test]
+    fn memory_goes_through_pointers_of_the_right_type() {
+        let ok = "alloca %p:i32*\nstore 5 %p\nload %v:i32 %p\n\
+                  alloca %pp:i32**\nstore %p %pp\nload %q:i32* %pp";
+
+        assert!(stmt_codes(ok).is_empty());
+        assert_eq!(stmt_codes("alloca %p:i32"), vec!("E0325"));
+        assert_eq!(stmt_codes("load %v:i64 %i"), vec!("E0325"));
+        assert_eq!(stmt_codes("store 1 %i"), vec!("E0325"));
+
+        let bad = [
+            ("alloca %p:i32*\nload %v:i64 %p", "E0319"),
+            ("alloca %p:i8*\nstore 300 %p", "E0315"),
+            ("alloca %p:i32*\nstore %d %p", "E0319"),
+        ];
+
+        for &(stmts, code) in &bad {
+            assert_eq!(stmt_codes(stmts), vec!(code), "{}", stmts);
+        }
+    }
+
+    const PAIR: &'static str = "type Pair = {i8, [4 x i32]}\n";
+
+    #[test]
+    fn aggregates_are_indexed_by_type() {
+        let ok = "\
+            alloca %p:Pair*
+            gep %e:i32* %p 0 1 %i
+            load %v:Pair %p
+            extractvalue %a:[4 x i32] %v 1
+            extractvalue %b:i32 %v 1 3
+            insertvalue %w:Pair %v 7 1 2
+            insertvalue %x:Pair %w 2 0";
+
+        let source = format!("{}{}", PAIR, func(&format!("{}\nret 0", ok)));
+
+        assert!(codes(&source).is_empty());
+
+        let bad = [
+            ("gep %e:i8* %p 0 1 0", "E0319"),
+            ("gep %e:i32* %p 0 2", "E0329"),
+            ("gep %e:i32* %p 0 %i", "E0329"),
+            ("gep %e:i8* %p 0 -1", "E0329"),
+            ("gep %e:i8* %p 0 0xffffffffffffffff", "E0329"),
+            ("gep %e:i8* %p 0 0 0", "E0329"),
+            ("gep %e:i32* %p 0 1 1.5", "E0328"),
+            ("load %v:Pair %p\nextractvalue %b:i8 %v 1 0", "E0319"),
+            ("load %v:Pair %p\nextractvalue %b:i32 %v 1 4", "E0329"),
+            ("load %v:Pair %p\ninsertvalue %w:Pair %v 1.5 0", "E0324"),
+        ];
+
+        for &(stmts, code) in &bad {
+            let body = format!("alloca %p:Pair*\n{}\nret 0", stmts);
+            let source = format!("{}{}", PAIR, func(&body));
+
+            assert_eq!(codes(&source), vec!(code), "{}", stmts);
+        }
+    }
+
+    #[test]
+    fn struct_definitions_are_checked() {
+        assert_eq!(codes(&format!("{}{}", PAIR, PAIR)), vec!("E0317"));
+        assert_eq!(codes("type A = {i8, B}\n"), vec!("E0318"));
+        assert_eq!(codes("type A = {i8, [2 x A]}\n"), vec!("E0318"));
+        assert_eq!(stmt_codes("alloca %p:Nope*"), vec!("E0318"));
+    }
+
+    #[test]
+    fn casts_must_change_width_the_right_way() {
+        let i8_ptr = Type::Ptr (Box::new(Type::I8));
+        let legal = [
+            (CastOp::Trunc, Type::I64, Type::I1),
+            (CastOp::ZExt, Type::I1, Type::I32),
+            (CastOp::SExt, Type::I8, Type::I16),
+            (CastOp::FPToSI, Type::F32, Type::I64),
+            (CastOp::UIToFP, Type::I8, Type::F64),
+            (CastOp::FPExt, Type::F32, Type::F64),
+            (CastOp::FPTrunc, Type::F64, Type::F32),
+            (CastOp::Bitcast, Type::I32, Type::F32),
+            (CastOp::Bitcast, i8_ptr.clone(), Type::Ptr (Box::new(Type::F64))),
+            (CastOp::PtrToInt, i8_ptr.clone(), Type::I64),
+            (CastOp::IntToPtr, Type::I32, i8_ptr.clone()),
+        ];
+        let illegal = [
+            (CastOp::Trunc, Type::I32, Type::I32),
+            (CastOp::Trunc, Type::F64, Type::F32),
+            (CastOp::ZExt, Type::I64, Type::I32),
+            (CastOp::SExt, Type::F32, Type::F64),
+            (CastOp::FPToUI, Type::I32, Type::I32),
+            (CastOp::SIToFP, Type::F32, Type::F64),
+            (CastOp::FPExt, Type::F64, Type::F64),
+            (CastOp::FPTrunc, Type::I64, Type::I32),
+            (CastOp::Bitcast, Type::I32, Type::F64),
+            (CastOp::Bitcast, Type::I64, i8_ptr.clone()),
+            (CastOp::PtrToInt, Type::I64, Type::I64),
+            (CastOp::IntToPtr, Type::F64, i8_ptr.clone()),
+        ];
+
+        for &(op, ref src, ref dest) in &legal {
+            assert!(is_legal_cast(op, src, dest), "{} {} {}", op, src, dest);
+        }
+        for &(op, ref src, ref dest) in &illegal {
+            assert!(!is_legal_cast(op, src, dest), "{} {} {}", op, src, dest);
+        }
+
+        assert!(stmt_codes("trunc %a:i8 %i\nsitofp %b:f32 %a").is_empty());
+        assert_eq!(stmt_codes("zext %a:i8 %i"), vec!("E0323"));
+        assert_eq!(stmt_codes("fptrunc %a:f32 %i"), vec!("E0323"));
+        assert_eq!(stmt_codes("bitcast %a:i32 %d"), vec!("E0323"));
+    }
+
+    const CALLEE: &'static str =
+        "func @g:i32 (%a:i32, %b:f64) {\n    ret %a\n}\n";
+
+    fn call_codes(stmts: &str) -> Vec<&'static str> {
+        let body = format!("{}\nret 0", stmts);
+
+        return codes(&format!("{}{}", CALLEE, func(&body)));
+    }
+
+    #[test]
+    fn calls_are_checked_against_the_callee() {
+        assert!(call_codes("trunc %n:i32 %i\ncall %r:i32 @g(%n, %d)")
+            .is_empty());
+        assert!(call_codes("call %r:i32 @g(-5, 2.5)").is_empty());
+
+        let bad = [
+            ("call %r:i32 @g(1)", "E0321"),
+            ("call %r:i32 @g(1, 2.0, 3)", "E0321"),
+            ("call %r:i32 @g(%i, %d)", "E0319"),
+            ("call %r:i32 @g(1.5, %d)", "E0324"),
+            ("call %r:i32 @g(4294967296, %d)", "E0315"),
+            ("call %r:i64 @g(1, %d)", "E0319"),
+            ("call %r:i32 @h(1, %d)", "E0305"),
+        ];
+
+        for &(stmts, code) in &bad {
+            assert_eq!(call_codes(stmts), vec!(code), "{}", stmts);
+        }
+    }
+
+    #[test]
+    fn checking_continues_past_calls() {
+        assert_eq!(
+            call_codes("call %r:i32 @g(1, %d)\nadd %x:i32 %r %nope"),
+            vec!("E0304")
+        );
+        assert_eq!(
+            call_codes("call %r:i32 @g(1)\ncall %s:i32 @g(%d, %d)"),
+            vec!("E0321", "E0319")
+        );
+    }
+
+    #[test]
+    fn functions_are_declared_once() {
+        let decl = "declare @g:i32 (%a:i32, %b:f64)\n";
+        let other = "declare @g:i32 (%a:i32)\n";
+
+        assert!(codes(&format!("{}{}", decl, decl)).is_empty());
+        assert!(codes(&format!("{}{}", decl, CALLEE)).is_empty());
+        assert!(codes(&format!("{}{}", CALLEE, decl)).is_empty());
+        assert_eq!(codes(&format!("{}{}", CALLEE, CALLEE)), vec!("E0306"));
+        assert_eq!(codes(&format!("{}{}", decl, other)), vec!("E0307"));
+        assert_eq!(codes(&format!("{}{}", other, CALLEE)), vec!("E0307"));
+        assert_eq!(stmt_codes("call %r:i32 @g(1, %d)"), vec!("E0305"));
+    }
+
+    #[test]
+    fn variadic_calls_take_extra_arguments() {
+        let printf = "declare @printf:i32 (%fmt:i8*, ...)\n";
+        let call_codes = |stmts: &str| {
+            let body = format!("inttoptr %s:i8* %i\n{}\nret 0", stmts);
+
+            codes(&format!("{}{}", printf, func(&body)))
+        };
+
+        assert!(call_codes("call %r:i32 @printf(%s)").is_empty());
+        assert!(call_codes("call %r:i32 @printf(%s, %i, %d, 7i8, 1.5)")
+            .is_empty());
+        assert_eq!(call_codes("call %r:i32 @printf()"), vec!("E0321"));
+        assert_eq!(call_codes("call %r:i32 @printf(%i)"), vec!("E0319"));
+    }
+
+    #[test]
+    fn initializers_match_the_global_type() {
+        let good = [
+            "global @a:i8 = -128",
+            "global @b:f32 = 0.5",
+            "global @c:[3 x i8] = \"ab\\0\"",
+            "global @d:[2 x [2 x i16]] = [[1, 2], zeroinit]",
+            "global @e:Pair = zeroinit",
+        ];
+        let bad = [
+            "global @a:i8 = 256",
+            "global @b:i32 = 0.5",
+            "global @c:[2 x i8] = \"abc\"",
+            "global @d:[2 x i16] = [1, 2, 3]",
+            "global @e:i64 = 3i32",
+            "global @f:i32* = \"a\"",
+        ];
+
+        for global in &good {
+            let source = format!("{}{}", PAIR, global);
+
+            assert!(codes(&source).is_empty(), "{}", global);
+        }
+        for global in &bad {
+            assert_eq!(codes(global), vec!("E0310"), "{}", global);
+        }
+
+        assert_eq!(
+            codes("global @a:i8 = 0\nconstant @a:i8 = 1"), vec!("E0308")
+        );
+        assert_eq!(
+            codes(&format!("global @g:i8 = 0\n{}", CALLEE)), vec!("E0308")
+        );
+        assert_eq!(stmt_codes("addrof %p:i8* @nope"), vec!("E0309"));
+    }
+
+    #[test]
+    fn constant_globals_are_read_only() {
+        let globals = "global @m:[4 x i64] = zeroinit\n\
+                       constant @k:[4 x i64] = [1, 2, 3, 4]\n\
+                       constant @c:i64 = 3\n";
+        let store_codes = |stmts: &str| {
+            let body = format!("{}\nret 0", stmts);
+
+            codes(&format!("{}{}", globals, func(&body)))
+        };
+
+        assert!(store_codes("addrof %p:[4 x i64]* @m\n\
+                             gep %e:i64* %p 0 1\n\
+                             store 5 %e").is_empty());
+        assert!(store_codes("addrof %p:[4 x i64]* @k\n\
+                             gep %e:i64* %p 0 1\n\
+                             load %v:i64 %e").is_empty());
+
+        let bad = [
+            "addrof %p:i64* @c\nstore 5 %p",
+            "addrof %p:[4 x i64]* @k\ngep %e:i64* %p 0 %i\nstore 5 %e",
+            "addrof %p:[4 x i64]* @k\nbitcast %q:i64* %p\nstore 5 %q",
+            "addrof %p:[4 x i64]* @k\nlet %q:[4 x i64]* %p\n\
+             gep %e:i64* %q 0 0\nstore %i %e",
+        ];
+
+        for stmts in &bad {
+            assert_eq!(store_codes(stmts), vec!("E0322"), "{}", stmts);
         }
     }
 
-    return true;
+    #[test]
+    fn ssa_is_checked_past_type_errors() {
+        let source = "\
+            let %c:i1 1
+            add %y:i64 %i 1.5
+            br %c a b
+        a:
+            let %x:i64 1
+            br b
+        b:
+            ret %x";
+
+        assert_eq!(codes(&func(source)), vec!("E0324", "E0314"));
+
+        // A statement in error is not checked again.
+        let source = format!(
+            "constant @c:i64 = 3\n{}",
+            func("addrof %p:i64* @c\nstore 1.5 %p\nret 0")
+        );
+
+        assert_eq!(codes(&source), vec!("E0324"));
+        assert_eq!(
+            codes(&func("let %a:i64 1\nlet %a:i8 2\nret %a")),
+            vec!("E0311")
+        );
+    }
+
+    #[test]
+    fn statement_errors_point_at_their_cause() {
+        let source = func("add %x:i64 %i 1.5\nzext %z:i8 %i\nret 0");
+        let tokens = tokenize(&source, FileId (0)).into_result().unwrap();
+        let ast = parse(&tokens).into_result().unwrap();
+        let diags: Vec<Diagnostic> = typecheck(&ast).unwrap_err().iter()
+            .map(|e| e.to_diagnostic())
+            .collect();
+
+        let literal = &diags[0].labels[0];
+        let stmt = &diags[0].labels[1];
+
+        assert_eq!(&source[literal.loc.start..literal.loc.end], "1.5");
+        assert!(literal.primary && !stmt.primary);
+        assert_eq!(stmt.loc.line, 2);
+        assert_eq!(stmt.message, "expects i64");
+
+        assert_eq!(diags[1].code, "E0323");
+        assert_eq!(diags[1].message, "cannot zext i64 to i8");
+        assert_eq!(diags[1].notes.len(), 1);
+    }
+
+    #[test]
+    fn every_error_is_reported_where_it_happens() {
+        let source = "\
+func @f:i32 (%a:i32, %a:i64) {
+    add %x:i32 %nope 1
+    ret 1.5
+}
+func @g:void () {
+    call %r:i32 @f(1)
+    call %s:i32 @h()
+    ret void
+}
+func @f:i32 () {
+    ret 0
+}
+";
+        let tokens = tokenize(source, FileId (0)).into_result().unwrap();
+        let ast = parse(&tokens).into_result().unwrap();
+        let errors = typecheck(&ast).unwrap_err();
+        let found: Vec<(&str, u64, u64)> = errors.iter()
+            .map(|e| (e.to_diagnostic().code, e.loc().line, e.loc().col))
+            .collect();
+
+        assert_eq!(found, vec!(
+            ("E0301", 1, 6),
+            ("E0304", 2, 5),
+            ("E0324", 3, 9),
+            ("E0321", 6, 5),
+            ("E0305", 7, 5),
+            ("E0306", 10, 6),
+        ));
+
+        for error in &errors {
+            let diag = error.to_diagnostic();
+
+            assert_eq!(diag.primary_loc().unwrap().start, error.loc().start);
+        }
+    }
 }
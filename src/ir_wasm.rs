@@ -0,0 +1,1251 @@
+use ir_codegen::*;
+use ir_lexer::*;
+use ir_parser::*;
+use ir_type_layout::*;
+use ir_typechecker::*;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// Globals are laid out from this address up, so that a null pointer, or one
+/// near it, never points at anything.
+const DATA_START: u64 = 16;
+
+/// Bytes of linear memory set aside above the globals for the stack, which
+/// holds allocas and aggregate values.
+const STACK_SIZE: u64 = 1 << 20;
+
+const PAGE_SIZE: u64 = 1 << 16;
+
+/// The wasm value type that values of type `t` are kept in, or `None` for
+/// aggregates, which always live in linear memory. Pointers are 64 bits wide
+/// in the IR, so they are kept as `i64` and wrapped into a 32-bit address
+/// when memory is accessed.
+fn value_type(t: &Type) -> Option<&'static str> {
+    return match t {
+        &Type::I1 | &Type::I8 | &Type::I16 | &Type::I32 => Some ("i32"),
+        &Type::I64 | &Type::Ptr (_) => Some ("i64"),
+        &Type::F32 => Some ("f32"),
+        &Type::F64 => Some ("f64"),
+        _ => None,
+    };
+}
+
+/// The mask that keeps the bits of an integer of type `t`, for the types
+/// narrower than the `i32` they are kept in.
+fn narrow_mask(t: &Type) -> Option<i32> {
+    return match t {
+        &Type::I1 => Some (1),
+        &Type::I8 => Some (0xff),
+        &Type::I16 => Some (0xffff),
+        _ => None,
+    };
+}
+
+/// A `$` identifier for `name`. IR names may hold any alphanumeric
+/// character, but wasm identifiers are ASCII, so others are spelled out as
+/// `^` and their code point. Names the emitter makes up for itself start
+/// with a `.`, which no IR name can hold.
+fn ident(name: &str) -> String {
+    let mut s = "$".to_owned();
+
+    for c in name.chars() {
+        if c.is_ascii() {
+            s.push(c);
+        }
+        else {
+            s.push_str(&format!("^{:x}", c as u32));
+        }
+    }
+
+    return s;
+}
+
+/// A float constant of type `t` with IEEE encoding `bits`, written so that
+/// it reads back to exactly the same bits.
+fn float_const(t: &Type, bits: u64) -> String {
+    let (x, mantissa) = match t {
+        &Type::F32 => {
+            let x = f32::from_bits(bits as u32);
+            (x as f64, bits & 0x7fffff)
+        }
+        _ => (f64::from_bits(bits), bits & 0xfffffffffffff),
+    };
+
+    let sign = if x.is_sign_negative() { "-" } else { "" };
+
+    return if x.is_nan() {
+        format!("{}nan:0x{:x}", sign, mantissa)
+    }
+    else if x.is_infinite() {
+        format!("{}inf", sign)
+    }
+    else {
+        match t {
+            &Type::F32 => format!("{:?}", f32::from_bits(bits as u32)),
+            _ => format!("{:?}", x),
+        }
+    };
+}
+
+/// A string literal holding `bytes`.
+fn escape_bytes(bytes: &[u8]) -> String {
+    let mut s = String::new();
+
+    for &b in bytes {
+        if b >= 0x20 && b < 0x7f && b != b'"' && b != b'\\' {
+            s.push(b as char);
+        }
+        else {
+            s.push_str(&format!("\\{:02x}", b));
+        }
+    }
+
+    return s;
+}
+
+/// `(param ...)` and `(result ...)` for the signature `sig`, with the
+/// parameters named when `named`.
+fn signature(sig: &FuncSig, named: bool) -> Result<String, CodegenError> {
+    let mut s = String::new();
+
+    for arg in &sig.arglist {
+        let vt = match value_type(&arg.typename) {
+            Some (vt) => vt,
+            None => {
+                return Err (CodegenError::Unsupported (
+                    "passing an aggregate by value".to_owned(),
+                    sig.loc.clone()
+                ));
+            }
+        };
+
+        if named {
+            s.push_str(&format!(" (param {} {})", ident(&arg.name), vt));
+        }
+        else {
+            s.push_str(&format!(" (param {})", vt));
+        }
+    }
+
+    match value_type(&sig.typename) {
+        Some (vt) => s.push_str(&format!(" (result {})", vt)),
+        None if is_void(&sig.typename) => {}
+        None => {
+            return Err (CodegenError::Unsupported (
+                "returning an aggregate".to_owned(), sig.loc.clone()
+            ));
+        }
+    }
+
+    return Ok (s);
+}
+
+/// What a memory access is based on: the address held in a pointer, or the
+/// frame of the function.
+#[derive(Clone, Copy)]
+enum Base<'e> {
+    Ptr (&'e str),
+    Frame,
+}
+
+/// A memory operand: a base plus a constant offset.
+#[derive(Clone, Copy)]
+struct Addr<'e> {
+    base: Base<'e>,
+    offset: u64,
+}
+
+impl<'e> Addr<'e> {
+    fn offset(&self, by: u64) -> Addr<'e> {
+        return Addr {base: self.base, offset: self.offset + by};
+    }
+}
+
+struct Emitter<'e> {
+    sig: &'e FuncSig,
+    blocks: &'e Vec<BasicBlock>,
+    structs: &'e StructTable,
+    sigs: &'e SigTable<'e>,
+    /// The address in linear memory of each global.
+    globals: &'e HashMap<String, u64>,
+    types: HashMap<&'e str, &'e Type>,
+    /// The offset into the frame of the storage of each alloca, and of each
+    /// value of aggregate type.
+    slots: HashMap<&'e str, u64>,
+    frame_size: u64,
+    out: String,
+    depth: usize,
+}
+
+impl<'e> Emitter<'e> {
+    fn ins<S: AsRef<str>>(&mut self, text: S) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+
+        self.out.push_str(text.as_ref());
+        self.out.push('\n');
+    }
+
+    /// Open a construct that is closed again with `end`.
+    fn open<S: AsRef<str>>(&mut self, text: S) {
+        self.ins(text);
+        self.depth += 1;
+    }
+
+    fn end(&mut self) {
+        self.depth -= 1;
+        self.ins("end");
+    }
+
+    fn block_label(&self, label: &str) -> String {
+        return ident(&format!(".b.{}", label));
+    }
+
+    fn block_index(&self, label: &str) -> usize {
+        return self.blocks.iter()
+            .position(|b| b.label == label)
+            .unwrap_or(0);
+    }
+
+    fn var_type(&self, var: &Variable) -> &'e Type {
+        return self.types[var.name.as_str()];
+    }
+
+    fn size_of(&self, t: &Type, tl: &TokLoc) -> Result<u64, CodegenError> {
+        return layout_of(t, self.structs)
+            .map(|layout| layout.size)
+            .map_err(|err| CodegenError::BadType (err, tl.clone()));
+    }
+
+    /// The storage of the aggregate value or alloca `name`.
+    fn slot(&self, name: &str) -> Addr<'e> {
+        let offset = self.slots.get(name).cloned().unwrap_or(0);
+
+        return Addr {base: Base::Frame, offset: offset};
+    }
+
+    /// Push an integer constant of type `t`, given in canonical form.
+    fn int_const(&mut self, val: i64, t: &Type) {
+        match (value_type(t), narrow_mask(t)) {
+            (Some ("i64"), _) => self.ins(format!("i64.const {}", val)),
+            (_, Some (mask)) => {
+                self.ins(format!("i32.const {}", val as i32 & mask));
+            }
+            _ => self.ins(format!("i32.const {}", val as i32)),
+        }
+    }
+
+    /// Push the value of `op`, of type `t`.
+    fn push(&mut self, op: &Operand, t: &Type) {
+        if let Some (var) = op.var() {
+            self.ins(format!("local.get {}", ident(&var.name)));
+        }
+        else if let Some (bits) = imm_bits(op, t) {
+            if is_float_type(t) {
+                let vt = value_type(t).unwrap_or("f64");
+                self.ins(format!("{}.const {}", vt, float_const(t, bits)));
+            }
+            else {
+                self.int_const(bits as i64, t);
+            }
+        }
+    }
+
+    fn push_var(&mut self, var: &Variable) {
+        self.ins(format!("local.get {}", ident(&var.name)));
+    }
+
+    fn set(&mut self, name: &str) {
+        self.ins(format!("local.set {}", ident(name)));
+    }
+
+    /// Integers narrower than 32 bits are kept zero-extended in an `i32`.
+    /// Bring the one on top of the stack back to that form after an
+    /// operation that may have disturbed the bits above its width.
+    fn mask(&mut self, t: &Type) {
+        if let Some (mask) = narrow_mask(t) {
+            self.ins(format!("i32.const {}", mask));
+            self.ins("i32.and");
+        }
+    }
+
+    /// Turn the integer of type `t` on top of the stack into its value read
+    /// as signed, at the width of the wasm type it is kept in.
+    fn sign_extend(&mut self, t: &Type) {
+        let shift = match t {
+            &Type::I1 => 31,
+            &Type::I8 => 24,
+            &Type::I16 => 16,
+            _ => return,
+        };
+
+        self.ins(format!("i32.const {}", shift));
+        self.ins("i32.shl");
+        self.ins(format!("i32.const {}", shift));
+        self.ins("i32.shr_s");
+    }
+
+    /// Turn the integer of type `from` on top of the stack into one of type
+    /// `to` with the same low bits, zero-extending if it widens.
+    fn convert_int(&mut self, from: &Type, to: &Type) {
+        match (value_type(from), value_type(to)) {
+            (Some ("i64"), Some ("i64")) => {}
+            (Some ("i64"), _) => {
+                self.ins("i32.wrap_i64");
+                self.mask(to);
+            }
+            (_, Some ("i64")) => self.ins("i64.extend_i32_u"),
+            _ => self.mask(to),
+        }
+    }
+
+    /// Push the 32-bit address that `addr` is based on. The offset is left
+    /// to the instruction that accesses memory.
+    fn push_base(&mut self, addr: Addr) {
+        match addr.base {
+            Base::Ptr (ptr) => {
+                self.ins(format!("local.get {}", ident(ptr)));
+                self.ins("i32.wrap_i64");
+            }
+            Base::Frame => self.ins("local.get $.fp"),
+        }
+    }
+
+    fn copy_mem(&mut self, dst: Addr, src: Addr, size: u64) {
+        let chunks = [
+            (8, "i64.load", "i64.store"),
+            (4, "i32.load", "i32.store"),
+            (2, "i32.load16_u", "i32.store16"),
+            (1, "i32.load8_u", "i32.store8"),
+        ];
+        let mut done = 0;
+
+        for &(width, load, store) in &chunks {
+            while size - done >= width {
+                self.push_base(dst);
+                self.push_base(src);
+                self.ins(format!("{} offset={}", load, src.offset + done));
+                self.ins(format!("{} offset={}", store, dst.offset + done));
+                done += width;
+            }
+        }
+    }
+
+    /// Load a value of type `t` from `addr` into `dest`.
+    fn load_mem(&mut self, t: &Type, addr: Addr, dest: &str, tl: &TokLoc)
+        -> Result<(), CodegenError>
+    {
+        let load = match t {
+            &Type::I1 | &Type::I8 => "i32.load8_u",
+            &Type::I16 => "i32.load16_u",
+            &Type::I32 => "i32.load",
+            &Type::I64 | &Type::Ptr (_) => "i64.load",
+            &Type::F32 => "f32.load",
+            &Type::F64 => "f64.load",
+            _ => {
+                let size = self.size_of(t, tl)?;
+                let dst = self.slot(dest);
+                self.copy_mem(dst, addr, size);
+                return Ok (());
+            }
+        };
+
+        self.push_base(addr);
+        self.ins(format!("{} offset={}", load, addr.offset));
+        self.set(dest);
+
+        return Ok (());
+    }
+
+    /// Store `value`, of type `t`, to `addr`.
+    fn store_mem(&mut self, t: &Type, value: &Operand, addr: Addr, tl: &TokLoc)
+        -> Result<(), CodegenError>
+    {
+        let store = match t {
+            &Type::I1 | &Type::I8 => "i32.store8",
+            &Type::I16 => "i32.store16",
+            &Type::I32 => "i32.store",
+            &Type::I64 | &Type::Ptr (_) => "i64.store",
+            &Type::F32 => "f32.store",
+            &Type::F64 => "f64.store",
+            _ => {
+                let size = self.size_of(t, tl)?;
+
+                if let Some (var) = value.var() {
+                    let src = self.slot(&var.name);
+                    self.copy_mem(addr, src, size);
+                }
+
+                return Ok (());
+            }
+        };
+
+        self.push_base(addr);
+        self.push(value, t);
+        self.ins(format!("{} offset={}", store, addr.offset));
+
+        return Ok (());
+    }
+
+    /// Give the phis of the block labeled `target` their values on the edge
+    /// from `pred`, then go there. All the incoming values are pushed before
+    /// any phi is set, so the phis take their values all at once.
+    fn goto(&mut self, pred: &BasicBlock, target: &str)
+        -> Result<(), CodegenError>
+    {
+        let blocks = self.blocks;
+        let succ = blocks.iter().find(|b| b.label == target);
+        let mut dests = Vec::new();
+
+        if let Some (succ) = succ {
+            for (stmt, tl) in succ.stmts.iter().zip(&succ.locs) {
+                let (dest, incoming) = match stmt {
+                    &Stmt::PhiInst (ref dest, ref incoming) => (dest, incoming),
+                    _ => break,
+                };
+
+                if value_type(&dest.typename).is_none() {
+                    return Err (CodegenError::Unsupported (
+                        "a phi of aggregate type".to_owned(), tl.clone()
+                    ));
+                }
+
+                let src = incoming.iter()
+                    .find(|&&(_, ref label)| label.name == pred.label);
+
+                if let Some (&(ref val, _)) = src {
+                    self.push(val, &dest.typename);
+                    dests.push(dest.name.as_str());
+                }
+            }
+        }
+
+        for dest in dests.into_iter().rev() {
+            self.set(dest);
+        }
+
+        let index = self.block_index(target);
+
+        self.ins(format!("i32.const {}", index));
+        self.ins("local.set $.pc");
+        self.ins("br $.dispatch");
+
+        return Ok (());
+    }
+
+    fn prologue(&mut self) {
+        if self.frame_size > 0 {
+            self.ins("global.get $.sp");
+            self.ins(format!("i32.const {}", self.frame_size));
+            self.ins("i32.sub");
+            self.ins("local.tee $.fp");
+            self.ins("global.set $.sp");
+        }
+
+        for arg in &self.sig.arglist {
+            if narrow_mask(&arg.typename).is_some() {
+                self.ins(format!("local.get {}", ident(&arg.name)));
+                self.mask(&arg.typename);
+                self.set(&arg.name);
+            }
+        }
+    }
+
+    fn epilogue(&mut self) {
+        if self.frame_size > 0 {
+            self.ins("local.get $.fp");
+            self.ins(format!("i32.const {}", self.frame_size));
+            self.ins("i32.add");
+            self.ins("global.set $.sp");
+        }
+
+        self.ins("return");
+    }
+
+    fn int_arith(&mut self, stmt: &Stmt, l: &Operand, r: &Operand, t: &Type) {
+        let vt = value_type(t).unwrap_or("i64");
+        let signed = match stmt {
+            &Stmt::SDivInst (..) | &Stmt::SRemInst (..) => true,
+            _ => false,
+        };
+
+        self.push(l, t);
+
+        if signed {
+            self.sign_extend(t);
+        }
+        else if let &Stmt::AShrInst (..) = stmt {
+            self.sign_extend(t);
+        }
+
+        self.push(r, t);
+
+        if signed {
+            self.sign_extend(t);
+        }
+
+        let op = match stmt {
+            &Stmt::AddInst (..) => "add",
+            &Stmt::SubInst (..) => "sub",
+            &Stmt::MulInst (..) => "mul",
+            &Stmt::SDivInst (..) => "div_s",
+            &Stmt::UDivInst (..) => "div_u",
+            &Stmt::SRemInst (..) => "rem_s",
+            &Stmt::URemInst (..) => "rem_u",
+            &Stmt::AndInst (..) => "and",
+            &Stmt::OrInst (..) => "or",
+            &Stmt::XorInst (..) => "xor",
+            &Stmt::ShlInst (..) => "shl",
+            &Stmt::LShrInst (..) => "shr_u",
+            _ => "shr_s",
+        };
+
+        self.ins(format!("{}.{}", vt, op));
+        self.mask(t);
+    }
+
+    fn icmp(&mut self, pred: ICmpPred, l: &Operand, r: &Operand, t: &Type) {
+        let vt = value_type(t).unwrap_or("i64");
+        let (op, signed) = match pred {
+            ICmpPred::Eq => ("eq", false),
+            ICmpPred::Ne => ("ne", false),
+            ICmpPred::Slt => ("lt_s", true),
+            ICmpPred::Sle => ("le_s", true),
+            ICmpPred::Sgt => ("gt_s", true),
+            ICmpPred::Sge => ("ge_s", true),
+            ICmpPred::Ult => ("lt_u", false),
+            ICmpPred::Ule => ("le_u", false),
+            ICmpPred::Ugt => ("gt_u", false),
+            ICmpPred::Uge => ("ge_u", false),
+        };
+
+        // Narrow integers order correctly as unsigned in the form they are
+        // kept in, but must be sign-extended to compare as signed.
+        self.push(l, t);
+
+        if signed {
+            self.sign_extend(t);
+        }
+
+        self.push(r, t);
+
+        if signed {
+            self.sign_extend(t);
+        }
+
+        self.ins(format!("{}.{}", vt, op));
+    }
+
+    fn fcmp(&mut self, pred: FCmpPred, l: &Operand, r: &Operand, t: &Type) {
+        let vt = value_type(t).unwrap_or("f64");
+
+        // Every comparison but `ne` is false for NaN, so each unordered
+        // predicate is the negation of the opposite ordered one.
+        let (op, negate) = match pred {
+            FCmpPred::Oeq => ("eq", false),
+            FCmpPred::Une => ("ne", false),
+            FCmpPred::Olt => ("lt", false),
+            FCmpPred::Ole => ("le", false),
+            FCmpPred::Ogt => ("gt", false),
+            FCmpPred::Oge => ("ge", false),
+            FCmpPred::Uge => ("lt", true),
+            FCmpPred::Ugt => ("le", true),
+            FCmpPred::Ule => ("gt", true),
+            FCmpPred::Ult => ("ge", true),
+            FCmpPred::Ord | FCmpPred::Uno => {
+                let (op, join) = match pred {
+                    FCmpPred::Ord => ("eq", "and"),
+                    _ => ("ne", "or"),
+                };
+
+                self.push(l, t);
+                self.push(l, t);
+                self.ins(format!("{}.{}", vt, op));
+                self.push(r, t);
+                self.push(r, t);
+                self.ins(format!("{}.{}", vt, op));
+                self.ins(format!("i32.{}", join));
+                return;
+            }
+            FCmpPred::One | FCmpPred::Ueq => {
+                self.push(l, t);
+                self.push(r, t);
+                self.ins(format!("{}.lt", vt));
+                self.push(l, t);
+                self.push(r, t);
+                self.ins(format!("{}.gt", vt));
+                self.ins("i32.or");
+
+                if pred == FCmpPred::Ueq {
+                    self.ins("i32.eqz");
+                }
+
+                return;
+            }
+        };
+
+        self.push(l, t);
+        self.push(r, t);
+        self.ins(format!("{}.{}", vt, op));
+
+        if negate {
+            self.ins("i32.eqz");
+        }
+    }
+
+    fn cast(&mut self, op: CastOp, src: &Variable, dest: &VarTypePair) {
+        let from = self.var_type(src);
+        let to = &dest.typename;
+        let from_vt = value_type(from).unwrap_or("i64");
+        let to_vt = value_type(to).unwrap_or("i64");
+
+        self.push_var(src);
+
+        match op {
+            CastOp::Trunc | CastOp::PtrToInt |
+            CastOp::ZExt | CastOp::IntToPtr => self.convert_int(from, to),
+            CastOp::SExt => {
+                self.sign_extend(from);
+
+                if from_vt == "i32" && to_vt == "i64" {
+                    self.ins("i64.extend_i32_s");
+                }
+                else {
+                    self.mask(to);
+                }
+            }
+            CastOp::FPToSI | CastOp::FPToUI => {
+                let sign = match op {
+                    CastOp::FPToSI => "s",
+                    _ => "u",
+                };
+
+                // Conversions saturate rather than trap, and go through i64
+                // as the interpreter's do.
+                self.ins(format!("i64.trunc_sat_{}_{}", from_vt, sign));
+                self.convert_int(&Type::I64, to);
+            }
+            CastOp::SIToFP => {
+                self.sign_extend(from);
+                self.ins(format!("{}.convert_{}_s", to_vt, from_vt));
+            }
+            CastOp::UIToFP => {
+                self.ins(format!("{}.convert_{}_u", to_vt, from_vt));
+            }
+            CastOp::FPExt => self.ins("f64.promote_f32"),
+            CastOp::FPTrunc => self.ins("f32.demote_f64"),
+            CastOp::Bitcast => {
+                if from_vt != to_vt {
+                    self.ins(format!("{}.reinterpret_{}", to_vt, from_vt));
+                }
+            }
+        }
+
+        self.set(&dest.name);
+    }
+
+    fn gep(
+        &mut self, dest: &VarTypePair, base: &Variable, indices: &Vec<Operand>,
+        tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        let mut cur = match self.var_type(base) {
+            &Type::Ptr (ref pointee) => &**pointee,
+            _ => return Ok (()),
+        };
+
+        self.push_var(base);
+
+        // The first index steps over whole pointees, and each one after
+        // that selects an element of the current aggregate.
+        for (i, index) in indices.iter().enumerate() {
+            let (size, next) = match cur {
+                _ if i == 0 => (self.size_of(cur, tl)?, cur),
+                &Type::Array (ref elem, _) => {
+                    (self.size_of(elem, tl)?, &**elem)
+                }
+                &Type::UserType (ref name) => {
+                    let field = match index {
+                        &Operand::OpInteger (field, _, _) => field as usize,
+                        _ => return Ok (()),
+                    };
+                    let offsets = field_offsets(name, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+
+                    if offsets[field] != 0 {
+                        self.ins(format!("i64.const {}", offsets[field]));
+                        self.ins("i64.add");
+                    }
+
+                    cur = &self.structs[name][field];
+                    continue;
+                }
+                _ => return Ok (()),
+            };
+
+            match index {
+                &Operand::OpInteger (i, _, _) => {
+                    if i != 0 {
+                        let step = (i as i64).wrapping_mul(size as i64);
+                        self.ins(format!("i64.const {}", step));
+                        self.ins("i64.add");
+                    }
+                }
+                &Operand::OpVariable (ref var) => {
+                    let t = self.var_type(var);
+
+                    self.push_var(var);
+                    self.sign_extend(t);
+
+                    if value_type(t) == Some ("i32") {
+                        self.ins("i64.extend_i32_s");
+                    }
+
+                    self.ins(format!("i64.const {}", size));
+                    self.ins("i64.mul");
+                    self.ins("i64.add");
+                }
+                &Operand::OpFloat (..) => {}
+            }
+
+            cur = next;
+        }
+
+        self.set(&dest.name);
+
+        return Ok (());
+    }
+
+    fn stmt(&mut self, block: &BasicBlock, stmt: &Stmt, tl: &TokLoc)
+        -> Result<(), CodegenError>
+    {
+        match stmt {
+            &Stmt::AddInst (ref dest, ref l, ref r) |
+            &Stmt::SubInst (ref dest, ref l, ref r) |
+            &Stmt::MulInst (ref dest, ref l, ref r) |
+            &Stmt::SDivInst (ref dest, ref l, ref r) |
+            &Stmt::UDivInst (ref dest, ref l, ref r) |
+            &Stmt::SRemInst (ref dest, ref l, ref r) |
+            &Stmt::URemInst (ref dest, ref l, ref r) |
+            &Stmt::AndInst (ref dest, ref l, ref r) |
+            &Stmt::OrInst (ref dest, ref l, ref r) |
+            &Stmt::XorInst (ref dest, ref l, ref r) |
+            &Stmt::ShlInst (ref dest, ref l, ref r) |
+            &Stmt::LShrInst (ref dest, ref l, ref r) |
+            &Stmt::AShrInst (ref dest, ref l, ref r) => {
+                let t = &dest.typename;
+
+                if is_float_type(t) {
+                    let op = match stmt {
+                        &Stmt::AddInst (..) => "add",
+                        &Stmt::SubInst (..) => "sub",
+                        _ => "mul",
+                    };
+
+                    self.push(l, t);
+                    self.push(r, t);
+                    self.ins(format!(
+                        "{}.{}", value_type(t).unwrap_or("f64"), op
+                    ));
+                }
+                else {
+                    self.int_arith(stmt, l, r, t);
+                }
+
+                self.set(&dest.name);
+            }
+            &Stmt::NegInst (ref dest, ref src) => {
+                let t = &dest.typename;
+                let vt = value_type(t).unwrap_or("i64");
+
+                if is_float_type(t) {
+                    self.push(src, t);
+                    self.ins(format!("{}.neg", vt));
+                }
+                else {
+                    self.ins(format!("{}.const 0", vt));
+                    self.push(src, t);
+                    self.ins(format!("{}.sub", vt));
+                    self.mask(t);
+                }
+
+                self.set(&dest.name);
+            }
+            &Stmt::NotInst (ref dest, ref src) => {
+                let t = &dest.typename;
+                let vt = value_type(t).unwrap_or("i64");
+
+                self.push(src, t);
+                self.ins(format!("{}.const -1", vt));
+                self.ins(format!("{}.xor", vt));
+                self.mask(t);
+                self.set(&dest.name);
+            }
+            &Stmt::ICmpInst (ref dest, pred, ref l, ref r) => {
+                let t = cmp_type(&self.types, l, r, &Type::I64);
+
+                self.icmp(pred, l, r, t);
+                self.set(&dest.name);
+            }
+            &Stmt::FCmpInst (ref dest, pred, ref l, ref r) => {
+                let t = cmp_type(&self.types, l, r, &Type::F64);
+
+                self.fcmp(pred, l, r, t);
+                self.set(&dest.name);
+            }
+            &Stmt::AllocaInst (ref dest) => {
+                let addr = self.slot(&dest.name);
+
+                self.push_base(addr);
+                self.ins(format!("i32.const {}", addr.offset));
+                self.ins("i32.add");
+                self.ins("i64.extend_i32_u");
+                self.set(&dest.name);
+            }
+            &Stmt::LoadInst (ref dest, ref ptr) => {
+                let addr = Addr {base: Base::Ptr (&ptr.name), offset: 0};
+
+                self.load_mem(&dest.typename, addr, &dest.name, tl)?;
+            }
+            &Stmt::StoreInst (ref value, ref ptr) => {
+                let pointee = match self.var_type(ptr) {
+                    &Type::Ptr (ref pointee) => &**pointee,
+                    _ => return Ok (()),
+                };
+                let addr = Addr {base: Base::Ptr (&ptr.name), offset: 0};
+
+                self.store_mem(pointee, value, addr, tl)?;
+            }
+            &Stmt::GepInst (ref dest, ref base, ref indices) => {
+                self.gep(dest, base, indices, tl)?;
+            }
+            &Stmt::ExtractValueInst (ref dest, ref agg, ref indices) => {
+                let agg_type = self.var_type(agg);
+                let (offset, elem) =
+                    element_offset(agg_type, indices, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+                let addr = self.slot(&agg.name).offset(offset);
+
+                self.load_mem(elem, addr, &dest.name, tl)?;
+            }
+            &Stmt::InsertValueInst (
+                ref dest, ref agg, ref value, ref indices
+            ) => {
+                let agg_type = self.var_type(agg);
+                let (offset, elem) =
+                    element_offset(agg_type, indices, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+                let size = self.size_of(agg_type, tl)?;
+                let dst = self.slot(&dest.name);
+                let src = self.slot(&agg.name);
+
+                if dst.offset != src.offset {
+                    self.copy_mem(dst, src, size);
+                }
+
+                self.store_mem(elem, value, dst.offset(offset), tl)?;
+            }
+            &Stmt::CastInst (ref dest, op, ref src) => self.cast(op, src, dest),
+            &Stmt::AddrOfInst (ref dest, ref global) => {
+                let addr = self.globals.get(&global.name).cloned()
+                    .unwrap_or(0);
+
+                self.ins(format!("i64.const {}", addr));
+                self.set(&dest.name);
+            }
+            &Stmt::LetInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                if value_type(t).is_some() {
+                    self.push(src, t);
+                    self.set(&dest.name);
+                }
+                else if let Some (var) = src.var() {
+                    let size = self.size_of(t, tl)?;
+                    let dst = self.slot(&dest.name);
+                    let from = self.slot(&var.name);
+
+                    self.copy_mem(dst, from, size);
+                }
+            }
+            &Stmt::RetInst (ref value) => {
+                if let &Some (ref val) = value {
+                    let t = &self.sig.typename;
+
+                    if value_type(t).is_none() {
+                        return Err (CodegenError::Unsupported (
+                            "returning an aggregate".to_owned(), tl.clone()
+                        ));
+                    }
+
+                    self.push(val, t);
+                }
+
+                self.epilogue();
+            }
+            &Stmt::CallInst (ref dest, ref callee, ref args) => {
+                let variadic = match self.sigs.get::<str>(&callee.name) {
+                    Some (sig) => sig.variadic,
+                    None => false,
+                };
+
+                if variadic {
+                    return Err (CodegenError::Unsupported (
+                        "a call to a variadic function".to_owned(), tl.clone()
+                    ));
+                }
+
+                let types =
+                    arg_types(&self.types, self.sigs, &callee.name, args);
+
+                for (arg, t) in args.iter().zip(types) {
+                    if value_type(t).is_none() {
+                        return Err (CodegenError::Unsupported (
+                            "passing an aggregate by value".to_owned(),
+                            tl.clone()
+                        ));
+                    }
+
+                    self.push(arg, t);
+                }
+
+                self.ins(format!("call {}", ident(&callee.name)));
+
+                match value_type(&dest.typename) {
+                    Some (_) => self.set(&dest.name),
+                    None if is_void(&dest.typename) => {}
+                    None => {
+                        return Err (CodegenError::Unsupported (
+                            "returning an aggregate".to_owned(), tl.clone()
+                        ));
+                    }
+                }
+            }
+            &Stmt::BrInst (ref target) => self.goto(block, &target.name)?,
+            &Stmt::CondBrInst (ref cond, ref on_true, ref on_false) => {
+                self.push(cond, &Type::I1);
+                self.open("if");
+                self.goto(block, &on_true.name)?;
+                self.depth -= 1;
+                self.ins("else");
+                self.depth += 1;
+                self.goto(block, &on_false.name)?;
+                self.end();
+            }
+            &Stmt::SwitchInst (ref val, ref default, ref cases) => {
+                let t = switch_type(&self.types, val);
+                let vt = value_type(t).unwrap_or("i64");
+
+                for &(case, ref target) in cases {
+                    self.push(val, t);
+                    self.int_const(wrap_int(case as i64, t), t);
+                    self.ins(format!("{}.eq", vt));
+                    self.open("if");
+                    self.goto(block, &target.name)?;
+                    self.end();
+                }
+
+                self.goto(block, &default.name)?;
+            }
+            // Phis are given their values on the way into their block.
+            &Stmt::PhiInst (..) => {}
+        }
+
+        return Ok (());
+    }
+
+    /// The body of the function. Wasm only has structured control flow, so
+    /// the blocks are laid out in a loop that a `br_table` at its head
+    /// dispatches on `$.pc`, the index of the block to run next. Each block
+    /// is placed just after the end of a `block` of its own, which a branch
+    /// to that label lands on.
+    fn body(&mut self) -> Result<(), CodegenError> {
+        let blocks = self.blocks;
+
+        self.prologue();
+        self.open("loop $.dispatch");
+
+        for block in blocks.iter().rev() {
+            let label = self.block_label(&block.label);
+            self.open(format!("block {}", label));
+        }
+
+        let labels: Vec<String> = blocks.iter()
+            .map(|b| self.block_label(&b.label))
+            .collect();
+
+        self.ins("local.get $.pc");
+        self.ins(format!("br_table {}", labels.join(" ")));
+
+        for block in blocks {
+            self.end();
+
+            let stmts = block.stmts.iter().zip(&block.locs);
+
+            for (stmt, tl) in stmts {
+                self.stmt(block, stmt, tl)?;
+            }
+        }
+
+        self.end();
+        self.ins("unreachable");
+
+        return Ok (());
+    }
+}
+
+fn emit_function(
+    sig: &FuncSig, blocks: &Vec<BasicBlock>, structs: &StructTable,
+    sigs: &SigTable, globals: &HashMap<String, u64>, out: &mut String
+) -> Result<(), CodegenError>
+{
+    let mut types = HashMap::new();
+    let mut locals = Vec::new();
+    let mut slots = HashMap::new();
+    let mut frame_size = 0;
+
+    for arg in &sig.arglist {
+        types.insert(arg.name.as_str(), &arg.typename);
+    }
+
+    for block in blocks {
+        for (stmt, tl) in block.stmts.iter().zip(&block.locs) {
+            let vtp = match stmt.def() {
+                Some (vtp) => vtp,
+                None => continue,
+            };
+
+            if types.insert(vtp.name.as_str(), &vtp.typename).is_some() {
+                continue;
+            }
+
+            // Allocas hand out storage of their pointee, and aggregates
+            // live in storage of their own.
+            let stored = match stmt {
+                &Stmt::AllocaInst (_) => match &vtp.typename {
+                    &Type::Ptr (ref pointee) => Some (&**pointee),
+                    _ => None,
+                },
+                _ if value_type(&vtp.typename).is_none()
+                    && !is_void(&vtp.typename) => Some (&vtp.typename),
+                _ => None,
+            };
+
+            if let Some (t) = stored {
+                let layout = layout_of(t, structs)
+                    .map_err(|err| CodegenError::BadType (err, tl.clone()))?;
+                let align = layout.align.max(1);
+                let offset = (frame_size + align - 1) / align * align;
+
+                slots.insert(vtp.name.as_str(), offset);
+                frame_size = offset + layout.size;
+            }
+
+            if let Some (vt) = value_type(&vtp.typename) {
+                locals.push((vtp.name.as_str(), vt));
+            }
+        }
+    }
+
+    out.push_str(&format!(
+        "\n  (func {} (export \"{}\"){}\n",
+        ident(&sig.name), escape_bytes(sig.name.as_bytes()),
+        signature(sig, true)?
+    ));
+
+    for (name, vt) in locals {
+        out.push_str(&format!("    (local {} {})\n", ident(name), vt));
+    }
+
+    out.push_str("    (local $.pc i32)\n");
+
+    if frame_size > 0 {
+        out.push_str("    (local $.fp i32)\n");
+    }
+
+    let mut emitter = Emitter {
+        sig: sig,
+        blocks: blocks,
+        structs: structs,
+        sigs: sigs,
+        globals: globals,
+        types: types,
+        slots: slots,
+        frame_size: (frame_size + 15) / 16 * 16,
+        out: String::new(),
+        depth: 2,
+    };
+
+    emitter.body()?;
+    out.push_str(&emitter.out);
+    out.push_str("  )\n");
+
+    return Ok (());
+}
+
+fn collect_decls<'a>(ast: &'a Node, decls: &mut Vec<&'a FuncSig>) {
+    match ast {
+        &Node::Module (ref items) => {
+            for item in items {
+                collect_decls(item, decls);
+            }
+        }
+        &Node::FuncDecl (ref sig) => decls.push(sig),
+        _ => {}
+    }
+}
+
+/// Lower a typechecked module to the WebAssembly text format. Declared
+/// functions are imported from the `env` module and every defined one is
+/// exported under its own name. Globals are laid out in linear memory, which
+/// is exported as `memory`, with the stack above them; wasm has no read-only
+/// memory, so constants are as writable as any other global.
+pub fn generate_wat(ast: &Node) -> Result<String, CodegenError> {
+    let mut structs = StructTable::new();
+    let mut funcs = Vec::new();
+    let mut globals = Vec::new();
+    let mut decls = Vec::new();
+    let mut sigs = SigTable::new();
+
+    collect_items(ast, &mut structs, &mut funcs, &mut globals);
+    collect_decls(ast, &mut decls);
+    collect_sigs(ast, &mut sigs);
+
+    let mut out = "(module\n".to_owned();
+    let mut imported = HashSet::new();
+
+    // A function may be declared more than once, and defined as well.
+    for sig in decls {
+        if funcs.iter().any(|&(def, _)| def.name == sig.name)
+            || !imported.insert(sig.name.as_str())
+        {
+            continue;
+        }
+
+        out.push_str(&format!(
+            "  (import \"env\" \"{}\" (func {}{}))\n",
+            escape_bytes(sig.name.as_bytes()), ident(&sig.name),
+            signature(sig, false)?
+        ));
+    }
+
+    let mut addrs = HashMap::new();
+    let mut data = String::new();
+    let mut top = DATA_START;
+
+    for global in globals {
+        let bad_type = |err| CodegenError::BadType (err, global.loc.clone());
+        let layout = layout_of(&global.typename, &structs).map_err(bad_type)?;
+        let align = layout.align.max(1);
+        let addr = (top + align - 1) / align * align;
+        let mut bytes = Vec::new();
+
+        for item in global_items(global, &structs).map_err(bad_type)? {
+            match item {
+                DataItem::Int (size, bits) => {
+                    for i in 0..size {
+                        bytes.push((bits >> (8 * i)) as u8);
+                    }
+                }
+                DataItem::Zero (size) => {
+                    let len = bytes.len() + size as usize;
+                    bytes.resize(len, 0);
+                }
+            }
+        }
+
+        // Memory starts out zeroed, so trailing zeros need not be written.
+        while bytes.last() == Some (&0) {
+            bytes.pop();
+        }
+
+        if !bytes.is_empty() {
+            data.push_str(&format!(
+                "  (data (i32.const {}) \"{}\")\n", addr, escape_bytes(&bytes)
+            ));
+        }
+
+        addrs.insert(global.name.clone(), addr);
+        top = addr + layout.size;
+    }
+
+    let pages = ((top + 15) / 16 * 16 + STACK_SIZE + PAGE_SIZE - 1)
+        / PAGE_SIZE;
+
+    out.push_str(&format!("  (memory (export \"memory\") {})\n", pages));
+    out.push_str(&format!(
+        "  (global $.sp (mut i32) (i32.const {}))\n", pages * PAGE_SIZE
+    ));
+    out.push_str(&data);
+
+    for (sig, blocks) in funcs {
+        emit_function(sig, blocks, &structs, &sigs, &addrs, &mut out)?;
+    }
+
+    out.push_str(")\n");
+
+    return Ok (out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wasmparser::Validator;
+
+    fn compile(source: &str) -> Result<String, CodegenError> {
+        let tokens = tokenize(source, FileId (0)).into_result().unwrap();
+        let ast = parse(&tokens).into_result().unwrap();
+
+        assert!(typecheck(&ast).is_ok());
+
+        return generate_wat(&ast);
+    }
+
+    /// Assemble `wat` to a binary module and validate it.
+    fn validate(wat: &str) {
+        let bytes = match wat::parse_str(wat) {
+            Ok (bytes) => bytes,
+            Err (err) => panic!("{}\n{}", wat, err),
+        };
+
+        if let Err (err) = Validator::new().validate_all(&bytes) {
+            panic!("{}\n{}", wat, err);
+        }
+    }
+
+    #[test]
+    fn output_is_a_valid_module() {
+        validate(&compile(include_str!("../testdata/codegen.ir")).unwrap());
+    }
+
+    #[test]
+    fn narrow_integers_are_masked() {
+        let wat = compile("\
+func @f:i8 (%a:i8, %b:i16) {
+    trunc %c:i8 %b
+    add %r:i8 %a %c
+    ret %r
+}
+").unwrap();
+
+        validate(&wat);
+        assert!(wat.contains("i32.const 255"), "{}", wat);
+    }
+
+    #[test]
+    fn variadic_calls_are_unsupported() {
+        match compile(include_str!("../testdata/printf.ir")) {
+            Err (CodegenError::Unsupported (..)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
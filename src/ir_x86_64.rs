@@ -0,0 +1,1306 @@
+use ir_codegen::*;
+use ir_lexer::*;
+use ir_parser::*;
+use ir_type_layout::*;
+use ir_typechecker::*;
+
+use std::fmt;
+
+/// Integer arguments, in the order the System V ABI assigns them.
+const INT_ARG_REGS: [&'static str; 6] = [
+    "rdi", "rsi", "rdx", "rcx", "r8", "r9",
+];
+
+/// Float arguments, in the order the System V ABI assigns them.
+const FLOAT_ARG_REGS: [&'static str; 8] = [
+    "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7",
+];
+
+/// Values are only kept in registers no argument is passed in, so that
+/// setting up a call never overwrites one. `rax`, `rcx`, `rdx` and `r11`,
+/// along with `xmm14` and `xmm15`, are left free as scratch.
+static REGISTERS: RegisterFile = RegisterFile {
+    int_regs: &["rbx", "r12", "r13", "r14", "r15", "r10"],
+    float_regs: &["xmm8", "xmm9", "xmm10", "xmm11", "xmm12", "xmm13"],
+    callee_saved: &["rbx", "r12", "r13", "r14", "r15"],
+};
+
+/// Scratch registers that break cycles in parallel moves. Nothing else is
+/// kept in them while a parallel move runs.
+fn cycle_temp(class: RegClass) -> Loc {
+    return match class {
+        RegClass::Int => Loc::Reg ("r11"),
+        RegClass::Float => Loc::Reg ("xmm14"),
+    };
+}
+
+/// The name of the low `bits` bits of the 64-bit register `reg`.
+fn sub_reg(reg: &str, bits: u64) -> String {
+    let legacy = match reg {
+        "rax" | "rbx" | "rcx" | "rdx" => Some (&reg[1..2]),
+        _ => None,
+    };
+
+    return match (legacy, bits) {
+        (Some (l), 32) => format!("e{}x", l),
+        (Some (l), 16) => format!("{}x", l),
+        (Some (l), _) => format!("{}l", l),
+        (None, _) if reg.starts_with("r") && reg.len() <= 3
+            && reg[1..].chars().all(|c| c.is_digit(10)) =>
+        {
+            match bits {
+                32 => format!("{}d", reg),
+                16 => format!("{}w", reg),
+                _ => format!("{}b", reg),
+            }
+        }
+        (None, 32) => format!("e{}", &reg[1..]),
+        (None, 16) => reg[1..].to_owned(),
+        (None, _) => format!("{}l", &reg[1..]),
+    };
+}
+
+fn fits_i32(val: i64) -> bool {
+    return val >= i32::min_value() as i64 && val <= i32::max_value() as i64;
+}
+
+/// The suffix of a scalar SSE instruction working on `t`.
+fn sse_suffix(t: &Type) -> &'static str {
+    return match t {
+        &Type::F32 => "ss",
+        _ => "sd",
+    };
+}
+
+/// A memory operand: a base register plus a displacement.
+#[derive(Clone, Copy)]
+struct Addr {
+    base: &'static str,
+    disp: i64,
+}
+
+impl Addr {
+    fn offset(&self, by: i64) -> Addr {
+        return Addr {base: self.base, disp: self.disp + by};
+    }
+}
+
+impl fmt::Display for Addr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.disp == 0 {
+            write!(f, "(%{})", self.base)
+        }
+        else {
+            write!(f, "{}(%{})", self.disp, self.base)
+        }
+    }
+}
+
+struct Emitter<'e> {
+    sig: &'e FuncSig,
+    blocks: &'e Vec<BasicBlock>,
+    frame: &'e FrameAlloc<'e>,
+    structs: &'e StructTable,
+    sigs: &'e SigTable<'e>,
+    /// Size of the area below the frame pointer where callee-saved
+    /// registers are kept, a multiple of 16.
+    save_size: u64,
+    out: String,
+    next_label: usize,
+    /// Blocks that carry the phi moves of an edge out of a block with more
+    /// than one successor: their label, their moves and where they go.
+    trampolines: Vec<(String, Vec<Move>, Vec<ConstMove<'e>>, String)>,
+}
+
+impl<'e> Emitter<'e> {
+    fn ins<S: AsRef<str>>(&mut self, text: S) {
+        self.out.push_str("    ");
+        self.out.push_str(text.as_ref());
+        self.out.push('\n');
+    }
+
+    fn label(&mut self, label: &str) {
+        self.out.push_str(label);
+        self.out.push_str(":\n");
+    }
+
+    fn block_label(&self, label: &str) -> String {
+        return format!(".L{}.{}", self.sig.name, label);
+    }
+
+    fn fresh_label(&mut self) -> String {
+        self.next_label += 1;
+
+        return format!(".L{}.{}", self.sig.name, self.next_label);
+    }
+
+    /// Where the stack slot at `offset` into the local area is.
+    fn slot(&self, offset: u64) -> Addr {
+        let base = (self.save_size + self.frame.local_size) as i64;
+
+        return Addr {base: "rbp", disp: offset as i64 - base};
+    }
+
+    fn operand(&self, loc: Loc) -> String {
+        return match loc {
+            Loc::Reg (reg) => format!("%{}", reg),
+            Loc::Stack (offset) => format!("{}", self.slot(offset)),
+        };
+    }
+
+    fn home(&self, name: &str) -> Option<Loc> {
+        return self.frame.homes.get(name).cloned();
+    }
+
+    fn var_type(&self, var: &Variable) -> &'e Type {
+        return self.frame.types[var.name.as_str()];
+    }
+
+    fn mov(&mut self, class: RegClass, dst: Loc, src: Loc) {
+        if dst == src {
+            return;
+        }
+
+        let (d, s) = (self.operand(dst), self.operand(src));
+
+        match (class, dst, src) {
+            (_, Loc::Stack (_), Loc::Stack (_)) => {
+                self.ins(format!("movq {}, %rax", s));
+                self.ins(format!("movq %rax, {}", d));
+            }
+            (RegClass::Int, _, _) => self.ins(format!("movq {}, {}", s, d)),
+            (RegClass::Float, Loc::Reg (_), Loc::Reg (_)) => {
+                self.ins(format!("movaps {}, {}", s, d));
+            }
+            (RegClass::Float, _, _) => self.ins(format!("movsd {}, {}", s, d)),
+        }
+    }
+
+    /// Store the scalar in `reg` to the home of `name`, if it has one.
+    fn put(&mut self, name: &str, reg: &'static str) {
+        let class = if reg.starts_with("xmm") {
+            RegClass::Float
+        }
+        else {
+            RegClass::Int
+        };
+
+        if let Some (home) = self.home(name) {
+            self.mov(class, home, Loc::Reg (reg));
+        }
+    }
+
+    fn mov_imm(&mut self, bits: u64, reg: &str) {
+        let val = bits as i64;
+
+        if fits_i32(val) {
+            self.ins(format!("movq ${}, %{}", val, reg));
+        }
+        else {
+            self.ins(format!("movabsq ${}, %{}", val, reg));
+        }
+    }
+
+    fn add_imm(&mut self, reg: &str, val: i64) {
+        if val == 0 {
+            return;
+        }
+
+        if fits_i32(val) {
+            self.ins(format!("addq ${}, %{}", val, reg));
+        }
+        else {
+            self.ins(format!("movabsq ${}, %rdx", val));
+            self.ins(format!("addq %rdx, %{}", reg));
+        }
+    }
+
+    /// Load an integer or pointer operand of type `t` into `reg`.
+    fn load_int(&mut self, op: &Operand, t: &Type, reg: &'static str) {
+        if let Some (var) = op.var() {
+            if let Some (home) = self.home(&var.name) {
+                self.mov(RegClass::Int, Loc::Reg (reg), home);
+            }
+        }
+        else if let Some (bits) = imm_bits(op, t) {
+            self.mov_imm(bits, reg);
+        }
+    }
+
+    /// Load a float operand of type `t` into the SSE register `reg`.
+    fn load_float(&mut self, op: &Operand, t: &Type, reg: &'static str) {
+        if let Some (var) = op.var() {
+            if let Some (home) = self.home(&var.name) {
+                self.mov(RegClass::Float, Loc::Reg (reg), home);
+            }
+        }
+        else if let Some (bits) = imm_bits(op, t) {
+            self.mov_imm(bits, "r11");
+            self.ins(format!("movq %r11, %{}", reg));
+        }
+    }
+
+    /// Bring the integer in `reg` back to canonical form for `t` after an
+    /// operation that may have disturbed the bits above its width.
+    fn normalize(&mut self, reg: &str, t: &Type) {
+        match t {
+            &Type::I1 => self.ins(format!("andq $1, %{}", reg)),
+            &Type::I8 => {
+                self.ins(format!("movsbq %{}, %{}", sub_reg(reg, 8), reg));
+            }
+            &Type::I16 => {
+                self.ins(format!("movswq %{}, %{}", sub_reg(reg, 16), reg));
+            }
+            &Type::I32 => {
+                self.ins(format!("movslq %{}, %{}", sub_reg(reg, 32), reg));
+            }
+            _ => {}
+        }
+    }
+
+    /// Turn the canonical integer in `reg` into its value read as unsigned.
+    fn zero_extend(&mut self, reg: &str, t: &Type) {
+        let (r8, r16) = (sub_reg(reg, 8), sub_reg(reg, 16));
+        let r32 = sub_reg(reg, 32);
+
+        match t {
+            &Type::I1 => self.ins(format!("andq $1, %{}", reg)),
+            &Type::I8 => self.ins(format!("movzbl %{}, %{}", r8, r32)),
+            &Type::I16 => self.ins(format!("movzwl %{}, %{}", r16, r32)),
+            &Type::I32 => self.ins(format!("movl %{}, %{}", r32, r32)),
+            _ => {}
+        }
+    }
+
+    /// Turn the canonical integer in `reg` into its value read as signed.
+    /// Only an `i1` is not already kept that way.
+    fn sign_extend(&mut self, reg: &str, t: &Type) {
+        if let &Type::I1 = t {
+            self.ins(format!("andq $1, %{}", reg));
+            self.ins(format!("negq %{}", reg));
+        }
+    }
+
+    fn load_ptr(&mut self, ptr: Option<&Variable>) {
+        if let Some (ptr) = ptr {
+            let t = self.var_type(ptr);
+            self.load_int(&Operand::OpVariable (ptr.clone()), t, "r11");
+        }
+    }
+
+    fn copy_mem(&mut self, dst: Addr, src: Addr, size: u64) {
+        let mut done = 0;
+
+        for &(width, reg, suffix) in &[
+            (8, "rax", "q"), (4, "eax", "l"), (2, "ax", "w"), (1, "al", "b")
+        ] {
+            while size - done >= width {
+                let at = done as i64;
+                self.ins(format!("mov{} {}, %{}", suffix, src.offset(at), reg));
+                self.ins(format!("mov{} %{}, {}", suffix, reg, dst.offset(at)));
+                done += width;
+            }
+        }
+    }
+
+    fn size_of(&self, t: &Type, tl: &TokLoc) -> Result<u64, CodegenError> {
+        return layout_of(t, self.structs)
+            .map(|layout| layout.size)
+            .map_err(|err| CodegenError::BadType (err, tl.clone()));
+    }
+
+    /// The slot holding the aggregate `name`.
+    fn aggregate_addr(&self, name: &str) -> Addr {
+        return match self.home(name) {
+            Some (Loc::Stack (offset)) => self.slot(offset),
+            _ => Addr {base: "rbp", disp: 0},
+        };
+    }
+
+    /// Load a value of type `t` from `addr` into the home of `dest`.
+    fn load_mem(&mut self, t: &Type, addr: Addr, dest: &str, tl: &TokLoc)
+        -> Result<(), CodegenError>
+    {
+        let load = match t {
+            &Type::I1 => "movzbq",
+            &Type::I8 => "movsbq",
+            &Type::I16 => "movswq",
+            &Type::I32 => "movslq",
+            &Type::I64 | &Type::Ptr (_) => "movq",
+            &Type::F32 | &Type::F64 => {
+                self.ins(format!("mov{} {}, %xmm14", sse_suffix(t), addr));
+                self.put(dest, "xmm14");
+                return Ok (());
+            }
+            _ => {
+                let size = self.size_of(t, tl)?;
+                let dst = self.aggregate_addr(dest);
+                self.copy_mem(dst, addr, size);
+                return Ok (());
+            }
+        };
+
+        self.ins(format!("{} {}, %rax", load, addr));
+        self.put(dest, "rax");
+
+        return Ok (());
+    }
+
+    /// Store `value`, of type `t`, to `addr`. When `ptr` is given, it is
+    /// loaded into r11 once the value is in hand, for `addr` to be based on.
+    fn store_mem(
+        &mut self, t: &Type, value: &Operand, ptr: Option<&Variable>,
+        addr: Addr, tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        match reg_class(t) {
+            Some (RegClass::Int) => {
+                self.load_int(value, t, "rax");
+                self.load_ptr(ptr);
+
+                let (suffix, reg) = match t {
+                    &Type::I1 | &Type::I8 => ("b", "al"),
+                    &Type::I16 => ("w", "ax"),
+                    &Type::I32 => ("l", "eax"),
+                    _ => ("q", "rax"),
+                };
+
+                self.ins(format!("mov{} %{}, {}", suffix, reg, addr));
+            }
+            Some (RegClass::Float) => {
+                self.load_float(value, t, "xmm14");
+                self.load_ptr(ptr);
+                self.ins(format!("mov{} %xmm14, {}", sse_suffix(t), addr));
+            }
+            None => {
+                let size = self.size_of(t, tl)?;
+                let src = match value.var() {
+                    Some (var) => self.aggregate_addr(&var.name),
+                    None => return Ok (()),
+                };
+
+                self.load_ptr(ptr);
+                self.copy_mem(addr, src, size);
+            }
+        }
+
+        return Ok (());
+    }
+
+    /// Where control should go to take the edge from `pred` to the block
+    /// labeled `target`: the block itself, or a trampoline that first sets
+    /// the block's phis.
+    fn edge(&mut self, pred: &BasicBlock, target: &str)
+        -> Result<String, CodegenError>
+    {
+        let succ = self.blocks.iter().find(|b| b.label == target);
+
+        return match succ {
+            Some (succ) if has_phis(succ) => {
+                let label = format!(
+                    ".L{}.{}.{}", self.sig.name, pred.label, target
+                );
+
+                if !self.trampolines.iter().any(|t| t.0 == label) {
+                    let (moves, consts) =
+                        phi_moves(self.frame, succ, &pred.label)?;
+                    let to = self.block_label(target);
+                    self.trampolines.push((label.clone(), moves, consts, to));
+                }
+
+                Ok (label)
+            }
+            _ => Ok (self.block_label(target)),
+        };
+    }
+
+    fn parallel_move(&mut self, moves: Vec<Move>, consts: Vec<ConstMove>) {
+        for mv in sequence_moves(moves, cycle_temp) {
+            self.mov(mv.class, mv.dst, mv.src);
+        }
+
+        for mv in consts {
+            let reg = match (mv.dst, cycle_temp(mv.class)) {
+                (Loc::Reg (reg), _) | (_, Loc::Reg (reg)) => reg,
+                _ => continue,
+            };
+
+            match mv.class {
+                RegClass::Int => self.load_int(mv.imm, mv.t, reg),
+                RegClass::Float => self.load_float(mv.imm, mv.t, reg),
+            }
+
+            self.mov(mv.class, mv.dst, Loc::Reg (reg));
+        }
+    }
+
+    fn prologue(&mut self, out_size: u64) -> Result<(), CodegenError> {
+        let frame_size = self.save_size + self.frame.local_size + out_size;
+
+        self.ins("pushq %rbp");
+        self.ins("movq %rsp, %rbp");
+
+        if frame_size > 0 {
+            self.ins(format!("subq ${}, %rsp", frame_size));
+        }
+
+        for (i, &reg) in self.frame.callee_saved.iter().enumerate() {
+            let at = -8 * (i as i64 + 1);
+            self.ins(format!("movq %{}, {}(%rbp)", reg, at));
+        }
+
+        let (mut ints, mut floats, mut stacked) = (0, 0, 0);
+
+        for arg in &self.sig.arglist {
+            let t = &arg.typename;
+
+            match reg_class(t) {
+                Some (RegClass::Int) if ints < INT_ARG_REGS.len() => {
+                    self.ins(format!("movq %{}, %rax", INT_ARG_REGS[ints]));
+                    self.normalize("rax", t);
+                    self.put(&arg.name, "rax");
+                    ints += 1;
+                }
+                Some (RegClass::Float) if floats < FLOAT_ARG_REGS.len() => {
+                    self.put(&arg.name, FLOAT_ARG_REGS[floats]);
+                    floats += 1;
+                }
+                Some (class) => {
+                    let at = 16 + 8 * stacked;
+
+                    if class == RegClass::Int {
+                        self.ins(format!("movq {}(%rbp), %rax", at));
+                        self.normalize("rax", t);
+                        self.put(&arg.name, "rax");
+                    }
+                    else {
+                        self.ins(format!("movsd {}(%rbp), %xmm14", at));
+                        self.put(&arg.name, "xmm14");
+                    }
+
+                    stacked += 1;
+                }
+                None => {
+                    return Err (CodegenError::Unsupported (
+                        "passing an aggregate by value".to_owned(),
+                        self.sig.loc.clone()
+                    ));
+                }
+            }
+        }
+
+        return Ok (());
+    }
+
+    fn epilogue(&mut self) {
+        for (i, &reg) in self.frame.callee_saved.iter().enumerate() {
+            let at = -8 * (i as i64 + 1);
+            self.ins(format!("movq {}(%rbp), %{}", at, reg));
+        }
+
+        self.ins("movq %rbp, %rsp");
+        self.ins("popq %rbp");
+        self.ins("ret");
+    }
+
+    fn call(
+        &mut self, block: usize, index: usize, stmt: &Stmt, dest: &VarTypePair,
+        callee: &Function, args: &Vec<Operand>, tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        let saved = self.frame.live_across_call(block, index, stmt);
+
+        for &reg in &saved {
+            let slot = Loc::Stack (self.frame.save_slots[reg]);
+            let class = if reg.starts_with("xmm") {
+                RegClass::Float
+            }
+            else {
+                RegClass::Int
+            };
+
+            self.mov(class, slot, Loc::Reg (reg));
+        }
+
+        let (mut ints, mut floats, mut stacked) = (0, 0, 0);
+        let types = arg_types(&self.frame.types, self.sigs, &callee.name, args);
+
+        for (arg, t) in args.iter().zip(types) {
+            if let Some (var) = arg.var() {
+                if self.home(&var.name).is_none() {
+                    continue;
+                }
+            }
+
+            match reg_class(t) {
+                Some (RegClass::Int) if ints < INT_ARG_REGS.len() => {
+                    self.load_int(arg, t, INT_ARG_REGS[ints]);
+                    ints += 1;
+                }
+                Some (RegClass::Float) if floats < FLOAT_ARG_REGS.len() => {
+                    self.load_float(arg, t, FLOAT_ARG_REGS[floats]);
+                    floats += 1;
+                }
+                Some (_) => {
+                    self.load_int(arg, t, "rax");
+                    self.ins(format!("movq %rax, {}(%rsp)", 8 * stacked));
+                    stacked += 1;
+                }
+                None => {
+                    return Err (CodegenError::Unsupported (
+                        "passing an aggregate by value".to_owned(), tl.clone()
+                    ));
+                }
+            }
+        }
+
+        // A variadic callee learns from al how many SSE registers hold
+        // arguments.
+        self.ins(format!("movl ${}, %eax", floats));
+        self.ins(format!("call {}", callee.name));
+
+        match reg_class(&dest.typename) {
+            Some (RegClass::Int) => {
+                self.normalize("rax", &dest.typename);
+                self.put(&dest.name, "rax");
+            }
+            Some (RegClass::Float) => self.put(&dest.name, "xmm0"),
+            None if is_void(&dest.typename) => {}
+            None => {
+                return Err (CodegenError::Unsupported (
+                    "returning an aggregate".to_owned(), tl.clone()
+                ));
+            }
+        }
+
+        for &reg in &saved {
+            let slot = Loc::Stack (self.frame.save_slots[reg]);
+            let class = if reg.starts_with("xmm") {
+                RegClass::Float
+            }
+            else {
+                RegClass::Int
+            };
+
+            self.mov(class, Loc::Reg (reg), slot);
+        }
+
+        return Ok (());
+    }
+
+    fn int_arith(&mut self, stmt: &Stmt, t: &Type) {
+        match stmt {
+            &Stmt::AddInst (..) => self.ins("addq %rcx, %rax"),
+            &Stmt::SubInst (..) => self.ins("subq %rcx, %rax"),
+            &Stmt::MulInst (..) => self.ins("imulq %rcx, %rax"),
+            &Stmt::SDivInst (..) | &Stmt::SRemInst (..) => {
+                self.sign_extend("rax", t);
+                self.sign_extend("rcx", t);
+                self.ins("cqto");
+                self.ins("idivq %rcx");
+
+                if let &Stmt::SRemInst (..) = stmt {
+                    self.ins("movq %rdx, %rax");
+                }
+            }
+            &Stmt::UDivInst (..) | &Stmt::URemInst (..) => {
+                self.zero_extend("rax", t);
+                self.zero_extend("rcx", t);
+                self.ins("xorl %edx, %edx");
+                self.ins("divq %rcx");
+
+                if let &Stmt::URemInst (..) = stmt {
+                    self.ins("movq %rdx, %rax");
+                }
+            }
+            &Stmt::AndInst (..) => self.ins("andq %rcx, %rax"),
+            &Stmt::OrInst (..) => self.ins("orq %rcx, %rax"),
+            &Stmt::XorInst (..) => self.ins("xorq %rcx, %rax"),
+            &Stmt::ShlInst (..) => self.ins("shlq %cl, %rax"),
+            &Stmt::LShrInst (..) => {
+                self.zero_extend("rax", t);
+                self.ins("shrq %cl, %rax");
+            }
+            &Stmt::AShrInst (..) => {
+                self.sign_extend("rax", t);
+                self.ins("sarq %cl, %rax");
+            }
+            _ => {}
+        }
+
+        self.normalize("rax", t);
+    }
+
+    fn fcmp(&mut self, pred: FCmpPred, t: &Type) {
+        let ucomi = format!("ucomi{}", sse_suffix(t));
+        let forward = format!("{} %xmm15, %xmm14", ucomi);
+        let swapped = format!("{} %xmm14, %xmm15", ucomi);
+
+        // The flags say how the second operand compares to the first, and
+        // an unordered result sets ZF, PF and CF all at once.
+        let (cmp, set, combine) = match pred {
+            FCmpPred::Ogt => (forward, "seta", None),
+            FCmpPred::Oge => (forward, "setae", None),
+            FCmpPred::Olt => (swapped, "seta", None),
+            FCmpPred::Ole => (swapped, "setae", None),
+            FCmpPred::Ult => (forward, "setb", None),
+            FCmpPred::Ule => (forward, "setbe", None),
+            FCmpPred::Ugt => (swapped, "setb", None),
+            FCmpPred::Uge => (swapped, "setbe", None),
+            FCmpPred::Oeq => (forward, "sete", Some (("setnp", "andb"))),
+            FCmpPred::One => (forward, "setne", Some (("setnp", "andb"))),
+            FCmpPred::Ueq => (forward, "sete", Some (("setp", "orb"))),
+            FCmpPred::Une => (forward, "setne", Some (("setp", "orb"))),
+            FCmpPred::Ord => (forward, "setnp", None),
+            FCmpPred::Uno => (forward, "setp", None),
+        };
+
+        self.ins(cmp);
+        self.ins(format!("{} %al", set));
+
+        if let Some ((parity, op)) = combine {
+            self.ins(format!("{} %cl", parity));
+            self.ins(format!("{} %cl, %al", op));
+        }
+
+        self.ins("movzbl %al, %eax");
+    }
+
+    fn cast(&mut self, op: CastOp, src: &Variable, dest: &VarTypePair) {
+        let from = self.var_type(src);
+        let to = &dest.typename;
+        let src_op = Operand::OpVariable (src.clone());
+
+        match op {
+            CastOp::Trunc | CastOp::PtrToInt => {
+                self.load_int(&src_op, from, "rax");
+                self.normalize("rax", to);
+                self.put(&dest.name, "rax");
+            }
+            CastOp::ZExt | CastOp::IntToPtr => {
+                self.load_int(&src_op, from, "rax");
+                self.zero_extend("rax", from);
+                self.put(&dest.name, "rax");
+            }
+            CastOp::SExt => {
+                self.load_int(&src_op, from, "rax");
+                self.sign_extend("rax", from);
+                self.put(&dest.name, "rax");
+            }
+            CastOp::FPToSI => {
+                self.load_float(&src_op, from, "xmm14");
+                self.ins(format!("cvtt{}2siq %xmm14, %rax", sse_suffix(from)));
+                self.normalize("rax", to);
+                self.put(&dest.name, "rax");
+            }
+            CastOp::FPToUI => {
+                self.load_float(&src_op, from, "xmm14");
+                self.fptoui(from, to);
+                self.normalize("rax", to);
+                self.put(&dest.name, "rax");
+            }
+            CastOp::SIToFP | CastOp::UIToFP => {
+                self.load_int(&src_op, from, "rax");
+
+                if op == CastOp::SIToFP {
+                    self.sign_extend("rax", from);
+                    self.ins(format!("cvtsi2{}q %rax, %xmm14", sse_suffix(to)));
+                }
+                else {
+                    self.zero_extend("rax", from);
+                    self.uitofp(from, to);
+                }
+
+                self.put(&dest.name, "xmm14");
+            }
+            CastOp::FPExt => {
+                self.load_float(&src_op, from, "xmm14");
+                self.ins("cvtss2sd %xmm14, %xmm14");
+                self.put(&dest.name, "xmm14");
+            }
+            CastOp::FPTrunc => {
+                self.load_float(&src_op, from, "xmm14");
+                self.ins("cvtsd2ss %xmm14, %xmm14");
+                self.put(&dest.name, "xmm14");
+            }
+            CastOp::Bitcast => {
+                match (reg_class(from), reg_class(to)) {
+                    (Some (RegClass::Float), Some (RegClass::Int)) => {
+                        self.load_float(&src_op, from, "xmm14");
+                        self.ins("movq %xmm14, %rax");
+                        self.normalize("rax", to);
+                        self.put(&dest.name, "rax");
+                    }
+                    (Some (RegClass::Int), Some (RegClass::Float)) => {
+                        self.load_int(&src_op, from, "rax");
+                        self.ins("movq %rax, %xmm14");
+                        self.put(&dest.name, "xmm14");
+                    }
+                    (Some (RegClass::Float), _) => {
+                        self.load_float(&src_op, from, "xmm14");
+                        self.put(&dest.name, "xmm14");
+                    }
+                    _ => {
+                        self.load_int(&src_op, from, "rax");
+                        self.put(&dest.name, "rax");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Convert the float in xmm14 to an unsigned integer in rax. Values of
+    /// 2^63 and up are out of reach of the signed conversion, so they are
+    /// brought down by 2^63 first and the top bit set again after.
+    fn fptoui(&mut self, from: &Type, to: &Type) {
+        let suffix = sse_suffix(from);
+
+        if let &Type::I64 = to {
+            let (big, done) = (self.fresh_label(), self.fresh_label());
+
+            match from {
+                &Type::F32 => self.ins("movl $0x5f000000, %r11d"),
+                _ => self.ins("movabsq $0x43e0000000000000, %r11"),
+            }
+
+            self.ins("movq %r11, %xmm15");
+            self.ins(format!("ucomi{} %xmm15, %xmm14", suffix));
+            self.ins(format!("jae {}", big));
+            self.ins(format!("cvtt{}2siq %xmm14, %rax", suffix));
+            self.ins(format!("jmp {}", done));
+            self.label(&big);
+            self.ins(format!("sub{} %xmm15, %xmm14", suffix));
+            self.ins(format!("cvtt{}2siq %xmm14, %rax", suffix));
+            self.ins("btcq $63, %rax");
+            self.label(&done);
+        }
+        else {
+            self.ins(format!("cvtt{}2siq %xmm14, %rax", suffix));
+        }
+    }
+
+    /// Convert the unsigned integer in rax to a float in xmm14. A value with
+    /// the top bit set is halved for the signed conversion, keeping its low
+    /// bit so that it still rounds the same, and then doubled.
+    fn uitofp(&mut self, from: &Type, to: &Type) {
+        let suffix = sse_suffix(to);
+
+        if let &Type::I64 = from {
+            let (big, done) = (self.fresh_label(), self.fresh_label());
+
+            self.ins("testq %rax, %rax");
+            self.ins(format!("js {}", big));
+            self.ins(format!("cvtsi2{}q %rax, %xmm14", suffix));
+            self.ins(format!("jmp {}", done));
+            self.label(&big);
+            self.ins("movq %rax, %rcx");
+            self.ins("shrq %rcx");
+            self.ins("andl $1, %eax");
+            self.ins("orq %rax, %rcx");
+            self.ins(format!("cvtsi2{}q %rcx, %xmm14", suffix));
+            self.ins(format!("add{} %xmm14, %xmm14", suffix));
+            self.label(&done);
+        }
+        else {
+            self.ins(format!("cvtsi2{}q %rax, %xmm14", suffix));
+        }
+    }
+
+    fn gep(
+        &mut self, dest: &VarTypePair, base: &Variable, indices: &Vec<Operand>,
+        tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        let base_type = self.var_type(base);
+        let mut cur = match base_type {
+            &Type::Ptr (ref pointee) => &**pointee,
+            _ => return Ok (()),
+        };
+
+        self.load_int(&Operand::OpVariable (base.clone()), base_type, "rax");
+
+        // The first index steps over whole pointees, and each one after
+        // that selects an element of the current aggregate.
+        for (i, index) in indices.iter().enumerate() {
+            let (size, next) = match cur {
+                _ if i == 0 => (self.size_of(cur, tl)?, cur),
+                &Type::Array (ref elem, _) => {
+                    (self.size_of(elem, tl)?, &**elem)
+                }
+                &Type::UserType (ref name) => {
+                    let field = match index {
+                        &Operand::OpInteger (field, _, _) => field as usize,
+                        _ => return Ok (()),
+                    };
+                    let offsets = field_offsets(name, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+
+                    self.add_imm("rax", offsets[field] as i64);
+                    cur = &self.structs[name][field];
+                    continue;
+                }
+                _ => return Ok (()),
+            };
+
+            match index {
+                &Operand::OpInteger (i, _, _) => {
+                    self.add_imm("rax", (i as i64).wrapping_mul(size as i64));
+                }
+                &Operand::OpVariable (ref var) => {
+                    let t = self.var_type(var);
+                    self.load_int(index, t, "rcx");
+                    self.sign_extend("rcx", t);
+
+                    if fits_i32(size as i64) {
+                        self.ins(format!("imulq ${}, %rcx, %rcx", size));
+                    }
+                    else {
+                        self.ins(format!("movabsq ${}, %rdx", size));
+                        self.ins("imulq %rdx, %rcx");
+                    }
+
+                    self.ins("addq %rcx, %rax");
+                }
+                &Operand::OpFloat (..) => {}
+            }
+
+            cur = next;
+        }
+
+        self.put(&dest.name, "rax");
+
+        return Ok (());
+    }
+
+    fn stmt(
+        &mut self, block: &BasicBlock, block_index: usize, index: usize,
+        stmt: &Stmt, tl: &TokLoc
+    ) -> Result<(), CodegenError>
+    {
+        match stmt {
+            &Stmt::AddInst (ref dest, ref l, ref r) |
+            &Stmt::SubInst (ref dest, ref l, ref r) |
+            &Stmt::MulInst (ref dest, ref l, ref r) |
+            &Stmt::SDivInst (ref dest, ref l, ref r) |
+            &Stmt::UDivInst (ref dest, ref l, ref r) |
+            &Stmt::SRemInst (ref dest, ref l, ref r) |
+            &Stmt::URemInst (ref dest, ref l, ref r) |
+            &Stmt::AndInst (ref dest, ref l, ref r) |
+            &Stmt::OrInst (ref dest, ref l, ref r) |
+            &Stmt::XorInst (ref dest, ref l, ref r) |
+            &Stmt::ShlInst (ref dest, ref l, ref r) |
+            &Stmt::LShrInst (ref dest, ref l, ref r) |
+            &Stmt::AShrInst (ref dest, ref l, ref r) => {
+                let t = &dest.typename;
+
+                if is_float_type(t) {
+                    let op = match stmt {
+                        &Stmt::AddInst (..) => "add",
+                        &Stmt::SubInst (..) => "sub",
+                        _ => "mul",
+                    };
+
+                    self.load_float(l, t, "xmm14");
+                    self.load_float(r, t, "xmm15");
+                    self.ins(format!("{}{} %xmm15, %xmm14", op, sse_suffix(t)));
+                    self.put(&dest.name, "xmm14");
+                }
+                else {
+                    self.load_int(l, t, "rax");
+                    self.load_int(r, t, "rcx");
+                    self.int_arith(stmt, t);
+                    self.put(&dest.name, "rax");
+                }
+            }
+            &Stmt::NegInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                if is_float_type(t) {
+                    let (mask, xor) = match t {
+                        &Type::F32 => (0x80000000, "xorps"),
+                        _ => (0x8000000000000000, "xorpd"),
+                    };
+
+                    self.load_float(src, t, "xmm14");
+                    self.mov_imm(mask, "r11");
+                    self.ins("movq %r11, %xmm15");
+                    self.ins(format!("{} %xmm15, %xmm14", xor));
+                    self.put(&dest.name, "xmm14");
+                }
+                else {
+                    self.load_int(src, t, "rax");
+                    self.ins("negq %rax");
+                    self.normalize("rax", t);
+                    self.put(&dest.name, "rax");
+                }
+            }
+            &Stmt::NotInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                self.load_int(src, t, "rax");
+                self.ins("notq %rax");
+                self.normalize("rax", t);
+                self.put(&dest.name, "rax");
+            }
+            &Stmt::ICmpInst (ref dest, pred, ref l, ref r) => {
+                let t = cmp_type(&self.frame.types, l, r, &Type::I64);
+                let signed = match pred {
+                    ICmpPred::Slt | ICmpPred::Sle |
+                    ICmpPred::Sgt | ICmpPred::Sge => true,
+                    _ => false,
+                };
+                let set = match pred {
+                    ICmpPred::Eq => "sete",
+                    ICmpPred::Ne => "setne",
+                    ICmpPred::Slt => "setl",
+                    ICmpPred::Sle => "setle",
+                    ICmpPred::Sgt => "setg",
+                    ICmpPred::Sge => "setge",
+                    ICmpPred::Ult => "setb",
+                    ICmpPred::Ule => "setbe",
+                    ICmpPred::Ugt => "seta",
+                    ICmpPred::Uge => "setae",
+                };
+
+                self.load_int(l, t, "rax");
+                self.load_int(r, t, "rcx");
+
+                // Canonical integers already order correctly as unsigned,
+                // but a true i1 is -1 when read as signed.
+                if signed {
+                    self.sign_extend("rax", t);
+                    self.sign_extend("rcx", t);
+                }
+
+                self.ins("cmpq %rcx, %rax");
+                self.ins(format!("{} %al", set));
+                self.ins("movzbl %al, %eax");
+                self.put(&dest.name, "rax");
+            }
+            &Stmt::FCmpInst (ref dest, pred, ref l, ref r) => {
+                let t = cmp_type(&self.frame.types, l, r, &Type::F64);
+
+                self.load_float(l, t, "xmm14");
+                self.load_float(r, t, "xmm15");
+                self.fcmp(pred, t);
+                self.put(&dest.name, "rax");
+            }
+            &Stmt::AllocaInst (ref dest) => {
+                let addr = self.slot(self.frame.allocas[dest.name.as_str()]);
+
+                self.ins(format!("leaq {}, %rax", addr));
+                self.put(&dest.name, "rax");
+            }
+            &Stmt::LoadInst (ref dest, ref ptr) => {
+                self.load_ptr(Some (ptr));
+                self.load_mem(
+                    &dest.typename, Addr {base: "r11", disp: 0}, &dest.name, tl
+                )?;
+            }
+            &Stmt::StoreInst (ref value, ref ptr) => {
+                let ptr_type = self.var_type(ptr);
+                let pointee = match ptr_type {
+                    &Type::Ptr (ref pointee) => &**pointee,
+                    _ => return Ok (()),
+                };
+
+                self.store_mem(
+                    pointee, value, Some (ptr), Addr {base: "r11", disp: 0}, tl
+                )?;
+            }
+            &Stmt::GepInst (ref dest, ref base, ref indices) => {
+                self.gep(dest, base, indices, tl)?;
+            }
+            &Stmt::ExtractValueInst (ref dest, ref agg, ref indices) => {
+                let agg_type = self.var_type(agg);
+                let (offset, elem) =
+                    element_offset(agg_type, indices, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+                let addr = self.aggregate_addr(&agg.name).offset(offset as i64);
+
+                self.load_mem(elem, addr, &dest.name, tl)?;
+            }
+            &Stmt::InsertValueInst (
+                ref dest, ref agg, ref value, ref indices
+            ) => {
+                let agg_type = self.var_type(agg);
+                let (offset, elem) =
+                    element_offset(agg_type, indices, self.structs)
+                        .map_err(|err| {
+                            CodegenError::BadType (err, tl.clone())
+                        })?;
+                let size = self.size_of(agg_type, tl)?;
+                let dst = self.aggregate_addr(&dest.name);
+                let src = self.aggregate_addr(&agg.name);
+
+                if dst.disp != src.disp {
+                    self.copy_mem(dst, src, size);
+                }
+
+                self.store_mem(
+                    elem, value, None, dst.offset(offset as i64), tl
+                )?;
+            }
+            &Stmt::CastInst (ref dest, op, ref src) => self.cast(op, src, dest),
+            &Stmt::AddrOfInst (ref dest, ref global) => {
+                self.ins(format!("leaq {}(%rip), %rax", global.name));
+                self.put(&dest.name, "rax");
+            }
+            &Stmt::LetInst (ref dest, ref src) => {
+                let t = &dest.typename;
+
+                match reg_class(t) {
+                    Some (RegClass::Int) => {
+                        self.load_int(src, t, "rax");
+                        self.put(&dest.name, "rax");
+                    }
+                    Some (RegClass::Float) => {
+                        self.load_float(src, t, "xmm14");
+                        self.put(&dest.name, "xmm14");
+                    }
+                    None => {
+                        let size = self.size_of(t, tl)?;
+
+                        if let Some (var) = src.var() {
+                            let dst = self.aggregate_addr(&dest.name);
+                            let from = self.aggregate_addr(&var.name);
+                            self.copy_mem(dst, from, size);
+                        }
+                    }
+                }
+            }
+            &Stmt::RetInst (ref value) => {
+                if let &Some (ref val) = value {
+                    let t = &self.sig.typename;
+
+                    match reg_class(t) {
+                        Some (RegClass::Int) => self.load_int(val, t, "rax"),
+                        Some (RegClass::Float) => {
+                            self.load_float(val, t, "xmm0");
+                        }
+                        None => {
+                            return Err (CodegenError::Unsupported (
+                                "returning an aggregate".to_owned(), tl.clone()
+                            ));
+                        }
+                    }
+                }
+
+                self.epilogue();
+            }
+            &Stmt::CallInst (ref dest, ref callee, ref args) => {
+                self.call(block_index, index, stmt, dest, callee, args, tl)?;
+            }
+            &Stmt::BrInst (ref target) => {
+                let succ = self.blocks.iter().find(|b| b.label == target.name);
+
+                if let Some (succ) = succ {
+                    let (moves, consts) =
+                        phi_moves(self.frame, succ, &block.label)?;
+                    self.parallel_move(moves, consts);
+                }
+
+                let label = self.block_label(&target.name);
+                self.ins(format!("jmp {}", label));
+            }
+            &Stmt::CondBrInst (ref cond, ref on_true, ref on_false) => {
+                let on_true = self.edge(block, &on_true.name)?;
+                let on_false = self.edge(block, &on_false.name)?;
+
+                self.load_int(cond, &Type::I1, "rax");
+                self.ins("testq %rax, %rax");
+
+                self.ins(format!("jne {}", on_true));
+                self.ins(format!("jmp {}", on_false));
+            }
+            &Stmt::SwitchInst (ref val, ref default, ref cases) => {
+                let t = switch_type(&self.frame.types, val);
+
+                self.load_int(val, t, "rax");
+
+                for &(case, ref target) in cases {
+                    let target = self.edge(block, &target.name)?;
+                    let case = wrap_int(case as i64, t);
+
+                    if fits_i32(case) {
+                        self.ins(format!("cmpq ${}, %rax", case));
+                    }
+                    else {
+                        self.ins(format!("movabsq ${}, %rcx", case));
+                        self.ins("cmpq %rcx, %rax");
+                    }
+
+                    self.ins(format!("je {}", target));
+                }
+
+                let default = self.edge(block, &default.name)?;
+                self.ins(format!("jmp {}", default));
+            }
+            // Phis are given their values on the way into their block.
+            &Stmt::PhiInst (..) => {}
+        }
+
+        return Ok (());
+    }
+}
+
+/// Emits AT&T syntax for the GNU assembler, following the System V AMD64
+/// calling convention.
+pub struct X86_64;
+
+impl AsmTarget for X86_64 {
+    fn registers(&self) -> &'static RegisterFile {
+        return &REGISTERS;
+    }
+
+    fn emit_function(
+        &self, sig: &FuncSig, blocks: &Vec<BasicBlock>, frame: &FrameAlloc,
+        structs: &StructTable, sigs: &SigTable, out: &mut String
+    ) -> Result<(), CodegenError>
+    {
+        let save_size = (8 * frame.callee_saved.len() as u64 + 15) / 16 * 16;
+        let mut emitter = Emitter {
+            sig: sig,
+            blocks: blocks,
+            frame: frame,
+            structs: structs,
+            sigs: sigs,
+            save_size: save_size,
+            out: String::new(),
+            next_label: 0,
+            trampolines: Vec::new(),
+        };
+
+        emitter.label(&sig.name);
+        let out_size = stack_args_size(
+            blocks, frame, sigs, INT_ARG_REGS.len(), FLOAT_ARG_REGS.len()
+        );
+
+        emitter.prologue(out_size)?;
+
+        for (i, block) in blocks.iter().enumerate() {
+            let label = emitter.block_label(&block.label);
+            emitter.label(&label);
+
+            let stmts = block.stmts.iter().zip(&block.locs);
+
+            for (j, (stmt, tl)) in stmts.enumerate() {
+                emitter.stmt(block, i, j, stmt, tl)?;
+            }
+        }
+
+        let trampolines: Vec<_> = emitter.trampolines.drain(..).collect();
+
+        for (label, moves, consts, target) in trampolines {
+            emitter.label(&label);
+            emitter.parallel_move(moves, consts);
+            emitter.ins(format!("jmp {}", target));
+        }
+
+        out.push_str(&emitter.out);
+
+        return Ok (());
+    }
+}
+
+/// Lower a typechecked module to x86-64 assembly. The output is marked as
+/// not needing an executable stack, which ELF linkers otherwise assume.
+pub fn generate_x86_64(ast: &Node) -> Result<String, CodegenError> {
+    let mut asm = emit_module(&X86_64, ast)?;
+    asm.push_str("\n    .section .note.GNU-stack,\"\",@progbits\n");
+
+    return Ok (asm);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+    use std::process::{self, Command};
+
+    /// Each sample module with the assembly it is expected to lower to. A
+    /// golden file is regenerated with `compile -f <module> -t x86-64`.
+    const GOLDEN: [(&'static str, &'static str, &'static str); 2] = [
+        (
+            "codegen",
+            include_str!("../testdata/codegen.ir"),
+            include_str!("../testdata/x86_64/codegen.s"),
+        ),
+        (
+            "printf",
+            include_str!("../testdata/printf.ir"),
+            include_str!("../testdata/x86_64/printf.s"),
+        ),
+    ];
+
+    fn compile(source: &str) -> String {
+        let tokens = tokenize(source, FileId (0)).into_result().unwrap();
+        let ast = parse(&tokens).into_result().unwrap();
+
+        assert!(typecheck(&ast).is_ok());
+
+        return generate_x86_64(&ast).unwrap();
+    }
+
+    #[test]
+    fn output_matches_golden_files() {
+        for &(name, source, golden) in &GOLDEN {
+            let asm = compile(source);
+            let lines = asm.lines().zip(golden.lines());
+
+            for (i, (got, want)) in lines.enumerate() {
+                assert_eq!(got, want, "{}.s differs at line {}", name, i + 1);
+            }
+
+            assert_eq!(
+                asm.lines().count(), golden.lines().count(),
+                "{}.s differs in length", name
+            );
+        }
+    }
+
+    /// Only run where the target is x86-64, since `as` assembles for the
+    /// host. Fails if there is no `as` to run.
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn output_assembles() {
+        for &(name, source, _) in &GOLDEN {
+            let dir = env::temp_dir();
+            let asm = dir.join(format!("ir-{}-{}.s", process::id(), name));
+            let obj = asm.with_extension("o");
+
+            fs::write(&asm, compile(source)).unwrap();
+
+            let result = Command::new("as").arg("-o").arg(&obj).arg(&asm)
+                .output();
+
+            let _ = fs::remove_file(&asm);
+            let _ = fs::remove_file(&obj);
+
+            match result {
+                Ok (output) => {
+                    assert!(
+                        output.status.success(), "{}: {}",
+                        name, String::from_utf8_lossy(&output.stderr)
+                    );
+                }
+                Err (err) => {
+                    panic!("could not run as, which this test needs: {}", err)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn variadic_calls_count_vector_registers() {
+        let asm = compile(GOLDEN[1].1);
+
+        assert!(asm.contains("movl $2, %eax"), "{}", asm);
+    }
+}
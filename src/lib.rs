@@ -1,8 +1,40 @@
+// The crate is written in an explicit style throughout: a `return` at the
+// end of every function, `&Enum::Variant (ref x)` patterns, `field: field`
+// initializers and `&Vec` parameters. Keep clippy from flagging that style,
+// along with the arithmetic idioms it would rewrite.
+#![allow(clippy::needless_return)]
+#![allow(clippy::needless_borrowed_reference)]
+#![allow(clippy::match_ref_pats)]
+#![allow(clippy::redundant_field_names)]
+#![allow(clippy::redundant_static_lifetimes)]
+#![allow(clippy::match_like_matches_macro)]
+#![allow(clippy::ptr_arg)]
+#![allow(clippy::len_zero)]
+#![allow(clippy::is_digit_ascii_radix)]
+#![allow(clippy::legacy_numeric_constants)]
+#![allow(clippy::manual_div_ceil)]
+#![allow(clippy::manual_range_contains)]
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::new_without_default)]
+#![allow(clippy::result_large_err)]
+
 pub mod ir_lexer;
+pub mod ir_diagnostic;
 pub mod ir_parser;
+pub mod ir_type_layout;
 pub mod ir_typechecker;
+pub mod ir_interpreter;
 
 extern crate petgraph;
 extern crate rand;
+#[cfg(test)]
+extern crate wasmparser;
+#[cfg(test)]
+extern crate wat;
 
 pub mod ir_reg_allocer;
+pub mod ir_codegen;
+pub mod ir_x86_64;
+pub mod ir_aarch64;
+pub mod ir_riscv64;
+pub mod ir_wasm;
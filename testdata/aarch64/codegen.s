@@ -0,0 +1,1501 @@
+    .text
+
+    .globl fib
+fib:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #32
+    str x19, [sp]
+    str x20, [sp, #8]
+    str x21, [sp, #16]
+    str x22, [sp, #24]
+    mov x16, x0
+    sxtw x16, w16
+    mov x21, x16
+.Lfib.entry:
+    mov x16, #0
+    mov x20, x16
+    mov x16, #1
+    mov x19, x16
+    mov x22, x20
+    mov x20, x21
+    mov x21, x19
+    b .Lfib.head
+.Lfib.head:
+    mov x16, x20
+    mov x17, #0
+    cmp x16, x17
+    cset x16, gt
+    mov x19, x16
+    mov x16, x19
+    cbnz x16, .Lfib.body
+    b .Lfib.exit
+.Lfib.body:
+    mov x16, x22
+    mov x17, x21
+    add x16, x16, x17
+    mov x22, x16
+    mov x16, x20
+    mov x17, #1
+    sub x16, x16, x17
+    sxtw x16, w16
+    mov x19, x16
+    mov x20, x19
+    mov x14, x22
+    mov x22, x21
+    mov x21, x14
+    b .Lfib.head
+.Lfib.exit:
+    mov x0, x22
+    ldr x19, [sp]
+    ldr x20, [sp, #8]
+    ldr x21, [sp, #16]
+    ldr x22, [sp, #24]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl rot
+rot:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #48
+    str x19, [sp]
+    str x20, [sp, #8]
+    str x21, [sp, #16]
+    str x22, [sp, #24]
+    str x23, [sp, #32]
+    mov x16, x0
+    sxtw x16, w16
+    mov x22, x16
+    mov x16, x1
+    mov x21, x16
+    mov x16, x2
+    mov x20, x16
+    mov x16, x3
+    mov x19, x16
+.Lrot.entry:
+    mov x14, x20
+    mov x20, x22
+    mov x22, x14
+    b .Lrot.head
+.Lrot.head:
+    mov x16, x20
+    mov x17, #1
+    sub x16, x16, x17
+    sxtw x16, w16
+    mov x23, x16
+    mov x16, x23
+    mov x17, #0
+    cmp x16, x17
+    cset x16, gt
+    mov x20, x16
+    mov x16, x20
+    cbnz x16, .Lrot.head.head
+    b .Lrot.exit
+.Lrot.exit:
+    mov x16, x21
+    mov x17, #100
+    mul x16, x16, x17
+    mov x21, x16
+    mov x16, x22
+    mov x17, #10
+    mul x16, x16, x17
+    mov x20, x16
+    mov x16, x21
+    mov x17, x20
+    add x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, x19
+    add x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp]
+    ldr x20, [sp, #8]
+    ldr x21, [sp, #16]
+    ldr x22, [sp, #24]
+    ldr x23, [sp, #32]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+.Lrot.head.head:
+    mov x20, x23
+    mov x14, x21
+    mov x21, x22
+    mov x22, x19
+    mov x19, x14
+    b .Lrot.head
+
+    .globl many
+many:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #144
+    str x19, [sp, #16]
+    str x20, [sp, #24]
+    str x21, [sp, #32]
+    str x22, [sp, #40]
+    str x23, [sp, #48]
+    str x24, [sp, #56]
+    str x25, [sp, #64]
+    str x26, [sp, #72]
+    str d8, [sp, #80]
+    str d9, [sp, #88]
+    str d10, [sp, #96]
+    str d11, [sp, #104]
+    str d12, [sp, #112]
+    str d13, [sp, #120]
+    str d14, [sp, #128]
+    str d15, [sp, #136]
+    mov x16, x0
+    mov x20, x16
+    mov x16, x1
+    sxtw x16, w16
+    mov x26, x16
+    mov x16, x2
+    sxth x16, w16
+    mov x25, x16
+    mov x16, x3
+    sxtb x16, w16
+    mov x24, x16
+    mov x16, x4
+    mov x23, x16
+    mov x16, x5
+    mov x22, x16
+    mov x16, x6
+    mov x21, x16
+    mov x16, x7
+    sxtw x16, w16
+    mov x19, x16
+    fmov d8, d0
+    fmov d17, d1
+    fmov d16, d2
+    fmov d15, d3
+    fmov d14, d4
+    fmov d13, d5
+    fmov d12, d6
+    fmov d11, d7
+    ldr d30, [x29, #16]
+    fmov d10, d30
+    ldr d30, [x29, #24]
+    fmov d9, d30
+.Lmany.entry:
+    mov x16, x26
+    mov x26, x16
+    mov x16, x25
+    mov x25, x16
+    mov x16, x24
+    mov x24, x16
+    mov x16, x19
+    mov x19, x16
+    mov x16, x20
+    mov x17, #3
+    mul x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, x26
+    add x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, #5
+    mul x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, x25
+    add x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, #7
+    mul x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, x24
+    add x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, x23
+    add x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, x22
+    sub x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, x21
+    add x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, #11
+    mul x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, x19
+    add x16, x16, x17
+    mov x20, x16
+    fmov d30, d17
+    fcvt d30, s30
+    fmov d17, d30
+    fmov d30, d9
+    fcvt d30, s30
+    fmov d9, d30
+    fmov d30, d8
+    fmov d31, d17
+    fadd d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fmov d31, d16
+    fmul d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fmov d31, d15
+    fsub d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fmov d31, d14
+    fadd d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fmov d31, d13
+    fadd d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fmov d31, d12
+    fmul d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fmov d31, d11
+    fadd d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fmov d31, d10
+    fsub d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fmov d31, d9
+    fmul d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fcvtzs x16, d30
+    mov x19, x16
+    mov x16, x20
+    mov x17, x19
+    add x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp, #16]
+    ldr x20, [sp, #24]
+    ldr x21, [sp, #32]
+    ldr x22, [sp, #40]
+    ldr x23, [sp, #48]
+    ldr x24, [sp, #56]
+    ldr x25, [sp, #64]
+    ldr x26, [sp, #72]
+    ldr d8, [sp, #80]
+    ldr d9, [sp, #88]
+    ldr d10, [sp, #96]
+    ldr d11, [sp, #104]
+    ldr d12, [sp, #112]
+    ldr d13, [sp, #120]
+    ldr d14, [sp, #128]
+    ldr d15, [sp, #136]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl callmany
+callmany:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #96
+    str x19, [sp, #16]
+    str x20, [sp, #24]
+    str x21, [sp, #32]
+    str x22, [sp, #40]
+    str x23, [sp, #48]
+    str d8, [sp, #56]
+    str d9, [sp, #64]
+    str d10, [sp, #72]
+    str d11, [sp, #80]
+    mov x16, x0
+    mov x22, x16
+.Lcallmany.entry:
+    mov x16, #-7
+    mov x23, x16
+    mov x16, #300
+    mov x21, x16
+    mov x16, #-3
+    mov x20, x16
+    mov x16, #99
+    mov x19, x16
+    movz x15, #16376, lsl #48
+    fmov d30, x15
+    fmov d11, d30
+    movz x15, #16416, lsl #16
+    fmov d30, x15
+    fmov d10, d30
+    movz x15, #48896, lsl #16
+    fmov d30, x15
+    fmov d9, d30
+    mov x16, x22
+    scvtf d30, x16
+    fmov d8, d30
+    mov x0, x22
+    mov x1, x23
+    mov x2, x21
+    mov x3, x20
+    mov x4, x22
+    mov x5, x22
+    mov x6, x22
+    mov x7, x19
+    fmov d0, d11
+    fmov d1, d10
+    fmov d2, d8
+    fmov d3, d11
+    fmov d4, d8
+    fmov d5, d11
+    fmov d6, d8
+    fmov d7, d11
+    fmov d30, d8
+    str d30, [sp]
+    fmov d30, d9
+    str d30, [sp, #8]
+    bl many
+    mov x16, x0
+    mov x19, x16
+    mov x16, x19
+    mov x17, x22
+    add x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp, #16]
+    ldr x20, [sp, #24]
+    ldr x21, [sp, #32]
+    ldr x22, [sp, #40]
+    ldr x23, [sp, #48]
+    ldr d8, [sp, #56]
+    ldr d9, [sp, #64]
+    ldr d10, [sp, #72]
+    ldr d11, [sp, #80]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl pressure
+pressure:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #112
+    str x19, [sp, #16]
+    str x20, [sp, #24]
+    str x21, [sp, #32]
+    str x22, [sp, #40]
+    str x23, [sp, #48]
+    str x24, [sp, #56]
+    str x25, [sp, #64]
+    str x26, [sp, #72]
+    str x27, [sp, #80]
+    str x28, [sp, #88]
+    str d8, [sp, #96]
+    mov x16, x0
+    mov x20, x16
+.Lpressure.entry:
+    mov x16, x20
+    mov x17, #1
+    add x16, x16, x17
+    mov x9, x16
+    mov x16, x20
+    mov x17, #3
+    mul x16, x16, x17
+    mov x19, x16
+    mov x16, x20
+    mov x17, #5
+    sub x16, x16, x17
+    mov x28, x16
+    mov x16, x20
+    mov x17, #77
+    eor x16, x16, x17
+    mov x27, x16
+    mov x16, x20
+    mov x17, #1024
+    orr x16, x16, x17
+    mov x26, x16
+    mov x16, x20
+    mov x17, #255
+    and x16, x16, x17
+    mov x25, x16
+    mov x16, x20
+    mov x17, #3
+    lsl x16, x16, x17
+    mov x24, x16
+    mov x16, x20
+    mov x17, #1
+    asr x16, x16, x17
+    mov x23, x16
+    mov x16, x20
+    mov x17, #2
+    lsr x16, x16, x17
+    mov x22, x16
+    mov x16, x20
+    mov x17, x20
+    add x16, x16, x17
+    mov x21, x16
+    mov x16, x20
+    scvtf d30, x16
+    fmov d8, d30
+    fmov d30, d8
+    movz x15, #16352, lsl #48
+    fmov d31, x15
+    fmul d30, d30, d31
+    fmov d8, d30
+    mov x16, x9
+    sxtw x16, w16
+    mov x20, x16
+    str x9, [sp]
+    mov x0, x20
+    bl fib
+    mov x16, x0
+    mov x20, x16
+    ldr x9, [sp]
+    mov x16, x9
+    mov x17, x19
+    mul x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x28
+    add x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x27
+    mul x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x26
+    add x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x25
+    sub x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x24
+    add x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x23
+    mul x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x22
+    add x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x21
+    add x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x20
+    add x16, x16, x17
+    mov x20, x16
+    fmov d30, d8
+    fcvtzs x16, d30
+    mov x19, x16
+    mov x16, x20
+    mov x17, x19
+    add x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp, #16]
+    ldr x20, [sp, #24]
+    ldr x21, [sp, #32]
+    ldr x22, [sp, #40]
+    ldr x23, [sp, #48]
+    ldr x24, [sp, #56]
+    ldr x25, [sp, #64]
+    ldr x26, [sp, #72]
+    ldr x27, [sp, #80]
+    ldr x28, [sp, #88]
+    ldr d8, [sp, #96]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl narrow
+narrow:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #112
+    str x19, [sp, #32]
+    str x20, [sp, #40]
+    str x21, [sp, #48]
+    str x22, [sp, #56]
+    str x23, [sp, #64]
+    str x24, [sp, #72]
+    str x25, [sp, #80]
+    str x26, [sp, #88]
+    str x27, [sp, #96]
+    str x28, [sp, #104]
+    mov x16, x0
+    sxtb x16, w16
+    mov x20, x16
+    mov x16, x1
+    sxtb x16, w16
+    mov x19, x16
+.Lnarrow.entry:
+    mov x16, x20
+    mov x17, x19
+    add x16, x16, x17
+    sxtb x16, w16
+    mov x10, x16
+    mov x16, x20
+    mov x17, x19
+    mul x16, x16, x17
+    sxtb x16, w16
+    mov x11, x16
+    mov x16, x20
+    mov x17, x19
+    and x16, x16, #0xff
+    and x17, x17, #0xff
+    udiv x14, x16, x17
+    mov x16, x14
+    sxtb x16, w16
+    mov x9, x16
+    mov x16, x20
+    mov x17, x19
+    sdiv x14, x16, x17
+    mov x16, x14
+    sxtb x16, w16
+    mov x28, x16
+    mov x16, x20
+    mov x17, x19
+    and x16, x16, #0xff
+    and x17, x17, #0xff
+    udiv x14, x16, x17
+    msub x16, x14, x17, x16
+    sxtb x16, w16
+    mov x27, x16
+    mov x16, x20
+    mov x17, x19
+    sdiv x14, x16, x17
+    msub x16, x14, x17, x16
+    sxtb x16, w16
+    mov x26, x16
+    mov x16, x20
+    mov x17, #1
+    and x16, x16, #0xff
+    lsr x16, x16, x17
+    sxtb x16, w16
+    mov x25, x16
+    mov x16, x20
+    mov x17, #1
+    asr x16, x16, x17
+    sxtb x16, w16
+    mov x24, x16
+    mov x16, x20
+    mov x17, #3
+    lsl x16, x16, x17
+    sxtb x16, w16
+    mov x23, x16
+    mov x16, x20
+    mov x17, x19
+    cmp x16, x17
+    cset x16, lo
+    mov x22, x16
+    mov x16, x20
+    mov x17, x19
+    cmp x16, x17
+    cset x16, lt
+    mov x21, x16
+    mov x16, x20
+    mvn x16, x16
+    sxtb x16, w16
+    mov x20, x16
+    mov x16, x19
+    neg x16, x16
+    sxtb x16, w16
+    mov x19, x16
+    mov x16, x10
+    and x16, x16, #0xff
+    mov x10, x16
+    mov x16, x11
+    and x16, x16, #0xff
+    mov x11, x16
+    mov x16, x9
+    and x16, x16, #0xff
+    mov x9, x16
+    mov x16, x28
+    mov x28, x16
+    mov x16, x27
+    and x16, x16, #0xff
+    mov x27, x16
+    mov x16, x26
+    mov x26, x16
+    mov x16, x25
+    and x16, x16, #0xff
+    mov x25, x16
+    mov x16, x24
+    mov x24, x16
+    mov x16, x23
+    mov x23, x16
+    mov x16, x22
+    and x16, x16, #1
+    mov x22, x16
+    mov x16, x21
+    sbfx x16, x16, #0, #1
+    mov x21, x16
+    mov x16, x20
+    mov x20, x16
+    mov x16, x19
+    and x16, x16, #0xff
+    mov x19, x16
+    mov x16, x10
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x10, x16
+    mov x16, x10
+    mov x17, x11
+    add x16, x16, x17
+    mov x10, x16
+    mov x16, x10
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x10, x16
+    mov x16, x10
+    mov x17, x9
+    add x16, x16, x17
+    mov x9, x16
+    mov x16, x9
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x9, x16
+    mov x16, x9
+    mov x17, x28
+    add x16, x16, x17
+    mov x28, x16
+    mov x16, x28
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x28, x16
+    mov x16, x28
+    mov x17, x27
+    add x16, x16, x17
+    mov x27, x16
+    mov x16, x27
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x27, x16
+    mov x16, x27
+    mov x17, x26
+    add x16, x16, x17
+    mov x26, x16
+    mov x16, x26
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x26, x16
+    mov x16, x26
+    mov x17, x25
+    add x16, x16, x17
+    mov x25, x16
+    mov x16, x25
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x25, x16
+    mov x16, x25
+    mov x17, x24
+    add x16, x16, x17
+    mov x24, x16
+    mov x16, x24
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x24, x16
+    mov x16, x24
+    mov x17, x23
+    add x16, x16, x17
+    mov x23, x16
+    mov x16, x23
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x23, x16
+    mov x16, x23
+    mov x17, x22
+    add x16, x16, x17
+    mov x22, x16
+    mov x16, x22
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x22, x16
+    mov x16, x22
+    mov x17, x21
+    add x16, x16, x17
+    mov x21, x16
+    mov x16, x21
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x21, x16
+    mov x16, x21
+    mov x17, x20
+    add x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    movz x17, #16963, lsl #0
+    movk x17, #15, lsl #16
+    mul x16, x16, x17
+    mov x20, x16
+    mov x16, x20
+    mov x17, x19
+    add x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp, #32]
+    ldr x20, [sp, #40]
+    ldr x21, [sp, #48]
+    ldr x22, [sp, #56]
+    ldr x23, [sp, #64]
+    ldr x24, [sp, #72]
+    ldr x25, [sp, #80]
+    ldr x26, [sp, #88]
+    ldr x27, [sp, #96]
+    ldr x28, [sp, #104]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl floats
+floats:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #112
+    str x19, [sp]
+    str x20, [sp, #8]
+    str x21, [sp, #16]
+    str x22, [sp, #24]
+    str x23, [sp, #32]
+    str x24, [sp, #40]
+    str x25, [sp, #48]
+    str x26, [sp, #56]
+    str d8, [sp, #64]
+    str d9, [sp, #72]
+    str d10, [sp, #80]
+    str d11, [sp, #88]
+    str d12, [sp, #96]
+    str d13, [sp, #104]
+    fmov d13, d0
+    fmov d11, d1
+.Lfloats.entry:
+    fmov d30, d11
+    fcvt d30, s30
+    fmov d10, d30
+    fmov d30, d13
+    fcvt s30, d30
+    fmov d9, d30
+    fmov d30, d13
+    fneg d30, d30
+    fmov d8, d30
+    fmov d30, d11
+    fneg s30, s30
+    fmov d12, d30
+    fmov d30, d13
+    fmov d31, d10
+    fcmp d30, d31
+    cset w16, mi
+    mov x19, x16
+    fmov d30, d13
+    fmov d31, d10
+    fcmp d30, d31
+    cset w16, ge
+    mov x26, x16
+    fmov d30, d13
+    fmov d31, d10
+    fcmp d30, d31
+    cset w16, eq
+    cset w17, vs
+    orr w16, w16, w17
+    mov x25, x16
+    fmov d30, d13
+    fmov d31, d10
+    fcmp d30, d31
+    cset w16, mi
+    cset w17, gt
+    orr w16, w16, w17
+    mov x24, x16
+    fmov d30, d13
+    fmov d31, d10
+    fcmp d30, d31
+    cset w16, vs
+    mov x23, x16
+    fmov d30, d9
+    fmov d31, d11
+    fcmp s30, s31
+    cset w16, le
+    mov x22, x16
+    fmov d30, d13
+    fmov d31, d13
+    fcmp d30, d31
+    cset w16, eq
+    mov x21, x16
+    fmov d30, d12
+    fmov d31, d11
+    fcmp s30, s31
+    cset w16, ne
+    mov x20, x16
+    mov x16, x19
+    and x16, x16, #1
+    mov x19, x16
+    mov x16, x26
+    and x16, x16, #1
+    mov x26, x16
+    mov x16, x25
+    and x16, x16, #1
+    mov x25, x16
+    mov x16, x24
+    and x16, x16, #1
+    mov x24, x16
+    mov x16, x23
+    and x16, x16, #1
+    mov x23, x16
+    mov x16, x22
+    and x16, x16, #1
+    mov x22, x16
+    mov x16, x21
+    and x16, x16, #1
+    mov x21, x16
+    mov x16, x20
+    and x16, x16, #1
+    mov x20, x16
+    mov x16, x26
+    mov x17, #1
+    lsl x16, x16, x17
+    mov x26, x16
+    mov x16, x25
+    mov x17, #2
+    lsl x16, x16, x17
+    mov x25, x16
+    mov x16, x24
+    mov x17, #3
+    lsl x16, x16, x17
+    mov x24, x16
+    mov x16, x23
+    mov x17, #4
+    lsl x16, x16, x17
+    mov x23, x16
+    mov x16, x22
+    mov x17, #5
+    lsl x16, x16, x17
+    mov x22, x16
+    mov x16, x21
+    mov x17, #6
+    lsl x16, x16, x17
+    mov x21, x16
+    mov x16, x20
+    mov x17, #7
+    lsl x16, x16, x17
+    mov x20, x16
+    mov x16, x19
+    mov x17, x26
+    orr x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x25
+    orr x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x24
+    orr x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x23
+    orr x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x22
+    orr x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x21
+    orr x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x20
+    orr x16, x16, x17
+    mov x20, x16
+    fmov d30, d8
+    movz x15, #16384, lsl #32
+    movk x15, #16527, lsl #48
+    fmov d31, x15
+    fmul d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fcvtzs x16, d30
+    mov x19, x16
+    mov x16, x19
+    mov x17, #256
+    mul x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x20
+    add x16, x16, x17
+    mov x20, x16
+    fmov d30, d12
+    fmov w16, s30
+    sxtw x16, w16
+    mov x19, x16
+    mov x16, x19
+    mov w16, w16
+    mov x19, x16
+    mov x16, x20
+    mov x17, x19
+    eor x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp]
+    ldr x20, [sp, #8]
+    ldr x21, [sp, #16]
+    ldr x22, [sp, #24]
+    ldr x23, [sp, #32]
+    ldr x24, [sp, #40]
+    ldr x25, [sp, #48]
+    ldr x26, [sp, #56]
+    ldr d8, [sp, #64]
+    ldr d9, [sp, #72]
+    ldr d10, [sp, #80]
+    ldr d11, [sp, #88]
+    ldr d12, [sp, #96]
+    ldr d13, [sp, #104]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl unsigned
+unsigned:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #48
+    str x19, [sp]
+    str x20, [sp, #8]
+    str x21, [sp, #16]
+    str d8, [sp, #24]
+    str d9, [sp, #32]
+    mov x16, x0
+    mov x20, x16
+.Lunsigned.entry:
+    mov x16, x20
+    ucvtf d30, x16
+    fmov d9, d30
+    fmov d30, d9
+    fcvtzu x16, d30
+    mov x19, x16
+    mov x16, x20
+    ucvtf s30, x16
+    fmov d8, d30
+    fmov d30, d8
+    fcvtzu x16, s30
+    mov x21, x16
+    fmov d30, d9
+    movz x15, #15728, lsl #48
+    fmov d31, x15
+    fmul d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fcvtzu x16, d30
+    sxtw x16, w16
+    mov x20, x16
+    mov x16, x20
+    mov w16, w16
+    mov x20, x16
+    mov x16, x19
+    mov x17, x21
+    eor x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x20
+    add x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp]
+    ldr x20, [sp, #8]
+    ldr x21, [sp, #16]
+    ldr d8, [sp, #24]
+    ldr d9, [sp, #32]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl sw
+sw:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #32
+    str x19, [sp]
+    str x20, [sp, #8]
+    str x21, [sp, #16]
+    str x22, [sp, #24]
+    mov x16, x0
+    sxth x16, w16
+    mov x22, x16
+.Lsw.entry:
+    mov x16, #1
+    mov x21, x16
+    mov x16, #2
+    mov x20, x16
+    mov x16, #3
+    mov x19, x16
+    mov x16, x22
+    mov x17, #-5
+    cmp x16, x17
+    b.eq .Lsw.a
+    cmp x16, #7
+    b.eq .Lsw.b
+    cmp x16, #300
+    b.eq .Lsw.c
+    mov x17, #-32768
+    cmp x16, x17
+    b.eq .Lsw.a
+    b .Lsw.dflt
+.Lsw.a:
+    mov x19, x21
+    b .Lsw.join
+.Lsw.b:
+    mov x19, x20
+    mov x20, x21
+    b .Lsw.join
+.Lsw.c:
+    mov x16, x22
+    cmp x16, #1
+    b.eq .Lsw.b
+    b .Lsw.c.join
+.Lsw.dflt:
+    mov x14, x19
+    mov x19, x20
+    mov x20, x14
+    b .Lsw.join
+.Lsw.join:
+    mov x16, x19
+    mov x17, #10
+    mul x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x20
+    add x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp]
+    ldr x20, [sp, #8]
+    ldr x21, [sp, #16]
+    ldr x22, [sp, #24]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+.Lsw.c.join:
+    mov x20, x21
+    b .Lsw.join
+
+    .globl agg
+agg:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #272
+    str x19, [sp, #192]
+    str x20, [sp, #200]
+    str x21, [sp, #208]
+    str x22, [sp, #216]
+    str x23, [sp, #224]
+    str x24, [sp, #232]
+    str x25, [sp, #240]
+    str d8, [sp, #248]
+    str d9, [sp, #256]
+    mov x16, x0
+    mov x23, x16
+    mov x16, x1
+    sxtw x16, w16
+    mov x19, x16
+.Lagg.entry:
+    mov x16, sp
+    add x16, x16, #128
+    mov x22, x16
+    mov x16, x22
+    mov x20, x16
+    mov x16, #-2
+    mov x17, x20
+    strb w16, [x17]
+    mov x16, x22
+    add x16, x16, #2
+    mov x20, x16
+    mov x16, x20
+    mov x17, x19
+    mov x14, #2
+    madd x16, x17, x14, x16
+    mov x21, x16
+    mov x16, x23
+    sxth x16, w16
+    mov x20, x16
+    mov x16, x20
+    mov x17, x21
+    strh w16, [x17]
+    mov x16, x22
+    add x16, x16, #8
+    mov x20, x16
+    movz x15, #16388, lsl #48
+    fmov d30, x15
+    mov x17, x20
+    str d30, [x17]
+    mov x16, x22
+    add x16, x16, #16
+    mov x21, x16
+    mov x16, x21
+    mov x20, x16
+    mov x16, #11
+    mov x17, x20
+    str w16, [x17]
+    mov x16, x21
+    add x16, x16, #8
+    mov x20, x16
+    mov x16, x23
+    mov x17, x20
+    str x16, [x17]
+    mov x17, x22
+    ldr x16, [x17]
+    str x16, [sp]
+    ldr x16, [x17, #8]
+    str x16, [sp, #8]
+    ldr x16, [x17, #16]
+    str x16, [sp, #16]
+    ldr x16, [x17, #24]
+    str x16, [sp, #24]
+    ldr x16, [sp, #16]
+    str x16, [sp, #32]
+    ldr x16, [sp, #24]
+    str x16, [sp, #40]
+    ldr x16, [sp, #32]
+    str x16, [sp, #48]
+    ldr x16, [sp, #40]
+    str x16, [sp, #56]
+    mov x16, #99
+    str w16, [sp, #48]
+    ldr x16, [sp]
+    str x16, [sp, #64]
+    ldr x16, [sp, #8]
+    str x16, [sp, #72]
+    ldr x16, [sp, #16]
+    str x16, [sp, #80]
+    ldr x16, [sp, #24]
+    str x16, [sp, #88]
+    ldr x16, [sp, #48]
+    str x16, [sp, #80]
+    ldr x16, [sp, #56]
+    str x16, [sp, #88]
+    ldr x16, [sp, #64]
+    str x16, [sp, #96]
+    ldr x16, [sp, #72]
+    str x16, [sp, #104]
+    ldr x16, [sp, #80]
+    str x16, [sp, #112]
+    ldr x16, [sp, #88]
+    str x16, [sp, #120]
+    mov x16, #-9
+    strh w16, [sp, #102]
+    mov x16, sp
+    add x16, x16, #160
+    mov x20, x16
+    mov x17, x20
+    ldr x16, [sp, #96]
+    str x16, [x17]
+    ldr x16, [sp, #104]
+    str x16, [x17, #8]
+    ldr x16, [sp, #112]
+    str x16, [x17, #16]
+    ldr x16, [sp, #120]
+    str x16, [x17, #24]
+    mov x16, x20
+    add x16, x16, #16
+    mov x20, x16
+    mov x17, x20
+    ldrsw x16, [x17]
+    mov x22, x16
+    ldrsh x16, [sp, #100]
+    mov x23, x16
+    ldrsh x16, [sp, #102]
+    mov x25, x16
+    ldrsb x16, [sp, #96]
+    mov x21, x16
+    ldr d30, [sp, #104]
+    fmov d9, d30
+    adrp x16, table
+    add x16, x16, :lo12:table
+    mov x20, x16
+    mov x16, x20
+    mov x17, x19
+    mov x14, #2
+    madd x16, x17, x14, x16
+    mov x19, x16
+    mov x17, x19
+    ldrsh x16, [x17]
+    mov x24, x16
+    adrp x16, fl
+    add x16, x16, :lo12:fl
+    mov x19, x16
+    mov x16, x19
+    add x16, x16, #4
+    mov x19, x16
+    mov x17, x19
+    ldr s30, [x17]
+    fmov d8, d30
+    fmov d30, d8
+    fcvt d30, s30
+    fmov d8, d30
+    fmov d30, d9
+    fmov d31, d8
+    fadd d30, d30, d31
+    fmov d8, d30
+    fmov d30, d8
+    fcvtzs x16, d30
+    mov x20, x16
+    mov x16, x22
+    mov x22, x16
+    mov x16, x23
+    mov x23, x16
+    mov x16, x25
+    mov x25, x16
+    mov x16, x21
+    mov x21, x16
+    mov x16, x24
+    mov x19, x16
+    mov x16, x22
+    movz x17, #34464, lsl #0
+    movk x17, #1, lsl #16
+    mul x16, x16, x17
+    mov x22, x16
+    mov x16, x22
+    mov x17, x23
+    add x16, x16, x17
+    mov x22, x16
+    mov x16, x22
+    movz x17, #34464, lsl #0
+    movk x17, #1, lsl #16
+    mul x16, x16, x17
+    mov x22, x16
+    mov x16, x22
+    mov x17, x25
+    add x16, x16, x17
+    mov x22, x16
+    mov x16, x22
+    mov x17, #1000
+    mul x16, x16, x17
+    mov x22, x16
+    mov x16, x22
+    mov x17, x21
+    add x16, x16, x17
+    mov x21, x16
+    mov x16, x21
+    movz x17, #34464, lsl #0
+    movk x17, #1, lsl #16
+    mul x16, x16, x17
+    mov x21, x16
+    mov x16, x21
+    mov x17, x19
+    add x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x20
+    add x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp, #192]
+    ldr x20, [sp, #200]
+    ldr x21, [sp, #208]
+    ldr x22, [sp, #216]
+    ldr x23, [sp, #224]
+    ldr x24, [sp, #232]
+    ldr x25, [sp, #240]
+    ldr d8, [sp, #248]
+    ldr d9, [sp, #256]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl seven
+seven:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lseven.entry:
+    mov x0, #7
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl half
+half:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+.Lhalf.entry:
+    movz x15, #48896, lsl #16
+    fmov d0, x15
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .globl imms
+imms:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #80
+    str x19, [sp, #16]
+    str x20, [sp, #24]
+    str x21, [sp, #32]
+    str x22, [sp, #40]
+    str x23, [sp, #48]
+    str x24, [sp, #56]
+    str d8, [sp, #64]
+    mov x16, x0
+    mov x21, x16
+.Limms.entry:
+    bl seven
+    mov x16, x0
+    mov x24, x16
+    bl half
+    fmov d8, d0
+    fmov d30, d8
+    fcvtzs x16, s30
+    mov x23, x16
+    mov x0, x21
+    mov x1, #-7
+    mov x2, #300
+    mov x3, #-3
+    mov x4, #11
+    mov x5, x21
+    mov x6, #13
+    mov x7, #99
+    movz x15, #16376, lsl #48
+    fmov d0, x15
+    movz x15, #16416, lsl #16
+    fmov d1, x15
+    movz x15, #16336, lsl #48
+    fmov d2, x15
+    movz x15, #49136, lsl #48
+    fmov d3, x15
+    movz x15, #16392, lsl #48
+    fmov d4, x15
+    movz x15, #16400, lsl #48
+    fmov d5, x15
+    movz x15, #16404, lsl #48
+    fmov d6, x15
+    movz x15, #16408, lsl #48
+    fmov d7, x15
+    movz x15, #16414, lsl #48
+    fmov d30, x15
+    str d30, [sp]
+    movz x15, #48896, lsl #16
+    fmov d30, x15
+    str d30, [sp, #8]
+    bl many
+    mov x16, x0
+    mov x20, x16
+    mov x16, x21
+    mov x17, #0
+    cmp x16, x17
+    cset x16, gt
+    mov x19, x16
+    mov x16, x19
+    cbnz x16, .Limms.pos
+    b .Limms.below
+.Limms.pos:
+    mov x16, #1
+    cbnz x16, .Limms.pos.mid
+    b .Limms.other
+.Limms.below:
+    mov x16, #0
+    cbnz x16, .Limms.other
+    b .Limms.below.mid
+.Limms.other:
+    mov x21, #-9
+    movz x15, #16368, lsl #48
+    fmov d8, x15
+    b .Limms.mid
+.Limms.mid:
+    fmov d30, d8
+    fcvtzs x16, d30
+    mov x22, x16
+    mov x16, #2
+    cmp x16, #1
+    b.eq .Limms.dflt
+    cmp x16, #2
+    b.eq .Limms.two
+    b .Limms.dflt
+.Limms.two:
+    mov x16, x20
+    mov x17, x24
+    add x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x23
+    add x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x21
+    add x16, x16, x17
+    mov x19, x16
+    mov x16, x19
+    mov x17, x22
+    add x16, x16, x17
+    mov x19, x16
+    mov x0, x19
+    ldr x19, [sp, #16]
+    ldr x20, [sp, #24]
+    ldr x21, [sp, #32]
+    ldr x22, [sp, #40]
+    ldr x23, [sp, #48]
+    ldr x24, [sp, #56]
+    ldr d8, [sp, #64]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+.Limms.dflt:
+    mov x0, #-1
+    ldr x19, [sp, #16]
+    ldr x20, [sp, #24]
+    ldr x21, [sp, #32]
+    ldr x22, [sp, #40]
+    ldr x23, [sp, #48]
+    ldr x24, [sp, #56]
+    ldr d8, [sp, #64]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+.Limms.pos.mid:
+    mov x21, #5
+    movz x15, #16388, lsl #48
+    fmov d8, x15
+    b .Limms.mid
+.Limms.below.mid:
+    movz x15, #49168, lsl #48
+    fmov d8, x15
+    b .Limms.mid
+
+    .data
+    .p2align 1
+    .globl table
+table:
+    .short 3
+    .short 65532
+    .short 500
+    .short 32768
+    .short 7
+
+    .section .rodata
+    .p2align 2
+    .globl fl
+fl:
+    .long 1069547520
+    .long 3222274048
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,80 @@
+    .text
+
+    .globl main
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #32
+    str x19, [sp]
+    str x20, [sp, #8]
+    str d8, [sp, #16]
+.Lmain.entry:
+    adrp x16, fmt
+    add x16, x16, :lo12:fmt
+    mov x19, x16
+    mov x16, x19
+    mov x19, x16
+    adrp x16, hi
+    add x16, x16, :lo12:hi
+    mov x20, x16
+    mov x16, x20
+    mov x20, x16
+    movz x15, #16388, lsl #48
+    fmov d30, x15
+    fmov d8, d30
+    mov x0, x19
+    mov x1, #7
+    mov x2, #-9
+    movz x15, #34414, lsl #0
+    movk x15, #61467, lsl #16
+    movk x15, #8697, lsl #32
+    movk x15, #16393, lsl #48
+    fmov d0, x15
+    mov x3, x20
+    fmov d1, d8
+    bl printf
+    mov x16, x0
+    sxtw x16, w16
+    mov x0, #0
+    ldr x19, [sp]
+    ldr x20, [sp, #8]
+    ldr d8, [sp, #16]
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+    .section .rodata
+    .p2align 0
+    .globl fmt
+fmt:
+    .byte 37
+    .byte 100
+    .byte 32
+    .byte 37
+    .byte 108
+    .byte 100
+    .byte 32
+    .byte 37
+    .byte 46
+    .byte 50
+    .byte 102
+    .byte 32
+    .byte 37
+    .byte 115
+    .byte 32
+    .byte 37
+    .byte 46
+    .byte 49
+    .byte 102
+    .byte 10
+    .byte 0
+
+    .section .rodata
+    .p2align 0
+    .globl hi
+hi:
+    .byte 104
+    .byte 105
+    .byte 0
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,52 @@
+    .text
+
+    .globl addrof
+addrof:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -16
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    mv t0, a0
+    mv s2, t0
+.Laddrof.entry:
+    lla t0, counter
+    mv s1, t0
+    mv t0, s2
+    mv t1, s1
+    sd t0, 0(t1)
+    lla t0, name
+    mv s1, t0
+    mv t0, s1
+    addi t0, t0, 1
+    mv s1, t0
+    mv t1, s1
+    lb t0, 0(t1)
+    mv s1, t0
+    mv a0, s1
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .data
+    .p2align 3
+    .globl counter
+counter:
+    .quad 0
+
+    .section .rodata
+    .p2align 0
+    .globl name
+name:
+    .byte 97
+    .byte 98
+    .byte 99
+    .byte 0
+
+    .section .note.GNU-stack,"",@progbits
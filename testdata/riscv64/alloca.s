@@ -0,0 +1,35 @@
+    .text
+
+    .globl alloca
+alloca:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -48
+    sd s1, 32(sp)
+    sd s2, 40(sp)
+.Lalloca.entry:
+    mv t0, sp
+    mv s2, t0
+    mv t0, sp
+    addi t0, t0, 16
+    mv s1, t0
+    mv t0, s2
+    mv s2, t0
+    mv t0, s1
+    mv s1, t0
+    mv t0, s2
+    mv t1, s1
+    sub t0, t0, t1
+    mv s1, t0
+    mv a0, s1
+    ld s1, 32(sp)
+    ld s2, 40(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,25 @@
+    .text
+
+    .globl br
+br:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -16
+    sd s1, 0(sp)
+    mv t0, a0
+    sext.w t0, t0
+    mv s1, t0
+.Lbr.entry:
+    j .Lbr.next
+.Lbr.next:
+    mv a0, s1
+    ld s1, 0(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
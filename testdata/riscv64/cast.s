@@ -0,0 +1,66 @@
+    .text
+
+    .globl cast
+cast:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -48
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    fsd fs0, 16(sp)
+    fsd fs1, 24(sp)
+    fsd fs2, 32(sp)
+    mv t0, a0
+    mv s1, t0
+    fmv.d fs0, fa0
+.Lcast.entry:
+    mv t0, s1
+    slli t0, t0, 56
+    srai t0, t0, 56
+    mv s2, t0
+    mv t0, s2
+    andi t0, t0, 255
+    mv s1, t0
+    mv t0, s2
+    mv s2, t0
+    mv t0, s1
+    slli t0, t0, 32
+    srli t0, t0, 32
+    fcvt.s.lu ft10, t0
+    fmv.d fs1, ft10
+    fmv.d ft10, fs0
+    fcvt.s.d ft10, ft10
+    fmv.d fs0, ft10
+    fmv.d ft10, fs0
+    fcvt.lu.s t0, ft10, rtz
+    sext.w t0, t0
+    mv s1, t0
+    mv t0, s1
+    fmv.w.x ft10, t0
+    fmv.d fs0, ft10
+    mv t0, s2
+    fcvt.s.l ft10, t0
+    fmv.d fs2, ft10
+    fmv.d ft10, fs1
+    fmv.d ft11, fs0
+    fadd.s ft10, ft10, ft11
+    fmv.d fs0, ft10
+    fmv.d ft10, fs0
+    fmv.d ft11, fs2
+    fadd.s ft10, ft10, ft11
+    fmv.d fs0, ft10
+    fmv.d fa0, fs0
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    fld fs0, 16(sp)
+    fld fs1, 24(sp)
+    fld fs2, 32(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,50 @@
+    .text
+
+    .globl condbr
+condbr:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -16
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    mv t0, a0
+    sext.w t0, t0
+    mv s2, t0
+.Lcondbr.entry:
+    mv t0, s2
+    li t1, 0
+    slt t0, t1, t0
+    mv s1, t0
+    mv t0, s1
+    beqz t0, .Lcondbr.1
+    j .Lcondbr.pos
+.Lcondbr.1:
+    j .Lcondbr.other
+.Lcondbr.pos:
+    li t0, 1
+    beqz t0, .Lcondbr.2
+    j .Lcondbr.other
+.Lcondbr.2:
+    j .Lcondbr.done
+.Lcondbr.other:
+    li a0, 0
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+.Lcondbr.done:
+    mv a0, s2
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
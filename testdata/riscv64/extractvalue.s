@@ -0,0 +1,33 @@
+    .text
+
+    .globl extractvalue
+extractvalue:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -48
+    sd s1, 32(sp)
+    fsd fs0, 40(sp)
+    mv t0, a0
+    mv s1, t0
+.Lextractvalue.entry:
+    mv t1, s1
+    ld t0, 0(t1)
+    sd t0, 0(sp)
+    ld t0, 8(t1)
+    sd t0, 8(sp)
+    ld t0, 16(t1)
+    sd t0, 16(sp)
+    fld ft10, 16(sp)
+    fmv.d fs0, ft10
+    fmv.d fa0, fs0
+    ld s1, 32(sp)
+    fld fs0, 40(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,48 @@
+    .text
+
+    .globl fcmp
+fcmp:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -32
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    fsd fs0, 16(sp)
+    fsd fs1, 24(sp)
+    fmv.d fs1, fa0
+    fmv.d fs0, fa1
+.Lfcmp.entry:
+    fmv.d ft10, fs0
+    fcvt.d.s ft10, ft10
+    fmv.d fs0, ft10
+    fmv.d ft10, fs1
+    fmv.d ft11, fs0
+    flt.d t0, ft10, ft11
+    mv s2, t0
+    fmv.d ft10, fs1
+    li t5, 0
+    fmv.d.x ft11, t5
+    feq.d t0, ft10, ft10
+    feq.d t1, ft11, ft11
+    and t0, t0, t1
+    xori t0, t0, 1
+    mv s1, t0
+    mv t0, s2
+    mv t1, s1
+    or t0, t0, t1
+    andi t0, t0, 1
+    mv s1, t0
+    mv a0, s1
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    fld fs0, 16(sp)
+    fld fs1, 24(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,37 @@
+    .text
+
+    .globl gep
+gep:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -16
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    mv t0, a0
+    mv s2, t0
+    mv t0, a1
+    mv s1, t0
+.Lgep.entry:
+    mv t0, s2
+    mv t1, s1
+    li t2, 20
+    mul t1, t1, t2
+    add t0, t0, t1
+    addi t0, t0, 4
+    mv t1, s1
+    li t2, 4
+    mul t1, t1, t2
+    add t0, t0, t1
+    mv s1, t0
+    mv a0, s1
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
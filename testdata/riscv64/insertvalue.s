@@ -0,0 +1,58 @@
+    .text
+
+    .globl insertvalue
+insertvalue:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -96
+    sd s1, 80(sp)
+    sd s2, 88(sp)
+    mv t0, a0
+    mv s2, t0
+    mv t0, a1
+    slli t0, t0, 56
+    srai t0, t0, 56
+    mv s1, t0
+.Linsertvalue.entry:
+    mv t1, s2
+    ld t0, 0(t1)
+    sd t0, 0(sp)
+    ld t0, 8(t1)
+    sd t0, 8(sp)
+    ld t0, 16(t1)
+    sd t0, 16(sp)
+    ld t0, 0(sp)
+    sd t0, 24(sp)
+    ld t0, 8(sp)
+    sd t0, 32(sp)
+    ld t0, 16(sp)
+    sd t0, 40(sp)
+    mv t0, s1
+    sb t0, 24(sp)
+    ld t0, 24(sp)
+    sd t0, 48(sp)
+    ld t0, 32(sp)
+    sd t0, 56(sp)
+    ld t0, 40(sp)
+    sd t0, 64(sp)
+    li t5, 4602678819172646912
+    fmv.d.x ft10, t5
+    fsd ft10, 56(sp)
+    mv t1, s2
+    ld t0, 48(sp)
+    sd t0, 0(t1)
+    ld t0, 56(sp)
+    sd t0, 8(t1)
+    ld t0, 64(sp)
+    sd t0, 16(t1)
+    ld s1, 80(sp)
+    ld s2, 88(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,46 @@
+    .text
+
+    .globl load
+load:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -32
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    fsd fs0, 16(sp)
+    fsd fs1, 24(sp)
+    mv t0, a0
+    mv s1, t0
+    mv t0, a1
+    mv s2, t0
+.Lload.entry:
+    mv t1, s1
+    lb t0, 0(t1)
+    mv s1, t0
+    mv t1, s2
+    flw ft10, 0(t1)
+    fmv.d fs0, ft10
+    fmv.d ft10, fs0
+    fcvt.d.s ft10, ft10
+    fmv.d fs1, ft10
+    mv t0, s1
+    fcvt.d.l ft10, t0
+    fmv.d fs0, ft10
+    fmv.d ft10, fs1
+    fmv.d ft11, fs0
+    fadd.d ft10, ft10, ft11
+    fmv.d fs0, ft10
+    fmv.d fa0, fs0
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    fld fs0, 16(sp)
+    fld fs1, 24(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,48 @@
+    .text
+
+    .globl lshr
+lshr:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -32
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    sd s3, 16(sp)
+    mv t0, a0
+    mv s2, t0
+    mv t0, a1
+    sext.w t0, t0
+    mv s3, t0
+.Llshr.entry:
+    mv t0, s3
+    mv s1, t0
+    mv t0, s2
+    mv t1, s1
+    srl t0, t0, t1
+    mv s2, t0
+    mv t0, s3
+    li t1, 3
+    slli t0, t0, 32
+    srli t0, t0, 32
+    srl t0, t0, t1
+    sext.w t0, t0
+    mv s1, t0
+    mv t0, s1
+    mv s1, t0
+    mv t0, s2
+    mv t1, s1
+    srl t0, t0, t1
+    mv s1, t0
+    mv a0, s1
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    ld s3, 16(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
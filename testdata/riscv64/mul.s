@@ -0,0 +1,88 @@
+    .text
+
+    .globl mul
+mul:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -32
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    sd s3, 16(sp)
+    mv t0, a0
+    mv s2, t0
+    mv t0, a1
+    sext.w t0, t0
+    mv s3, t0
+.Lmul.entry:
+    mv t0, s3
+    mv s1, t0
+    mv t0, s2
+    mv t1, s1
+    mul t0, t0, t1
+    mv s2, t0
+    mv t0, s3
+    li t1, 3
+    mul t0, t0, t1
+    sext.w t0, t0
+    mv s1, t0
+    mv t0, s1
+    mv s1, t0
+    mv t0, s2
+    mv t1, s1
+    mul t0, t0, t1
+    mv s1, t0
+    mv a0, s1
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    ld s3, 16(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .globl fmul
+fmul:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -32
+    fsd fs0, 0(sp)
+    fsd fs1, 8(sp)
+    fsd fs2, 16(sp)
+    fmv.d fs1, fa0
+    fmv.d fs2, fa1
+.Lfmul.entry:
+    fmv.d ft10, fs2
+    fcvt.d.s ft10, ft10
+    fmv.d fs0, ft10
+    fmv.d ft10, fs1
+    fmv.d ft11, fs0
+    fmul.d ft10, ft10, ft11
+    fmv.d fs1, ft10
+    fmv.d ft10, fs2
+    li t5, 1069547520
+    fmv.w.x ft11, t5
+    fmul.s ft10, ft10, ft11
+    fmv.d fs0, ft10
+    fmv.d ft10, fs0
+    fcvt.d.s ft10, ft10
+    fmv.d fs0, ft10
+    fmv.d ft10, fs1
+    fmv.d ft11, fs0
+    fmul.d ft10, ft10, ft11
+    fmv.d fs0, ft10
+    fmv.d fa0, fs0
+    fld fs0, 0(sp)
+    fld fs1, 8(sp)
+    fld fs2, 16(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,40 @@
+    .text
+
+    .globl neg
+neg:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -32
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    fsd fs0, 16(sp)
+    mv t0, a0
+    mv s1, t0
+    fmv.d fs0, fa0
+.Lneg.entry:
+    mv t0, s1
+    neg t0, t0
+    mv s2, t0
+    fmv.d ft10, fs0
+    fneg.d ft10, ft10
+    fmv.d fs0, ft10
+    fmv.d ft10, fs0
+    fcvt.l.d t0, ft10, rtz
+    mv s1, t0
+    mv t0, s2
+    mv t1, s1
+    add t0, t0, t1
+    mv s1, t0
+    mv a0, s1
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    fld fs0, 16(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,29 @@
+    .text
+
+    .globl not
+not:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -16
+    sd s1, 0(sp)
+    mv t0, a0
+    slli t0, t0, 48
+    srai t0, t0, 48
+    mv s1, t0
+.Lnot.entry:
+    mv t0, s1
+    not t0, t0
+    slli t0, t0, 48
+    srai t0, t0, 48
+    mv s1, t0
+    mv a0, s1
+    ld s1, 0(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,65 @@
+    .text
+
+    .globl phi
+phi:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -32
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    sd s3, 16(sp)
+    fsd fs0, 24(sp)
+    mv t0, a0
+    mv s1, t0
+.Lphi.entry:
+    li s2, 0
+    li t5, 4607182418800017408
+    fmv.d.x fs0, t5
+    j .Lphi.head
+.Lphi.head:
+    mv t0, s1
+    li t1, 0
+    slt t0, t1, t0
+    mv s3, t0
+    mv t0, s3
+    beqz t0, .Lphi.1
+    j .Lphi.body
+.Lphi.1:
+    j .Lphi.exit
+.Lphi.body:
+    mv t0, s2
+    mv t1, s1
+    add t0, t0, t1
+    mv s2, t0
+    mv t0, s1
+    li t1, 1
+    sub t0, t0, t1
+    mv s1, t0
+    fmv.d ft10, fs0
+    li t5, 4611686018427387904
+    fmv.d.x ft11, t5
+    fmul.d ft10, ft10, ft11
+    fmv.d fs0, ft10
+    j .Lphi.head
+.Lphi.exit:
+    fmv.d ft10, fs0
+    fcvt.l.d t0, ft10, rtz
+    mv s1, t0
+    mv t0, s2
+    mv t1, s1
+    add t0, t0, t1
+    mv s1, t0
+    mv a0, s1
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    ld s3, 16(sp)
+    fld fs0, 24(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
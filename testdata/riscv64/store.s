@@ -0,0 +1,38 @@
+    .text
+
+    .globl store
+store:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -32
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    sd s3, 16(sp)
+    mv t0, a0
+    mv s3, t0
+    mv t0, a1
+    mv s2, t0
+    mv t0, a2
+    slli t0, t0, 48
+    srai t0, t0, 48
+    mv s1, t0
+.Lstore.entry:
+    mv t0, s1
+    mv t1, s3
+    sh t0, 0(t1)
+    li t5, -4610560118520545280
+    fmv.d.x ft10, t5
+    mv t1, s2
+    fsd ft10, 0(t1)
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    ld s3, 16(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
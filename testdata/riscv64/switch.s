@@ -0,0 +1,68 @@
+    .text
+
+    .globl switch
+switch:
+    addi sp, sp, -16
+    sd ra, 8(sp)
+    sd s0, 0(sp)
+    addi s0, sp, 16
+    addi sp, sp, -16
+    sd s1, 0(sp)
+    mv t0, a0
+    sext.w t0, t0
+    mv s1, t0
+.Lswitch.entry:
+    mv t0, s1
+    li t1, 1
+    sub t1, t0, t1
+    seqz t1, t1
+    beqz t1, .Lswitch.1
+    j .Lswitch.one
+.Lswitch.1:
+    li t1, 2
+    sub t1, t0, t1
+    seqz t1, t1
+    beqz t1, .Lswitch.2
+    j .Lswitch.two
+.Lswitch.2:
+    li t1, -3
+    sub t1, t0, t1
+    seqz t1, t1
+    beqz t1, .Lswitch.3
+    j .Lswitch.three
+.Lswitch.3:
+    j .Lswitch.other
+.Lswitch.one:
+    li a0, 10
+    ld s1, 0(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+.Lswitch.two:
+    li a0, 20
+    ld s1, 0(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+.Lswitch.three:
+    li a0, 30
+    ld s1, 0(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+.Lswitch.other:
+    li a0, 0
+    ld s1, 0(sp)
+    addi sp, s0, -16
+    ld ra, 8(sp)
+    ld s0, 0(sp)
+    addi sp, sp, 16
+    ret
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,1467 @@
+    .text
+
+    .globl fib
+fib:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $32, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %rdi, %rax
+    movslq %eax, %rax
+    movq %rax, %r13
+.Lfib.entry:
+    movq $0, %rax
+    movq %rax, %r12
+    movq $1, %rax
+    movq %rax, %rbx
+    movq %r12, %r14
+    movq %r13, %r12
+    movq %rbx, %r13
+    jmp .Lfib.head
+.Lfib.head:
+    movq %r12, %rax
+    movq $0, %rcx
+    cmpq %rcx, %rax
+    setg %al
+    movzbl %al, %eax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    testq %rax, %rax
+    jne .Lfib.body
+    jmp .Lfib.exit
+.Lfib.body:
+    movq %r14, %rax
+    movq %r13, %rcx
+    addq %rcx, %rax
+    movq %rax, %r14
+    movq %r12, %rax
+    movq $1, %rcx
+    subq %rcx, %rax
+    movslq %eax, %rax
+    movq %rax, %rbx
+    movq %rbx, %r12
+    movq %r14, %r11
+    movq %r13, %r14
+    movq %r11, %r13
+    jmp .Lfib.head
+.Lfib.exit:
+    movq %r14, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl rot
+rot:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $48, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %r15, -40(%rbp)
+    movq %rdi, %rax
+    movslq %eax, %rax
+    movq %rax, %r14
+    movq %rsi, %rax
+    movq %rax, %r13
+    movq %rdx, %rax
+    movq %rax, %r12
+    movq %rcx, %rax
+    movq %rax, %rbx
+.Lrot.entry:
+    movq %r12, %r11
+    movq %r14, %r12
+    movq %r11, %r14
+    jmp .Lrot.head
+.Lrot.head:
+    movq %r12, %rax
+    movq $1, %rcx
+    subq %rcx, %rax
+    movslq %eax, %rax
+    movq %rax, %r15
+    movq %r15, %rax
+    movq $0, %rcx
+    cmpq %rcx, %rax
+    setg %al
+    movzbl %al, %eax
+    movq %rax, %r12
+    movq %r12, %rax
+    testq %rax, %rax
+    jne .Lrot.head.head
+    jmp .Lrot.exit
+.Lrot.exit:
+    movq %r13, %rax
+    movq $100, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r13
+    movq %r14, %rax
+    movq $10, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r12
+    movq %r13, %rax
+    movq %r12, %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %rbx, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq -40(%rbp), %r15
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+.Lrot.head.head:
+    movq %r15, %r12
+    movq %r13, %r11
+    movq %r14, %r13
+    movq %rbx, %r14
+    movq %r11, %rbx
+    jmp .Lrot.head
+
+    .globl many
+many:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $160, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %r15, -40(%rbp)
+    movq %rdi, %rax
+    movq %rax, %r12
+    movq %rsi, %rax
+    movslq %eax, %rax
+    movq %rax, %r10
+    movq %rdx, %rax
+    movswq %ax, %rax
+    movq %rax, %r15
+    movq %rcx, %rax
+    movsbq %al, %rax
+    movq %rax, %r14
+    movq %r8, %rax
+    movq %rax, %r13
+    movq %r9, %rax
+    movq %rax, -160(%rbp)
+    movq 16(%rbp), %rax
+    movq %rax, -152(%rbp)
+    movq 24(%rbp), %rax
+    movslq %eax, %rax
+    movq %rax, %rbx
+    movaps %xmm0, %xmm12
+    movaps %xmm1, %xmm8
+    movaps %xmm2, %xmm9
+    movaps %xmm3, %xmm11
+    movaps %xmm4, %xmm10
+    movsd %xmm5, -144(%rbp)
+    movsd %xmm6, -136(%rbp)
+    movsd %xmm7, -128(%rbp)
+    movsd 32(%rbp), %xmm14
+    movsd %xmm14, -120(%rbp)
+    movsd 40(%rbp), %xmm14
+    movaps %xmm14, %xmm13
+.Lmany.entry:
+    movq %r10, %rax
+    movq %rax, %r10
+    movq %r15, %rax
+    movq %rax, %r15
+    movq %r14, %rax
+    movq %rax, %r14
+    movq %rbx, %rax
+    movq %rax, %rbx
+    movq %r12, %rax
+    movq $3, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %r10, %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq $5, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %r15, %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq $7, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %r14, %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %r13, %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq -160(%rbp), %rcx
+    subq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq -152(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq $11, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %rbx, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movaps %xmm8, %xmm14
+    cvtss2sd %xmm14, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm13, %xmm14
+    cvtss2sd %xmm14, %xmm14
+    movsd %xmm14, -112(%rbp)
+    movaps %xmm12, %xmm14
+    movaps %xmm8, %xmm15
+    addsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movaps %xmm9, %xmm15
+    mulsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movaps %xmm11, %xmm15
+    subsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movaps %xmm10, %xmm15
+    addsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movsd -144(%rbp), %xmm15
+    addsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movsd -136(%rbp), %xmm15
+    mulsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movsd -128(%rbp), %xmm15
+    addsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movsd -120(%rbp), %xmm15
+    subsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movsd -112(%rbp), %xmm15
+    mulsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    cvttsd2siq %xmm14, %rax
+    movq %rax, %r12
+    movq %rbx, %rax
+    movq %r12, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq -40(%rbp), %r15
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl callmany
+callmany:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $112, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %r15, -40(%rbp)
+    movq %rdi, %rax
+    movq %rax, %r14
+.Lcallmany.entry:
+    movq $-7, %rax
+    movq %rax, %r15
+    movq $300, %rax
+    movq %rax, %r13
+    movq $-3, %rax
+    movq %rax, %r12
+    movq $99, %rax
+    movq %rax, %rbx
+    movabsq $4609434218613702656, %r11
+    movq %r11, %xmm14
+    movaps %xmm14, %xmm11
+    movq $1075838976, %r11
+    movq %r11, %xmm14
+    movaps %xmm14, %xmm10
+    movabsq $3204448256, %r11
+    movq %r11, %xmm14
+    movaps %xmm14, %xmm9
+    movq %r14, %rax
+    cvtsi2sdq %rax, %xmm14
+    movaps %xmm14, %xmm8
+    movq %r14, %rdi
+    movq %r15, %rsi
+    movq %r13, %rdx
+    movq %r12, %rcx
+    movq %r14, %r8
+    movq %r14, %r9
+    movq %r14, %rax
+    movq %rax, 0(%rsp)
+    movq %rbx, %rax
+    movq %rax, 8(%rsp)
+    movaps %xmm11, %xmm0
+    movaps %xmm10, %xmm1
+    movaps %xmm8, %xmm2
+    movaps %xmm11, %xmm3
+    movaps %xmm8, %xmm4
+    movaps %xmm11, %xmm5
+    movaps %xmm8, %xmm6
+    movaps %xmm11, %xmm7
+    movq %xmm8, %rax
+    movq %rax, 16(%rsp)
+    movq %xmm9, %rax
+    movq %rax, 24(%rsp)
+    movl $8, %eax
+    call many
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r14, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq -40(%rbp), %r15
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl pressure
+pressure:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $112, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %r15, -40(%rbp)
+    movq %rdi, %rax
+    movq %rax, %rbx
+.Lpressure.entry:
+    movq %rbx, %rax
+    movq $1, %rcx
+    addq %rcx, %rax
+    movq %rax, %r10
+    movq %rbx, %rax
+    movq $3, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r12
+    movq %rbx, %rax
+    movq $5, %rcx
+    subq %rcx, %rax
+    movq %rax, %r15
+    movq %rbx, %rax
+    movq $77, %rcx
+    xorq %rcx, %rax
+    movq %rax, %r14
+    movq %rbx, %rax
+    movq $1024, %rcx
+    orq %rcx, %rax
+    movq %rax, %r13
+    movq %rbx, %rax
+    movq $255, %rcx
+    andq %rcx, %rax
+    movq %rax, -112(%rbp)
+    movq %rbx, %rax
+    movq $3, %rcx
+    shlq %cl, %rax
+    movq %rax, -104(%rbp)
+    movq %rbx, %rax
+    movq $1, %rcx
+    sarq %cl, %rax
+    movq %rax, -96(%rbp)
+    movq %rbx, %rax
+    movq $2, %rcx
+    shrq %cl, %rax
+    movq %rax, -88(%rbp)
+    movq %rbx, %rax
+    movq %rbx, %rcx
+    addq %rcx, %rax
+    movq %rax, -80(%rbp)
+    movq %rbx, %rax
+    cvtsi2sdq %rax, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movabsq $4602678819172646912, %r11
+    movq %r11, %xmm15
+    mulsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movq %r10, %rax
+    movslq %eax, %rax
+    movq %rax, %rbx
+    movq %r10, -64(%rbp)
+    movsd %xmm8, -56(%rbp)
+    movq %rbx, %rdi
+    movl $0, %eax
+    call fib
+    movq %rax, -72(%rbp)
+    movq -64(%rbp), %r10
+    movsd -56(%rbp), %xmm8
+    movq %r10, %rax
+    movq %r12, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r15, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r14, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r13, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -112(%rbp), %rcx
+    subq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -104(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -96(%rbp), %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -88(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -80(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -72(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movaps %xmm8, %xmm14
+    cvttsd2siq %xmm14, %rax
+    movq %rax, %rbx
+    movq %r12, %rax
+    movq %rbx, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq -40(%rbp), %r15
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl narrow
+narrow:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $192, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %r15, -40(%rbp)
+    movq %rdi, %rax
+    movsbq %al, %rax
+    movq %rax, %r10
+    movq %rsi, %rax
+    movsbq %al, %rax
+    movq %rax, %r14
+.Lnarrow.entry:
+    movq %r10, %rax
+    movq %r14, %rcx
+    addq %rcx, %rax
+    movsbq %al, %rax
+    movq %rax, %r13
+    movq %r10, %rax
+    movq %r14, %rcx
+    imulq %rcx, %rax
+    movsbq %al, %rax
+    movq %rax, %r15
+    movq %r10, %rax
+    movq %r14, %rcx
+    movzbl %al, %eax
+    movzbl %cl, %ecx
+    xorl %edx, %edx
+    divq %rcx
+    movsbq %al, %rax
+    movq %rax, %r12
+    movq %r10, %rax
+    movq %r14, %rcx
+    cqto
+    idivq %rcx
+    movsbq %al, %rax
+    movq %rax, %rbx
+    movq %r10, %rax
+    movq %r14, %rcx
+    movzbl %al, %eax
+    movzbl %cl, %ecx
+    xorl %edx, %edx
+    divq %rcx
+    movq %rdx, %rax
+    movsbq %al, %rax
+    movq %rax, -192(%rbp)
+    movq %r10, %rax
+    movq %r14, %rcx
+    cqto
+    idivq %rcx
+    movq %rdx, %rax
+    movsbq %al, %rax
+    movq %rax, -184(%rbp)
+    movq %r10, %rax
+    movq $1, %rcx
+    movzbl %al, %eax
+    shrq %cl, %rax
+    movsbq %al, %rax
+    movq %rax, -176(%rbp)
+    movq %r10, %rax
+    movq $1, %rcx
+    sarq %cl, %rax
+    movsbq %al, %rax
+    movq %rax, -168(%rbp)
+    movq %r10, %rax
+    movq $3, %rcx
+    shlq %cl, %rax
+    movsbq %al, %rax
+    movq %rax, -160(%rbp)
+    movq %r10, %rax
+    movq %r14, %rcx
+    cmpq %rcx, %rax
+    setb %al
+    movzbl %al, %eax
+    movq %rax, -152(%rbp)
+    movq %r10, %rax
+    movq %r14, %rcx
+    cmpq %rcx, %rax
+    setl %al
+    movzbl %al, %eax
+    movq %rax, -144(%rbp)
+    movq %r10, %rax
+    notq %rax
+    movsbq %al, %rax
+    movq %rax, %r10
+    movq %r14, %rax
+    negq %rax
+    movsbq %al, %rax
+    movq %rax, %r14
+    movq %r13, %rax
+    movzbl %al, %eax
+    movq %rax, %r13
+    movq %r15, %rax
+    movzbl %al, %eax
+    movq %rax, %r15
+    movq %r12, %rax
+    movzbl %al, %eax
+    movq %rax, %r12
+    movq %rbx, %rax
+    movq %rax, %rbx
+    movq -192(%rbp), %rax
+    movzbl %al, %eax
+    movq %rax, -136(%rbp)
+    movq -184(%rbp), %rax
+    movq %rax, -128(%rbp)
+    movq -176(%rbp), %rax
+    movzbl %al, %eax
+    movq %rax, -120(%rbp)
+    movq -168(%rbp), %rax
+    movq %rax, -112(%rbp)
+    movq -160(%rbp), %rax
+    movq %rax, -104(%rbp)
+    movq -152(%rbp), %rax
+    andq $1, %rax
+    movq %rax, -96(%rbp)
+    movq -144(%rbp), %rax
+    andq $1, %rax
+    negq %rax
+    movq %rax, -88(%rbp)
+    movq %r10, %rax
+    movq %rax, -80(%rbp)
+    movq %r14, %rax
+    movzbl %al, %eax
+    movq %rax, -72(%rbp)
+    movq %r13, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r13
+    movq %r13, %rax
+    movq %r15, %rcx
+    addq %rcx, %rax
+    movq %rax, %r13
+    movq %r13, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r13
+    movq %r13, %rax
+    movq %r12, %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %rbx, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -136(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -128(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -120(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -112(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -104(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -96(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -88(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -80(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $1000003, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -72(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq -40(%rbp), %r15
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl floats
+floats:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $144, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %r15, -40(%rbp)
+    movaps %xmm0, %xmm13
+    movaps %xmm1, %xmm11
+.Lfloats.entry:
+    movaps %xmm11, %xmm14
+    cvtss2sd %xmm14, %xmm14
+    movaps %xmm14, %xmm10
+    movaps %xmm13, %xmm14
+    cvtsd2ss %xmm14, %xmm14
+    movaps %xmm14, %xmm9
+    movaps %xmm13, %xmm14
+    movabsq $-9223372036854775808, %r11
+    movq %r11, %xmm15
+    xorpd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm11, %xmm14
+    movabsq $2147483648, %r11
+    movq %r11, %xmm15
+    xorps %xmm15, %xmm14
+    movaps %xmm14, %xmm12
+    movaps %xmm13, %xmm14
+    movaps %xmm10, %xmm15
+    ucomisd %xmm14, %xmm15
+    seta %al
+    movzbl %al, %eax
+    movq %rax, %r10
+    movaps %xmm13, %xmm14
+    movaps %xmm10, %xmm15
+    ucomisd %xmm15, %xmm14
+    setae %al
+    movzbl %al, %eax
+    movq %rax, %rbx
+    movaps %xmm13, %xmm14
+    movaps %xmm10, %xmm15
+    ucomisd %xmm15, %xmm14
+    sete %al
+    setp %cl
+    orb %cl, %al
+    movzbl %al, %eax
+    movq %rax, %r15
+    movaps %xmm13, %xmm14
+    movaps %xmm10, %xmm15
+    ucomisd %xmm15, %xmm14
+    setne %al
+    setnp %cl
+    andb %cl, %al
+    movzbl %al, %eax
+    movq %rax, %r14
+    movaps %xmm13, %xmm14
+    movaps %xmm10, %xmm15
+    ucomisd %xmm15, %xmm14
+    setp %al
+    movzbl %al, %eax
+    movq %rax, %r13
+    movaps %xmm9, %xmm14
+    movaps %xmm11, %xmm15
+    ucomiss %xmm15, %xmm14
+    setbe %al
+    movzbl %al, %eax
+    movq %rax, %r12
+    movaps %xmm13, %xmm14
+    movaps %xmm13, %xmm15
+    ucomisd %xmm15, %xmm14
+    sete %al
+    setnp %cl
+    andb %cl, %al
+    movzbl %al, %eax
+    movq %rax, -144(%rbp)
+    movaps %xmm12, %xmm14
+    movaps %xmm11, %xmm15
+    ucomiss %xmm15, %xmm14
+    setne %al
+    setp %cl
+    orb %cl, %al
+    movzbl %al, %eax
+    movq %rax, -136(%rbp)
+    movq %r10, %rax
+    andq $1, %rax
+    movq %rax, -128(%rbp)
+    movq %rbx, %rax
+    andq $1, %rax
+    movq %rax, %rbx
+    movq %r15, %rax
+    andq $1, %rax
+    movq %rax, %r10
+    movq %r14, %rax
+    andq $1, %rax
+    movq %rax, %r15
+    movq %r13, %rax
+    andq $1, %rax
+    movq %rax, %r14
+    movq %r12, %rax
+    andq $1, %rax
+    movq %rax, %r13
+    movq -144(%rbp), %rax
+    andq $1, %rax
+    movq %rax, %r12
+    movq -136(%rbp), %rax
+    andq $1, %rax
+    movq %rax, -120(%rbp)
+    movq %rbx, %rax
+    movq $1, %rcx
+    shlq %cl, %rax
+    movq %rax, %rbx
+    movq %r10, %rax
+    movq $2, %rcx
+    shlq %cl, %rax
+    movq %rax, %r10
+    movq %r15, %rax
+    movq $3, %rcx
+    shlq %cl, %rax
+    movq %rax, %r15
+    movq %r14, %rax
+    movq $4, %rcx
+    shlq %cl, %rax
+    movq %rax, %r14
+    movq %r13, %rax
+    movq $5, %rcx
+    shlq %cl, %rax
+    movq %rax, %r13
+    movq %r12, %rax
+    movq $6, %rcx
+    shlq %cl, %rax
+    movq %rax, %r12
+    movq -120(%rbp), %rax
+    movq $7, %rcx
+    shlq %cl, %rax
+    movq %rax, -112(%rbp)
+    movq -128(%rbp), %rax
+    movq %rbx, %rcx
+    orq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r10, %rcx
+    orq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r15, %rcx
+    orq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r14, %rcx
+    orq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r13, %rcx
+    orq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r12, %rcx
+    orq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -112(%rbp), %rcx
+    orq %rcx, %rax
+    movq %rax, %r12
+    movaps %xmm8, %xmm14
+    movabsq $4652007308841189376, %r11
+    movq %r11, %xmm15
+    mulsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    cvttsd2siq %xmm14, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq $256, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r12, %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movaps %xmm12, %xmm14
+    movq %xmm14, %rax
+    movslq %eax, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movl %eax, %eax
+    movq %rax, %rbx
+    movq %r12, %rax
+    movq %rbx, %rcx
+    xorq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq -40(%rbp), %r15
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl unsigned
+unsigned:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $48, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %rdi, %rax
+    movq %rax, %r12
+.Lunsigned.entry:
+    movq %r12, %rax
+    testq %rax, %rax
+    js .Lunsigned.1
+    cvtsi2sdq %rax, %xmm14
+    jmp .Lunsigned.2
+.Lunsigned.1:
+    movq %rax, %rcx
+    shrq %rcx
+    andl $1, %eax
+    orq %rax, %rcx
+    cvtsi2sdq %rcx, %xmm14
+    addsd %xmm14, %xmm14
+.Lunsigned.2:
+    movaps %xmm14, %xmm9
+    movaps %xmm9, %xmm14
+    movabsq $0x43e0000000000000, %r11
+    movq %r11, %xmm15
+    ucomisd %xmm15, %xmm14
+    jae .Lunsigned.3
+    cvttsd2siq %xmm14, %rax
+    jmp .Lunsigned.4
+.Lunsigned.3:
+    subsd %xmm15, %xmm14
+    cvttsd2siq %xmm14, %rax
+    btcq $63, %rax
+.Lunsigned.4:
+    movq %rax, %rbx
+    movq %r12, %rax
+    testq %rax, %rax
+    js .Lunsigned.5
+    cvtsi2ssq %rax, %xmm14
+    jmp .Lunsigned.6
+.Lunsigned.5:
+    movq %rax, %rcx
+    shrq %rcx
+    andl $1, %eax
+    orq %rax, %rcx
+    cvtsi2ssq %rcx, %xmm14
+    addss %xmm14, %xmm14
+.Lunsigned.6:
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    movl $0x5f000000, %r11d
+    movq %r11, %xmm15
+    ucomiss %xmm15, %xmm14
+    jae .Lunsigned.7
+    cvttss2siq %xmm14, %rax
+    jmp .Lunsigned.8
+.Lunsigned.7:
+    subss %xmm15, %xmm14
+    cvttss2siq %xmm14, %rax
+    btcq $63, %rax
+.Lunsigned.8:
+    movq %rax, %r13
+    movaps %xmm9, %xmm14
+    movabsq $4427038433705197568, %r11
+    movq %r11, %xmm15
+    mulsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    cvttsd2siq %xmm14, %rax
+    movslq %eax, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movl %eax, %eax
+    movq %rax, %r12
+    movq %rbx, %rax
+    movq %r13, %rcx
+    xorq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r12, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl sw
+sw:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $32, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %rdi, %rax
+    movswq %ax, %rax
+    movq %rax, %r14
+.Lsw.entry:
+    movq $1, %rax
+    movq %rax, %r13
+    movq $2, %rax
+    movq %rax, %r12
+    movq $3, %rax
+    movq %rax, %rbx
+    movq %r14, %rax
+    cmpq $-5, %rax
+    je .Lsw.a
+    cmpq $7, %rax
+    je .Lsw.b
+    cmpq $300, %rax
+    je .Lsw.c
+    cmpq $-32768, %rax
+    je .Lsw.a
+    jmp .Lsw.dflt
+.Lsw.a:
+    movq %r13, %rbx
+    jmp .Lsw.join
+.Lsw.b:
+    movq %r12, %rbx
+    movq %r13, %r12
+    jmp .Lsw.join
+.Lsw.c:
+    movq %r14, %rax
+    cmpq $1, %rax
+    je .Lsw.b
+    jmp .Lsw.c.join
+.Lsw.dflt:
+    movq %rbx, %r11
+    movq %r12, %rbx
+    movq %r11, %r12
+    jmp .Lsw.join
+.Lsw.join:
+    movq %rbx, %rax
+    movq $10, %rcx
+    imulq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r12, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+.Lsw.c.join:
+    movq %r13, %r12
+    jmp .Lsw.join
+
+    .globl agg
+agg:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $288, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %r15, -40(%rbp)
+    movq %rdi, %rax
+    movq %rax, %rbx
+    movq %rsi, %rax
+    movslq %eax, %rax
+    movq %rax, -288(%rbp)
+.Lagg.entry:
+    leaq -144(%rbp), %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %rax, %r13
+    movq $-2, %rax
+    movq %r13, %r11
+    movb %al, (%r11)
+    movq %r12, %rax
+    addq $2, %rax
+    movq %rax, %r13
+    movq %r13, %rax
+    movq -288(%rbp), %rcx
+    imulq $2, %rcx, %rcx
+    addq %rcx, %rax
+    movq %rax, %r14
+    movq %rbx, %rax
+    movswq %ax, %rax
+    movq %rax, %r13
+    movq %r13, %rax
+    movq %r14, %r11
+    movw %ax, (%r11)
+    movq %r12, %rax
+    addq $8, %rax
+    movq %rax, %r13
+    movabsq $4612811918334230528, %r11
+    movq %r11, %xmm14
+    movq %r13, %r11
+    movsd %xmm14, (%r11)
+    movq %r12, %rax
+    addq $16, %rax
+    movq %rax, %r14
+    movq %r14, %rax
+    movq %rax, %r13
+    movq $11, %rax
+    movq %r13, %r11
+    movl %eax, (%r11)
+    movq %r14, %rax
+    addq $8, %rax
+    movq %rax, %r13
+    movq %rbx, %rax
+    movq %r13, %r11
+    movq %rax, (%r11)
+    movq %r12, %r11
+    movq (%r11), %rax
+    movq %rax, -272(%rbp)
+    movq 8(%r11), %rax
+    movq %rax, -264(%rbp)
+    movq 16(%r11), %rax
+    movq %rax, -256(%rbp)
+    movq 24(%r11), %rax
+    movq %rax, -248(%rbp)
+    movq -256(%rbp), %rax
+    movq %rax, -240(%rbp)
+    movq -248(%rbp), %rax
+    movq %rax, -232(%rbp)
+    movq -240(%rbp), %rax
+    movq %rax, -224(%rbp)
+    movq -232(%rbp), %rax
+    movq %rax, -216(%rbp)
+    movq $99, %rax
+    movl %eax, -224(%rbp)
+    movq -272(%rbp), %rax
+    movq %rax, -208(%rbp)
+    movq -264(%rbp), %rax
+    movq %rax, -200(%rbp)
+    movq -256(%rbp), %rax
+    movq %rax, -192(%rbp)
+    movq -248(%rbp), %rax
+    movq %rax, -184(%rbp)
+    movq -224(%rbp), %rax
+    movq %rax, -192(%rbp)
+    movq -216(%rbp), %rax
+    movq %rax, -184(%rbp)
+    movq -208(%rbp), %rax
+    movq %rax, -176(%rbp)
+    movq -200(%rbp), %rax
+    movq %rax, -168(%rbp)
+    movq -192(%rbp), %rax
+    movq %rax, -160(%rbp)
+    movq -184(%rbp), %rax
+    movq %rax, -152(%rbp)
+    movq $-9, %rax
+    movw %ax, -170(%rbp)
+    leaq -112(%rbp), %rax
+    movq %rax, %rbx
+    movq %rbx, %r11
+    movq -176(%rbp), %rax
+    movq %rax, (%r11)
+    movq -168(%rbp), %rax
+    movq %rax, 8(%r11)
+    movq -160(%rbp), %rax
+    movq %rax, 16(%r11)
+    movq -152(%rbp), %rax
+    movq %rax, 24(%r11)
+    movq %rbx, %rax
+    addq $16, %rax
+    movq %rax, %rbx
+    movq %rbx, %r11
+    movslq (%r11), %rax
+    movq %rax, %r15
+    movswq -172(%rbp), %rax
+    movq %rax, %r14
+    movswq -170(%rbp), %rax
+    movq %rax, %r13
+    movsbq -176(%rbp), %rax
+    movq %rax, %r12
+    movsd -168(%rbp), %xmm14
+    movaps %xmm14, %xmm9
+    leaq table(%rip), %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -288(%rbp), %rcx
+    imulq $2, %rcx, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %r11
+    movswq (%r11), %rax
+    movq %rax, %rbx
+    leaq fl(%rip), %rax
+    movq %rax, %r10
+    movq %r10, %rax
+    addq $4, %rax
+    movq %rax, %r10
+    movq %r10, %r11
+    movss (%r11), %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    cvtss2sd %xmm14, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm9, %xmm14
+    movaps %xmm8, %xmm15
+    addsd %xmm15, %xmm14
+    movaps %xmm14, %xmm8
+    movaps %xmm8, %xmm14
+    cvttsd2siq %xmm14, %rax
+    movq %rax, -280(%rbp)
+    movq %r15, %rax
+    movq %rax, %r15
+    movq %r14, %rax
+    movq %rax, %r14
+    movq %r13, %rax
+    movq %rax, %r13
+    movq %r12, %rax
+    movq %rax, %r12
+    movq %rbx, %rax
+    movq %rax, %rbx
+    movq %r15, %rax
+    movq $100000, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r15
+    movq %r15, %rax
+    movq %r14, %rcx
+    addq %rcx, %rax
+    movq %rax, %r14
+    movq %r14, %rax
+    movq $100000, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r14
+    movq %r14, %rax
+    movq %r13, %rcx
+    addq %rcx, %rax
+    movq %rax, %r13
+    movq %r13, %rax
+    movq $1000, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r13
+    movq %r13, %rax
+    movq %r12, %rcx
+    addq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq $100000, %rcx
+    imulq %rcx, %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %rbx, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -280(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq -40(%rbp), %r15
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl seven
+seven:
+    pushq %rbp
+    movq %rsp, %rbp
+.Lseven.entry:
+    movq $7, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl half
+half:
+    pushq %rbp
+    movq %rsp, %rbp
+.Lhalf.entry:
+    movabsq $3204448256, %r11
+    movq %r11, %xmm0
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .globl imms
+imms:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $96, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+    movq %r13, -24(%rbp)
+    movq %r14, -32(%rbp)
+    movq %r15, -40(%rbp)
+    movq %rdi, %rax
+    movq %rax, %r13
+.Limms.entry:
+    movl $0, %eax
+    call seven
+    movq %rax, %r15
+    movl $0, %eax
+    call half
+    movaps %xmm0, %xmm8
+    movaps %xmm8, %xmm14
+    cvttss2siq %xmm14, %rax
+    movq %rax, %r14
+    movq %r13, %rdi
+    movq $-7, %rsi
+    movq $300, %rdx
+    movq $-3, %rcx
+    movq $11, %r8
+    movq %r13, %r9
+    movq $13, %rax
+    movq %rax, 0(%rsp)
+    movq $99, %rax
+    movq %rax, 8(%rsp)
+    movabsq $4609434218613702656, %r11
+    movq %r11, %xmm0
+    movq $1075838976, %r11
+    movq %r11, %xmm1
+    movabsq $4598175219545276416, %r11
+    movq %r11, %xmm2
+    movabsq $-4616189618054758400, %r11
+    movq %r11, %xmm3
+    movabsq $4613937818241073152, %r11
+    movq %r11, %xmm4
+    movabsq $4616189618054758400, %r11
+    movq %r11, %xmm5
+    movabsq $4617315517961601024, %r11
+    movq %r11, %xmm6
+    movabsq $4618441417868443648, %r11
+    movq %r11, %xmm7
+    movabsq $4620130267728707584, %rax
+    movq %rax, 16(%rsp)
+    movabsq $3204448256, %rax
+    movq %rax, 24(%rsp)
+    movl $8, %eax
+    call many
+    movq %rax, %r12
+    movq %r13, %rax
+    movq $0, %rcx
+    cmpq %rcx, %rax
+    setg %al
+    movzbl %al, %eax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    testq %rax, %rax
+    jne .Limms.pos
+    jmp .Limms.below
+.Limms.pos:
+    movq $1, %rax
+    testq %rax, %rax
+    jne .Limms.pos.mid
+    jmp .Limms.other
+.Limms.below:
+    movq $0, %rax
+    testq %rax, %rax
+    jne .Limms.other
+    jmp .Limms.below.mid
+.Limms.other:
+    movq $-9, %r13
+    movabsq $4607182418800017408, %r11
+    movq %r11, %xmm8
+    jmp .Limms.mid
+.Limms.mid:
+    movaps %xmm8, %xmm14
+    cvttsd2siq %xmm14, %rax
+    movq %rax, -64(%rbp)
+    movq $2, %rax
+    cmpq $1, %rax
+    je .Limms.dflt
+    cmpq $2, %rax
+    je .Limms.two
+    jmp .Limms.dflt
+.Limms.two:
+    movq %r12, %rax
+    movq %r15, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r14, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %r13, %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -64(%rbp), %rcx
+    addq %rcx, %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq -40(%rbp), %r15
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+.Limms.dflt:
+    movq $-1, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq -24(%rbp), %r13
+    movq -32(%rbp), %r14
+    movq -40(%rbp), %r15
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+.Limms.pos.mid:
+    movq $5, %r13
+    movabsq $4612811918334230528, %r11
+    movq %r11, %xmm8
+    jmp .Limms.mid
+.Limms.below.mid:
+    movabsq $-4607182418800017408, %r11
+    movq %r11, %xmm8
+    jmp .Limms.mid
+
+    .data
+    .p2align 1
+    .globl table
+table:
+    .short 3
+    .short 65532
+    .short 500
+    .short 32768
+    .short 7
+
+    .section .rodata
+    .p2align 2
+    .globl fl
+fl:
+    .long 1069547520
+    .long 3222274048
+
+    .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,73 @@
+    .text
+
+    .globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $32, %rsp
+    movq %rbx, -8(%rbp)
+    movq %r12, -16(%rbp)
+.Lmain.entry:
+    leaq fmt(%rip), %rax
+    movq %rax, %rbx
+    movq %rbx, %rax
+    movq %rax, %rbx
+    leaq hi(%rip), %rax
+    movq %rax, %r12
+    movq %r12, %rax
+    movq %rax, %r12
+    movabsq $4612811918334230528, %r11
+    movq %r11, %xmm14
+    movaps %xmm14, %xmm8
+    movq %rbx, %rdi
+    movq $7, %rsi
+    movq $-9, %rdx
+    movabsq $4614256650576692846, %r11
+    movq %r11, %xmm0
+    movq %r12, %rcx
+    movaps %xmm8, %xmm1
+    movl $2, %eax
+    call printf
+    movslq %eax, %rax
+    movq $0, %rax
+    movq -8(%rbp), %rbx
+    movq -16(%rbp), %r12
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+    .section .rodata
+    .p2align 0
+    .globl fmt
+fmt:
+    .byte 37
+    .byte 100
+    .byte 32
+    .byte 37
+    .byte 108
+    .byte 100
+    .byte 32
+    .byte 37
+    .byte 46
+    .byte 50
+    .byte 102
+    .byte 32
+    .byte 37
+    .byte 115
+    .byte 32
+    .byte 37
+    .byte 46
+    .byte 49
+    .byte 102
+    .byte 10
+    .byte 0
+
+    .section .rodata
+    .p2align 0
+    .globl hi
+hi:
+    .byte 104
+    .byte 105
+    .byte 0
+
+    .section .note.GNU-stack,"",@progbits